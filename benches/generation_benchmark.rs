@@ -5,7 +5,8 @@ use criterion::{black_box, Criterion};
 use rust_bert::gpt2::{
     Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources, Gpt2VocabResources,
 };
-use rust_bert::pipelines::common::ModelType;
+use rust_bert::pipelines::common::{DetokenizationProfile, ModelType};
+use rust_bert::pipelines::generation_utils::{RepetitionPenaltyMode, WarperKind};
 use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
 use rust_bert::resources::{RemoteResource, Resource};
 use std::time::{Duration, Instant};
@@ -35,6 +36,25 @@ fn create_text_generation_model() -> TextGenerationModel {
         no_repeat_ngram_size: 3,
         num_beam_groups: None,
         diversity_penalty: None,
+        corpus_penalty: None,
+        reasoning_budget: None,
+        generation_microbatch_size: None,
+        eos_probability_threshold: None,
+        length_bias: None,
+        repetition_penalty_mode: RepetitionPenaltyMode::Multiplicative,
+        warper_order: WarperKind::default_order(),
+        output_top_k_alternatives: None,
+        output_cache_memory: false,
+        frequency_penalty: 0.0,
+        presence_penalty: 0.0,
+        source_copy_bias: 0.0,
+        token_healing: false,
+        detokenization_profile: DetokenizationProfile::Natural,
+        end_suffix_ids: None,
+        max_output_bytes: None,
+        max_output_chars: None,
+        post_process: None,
+        rerank: None,
         num_return_sequences: 5,
         device: Device::cuda_if_available(),
     };
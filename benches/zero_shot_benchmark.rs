@@ -0,0 +1,70 @@
+#[macro_use]
+extern crate criterion;
+
+use criterion::{black_box, Criterion};
+use rust_bert::pipelines::zero_shot_classification::{
+    ZeroShotClassificationConfig, ZeroShotClassificationModel,
+};
+use std::time::{Duration, Instant};
+use tch::Device;
+
+static BATCH_SIZE: usize = 16;
+
+fn create_zero_shot_model() -> ZeroShotClassificationModel {
+    let config = ZeroShotClassificationConfig {
+        device: Device::cuda_if_available(),
+        ..Default::default()
+    };
+    ZeroShotClassificationModel::new(config).unwrap()
+}
+
+fn zero_shot_forward_pass(
+    iters: u64,
+    model: &ZeroShotClassificationModel,
+    data: &[&str],
+    labels: &[&str],
+) -> Duration {
+    let mut duration = Duration::new(0, 0);
+    for _i in 0..iters {
+        let start = Instant::now();
+        for batch in data.chunks(BATCH_SIZE) {
+            let _ = model.predict(batch, labels, None, 128);
+        }
+        duration = duration.checked_add(start.elapsed()).unwrap();
+    }
+    duration
+}
+
+fn bench_zero_shot(c: &mut Criterion) {
+    //    Set-up zero-shot classification model
+    unsafe {
+        torch_sys::dummy_cuda_dependency();
+    }
+    let model = create_zero_shot_model();
+
+    //    Define input: many sequences classified against a small fixed label set
+    let sentences = [
+        "Who are you voting for in 2020?",
+        "The prime minister has announced a stimulus package which was widely criticized by the opposition.",
+        "The team scored in the last minute of the game and qualified for the final.",
+        "Hospitals are preparing for a new wave of infections this winter.",
+    ];
+    let input = sentences
+        .iter()
+        .cycle()
+        .take(64)
+        .copied()
+        .collect::<Vec<&str>>();
+    let labels = ["politics", "public health", "economy", "sports"];
+    c.bench_function("Zero-shot classification", |b| {
+        b.iter_custom(|iters| black_box(zero_shot_forward_pass(iters, &model, &input, &labels)))
+    });
+}
+
+criterion_group! {
+name = benches;
+config = Criterion::default().sample_size(10);
+targets = bench_zero_shot
+}
+
+criterion_main!(benches);
@@ -32,8 +32,8 @@ fn main() -> anyhow::Result<()> {
     let second_input_context = "The cat was";
     let output = model.generate(&[input_context, second_input_context], None);
 
-    for sentence in output {
-        println!("{:?}", sentence);
+    for sentence in output.into_iter().flatten() {
+        println!("{:?}", sentence.text);
     }
     Ok(())
 }
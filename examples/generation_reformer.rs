@@ -57,8 +57,8 @@ fn main() -> anyhow::Result<()> {
     let input_context_2 = "It was a gloom winter night, and";
     let output = model.generate(&[input_context_1, input_context_2], None);
 
-    for sentence in output {
-        println!("{}", sentence);
+    for sentence in output.into_iter().flatten() {
+        println!("{}", sentence.text);
     }
     Ok(())
 }
@@ -53,8 +53,8 @@ fn main() -> anyhow::Result<()> {
     let input_context = "Once upon a time,";
     let output = model.generate(&[input_context], None);
 
-    for sentence in output {
-        println!("{}", sentence);
+    for sentence in output.into_iter().flatten() {
+        println!("{}", sentence.text);
     }
     Ok(())
 }
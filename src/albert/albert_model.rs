@@ -446,7 +446,7 @@ impl AlbertForMaskedLM {
 /// - `dropout`: Dropout layer
 /// - `classifier`: linear layer for classification
 pub struct AlbertForSequenceClassification {
-    albert: AlbertModel,
+    pub(crate) albert: AlbertModel,
     dropout: Dropout,
     classifier: nn::Linear,
 }
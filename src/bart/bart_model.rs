@@ -404,6 +404,7 @@ impl BartModel {
     ///   - `all_encoder_attentions` - `Option<Vec<Tensor>>` of length *num_encoder_layers* with shape (*batch size*, *source_sequence_length*, *hidden_size*)
     ///   - `all_decoder_hidden_states` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
     ///   - `all_decoder_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
+    ///   - `all_cross_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *num_heads*, *target_sequence_length*, *source_sequence_length*)
     ///
     /// # Example
     ///
@@ -490,6 +491,7 @@ impl BartModel {
             &self.embeddings,
             layer_states,
             train,
+            false,
         );
         BartModelOutput {
             decoder_output: decoder_output.hidden_state,
@@ -497,6 +499,7 @@ impl BartModel {
             cache: decoder_output.next_decoder_cache,
             all_decoder_hidden_states: decoder_output.all_hidden_states,
             all_decoder_attentions: decoder_output.all_attentions,
+            all_cross_attentions: decoder_output.all_cross_attentions,
             all_encoder_hidden_states,
             all_encoder_attentions,
         }
@@ -565,6 +568,7 @@ impl BartForConditionalGeneration {
     ///   - `all_encoder_attentions` - `Option<Vec<Tensor>>` of length *num_encoder_layers* with shape (*batch size*, *source_sequence_length*, *hidden_size*)
     ///   - `all_decoder_hidden_states` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
     ///   - `all_decoder_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
+    ///   - `all_cross_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *num_heads*, *target_sequence_length*, *source_sequence_length*)
     ///
     /// # Example
     ///
@@ -637,6 +641,30 @@ impl BartForConditionalGeneration {
             )
             .hidden_state
     }
+
+    /// Returns the cross-attention weights of the decoder layers for teacher-forced decoder inputs, whether or not
+    /// the model was configured to output attentions. Attentions are only collected for this forward pass.
+    pub(crate) fn forward_cross_attentions(
+        &self,
+        input_ids: &Tensor,
+        decoder_input_ids: &Tensor,
+    ) -> Vec<Tensor> {
+        let encoder_output = self.encode(input_ids, None);
+        self.base_model
+            .decoder
+            .forward_t(
+                decoder_input_ids,
+                &encoder_output,
+                None,
+                None,
+                &self.base_model.embeddings,
+                None,
+                false,
+                true,
+            )
+            .all_cross_attentions
+            .unwrap_or_default()
+    }
 }
 
 pub struct BartClassificationHead {
@@ -759,6 +787,7 @@ impl BartForSequenceClassification {
     ///   - `all_encoder_attentions` - `Option<Vec<Tensor>>` of length *num_encoder_layers* with shape (*batch size*, *source_sequence_length*, *hidden_size*)
     ///   - `all_decoder_hidden_states` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
     ///   - `all_decoder_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
+    ///   - `all_cross_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *num_heads*, *target_sequence_length*, *source_sequence_length*)
     ///
     /// # Example
     ///
@@ -833,6 +862,7 @@ impl BartForSequenceClassification {
             cache: None,
             all_decoder_hidden_states: base_model_output.all_decoder_hidden_states,
             all_decoder_attentions: base_model_output.all_decoder_attentions,
+            all_cross_attentions: base_model_output.all_cross_attentions,
             all_encoder_hidden_states: base_model_output.all_encoder_hidden_states,
             all_encoder_attentions: base_model_output.all_encoder_attentions,
         }
@@ -958,12 +988,67 @@ pub struct BartModelOutput {
     pub all_decoder_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all layers of the decoder
     pub all_decoder_attentions: Option<Vec<Tensor>>,
+    /// Cross-attention weights (attention of the decoder over the encoder hidden states) for all layers of the decoder
+    pub all_cross_attentions: Option<Vec<Tensor>>,
     /// Hidden states for all layers of the encoder
     pub all_encoder_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all layers of the encoder
     pub all_encoder_attentions: Option<Vec<Tensor>>,
 }
 
+impl BartModelOutput {
+    /// Returns the mean L2 norm of the token hidden states at each layer of the encoder, computed from the
+    /// collected `all_encoder_hidden_states` (the model must be built with `output_hidden_states` set to true).
+    /// The first value is the norm of the embeddings output. Used to monitor training instability and outlier features.
+    ///
+    /// # Arguments
+    ///
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *source_sequence_length*) excluding the padding tokens from the average
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Vec<f32>>` mean hidden state norm indexed by layer, `None` if the hidden states were not collected
+    pub fn encoder_hidden_state_norms(&self, attention_mask: Option<&Tensor>) -> Option<Vec<f32>> {
+        self.all_encoder_hidden_states
+            .as_ref()
+            .map(|hidden_states| {
+                hidden_states
+                    .iter()
+                    .map(|hidden_state| {
+                        let token_norms = hidden_state.to_kind(Kind::Float).norm2(2, &[-1], false);
+                        let mean_norm = match attention_mask {
+                            Some(mask) => {
+                                let mask = mask.to_kind(Kind::Float);
+                                (token_norms * &mask).sum(Kind::Float)
+                                    / mask.sum(Kind::Float).clamp_min(1.0)
+                            }
+                            None => token_norms.mean(Kind::Float),
+                        };
+                        mean_norm.double_value(&[]) as f32
+                    })
+                    .collect()
+            })
+    }
+
+    /// Returns the maximum absolute activation of each token at each layer of the encoder, computed from the
+    /// collected `all_encoder_hidden_states` (the model must be built with `output_hidden_states` set to true).
+    /// Tokens with a maximum activation far above the layer norm point to massive activations in specific dimensions.
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Vec<Tensor>>` tensors of shape (*batch size*, *source_sequence_length*) indexed by layer, `None` if the hidden states were not collected
+    pub fn encoder_max_activations(&self) -> Option<Vec<Tensor>> {
+        self.all_encoder_hidden_states
+            .as_ref()
+            .map(|hidden_states| {
+                hidden_states
+                    .iter()
+                    .map(|hidden_state| hidden_state.abs().amax(&[-1], false))
+                    .collect()
+            })
+    }
+}
+
 /// # Language generation model based on the Bart architecture
 pub struct BartGenerator {
     model: BartForConditionalGeneration,
@@ -1078,6 +1163,7 @@ impl BartGenerator {
         });
         let pad_token_id = Some(config.pad_token_id.unwrap_or(1));
         let vocab_size = config.vocab_size;
+        generate_config.validate_corpus_penalty(vocab_size)?;
         let is_encoder_decoder = true;
         let decoder_start_id = Some(2);
 
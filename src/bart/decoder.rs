@@ -25,6 +25,9 @@ use crate::{
 use std::borrow::{Borrow, BorrowMut};
 use tch::{nn, Tensor};
 
+/// Cached keys and values of the self-attention and cross-attention of a decoder layer
+pub(crate) type DecoderLayerState = (Option<LayerState>, Option<LayerState>);
+
 pub struct DecoderLayer {
     self_attention: BartAttention,
     encoder_attention: BartAttention,
@@ -59,6 +62,7 @@ impl DecoderLayer {
             true,
             output_attention,
         );
+        //        Cross-attention weights are always returned, the decoder only collects them when requested
         let encoder_attention = BartAttention::new(
             p / "encoder_attn",
             config.d_model,
@@ -66,7 +70,7 @@ impl DecoderLayer {
             config.attention_dropout,
             true,
             true,
-            output_attention,
+            true,
         );
         let self_attention_layer_norm = nn::layer_norm(
             p / "self_attn_layer_norm",
@@ -124,24 +128,21 @@ impl DecoderLayer {
         decoder_attention_mask: Option<&Tensor>,
         layer_states: (Option<LayerState>, Option<LayerState>),
         train: bool,
-    ) -> (
-        Tensor,
-        Option<Tensor>,
-        (Option<LayerState>, Option<LayerState>),
-    ) {
+    ) -> (Tensor, Option<Tensor>, Option<Tensor>, DecoderLayerState) {
         let (output, attention_weights, new_self_layer_states) =
             self.self_attention
                 .forward_t(x, None, decoder_attention_mask, layer_states.0, train);
         let output: Tensor = output.apply_t(&self.dropout, train) + x;
         let output = output.apply(&self.self_attention_layer_norm);
 
-        let (output1, _, new_encoder_layer_states) = self.encoder_attention.forward_t(
-            &output,
-            Some(encoder_hidden_states),
-            encoder_attention_mask,
-            layer_states.1,
-            train,
-        );
+        let (output1, cross_attention_weights, new_encoder_layer_states) =
+            self.encoder_attention.forward_t(
+                &output,
+                Some(encoder_hidden_states),
+                encoder_attention_mask,
+                layer_states.1,
+                train,
+            );
         let output1: Tensor = output1.apply_t(&self.dropout, train) + output;
         let output1 = output1.apply(&self.encoder_attention_layer_norm);
         let output2 = (self.activation.get_fn())(&output1.apply(&self.fc1));
@@ -153,6 +154,7 @@ impl DecoderLayer {
         (
             output2.apply(&self.final_layer_norm),
             attention_weights,
+            cross_attention_weights,
             (new_self_layer_states, new_encoder_layer_states),
         )
     }
@@ -251,6 +253,7 @@ impl BartDecoder {
         embeddings: &nn::Embedding,
         old_layer_states: Option<Vec<(Option<LayerState>, Option<LayerState>)>>,
         train: bool,
+        output_cross_attentions: bool,
     ) -> BartDecoderOutput {
         let past_key_values_length = if let Some(old_layer_states_values) = &old_layer_states {
             if let Some(old_value_state) = &old_layer_states_values[0].0 {
@@ -307,7 +310,14 @@ impl BartDecoder {
                 None
             };
 
+        let mut all_cross_attentions: Option<Vec<Tensor>> =
+            if self.output_attentions || output_cross_attentions {
+                Some(Vec::with_capacity(self.layers.len()))
+            } else {
+                None
+            };
         let mut attention_weights: Option<Tensor>;
+        let mut cross_attention_weights: Option<Tensor>;
 
         for (layer_idx, layer) in self.layers.iter().enumerate() {
             let layer_state = match &next_decoder_cache {
@@ -324,14 +334,18 @@ impl BartDecoder {
             );
             hidden_state = temp.0;
             attention_weights = temp.1;
+            cross_attention_weights = temp.2;
             if let Some(hidden_states) = all_hidden_states.borrow_mut() {
                 hidden_states.push(hidden_state.as_ref().copy());
             };
             if let Some(attentions) = all_attentions.borrow_mut() {
                 attentions.push(attention_weights.as_ref().unwrap().copy());
             };
+            if let Some(cross_attentions) = all_cross_attentions.borrow_mut() {
+                cross_attentions.push(cross_attention_weights.as_ref().unwrap().copy());
+            };
             if let Some(value) = &mut next_decoder_cache {
-                value[layer_idx] = temp.2
+                value[layer_idx] = temp.3
             };
         }
 
@@ -341,6 +355,7 @@ impl BartDecoder {
             next_decoder_cache,
             all_hidden_states,
             all_attentions,
+            all_cross_attentions,
         }
     }
 }
@@ -357,4 +372,6 @@ pub struct BartDecoderOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Cross-attention weights (attention of the decoder over the encoder hidden states) for all intermediate layers
+    pub all_cross_attentions: Option<Vec<Tensor>>,
 }
@@ -72,5 +72,5 @@ pub use bart_model::{
 
 pub(crate) use attention::BartAttention;
 pub(crate) use bart_model::{_expand_mask, _prepare_decoder_attention_mask};
-pub(crate) use decoder::BartDecoderOutput;
+pub(crate) use decoder::{BartDecoderOutput, DecoderLayerState};
 pub(crate) use encoder::BartEncoderOutput;
@@ -605,7 +605,7 @@ impl BertForMaskedLM {
 /// - `bert`: Base BertModel
 /// - `classifier`: BERT linear layer for classification
 pub struct BertForSequenceClassification {
-    bert: BertModel<BertEmbeddings>,
+    pub(crate) bert: BertModel<BertEmbeddings>,
     dropout: Dropout,
     classifier: nn::Linear,
 }
@@ -0,0 +1,92 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Weight initialization of untrained models
+//! The model constructors build all sub-modules with the default initialization of the layers, expecting pretrained
+//! weights to be loaded in the variable store afterwards. To train a model from scratch, the weights can instead be
+//! initialized following the model configuration (e.g. `init_std` or `initializer_range`):
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::initialization::{initialize_weights, WeightInitialization};
+//! use rust_bert::pegasus::{PegasusConfig, PegasusForConditionalGeneration};
+//! use rust_bert::Config;
+//! use tch::{nn, Device};
+//!
+//! let var_store = nn::VarStore::new(Device::Cpu);
+//! let config = PegasusConfig::from_file("path/to/config.json");
+//! let _model = PegasusForConditionalGeneration::new(&var_store.root(), &config);
+//! initialize_weights(
+//!     &var_store,
+//!     WeightInitialization::Normal {
+//!         std: config.init_std,
+//!     },
+//! );
+//! # Ok(())
+//! # }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use tch::nn::VarStore;
+use tch::no_grad;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// # Initialization scheme for the weight matrices of a model
+pub enum WeightInitialization {
+    /// Normal distribution with a mean of 0 and the given standard deviation (initialization of the Transformers library)
+    Normal { std: f64 },
+    /// Xavier (Glorot) uniform initialization, scaled by the fan-in and fan-out of the weights
+    XavierUniform,
+    /// Kaiming (He) uniform initialization for ReLU-like activations, scaled by the fan-in of the weights
+    KaimingUniform,
+}
+
+/// Initializes the trainable variables of a variable store in place:
+/// - biases (variables with a name ending with `bias`) are set to 0,
+/// - weight matrices (variables with 2 dimensions or more, e.g. linear layers and embeddings) follow the initialization scheme,
+/// - other weights (one-dimensional, e.g. layer normalization) are set to 1.
+///
+/// Frozen variables are left unchanged.
+///
+/// # Arguments
+///
+/// * `var_store` - `VarStore` holding the variables of the model to initialize
+/// * `initialization` - `WeightInitialization` scheme for the weight matrices
+pub fn initialize_weights(var_store: &VarStore, initialization: WeightInitialization) {
+    no_grad(|| {
+        for (name, mut variable) in var_store.variables() {
+            if !variable.requires_grad() {
+                continue;
+            }
+            let size = variable.size();
+            if name.ends_with("bias") {
+                let _ = variable.zero_();
+            } else if size.len() >= 2 {
+                let receptive_field_size: i64 = size[2..].iter().product();
+                let fan_in = (size[1] * receptive_field_size) as f64;
+                let fan_out = (size[0] * receptive_field_size) as f64;
+                let _ = match initialization {
+                    WeightInitialization::Normal { std } => variable.normal_(0.0, std),
+                    WeightInitialization::XavierUniform => {
+                        let bound = (6.0 / (fan_in + fan_out)).sqrt();
+                        variable.uniform_(-bound, bound)
+                    }
+                    WeightInitialization::KaimingUniform => {
+                        let bound = (6.0 / fan_in).sqrt();
+                        variable.uniform_(-bound, bound)
+                    }
+                };
+            } else {
+                let _ = variable.fill_(1.0);
+            }
+        }
+    });
+}
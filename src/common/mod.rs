@@ -2,7 +2,9 @@ pub(crate) mod activations;
 pub mod config;
 pub(crate) mod dropout;
 pub mod error;
+pub mod initialization;
 pub(crate) mod linear;
+pub mod quantization;
 pub mod resources;
 pub(crate) mod summary;
 
@@ -0,0 +1,253 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Loading of GPTQ-quantized checkpoints
+//! Loads checkpoints in which the weights of the linear layers are quantized with GPTQ: for a linear layer `name`,
+//! the checkpoint contains the packed integer weights `name.qweight` (int32, `bits` values per element packed along
+//! the input dimension), the packed zero-points `name.qzeros` (int32, packed along the output dimension) and the
+//! per-group scales `name.scales`, optionally with the group index of each input feature `name.g_idx` (act-order).
+//! All other variables are stored in floating point as in a regular checkpoint.
+//!
+//! The quantized weights are dequantized when the checkpoint is loaded (`W = (q - (z + 1)) * s`, following the GPTQ
+//! packing convention) and the models run their regular floating point matmul. This reduces the size of the
+//! checkpoints to download and store, but not the device memory used at inference time.
+//! The dequantized weights follow the layout of the target layers: (*out_features*, *in_features*) for `nn::Linear`
+//! layers and (*in_features*, *out_features*) for the `GPTConv1D` layers of GPT-2 and OpenAI GPT (`c_attn`, `c_proj`
+//! and `c_fc`).
+//!
+//! The checkpoint must be converted to the Torch format expected by this library (see the utility scripts in `./utils`),
+//! and the quantization parameters are read from the `quantize_config.json` file provided with GPTQ checkpoints.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::gpt2::{GPT2LMHeadModel, Gpt2Config};
+//! use rust_bert::quantization::{load_quantized_weights, QuantizationConfig};
+//! use rust_bert::resources::{LocalResource, Resource};
+//! use rust_bert::Config;
+//! use std::path::PathBuf;
+//! use tch::{nn, Device};
+//!
+//! let var_store = nn::VarStore::new(Device::Cpu);
+//! let config = Gpt2Config::from_file("path/to/config.json");
+//! let _model = GPT2LMHeadModel::new(&var_store.root(), &config);
+//! let quantization_config = QuantizationConfig::from_file("path/to/quantize_config.json");
+//! let weights_resource = Resource::Local(LocalResource {
+//!     local_path: PathBuf::from("path/to/rust_model_gptq.ot"),
+//! });
+//! load_quantized_weights(&var_store, &weights_resource, &quantization_config)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::common::resources::Resource;
+use crate::Config;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tch::kind::Kind::{Float, Int64};
+use tch::nn::VarStore;
+use tch::{no_grad, Tensor};
+
+/// Bit-widths supported for the quantized weights
+const SUPPORTED_BITS: [i64; 3] = [2, 4, 8];
+
+/// Names of the `GPTConv1D` layers (GPT-2 and OpenAI GPT), storing their weight as (*in_features*, *out_features*).
+/// Other quantized layers are `nn::Linear` layers, storing their weight as (*out_features*, *in_features*)
+const CONV1D_LAYER_NAMES: [&str; 3] = ["c_attn", "c_proj", "c_fc"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # Quantization parameters of a GPTQ checkpoint
+/// Read from the `quantize_config.json` file of the checkpoint (other fields of the file are ignored).
+pub struct QuantizationConfig {
+    /// Bit-width of the quantized weights (2, 4 or 8)
+    pub bits: i64,
+    /// Number of input features sharing a scale and zero-point. `-1` indicates a single group per output feature
+    pub group_size: i64,
+}
+
+impl Config<QuantizationConfig> for QuantizationConfig {}
+
+/// Loads a GPTQ-quantized checkpoint into a variable store, dequantizing the quantized linear layers weights.
+/// The bit-width and group size are validated against the shapes of the packed tensors of the checkpoint, and
+/// all variables are validated before any of them is loaded: the variable store is left unchanged on error.
+///
+/// # Arguments
+///
+/// * `var_store` - `VarStore` of the model to load the weights into
+/// * `weights_resource` - `Resource` pointing to the quantized checkpoint
+/// * `quantization_config` - `QuantizationConfig` with the quantization parameters of the checkpoint
+pub fn load_quantized_weights(
+    var_store: &VarStore,
+    weights_resource: &Resource,
+    quantization_config: &QuantizationConfig,
+) -> Result<(), RustBertError> {
+    let bits = quantization_config.bits;
+    if !SUPPORTED_BITS.contains(&bits) {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "Unsupported quantization bit-width {}, expected one of {:?}",
+            bits, SUPPORTED_BITS
+        )));
+    }
+    if (quantization_config.group_size == 0) || (quantization_config.group_size < -1) {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "Invalid quantization group size {}",
+            quantization_config.group_size
+        )));
+    }
+
+    let weights_path = weights_resource.get_local_path()?;
+    let mut checkpoint = Tensor::load_multi_with_device(&weights_path, var_store.device())?
+        .into_iter()
+        .collect::<HashMap<String, Tensor>>();
+
+    let variables = var_store.variables();
+    let mut new_weights = HashMap::with_capacity(variables.len());
+    for (name, variable) in variables.iter() {
+        let new_weight = match checkpoint.remove(name) {
+            Some(weight) => weight,
+            None => match name.strip_suffix(".weight") {
+                Some(layer_name) if checkpoint.contains_key(&format!("{}.qweight", layer_name)) => {
+                    let weight =
+                        dequantize_layer(&mut checkpoint, layer_name, quantization_config)?;
+                    if is_conv1d_layer(layer_name) {
+                        weight.contiguous()
+                    } else {
+                        weight.transpose(0, 1).contiguous()
+                    }
+                }
+                _ => {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "Variable {} not found in weights file {:?}",
+                        name, weights_path
+                    )));
+                }
+            },
+        };
+        if new_weight.size() != variable.size() {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Shape mismatch for variable {}: expected {:?}, got {:?}",
+                name,
+                variable.size(),
+                new_weight.size()
+            )));
+        }
+        new_weights.insert(name.clone(), new_weight);
+    }
+    no_grad(|| {
+        for (name, mut variable) in variables {
+            variable.copy_(&new_weights[&name]);
+        }
+    });
+    Ok(())
+}
+
+fn is_conv1d_layer(layer_name: &str) -> bool {
+    let module_name = layer_name.rsplit('.').next().unwrap_or(layer_name);
+    CONV1D_LAYER_NAMES.contains(&module_name)
+}
+
+/// Unpacks the `bits`-wide values of a packed int32 tensor along its last dimension (least significant bits first)
+fn unpack(packed: &Tensor, bits: i64) -> Tensor {
+    let values_per_element = 32 / bits;
+    //        Packed values are stored in signed int32: map them back to their unsigned representation
+    let packed = packed.to_kind(Int64).remainder(1i64 << 32);
+    let unpacked = (0..values_per_element)
+        .map(|index| {
+            packed
+                .floor_divide1(1i64 << (bits * index))
+                .remainder(1i64 << bits)
+        })
+        .collect::<Vec<Tensor>>();
+    let mut size = packed.size();
+    let last_dim = size.pop().unwrap();
+    size.push(last_dim * values_per_element);
+    Tensor::stack(&unpacked, -1).view(size.as_slice())
+}
+
+/// Dequantizes the weight of a linear layer, returned with shape (*in_features*, *out_features*)
+fn dequantize_layer(
+    checkpoint: &mut HashMap<String, Tensor>,
+    layer_name: &str,
+    quantization_config: &QuantizationConfig,
+) -> Result<Tensor, RustBertError> {
+    let bits = quantization_config.bits;
+    let values_per_element = 32 / bits;
+    let mut get_tensor = |suffix: &str| {
+        checkpoint
+            .remove(&format!("{}.{}", layer_name, suffix))
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(format!(
+                    "Quantized layer {} is missing its {} tensor",
+                    layer_name, suffix
+                ))
+            })
+    };
+    let qweight = get_tensor("qweight")?;
+    let qzeros = get_tensor("qzeros")?;
+    let scales = get_tensor("scales")?.to_kind(Float);
+    let g_idx = get_tensor("g_idx").ok();
+
+    let (in_features, out_features) = match (qweight.size().as_slice(), scales.size().as_slice()) {
+        (&[packed_in_features, out_features], &[_, scales_out_features])
+            if out_features == scales_out_features =>
+        {
+            (packed_in_features * values_per_element, out_features)
+        }
+        _ => {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Invalid shapes for quantized layer {}: qweight {:?}, scales {:?}",
+                layer_name,
+                qweight.size(),
+                scales.size()
+            )));
+        }
+    };
+    let group_size = match quantization_config.group_size {
+        -1 => in_features,
+        group_size => group_size,
+    };
+    if in_features % group_size != 0 {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "Group size {} does not divide the {} input features of quantized layer {}",
+            group_size, in_features, layer_name
+        )));
+    }
+    let num_groups = in_features / group_size;
+    if scales.size()[0] != num_groups {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "Quantized layer {} has {} scale groups, expected {} for a group size of {}",
+            layer_name,
+            scales.size()[0],
+            num_groups,
+            group_size
+        )));
+    }
+    if qzeros.size() != [num_groups, out_features / values_per_element] {
+        return Err(RustBertError::InvalidConfigurationError(format!(
+            "Invalid zero-points shape {:?} for quantized layer {} with {}-bit weights",
+            qzeros.size(),
+            layer_name,
+            bits
+        )));
+    }
+
+    let group_index = match g_idx {
+        Some(g_idx) => g_idx.to_kind(Int64),
+        None => Tensor::arange(in_features, (Int64, qweight.device())).floor_divide1(group_size),
+    };
+    let weight = unpack(&qweight.transpose(0, 1), bits)
+        .transpose(0, 1)
+        .to_kind(Float);
+    let zeros = unpack(&qzeros, bits).to_kind(Float) + 1;
+    let weight =
+        (weight - zeros.index_select(0, &group_index)) * scales.index_select(0, &group_index);
+    Ok(weight)
+}
@@ -238,7 +238,7 @@ impl DistilBertModel {
 /// - `pre_classifier`: DistilBERT linear layer for classification
 /// - `classifier`: DistilBERT linear layer for classification
 pub struct DistilBertModelClassifier {
-    distil_bert_model: DistilBertModel,
+    pub(crate) distil_bert_model: DistilBertModel,
     pre_classifier: nn::Linear,
     classifier: nn::Linear,
     dropout: Dropout,
@@ -731,6 +731,7 @@ impl GPT2Generator {
         let pad_token_id = Some(tokenizer.convert_tokens_to_ids(&[Gpt2Vocab::eos_value()])[0]);
         let is_encoder_decoder = false;
         let vocab_size = config.vocab_size;
+        generate_config.validate_corpus_penalty(vocab_size)?;
         let decoder_start_id = None;
 
         Ok(GPT2Generator {
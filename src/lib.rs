@@ -86,5 +86,7 @@ pub mod t5;
 pub mod xlnet;
 
 pub use common::error::RustBertError;
+pub use common::initialization;
+pub use common::quantization;
 pub use common::resources;
 pub use common::{Activation, Config};
@@ -557,6 +557,7 @@ impl MarianForConditionalGeneration {
     ///   - `cache` - `(Option<Tensor>, Option<Vec<&LayerState, &LayerState>>)` of length *n_layer* containing the encoder padding mask and past keys and values for both the self attention and the encoder cross attention of each layer of the decoder.
     ///   - `all_decoder_hidden_states` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
     ///   - `all_decoder_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
+    ///   - `all_cross_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *num_heads*, *target_sequence_length*, *source_sequence_length*)
     ///
     /// # Example
     ///
@@ -823,6 +824,7 @@ impl MarianGenerator {
         let pad_token_id = Some(tokenizer.convert_tokens_to_ids(&[MarianVocab::pad_value()])[0]);
 
         let vocab_size = config.vocab_size;
+        generate_config.validate_corpus_penalty(vocab_size)?;
         let is_encoder_decoder = true;
         let decoder_start_id =
             Some(tokenizer.convert_tokens_to_ids(&[MarianVocab::pad_value()])[0]);
@@ -643,7 +643,7 @@ impl MobileBertForMaskedLM {
 /// - `dropout`: Dropout layer before the last linear layer
 /// - `classifier`: linear layer mapping from hidden to the number of classes to predict
 pub struct MobileBertForSequenceClassification {
-    mobilebert: MobileBertModel,
+    pub(crate) mobilebert: MobileBertModel,
     dropout: Dropout,
     classifier: nn::Linear,
 }
@@ -553,6 +553,7 @@ impl OpenAIGenerator {
         let pad_token_id = None;
         let is_encoder_decoder = false;
         let vocab_size = config.vocab_size;
+        generate_config.validate_corpus_penalty(vocab_size)?;
         let decoder_start_id = None;
 
         Ok(OpenAIGenerator {
@@ -10,7 +10,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::bart::{BartDecoderOutput, _expand_mask, _prepare_decoder_attention_mask};
+use crate::bart::{
+    BartDecoderOutput, DecoderLayerState, _expand_mask, _prepare_decoder_attention_mask,
+};
 use crate::common::activations::TensorFunction;
 use crate::common::dropout::Dropout;
 use crate::pegasus::attention::{LayerState, PegasusAttention};
@@ -54,6 +56,7 @@ impl DecoderLayer {
             true,
             output_attention,
         );
+        //        Cross-attention weights are always returned, the decoder only collects them when requested
         let encoder_attention = PegasusAttention::new(
             p / "encoder_attn",
             config.d_model,
@@ -61,7 +64,7 @@ impl DecoderLayer {
             config.attention_dropout,
             true,
             true,
-            output_attention,
+            true,
         );
         let self_attention_layer_norm = nn::layer_norm(
             p / "self_attn_layer_norm",
@@ -119,11 +122,7 @@ impl DecoderLayer {
         decoder_attention_mask: Option<&Tensor>,
         layer_states: (Option<LayerState>, Option<LayerState>),
         train: bool,
-    ) -> (
-        Tensor,
-        Option<Tensor>,
-        (Option<LayerState>, Option<LayerState>),
-    ) {
+    ) -> (Tensor, Option<Tensor>, Option<Tensor>, DecoderLayerState) {
         let output = x.apply(&self.self_attention_layer_norm);
 
         let (output, attention_weights, new_self_layer_states) = self.self_attention.forward_t(
@@ -136,13 +135,14 @@ impl DecoderLayer {
         let output: Tensor = output.apply_t(&self.dropout, train) + x;
 
         let output1 = output.apply(&self.encoder_attention_layer_norm);
-        let (output1, _, new_encoder_layer_states) = self.encoder_attention.forward_t(
-            &output1,
-            Some(encoder_hidden_states),
-            encoder_attention_mask,
-            layer_states.1,
-            train,
-        );
+        let (output1, cross_attention_weights, new_encoder_layer_states) =
+            self.encoder_attention.forward_t(
+                &output1,
+                Some(encoder_hidden_states),
+                encoder_attention_mask,
+                layer_states.1,
+                train,
+            );
         let output1: Tensor = output1.apply_t(&self.dropout, train) + output;
 
         let output2 = output1.apply(&self.final_layer_norm);
@@ -155,6 +155,7 @@ impl DecoderLayer {
         (
             output2,
             attention_weights,
+            cross_attention_weights,
             (new_self_layer_states, new_encoder_layer_states),
         )
     }
@@ -233,6 +234,7 @@ impl PegasusDecoder {
         embeddings: &nn::Embedding,
         old_layer_states: Option<Vec<(Option<LayerState>, Option<LayerState>)>>,
         train: bool,
+        output_cross_attentions: bool,
     ) -> PegasusDecoderOutput {
         let past_key_values_length = if let Some(old_layer_states_values) = &old_layer_states {
             if let Some(old_value_state) = &old_layer_states_values[0].0 {
@@ -284,7 +286,14 @@ impl PegasusDecoder {
                 None
             };
 
+        let mut all_cross_attentions: Option<Vec<Tensor>> =
+            if self.output_attentions || output_cross_attentions {
+                Some(Vec::with_capacity(self.layers.len()))
+            } else {
+                None
+            };
         let mut attention_weights: Option<Tensor>;
+        let mut cross_attention_weights: Option<Tensor>;
 
         for (layer_idx, layer) in self.layers.iter().enumerate() {
             let layer_state = match &next_decoder_cache {
@@ -301,14 +310,18 @@ impl PegasusDecoder {
             );
             hidden_state = temp.0;
             attention_weights = temp.1;
+            cross_attention_weights = temp.2;
             if let Some(hidden_states) = all_hidden_states.borrow_mut() {
                 hidden_states.push(hidden_state.as_ref().copy());
             };
             if let Some(attentions) = all_attentions.borrow_mut() {
                 attentions.push(attention_weights.as_ref().unwrap().copy());
             };
+            if let Some(cross_attentions) = all_cross_attentions.borrow_mut() {
+                cross_attentions.push(cross_attention_weights.as_ref().unwrap().copy());
+            };
             if let Some(value) = &mut next_decoder_cache {
-                value[layer_idx] = temp.2
+                value[layer_idx] = temp.3
             };
         }
 
@@ -320,6 +333,7 @@ impl PegasusDecoder {
             next_decoder_cache,
             all_hidden_states,
             all_attentions,
+            all_cross_attentions,
         }
     }
 }
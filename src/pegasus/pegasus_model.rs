@@ -171,6 +171,7 @@ impl PegasusModel {
     ///   - `all_encoder_attentions` - `Option<Vec<Tensor>>` of length *num_encoder_layers* with shape (*batch size*, *source_sequence_length*, *hidden_size*)
     ///   - `all_decoder_hidden_states` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
     ///   - `all_decoder_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
+    ///   - `all_cross_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *num_heads*, *target_sequence_length*, *source_sequence_length*)
     ///
     /// # Example
     ///
@@ -248,6 +249,7 @@ impl PegasusModel {
             &self.embeddings,
             layer_states,
             train,
+            false,
         );
         PegasusModelOutput {
             decoder_output: decoder_output.hidden_state,
@@ -255,6 +257,7 @@ impl PegasusModel {
             cache: decoder_output.next_decoder_cache,
             all_decoder_hidden_states: decoder_output.all_hidden_states,
             all_decoder_attentions: decoder_output.all_attentions,
+            all_cross_attentions: decoder_output.all_cross_attentions,
             all_encoder_hidden_states,
             all_encoder_attentions,
         }
@@ -342,6 +345,7 @@ impl PegasusForConditionalGeneration {
     ///   - `all_encoder_attentions` - `Option<Vec<Tensor>>` of length *num_encoder_layers* with shape (*batch size*, *source_sequence_length*, *hidden_size*)
     ///   - `all_decoder_hidden_states` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
     ///   - `all_decoder_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *target_sequence_length*, *hidden_size*)
+    ///   - `all_cross_attentions` - `Option<Vec<Tensor>>` of length *num_decoder_layers* with shape (*batch size*, *num_heads*, *target_sequence_length*, *source_sequence_length*)
     ///
     /// # Example
     ///
@@ -428,6 +432,30 @@ impl PegasusForConditionalGeneration {
             )
             .hidden_state
     }
+
+    /// Returns the cross-attention weights of the decoder layers for teacher-forced decoder inputs, whether or not
+    /// the model was configured to output attentions. Attentions are only collected for this forward pass.
+    pub(crate) fn forward_cross_attentions(
+        &self,
+        input_ids: &Tensor,
+        decoder_input_ids: &Tensor,
+    ) -> Vec<Tensor> {
+        let encoder_output = self.encode(input_ids, None);
+        self.base_model
+            .decoder
+            .forward_t(
+                decoder_input_ids,
+                &encoder_output,
+                None,
+                None,
+                &self.base_model.embeddings,
+                None,
+                false,
+                true,
+            )
+            .all_cross_attentions
+            .unwrap_or_default()
+    }
 }
 
 impl LMHeadModel for PegasusForConditionalGeneration {
@@ -655,6 +683,7 @@ impl PegasusConditionalGenerator {
         });
         let pad_token_id = Some(config.pad_token_id.unwrap_or(0));
         let vocab_size = config.vocab_size;
+        generate_config.validate_corpus_penalty(vocab_size)?;
         let is_encoder_decoder = true;
         let decoder_start_id = Some(0);
 
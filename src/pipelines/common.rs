@@ -20,6 +20,7 @@ use crate::albert::AlbertConfig;
 use crate::bart::BartConfig;
 use crate::bert::BertConfig;
 use crate::common::error::RustBertError;
+use crate::common::resources::Resource;
 use crate::distilbert::DistilBertConfig;
 use crate::electra::ElectraConfig;
 use crate::gpt2::Gpt2Config;
@@ -40,10 +41,14 @@ use rust_tokenizers::vocab::{
     AlbertVocab, BertVocab, Gpt2Vocab, MarianVocab, OpenAiGptVocab, PegasusVocab, ProphetNetVocab,
     ReformerVocab, RobertaVocab, T5Vocab, Vocab, XLMRobertaVocab, XLNetVocab,
 };
-use rust_tokenizers::{TokenIdsWithOffsets, TokenizedInput, TokensWithOffsets};
+use rust_tokenizers::{Mask, TokenIdsWithOffsets, TokenizedInput, TokensWithOffsets};
 use serde::{Deserialize, Serialize};
+use std::cmp::{max, min};
 use std::collections::HashMap;
+use std::ops::Range;
 use std::path::Path;
+use tch::nn::VarStore;
+use tch::{no_grad, Kind, Tensor};
 
 #[derive(Clone, Copy, Serialize, Deserialize, Debug)]
 /// # Identifies the type of model
@@ -127,6 +132,28 @@ pub enum TokenizerOption {
     Pegasus(PegasusTokenizer),
 }
 
+#[derive(Debug, Clone, PartialEq)]
+/// # Special token ids of a tokenizer
+/// Ids are `None` if the tokenizer does not define the corresponding special token.
+pub struct SpecialTokens {
+    /// Beginning of sequence token id
+    pub bos: Option<i64>,
+    /// End of sequence token id
+    pub eos: Option<i64>,
+    /// Padding token id
+    pub pad: Option<i64>,
+    /// Unknown token id
+    pub unk: Option<i64>,
+    /// Separator token id
+    pub sep: Option<i64>,
+    /// Classification token id
+    pub cls: Option<i64>,
+    /// Mask token id
+    pub mask: Option<i64>,
+    /// Other special tokens of the vocabulary (e.g. `<eod>` for XLNet) with their ids, sorted by id
+    pub additional: Vec<(String, i64)>,
+}
+
 impl ConfigOption {
     /// Interface method to load a configuration from file
     pub fn from_file<P: AsRef<Path>>(model_type: ModelType, path: P) -> Self {
@@ -290,20 +317,11 @@ impl TokenizerOption {
                 }
                 TokenizerOption::XLMRoberta(XLMRobertaTokenizer::from_file(vocab_path, lower_case)?)
             }
-            ModelType::Albert => {
-                if strip_accents.is_some() {
-                    return Err(RustBertError::InvalidConfigurationError(format!(
-                        "Optional input `strip_accents` set to value {} but cannot be used by {:?}",
-                        strip_accents.unwrap(),
-                        model_type
-                    )));
-                }
-                TokenizerOption::Albert(AlbertTokenizer::from_file(
-                    vocab_path,
-                    lower_case,
-                    strip_accents.unwrap_or(lower_case),
-                )?)
-            }
+            ModelType::Albert => TokenizerOption::Albert(AlbertTokenizer::from_file(
+                vocab_path,
+                lower_case,
+                strip_accents.unwrap_or(lower_case),
+            )?),
             ModelType::XLNet => {
                 if add_prefix_space.is_some() {
                     return Err(RustBertError::InvalidConfigurationError(
@@ -314,7 +332,7 @@ impl TokenizerOption {
                 TokenizerOption::XLNet(XLNetTokenizer::from_file(
                     vocab_path,
                     lower_case,
-                    strip_accents.unwrap(),
+                    strip_accents.unwrap_or(lower_case),
                 )?)
             }
             ModelType::Reformer => {
@@ -325,23 +343,40 @@ impl TokenizerOption {
                                 model_type)));
                 }
                 if strip_accents.is_some() {
-                    return Err(RustBertError::InvalidConfigurationError(
-                        format!("Optional input `add_prefix_space` set to value {} but cannot be used by {:?}",
-                                add_prefix_space.unwrap(),
-                                model_type)));
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "Optional input `strip_accents` set to value {} but cannot be used by {:?}",
+                        strip_accents.unwrap(),
+                        model_type
+                    )));
                 }
                 TokenizerOption::Reformer(ReformerTokenizer::from_file(vocab_path, lower_case)?)
             }
-            ModelType::GPT2 => TokenizerOption::GPT2(Gpt2Tokenizer::from_file(
-                vocab_path,
-                merges_path.expect("No merges specified!"),
-                lower_case,
-            )?),
-            ModelType::OpenAiGpt => TokenizerOption::OpenAiGpt(OpenAiGptTokenizer::from_file(
-                vocab_path,
-                merges_path.expect("No merges specified!"),
-                lower_case,
-            )?),
+            ModelType::GPT2 => {
+                if let Some(strip_accents) = strip_accents {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "Optional input `strip_accents` set to value {} but cannot be used by {:?}",
+                        strip_accents, model_type
+                    )));
+                }
+                TokenizerOption::GPT2(Gpt2Tokenizer::from_file(
+                    vocab_path,
+                    merges_path.expect("No merges specified!"),
+                    lower_case,
+                )?)
+            }
+            ModelType::OpenAiGpt => {
+                if let Some(strip_accents) = strip_accents {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "Optional input `strip_accents` set to value {} but cannot be used by {:?}",
+                        strip_accents, model_type
+                    )));
+                }
+                TokenizerOption::OpenAiGpt(OpenAiGptTokenizer::from_file(
+                    vocab_path,
+                    merges_path.expect("No merges specified!"),
+                    lower_case,
+                )?)
+            }
             ModelType::ProphetNet => {
                 if add_prefix_space.is_some() {
                     return Err(RustBertError::InvalidConfigurationError(
@@ -741,6 +776,104 @@ impl TokenizerOption {
         }
     }
 
+    /// Decodes a sequence of token ids following a `DetokenizationProfile`
+    ///
+    /// # Arguments
+    ///
+    /// * `token_ids` - `Vec<i64>` token ids to decode
+    /// * `skip_special_tokens` - `bool` flag indicating if special tokens should be excluded from the output
+    /// * `profile` - `DetokenizationProfile` controlling the merging and clean-up of the tokens
+    ///
+    /// # Returns
+    ///
+    /// * `String` decoded text
+    pub fn decode_with_profile(
+        &self,
+        token_ids: Vec<i64>,
+        skip_special_tokens: bool,
+        profile: DetokenizationProfile,
+    ) -> String {
+        match profile {
+            DetokenizationProfile::Natural => self.decode(token_ids, skip_special_tokens, true),
+            DetokenizationProfile::Code => self.decode(token_ids, skip_special_tokens, false),
+            DetokenizationProfile::Raw => match *self {
+                Self::Bert(ref tokenizer) => {
+                    tokenizer.decode_to_vec(token_ids, skip_special_tokens)
+                }
+                Self::Roberta(ref tokenizer) => {
+                    tokenizer.decode_to_vec(token_ids, skip_special_tokens)
+                }
+                Self::Marian(ref tokenizer) => {
+                    tokenizer.decode_to_vec(token_ids, skip_special_tokens)
+                }
+                Self::T5(ref tokenizer) => tokenizer.decode_to_vec(token_ids, skip_special_tokens),
+                Self::XLMRoberta(ref tokenizer) => {
+                    tokenizer.decode_to_vec(token_ids, skip_special_tokens)
+                }
+                Self::Albert(ref tokenizer) => {
+                    tokenizer.decode_to_vec(token_ids, skip_special_tokens)
+                }
+                Self::XLNet(ref tokenizer) => {
+                    tokenizer.decode_to_vec(token_ids, skip_special_tokens)
+                }
+                Self::GPT2(ref tokenizer) => {
+                    tokenizer.decode_to_vec(token_ids, skip_special_tokens)
+                }
+                Self::OpenAiGpt(ref tokenizer) => {
+                    tokenizer.decode_to_vec(token_ids, skip_special_tokens)
+                }
+                Self::Reformer(ref tokenizer) => {
+                    tokenizer.decode_to_vec(token_ids, skip_special_tokens)
+                }
+                Self::ProphetNet(ref tokenizer) => {
+                    tokenizer.decode_to_vec(token_ids, skip_special_tokens)
+                }
+                Self::Pegasus(ref tokenizer) => {
+                    tokenizer.decode_to_vec(token_ids, skip_special_tokens)
+                }
+            }
+            .concat(),
+        }
+    }
+
+    /// Decodes a sequence of token ids following a `DetokenizationProfile`, returning the byte offsets of each token in
+    /// the decoded text. The offsets are obtained by decoding the successive prefixes of the sequence and tracking their
+    /// common prefix with the full decoded text, so that merged tokens and removed markers are accounted for (with a cost
+    /// quadratic in the sequence length). Tokens that do not add any text (e.g. skipped special tokens or the first bytes
+    /// of a multi-byte character) are given an empty range, the bytes of a character spanning several tokens being
+    /// assigned to the token completing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `token_ids` - `&[i64]` token ids to decode
+    /// * `skip_special_tokens` - `bool` flag indicating if special tokens should be excluded from the output
+    /// * `profile` - `DetokenizationProfile` controlling the merging and clean-up of the tokens
+    ///
+    /// # Returns
+    ///
+    /// * `(String, Vec<(usize, usize)>)` decoded text and byte offsets (start, end) of each token in the decoded text
+    pub fn decode_with_offsets(
+        &self,
+        token_ids: &[i64],
+        skip_special_tokens: bool,
+        profile: DetokenizationProfile,
+    ) -> (String, Vec<(usize, usize)>) {
+        let text = self.decode_with_profile(token_ids.to_vec(), skip_special_tokens, profile);
+        let mut offsets = Vec::with_capacity(token_ids.len());
+        let mut position = 0;
+        for prefix_length in 1..=token_ids.len() {
+            let prefix = self.decode_with_profile(
+                token_ids[..prefix_length].to_vec(),
+                skip_special_tokens,
+                profile,
+            );
+            let end = max(position, common_prefix_length(&prefix, &text));
+            offsets.push((position, end));
+            position = end;
+        }
+        (text, offsets)
+    }
+
     /// Interface method to build input with special tokens
     pub fn build_input_with_special_tokens(
         &self,
@@ -809,6 +942,68 @@ impl TokenizerOption {
         }
     }
 
+    /// Returns the number of special tokens added to a single sequence (e.g. 2 for `[CLS]` and `[SEP]` with BERT)
+    pub fn num_special_tokens(&self) -> usize {
+        self.build_input_with_special_tokens(
+            TokenIdsWithOffsets {
+                ids: vec![],
+                offsets: vec![],
+                reference_offsets: vec![],
+                masks: vec![],
+            },
+            None,
+        )
+        .token_ids
+        .len()
+    }
+
+    /// Encodes a list of texts, keeping the first `head` and the last `tail` content tokens of the texts longer than
+    /// `head + tail` tokens. The offsets of the kept tokens refer to the original text: they jump over the dropped
+    /// middle part of the text between the last head token and the first tail token.
+    ///
+    /// # Arguments
+    ///
+    /// * `text_list` - `&[&str]` texts to encode
+    /// * `head` - number of content tokens kept from the start of the texts
+    /// * `tail` - number of content tokens kept from the end of the texts
+    ///
+    /// # Returns
+    /// * `Vec<TokenizedInput>` encoded texts with special tokens, with at most `head + tail` content tokens
+    pub fn encode_list_head_tail(
+        &self,
+        text_list: &[&str],
+        head: usize,
+        tail: usize,
+    ) -> Vec<TokenizedInput> {
+        text_list
+            .iter()
+            .map(|text| {
+                let tokens = self.tokenize_with_offsets(text);
+                let mut token_ids_with_offsets = TokenIdsWithOffsets {
+                    ids: self.convert_tokens_to_ids(&tokens.tokens),
+                    offsets: tokens.offsets,
+                    reference_offsets: tokens.reference_offsets,
+                    masks: tokens.masks,
+                };
+                let num_truncated_tokens =
+                    token_ids_with_offsets.ids.len().saturating_sub(head + tail);
+                if num_truncated_tokens > 0 {
+                    let middle = head..head + num_truncated_tokens;
+                    token_ids_with_offsets.ids.drain(middle.clone());
+                    token_ids_with_offsets.offsets.drain(middle.clone());
+                    token_ids_with_offsets
+                        .reference_offsets
+                        .drain(middle.clone());
+                    token_ids_with_offsets.masks.drain(middle);
+                }
+                let mut tokenized_input =
+                    self.build_input_with_special_tokens(token_ids_with_offsets, None);
+                tokenized_input.num_truncated_tokens = num_truncated_tokens;
+                tokenized_input
+            })
+            .collect()
+    }
+
     /// Interface method to convert tokens to ids
     pub fn convert_tokens_to_ids<S, ST>(&self, tokens: S) -> Vec<i64>
     where
@@ -831,6 +1026,258 @@ impl TokenizerOption {
         }
     }
 
+    /// Returns the raw vocabulary entry for a token id, without any detokenization (e.g. sub-word or
+    /// space markers are kept). Returns `None` if the id is not in the vocabulary.
+    pub fn id_to_token(&self, id: i64) -> Option<String> {
+        match *self {
+            Self::Bert(ref tokenizer) => {
+                vocab_id_to_token(MultiThreadedTokenizer::vocab(tokenizer), id)
+            }
+            Self::Roberta(ref tokenizer) => {
+                vocab_id_to_token(MultiThreadedTokenizer::vocab(tokenizer), id)
+            }
+            Self::Marian(ref tokenizer) => {
+                vocab_id_to_token(MultiThreadedTokenizer::vocab(tokenizer), id)
+            }
+            Self::T5(ref tokenizer) => {
+                vocab_id_to_token(MultiThreadedTokenizer::vocab(tokenizer), id)
+            }
+            Self::XLMRoberta(ref tokenizer) => {
+                vocab_id_to_token(MultiThreadedTokenizer::vocab(tokenizer), id)
+            }
+            Self::Albert(ref tokenizer) => {
+                vocab_id_to_token(MultiThreadedTokenizer::vocab(tokenizer), id)
+            }
+            Self::XLNet(ref tokenizer) => {
+                vocab_id_to_token(MultiThreadedTokenizer::vocab(tokenizer), id)
+            }
+            Self::GPT2(ref tokenizer) => {
+                vocab_id_to_token(MultiThreadedTokenizer::vocab(tokenizer), id)
+            }
+            Self::OpenAiGpt(ref tokenizer) => {
+                vocab_id_to_token(MultiThreadedTokenizer::vocab(tokenizer), id)
+            }
+            Self::Reformer(ref tokenizer) => {
+                vocab_id_to_token(MultiThreadedTokenizer::vocab(tokenizer), id)
+            }
+            Self::ProphetNet(ref tokenizer) => {
+                vocab_id_to_token(MultiThreadedTokenizer::vocab(tokenizer), id)
+            }
+            Self::Pegasus(ref tokenizer) => {
+                vocab_id_to_token(MultiThreadedTokenizer::vocab(tokenizer), id)
+            }
+        }
+    }
+
+    /// Returns the raw vocabulary entries for a list of token ids, without merging them into text as
+    /// done by `decode`. Ids that are not in the vocabulary are mapped to the unknown token.
+    pub fn ids_to_tokens(&self, ids: &[i64]) -> Vec<String> {
+        match *self {
+            Self::Bert(ref tokenizer) => {
+                let vocab = MultiThreadedTokenizer::vocab(tokenizer);
+                ids.iter().map(|id| vocab.id_to_token(id)).collect()
+            }
+            Self::Roberta(ref tokenizer) => {
+                let vocab = MultiThreadedTokenizer::vocab(tokenizer);
+                ids.iter().map(|id| vocab.id_to_token(id)).collect()
+            }
+            Self::Marian(ref tokenizer) => {
+                let vocab = MultiThreadedTokenizer::vocab(tokenizer);
+                ids.iter().map(|id| vocab.id_to_token(id)).collect()
+            }
+            Self::T5(ref tokenizer) => {
+                let vocab = MultiThreadedTokenizer::vocab(tokenizer);
+                ids.iter().map(|id| vocab.id_to_token(id)).collect()
+            }
+            Self::XLMRoberta(ref tokenizer) => {
+                let vocab = MultiThreadedTokenizer::vocab(tokenizer);
+                ids.iter().map(|id| vocab.id_to_token(id)).collect()
+            }
+            Self::Albert(ref tokenizer) => {
+                let vocab = MultiThreadedTokenizer::vocab(tokenizer);
+                ids.iter().map(|id| vocab.id_to_token(id)).collect()
+            }
+            Self::XLNet(ref tokenizer) => {
+                let vocab = MultiThreadedTokenizer::vocab(tokenizer);
+                ids.iter().map(|id| vocab.id_to_token(id)).collect()
+            }
+            Self::GPT2(ref tokenizer) => {
+                let vocab = MultiThreadedTokenizer::vocab(tokenizer);
+                ids.iter().map(|id| vocab.id_to_token(id)).collect()
+            }
+            Self::OpenAiGpt(ref tokenizer) => {
+                let vocab = MultiThreadedTokenizer::vocab(tokenizer);
+                ids.iter().map(|id| vocab.id_to_token(id)).collect()
+            }
+            Self::Reformer(ref tokenizer) => {
+                let vocab = MultiThreadedTokenizer::vocab(tokenizer);
+                ids.iter().map(|id| vocab.id_to_token(id)).collect()
+            }
+            Self::ProphetNet(ref tokenizer) => {
+                let vocab = MultiThreadedTokenizer::vocab(tokenizer);
+                ids.iter().map(|id| vocab.id_to_token(id)).collect()
+            }
+            Self::Pegasus(ref tokenizer) => {
+                let vocab = MultiThreadedTokenizer::vocab(tokenizer);
+                ids.iter().map(|id| vocab.id_to_token(id)).collect()
+            }
+        }
+    }
+
+    /// Returns the ids of the special tokens of the tokenizer (e.g. to implement custom logits processors)
+    ///
+    /// # Returns
+    ///
+    /// * `SpecialTokens` ids of the named special tokens (if defined by the tokenizer) and of the additional special tokens
+    pub fn special_tokens(&self) -> SpecialTokens {
+        match *self {
+            Self::Bert(ref tokenizer) => vocab_special_tokens(
+                MultiThreadedTokenizer::vocab(tokenizer),
+                [
+                    None,
+                    None,
+                    Some(BertVocab::pad_value()),
+                    Some(BertVocab::sep_value()),
+                    Some(BertVocab::cls_value()),
+                    Some(BertVocab::mask_value()),
+                ],
+            ),
+            Self::Roberta(ref tokenizer) => vocab_special_tokens(
+                MultiThreadedTokenizer::vocab(tokenizer),
+                [
+                    Some(RobertaVocab::bos_value()),
+                    Some(RobertaVocab::eos_value()),
+                    Some(RobertaVocab::pad_value()),
+                    Some(RobertaVocab::sep_value()),
+                    Some(RobertaVocab::cls_value()),
+                    Some(RobertaVocab::mask_value()),
+                ],
+            ),
+            Self::XLMRoberta(ref tokenizer) => vocab_special_tokens(
+                MultiThreadedTokenizer::vocab(tokenizer),
+                [
+                    Some(XLMRobertaVocab::bos_value()),
+                    Some(XLMRobertaVocab::eos_value()),
+                    Some(XLMRobertaVocab::pad_value()),
+                    Some(XLMRobertaVocab::sep_value()),
+                    Some(XLMRobertaVocab::cls_value()),
+                    Some(XLMRobertaVocab::mask_value()),
+                ],
+            ),
+            Self::Marian(ref tokenizer) => vocab_special_tokens(
+                MultiThreadedTokenizer::vocab(tokenizer),
+                [
+                    None,
+                    Some(MarianVocab::eos_value()),
+                    Some(MarianVocab::pad_value()),
+                    None,
+                    None,
+                    None,
+                ],
+            ),
+            Self::T5(ref tokenizer) => vocab_special_tokens(
+                MultiThreadedTokenizer::vocab(tokenizer),
+                [
+                    None,
+                    Some(T5Vocab::eos_value()),
+                    Some(T5Vocab::pad_value()),
+                    None,
+                    None,
+                    None,
+                ],
+            ),
+            Self::Albert(ref tokenizer) => vocab_special_tokens(
+                MultiThreadedTokenizer::vocab(tokenizer),
+                [
+                    Some(AlbertVocab::bos_value()),
+                    Some(AlbertVocab::eos_value()),
+                    Some(AlbertVocab::pad_value()),
+                    Some(AlbertVocab::sep_value()),
+                    Some(AlbertVocab::cls_value()),
+                    Some(AlbertVocab::mask_value()),
+                ],
+            ),
+            Self::XLNet(ref tokenizer) => vocab_special_tokens(
+                MultiThreadedTokenizer::vocab(tokenizer),
+                [
+                    Some(XLNetVocab::bos_value()),
+                    Some(XLNetVocab::eos_value()),
+                    Some(XLNetVocab::pad_value()),
+                    Some(XLNetVocab::sep_value()),
+                    Some(XLNetVocab::cls_value()),
+                    Some(XLNetVocab::mask_value()),
+                ],
+            ),
+            Self::GPT2(ref tokenizer) => vocab_special_tokens(
+                MultiThreadedTokenizer::vocab(tokenizer),
+                [
+                    Some(Gpt2Vocab::bos_value()),
+                    Some(Gpt2Vocab::eos_value()),
+                    None,
+                    None,
+                    None,
+                    None,
+                ],
+            ),
+            Self::OpenAiGpt(ref tokenizer) => vocab_special_tokens(
+                MultiThreadedTokenizer::vocab(tokenizer),
+                [None, None, None, None, None, None],
+            ),
+            Self::Reformer(ref tokenizer) => vocab_special_tokens(
+                MultiThreadedTokenizer::vocab(tokenizer),
+                [
+                    None,
+                    Some(ReformerVocab::eos_value()),
+                    None,
+                    None,
+                    None,
+                    None,
+                ],
+            ),
+            Self::ProphetNet(ref tokenizer) => vocab_special_tokens(
+                MultiThreadedTokenizer::vocab(tokenizer),
+                [
+                    None,
+                    None,
+                    Some(ProphetNetVocab::pad_value()),
+                    Some(ProphetNetVocab::sep_value()),
+                    Some(ProphetNetVocab::cls_value()),
+                    Some(ProphetNetVocab::mask_value()),
+                ],
+            ),
+            Self::Pegasus(ref tokenizer) => vocab_special_tokens(
+                MultiThreadedTokenizer::vocab(tokenizer),
+                [
+                    None,
+                    Some(PegasusVocab::eos_value()),
+                    Some(PegasusVocab::pad_value()),
+                    None,
+                    None,
+                    Some(PegasusVocab::mask_value()),
+                ],
+            ),
+        }
+    }
+
+    /// Returns the size of the vocabulary, i.e. the largest token id + 1. Note that the output layer of a model may be larger
+    /// than the vocabulary if its embeddings are padded (e.g. for Marian models).
+    pub fn vocab_size(&self) -> usize {
+        match *self {
+            Self::Bert(ref tokenizer) => vocab_size(MultiThreadedTokenizer::vocab(tokenizer)),
+            Self::Roberta(ref tokenizer) => vocab_size(MultiThreadedTokenizer::vocab(tokenizer)),
+            Self::XLMRoberta(ref tokenizer) => vocab_size(MultiThreadedTokenizer::vocab(tokenizer)),
+            Self::Marian(ref tokenizer) => vocab_size(MultiThreadedTokenizer::vocab(tokenizer)),
+            Self::T5(ref tokenizer) => vocab_size(MultiThreadedTokenizer::vocab(tokenizer)),
+            Self::Albert(ref tokenizer) => vocab_size(MultiThreadedTokenizer::vocab(tokenizer)),
+            Self::XLNet(ref tokenizer) => vocab_size(MultiThreadedTokenizer::vocab(tokenizer)),
+            Self::GPT2(ref tokenizer) => vocab_size(MultiThreadedTokenizer::vocab(tokenizer)),
+            Self::OpenAiGpt(ref tokenizer) => vocab_size(MultiThreadedTokenizer::vocab(tokenizer)),
+            Self::Reformer(ref tokenizer) => vocab_size(MultiThreadedTokenizer::vocab(tokenizer)),
+            Self::ProphetNet(ref tokenizer) => vocab_size(MultiThreadedTokenizer::vocab(tokenizer)),
+            Self::Pegasus(ref tokenizer) => vocab_size(MultiThreadedTokenizer::vocab(tokenizer)),
+        }
+    }
+
     /// Interface method
     pub fn get_unk_id(&self) -> i64 {
         match *self {
@@ -996,3 +1443,320 @@ impl TokenizerOption {
         }
     }
 }
+
+/// Loads the weights from a resource into the variables of an existing `VarStore`, copying the new
+/// values in place. The new weights must match the model architecture: the shapes of all variables are
+/// validated before any of them is modified, so that a mismatching weights file leaves the `VarStore`
+/// unchanged. Used by the `reload_weights` methods of the pipelines, which do not reload the tokenizer
+/// or the model configuration.
+pub(crate) fn reload_var_store(
+    var_store: &VarStore,
+    weights_resource: &Resource,
+) -> Result<(), RustBertError> {
+    let weights_path = weights_resource.get_local_path()?;
+    let new_weights = Tensor::load_multi_with_device(&weights_path, var_store.device())?
+        .into_iter()
+        .collect::<HashMap<String, Tensor>>();
+    let variables = var_store.variables();
+    for (name, variable) in variables.iter() {
+        match new_weights.get(name) {
+            Some(new_weight) if new_weight.size() == variable.size() => {}
+            Some(new_weight) => {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Shape mismatch for variable {}: expected {:?}, got {:?}",
+                    name,
+                    variable.size(),
+                    new_weight.size()
+                )));
+            }
+            None => {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Variable {} not found in weights file {:?}",
+                    name, weights_path
+                )));
+            }
+        }
+    }
+    no_grad(|| {
+        for (name, mut variable) in variables {
+            variable.copy_(&new_weights[&name]);
+        }
+    });
+    Ok(())
+}
+
+/// Checks that a tensor produced by an inference forward pass is not part of an autograd graph, i.e. that the
+/// forward pass was run under `no_grad`. Only active in debug builds with the `grad-checks` feature enabled.
+pub(crate) fn assert_no_grad(tensor: &Tensor) {
+    if cfg!(feature = "grad-checks") {
+        debug_assert!(
+            !tensor.requires_grad(),
+            "Inference output requires grad: the forward pass must be run under `no_grad`"
+        );
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+/// # Side on which the sequences of a batch are padded to the same length
+pub enum PaddingSide {
+    /// Padding tokens are prepended to the sequences (e.g. prompts for generation)
+    Left,
+    /// Padding tokens are appended to the sequences
+    Right,
+}
+
+/// Builds the attention mask of a batch of padded token ids, masking the padding tokens. Only the contiguous run of
+/// padding tokens on the padded side of each sequence is masked, so that tokens sharing the id of the padding token
+/// within the sequences (e.g. EOS tokens for models where the padding and EOS tokens are the same) remain attended to.
+/// An explicitly provided attention mask should be preferred when available.
+///
+/// # Arguments
+///
+/// * `input_ids` - `Tensor` of shape (*batch size*, *sequence length*) with the padded token ids
+/// * `pad_token_id` - `Option<i64>` padding token id. If `None`, all positions are attended to
+/// * `padding_side` - `PaddingSide` side on which the sequences are padded
+///
+/// # Returns
+/// * `Tensor` of shape (*batch size*, *sequence length*) with 1 for the positions to attend to and 0 for the padding
+pub fn build_padding_attention_mask(
+    input_ids: &Tensor,
+    pad_token_id: Option<i64>,
+    padding_side: PaddingSide,
+) -> Tensor {
+    match pad_token_id {
+        Some(pad_token_id) => {
+            let is_pad = input_ids.eq(pad_token_id).to_kind(Kind::Int64);
+            let padding = match padding_side {
+                PaddingSide::Left => is_pad.cumprod(1, Kind::Int64),
+                PaddingSide::Right => is_pad.flip(&[1]).cumprod(1, Kind::Int64).flip(&[1]),
+            };
+            padding.eq(0).to_kind(Kind::Int64)
+        }
+        None => input_ids.ones_like().to_kind(Kind::Int64),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+/// # Detokenization profile used to convert generated token ids back to text
+pub enum DetokenizationProfile {
+    /// Natural language output: sub-tokens are merged and the tokenization spaces cleaned up (e.g. no space before punctuation)
+    Natural,
+    /// Code output: sub-tokens are merged (for byte-level BPE, space markers are mapped back to the original bytes)
+    /// without applying any natural language spacing rule, preserving indentation and operators
+    Code,
+    /// Raw output: exact concatenation of the vocabulary entries of the tokens, without any merging or clean-up
+    Raw,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+/// # Truncation of the inputs exceeding the maximum length of a pipeline
+pub enum InputTruncation {
+    /// Keep the first tokens of the input
+    Head,
+    /// Keep the first `head` and the last `tail` content tokens and drop the middle of the input, e.g. for long documents
+    /// where both the introduction and the conclusion matter (Sun et al., 2019). The special tokens of the model are added
+    /// around the kept tokens: a truncated input contains `head + tail` content tokens plus the special tokens of a single
+    /// sequence (e.g. 2 for `[CLS]` and `[SEP]` with BERT).
+    HeadTail { head: usize, tail: usize },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+/// # Boundaries at which long inputs may be split into windows
+pub enum WindowBoundary {
+    /// Windows may start and end at any token position
+    Token,
+    /// Windows start and end at word boundaries, so that no word is split across windows
+    Word,
+    /// Windows start and end at sentence boundaries where possible, falling back to word boundaries
+    Sentence,
+}
+
+/// # Sliding windows over long tokenized inputs
+/// Splits a tokenized input into windows of at most `window_size` tokens, consecutive windows overlapping by
+/// at most `stride` tokens. Window boundaries are snapped according to the `boundary` setting:
+/// - a window ends at the last sentence (or word) boundary that keeps it at least half full,
+/// - the overlap with the previous window starts at the first sentence (or word) boundary among its last `stride` tokens.
+///
+/// Word boundaries are identified from the sub-token masks of the tokenizer, and a sentence ends after a sentence-final
+/// punctuation token followed by a whitespace. A single word longer than a window is split at a token position.
+#[derive(Clone, Copy, Debug)]
+pub struct Windower {
+    /// Maximum number of tokens in a window
+    pub window_size: usize,
+    /// Maximum number of overlapping tokens between consecutive windows
+    pub stride: usize,
+    /// Boundaries at which windows may start and end
+    pub boundary: WindowBoundary,
+}
+
+impl Windower {
+    /// Creates a new `Windower`
+    ///
+    /// # Arguments
+    ///
+    /// * `window_size` - Maximum number of tokens in a window
+    /// * `stride` - Maximum number of overlapping tokens between consecutive windows
+    /// * `boundary` - `WindowBoundary` at which windows may start and end
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::common::{WindowBoundary, Windower};
+    ///
+    /// let windower = Windower::new(256, 128, WindowBoundary::Sentence);
+    /// ```
+    pub fn new(window_size: usize, stride: usize, boundary: WindowBoundary) -> Windower {
+        Windower {
+            window_size,
+            stride,
+            boundary,
+        }
+    }
+
+    /// Splits a tokenized input into windows
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `TokenIdsWithOffsets` tokenized input to split
+    /// * `text` - `&str` original text the input was tokenized from, referenced by the token offsets
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Range<usize>>` token ranges of the windows, in order. Every token is covered by at least one window.
+    pub fn windows(&self, input: &TokenIdsWithOffsets, text: &str) -> Vec<Range<usize>> {
+        let length = input.ids.len();
+        if length == 0 {
+            return vec![];
+        }
+        let window_size = self.window_size.max(1);
+        let word_boundaries = (0..=length)
+            .map(|position| match self.boundary {
+                WindowBoundary::Token => true,
+                WindowBoundary::Word | WindowBoundary::Sentence => {
+                    input.masks.get(position) != Some(&Mask::Continuation)
+                }
+            })
+            .collect::<Vec<bool>>();
+        let sentence_boundaries = match self.boundary {
+            WindowBoundary::Sentence => {
+                let characters = text.chars().collect::<Vec<char>>();
+                (0..=length)
+                    .map(|position| {
+                        position == 0
+                            || position == length
+                            || Self::ends_sentence(input, &characters, position - 1)
+                    })
+                    .collect::<Vec<bool>>()
+            }
+            WindowBoundary::Token | WindowBoundary::Word => word_boundaries.clone(),
+        };
+
+        let mut windows = vec![];
+        let mut start = 0;
+        let mut previous_end = 0;
+        loop {
+            let max_end = min(start + window_size, length);
+            let end = if max_end == length {
+                length
+            } else {
+                let min_end = max(start + window_size / 2, previous_end + 1).min(max_end);
+                (min_end..=max_end)
+                    .rev()
+                    .find(|&position| sentence_boundaries[position])
+                    .or_else(|| {
+                        (previous_end + 1..=max_end)
+                            .rev()
+                            .find(|&position| word_boundaries[position])
+                    })
+                    .unwrap_or(max_end)
+            };
+            windows.push(start..end);
+            if end == length {
+                break;
+            }
+            let min_start = end.saturating_sub(self.stride).max(start + 1);
+            start = (min_start..end)
+                .find(|&position| sentence_boundaries[position])
+                .or_else(|| (min_start..end).find(|&position| word_boundaries[position]))
+                .unwrap_or(min_start);
+            previous_end = end;
+        }
+        windows
+    }
+
+    fn ends_sentence(input: &TokenIdsWithOffsets, characters: &[char], position: usize) -> bool {
+        let offset = match input.offsets[position] {
+            Some(offset) => offset,
+            None => return false,
+        };
+        let token_characters = match characters.get(offset.begin as usize..offset.end as usize) {
+            Some(token_characters) => token_characters,
+            None => return false,
+        };
+        let is_sentence_final = !token_characters.is_empty()
+            && token_characters
+                .iter()
+                .all(|character| ['.', '!', '?', '。', '！', '？'].contains(character));
+        let is_followed_by_whitespace = match input.offsets.get(position + 1) {
+            Some(Some(next_offset)) => next_offset.begin > offset.end,
+            _ => true,
+        };
+        is_sentence_final && is_followed_by_whitespace
+    }
+}
+
+/// Length in bytes of the longest common prefix of two strings, ending at a character boundary
+fn common_prefix_length(text_1: &str, text_2: &str) -> usize {
+    text_1
+        .char_indices()
+        .zip(text_2.chars())
+        .take_while(|((_, character_1), character_2)| character_1 == character_2)
+        .last()
+        .map_or(0, |((position, character), _)| {
+            position + character.len_utf8()
+        })
+}
+
+/// Collects the special token ids of a vocabulary, given the values of its named special tokens (BOS, EOS, padding,
+/// separator, classification and mask), the remaining special values being returned as additional special tokens
+fn vocab_special_tokens<V: Vocab>(vocab: &V, named_values: [Option<&str>; 6]) -> SpecialTokens {
+    let special_values = vocab.special_values();
+    let get_id = |value: Option<&str>| value.and_then(|value| special_values.get(value).copied());
+    let [bos, eos, pad, sep, cls, mask] = named_values;
+    let unk = vocab.get_unknown_value();
+    let mut additional = special_values
+        .iter()
+        .filter(|(value, _)| {
+            (value.as_str() != unk) && !named_values.contains(&Some(value.as_str()))
+        })
+        .map(|(value, id)| (value.clone(), *id))
+        .collect::<Vec<(String, i64)>>();
+    additional.sort_by_key(|(_, id)| *id);
+    SpecialTokens {
+        bos: get_id(bos),
+        eos: get_id(eos),
+        pad: get_id(pad),
+        unk: get_id(Some(unk)),
+        sep: get_id(sep),
+        cls: get_id(cls),
+        mask: get_id(mask),
+        additional,
+    }
+}
+
+fn vocab_size<V: Vocab>(vocab: &V) -> usize {
+    vocab
+        .indices()
+        .keys()
+        .chain(vocab.special_indices().keys())
+        .max()
+        .map_or(0, |max_id| *max_id as usize + 1)
+}
+
+fn vocab_id_to_token<V: Vocab>(vocab: &V, id: i64) -> Option<String> {
+    vocab
+        .special_indices()
+        .get(&id)
+        .or_else(|| vocab.indices().get(&id))
+        .cloned()
+}
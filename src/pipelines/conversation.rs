@@ -49,10 +49,14 @@ use crate::common::resources::{RemoteResource, Resource};
 use crate::gpt2::{
     GPT2Generator, Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources, Gpt2VocabResources,
 };
-use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::common::{DetokenizationProfile, ModelType, TokenizerOption};
+use crate::pipelines::diagnostics::{report_generation_diagnostics, DiagnosticsSink};
 use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
-use crate::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
-use std::collections::HashMap;
+use crate::pipelines::generation_utils::{
+    GenerateConfig, LanguageGenerator, LengthBias, PostProcess, ReasoningBudget,
+    RepetitionPenaltyMode, WarperKind,
+};
+use std::collections::{HashMap, HashSet};
 use tch::{Device, Kind, Tensor};
 use uuid::Uuid;
 
@@ -100,6 +104,64 @@ pub struct ConversationConfig {
     pub num_beam_groups: Option<i64>,
     /// Diversity penalty for diverse beam search. High values will enforce more difference between beam groups (default: 5.5)
     pub diversity_penalty: Option<f64>,
+    /// Corpus-derived additive logits adjustments, keyed by token id. Applied as a static bias on top of the dynamic repetition penalty (default: None)
+    pub corpus_penalty: Option<HashMap<i64, f64>>,
+    /// Reasoning budget capping the number of tokens generated inside a delimited reasoning block before the answer (default: None)
+    pub reasoning_budget: Option<ReasoningBudget>,
+    /// Maximum number of sequences decoded simultaneously, splitting generation into sequential micro-batches to bound peak memory (default: None)
+    pub generation_microbatch_size: Option<i64>,
+    /// EOS probability threshold above which a sequence is ended, even if EOS is not the most likely token (default: None)
+    pub eos_probability_threshold: Option<f64>,
+    /// Soft length control: a bias varying with the sequence length is added to the logits of the EOS tokens at each step, encouraging
+    /// the sequences to end near a target length (see `LengthBias`). `min_length` still prevents EOS before it is reached (default: None)
+    pub length_bias: Option<LengthBias>,
+    /// Form of the repetition penalty applied to previously generated tokens (default: `RepetitionPenaltyMode::Multiplicative`)
+    pub repetition_penalty_mode: RepetitionPenaltyMode,
+    /// Order in which the logits warpers are applied. Warpers omitted from the list are not applied
+    /// (default: `WarperKind::default_order()`, i.e. repetition penalty, temperature, top-k and top-p)
+    pub warper_order: Vec<WarperKind>,
+    /// Number of most likely candidate tokens to report, with their probabilities, for each generated position. The alternatives are
+    /// returned in the `top_k_alternatives` of `generate_indices_with_output` (default: None)
+    pub output_top_k_alternatives: Option<usize>,
+    /// Report the memory footprint (in bytes) of the key/value cache after each decoding step, returned in the `cache_memory` of
+    /// `generate_indices_with_output`. Measuring the cache at each step has a cost and is disabled by default (default: false)
+    pub output_cache_memory: bool,
+    /// Frequency penalty (OpenAI-style): subtracted from the logits of each token, multiplied by the number of times the token was
+    /// already generated. Negative values encourage repetitions (default: 0.0)
+    pub frequency_penalty: f64,
+    /// Presence penalty (OpenAI-style): subtracted from the logits of each token already generated at least once.
+    /// Negative values encourage repetitions (default: 0.0)
+    pub presence_penalty: f64,
+    /// Source copy bias: added to the logits of the tokens present in the encoder input (or prompt for decoder-only models) at each step,
+    /// a soft prior towards copying from the source improving the faithfulness of abstractive summaries. High values push the generation
+    /// towards extraction (default: 0.0)
+    pub source_copy_bias: f64,
+    /// Token healing for decoder-only models: the last token of the prompt is removed and the first generated token is restricted to
+    /// the tokens starting with its surface form, avoiding unnatural continuations of prompts ending mid-word. Prompts of a single token
+    /// are not healed (default: false)
+    pub token_healing: bool,
+    /// Detokenization profile used to convert the generated tokens to text. `DetokenizationProfile::Code` preserves the literal output
+    /// of the model (e.g. indentation and spacing around operators) (default: `DetokenizationProfile::Natural`)
+    pub detokenization_profile: DetokenizationProfile,
+    /// Suffix forced at the end of the generated sequences, before the EOS token (e.g. a closing tag). Hypotheses cannot end before
+    /// emitting the full suffix, and the remaining suffix tokens are forced once the positions left before `max_length` are just enough
+    /// to emit them followed by EOS. A suffix emitted by the model on its own before that point allows the sequence to end (default: None)
+    pub end_suffix_ids: Option<Vec<i64>>,
+    /// Maximum size in bytes of the generated text (excluding the prompt), for outputs subject to downstream length limits. The running
+    /// output is decoded at each step and a sequence ends before the token that would exceed the budget. Trailing tokens decoding to an
+    /// incomplete character are removed, so that multi-byte characters are never split. Decoding at each step is costly: this is meant for
+    /// short outputs. Only supported for greedy decoding and sampling (default: None)
+    pub max_output_bytes: Option<usize>,
+    /// Maximum number of characters of the generated text (excluding the prompt), see `max_output_bytes` (default: None)
+    pub max_output_chars: Option<usize>,
+    /// Number of previous responses of a conversation whose n-grams are blocked from the next response, preventing the model from
+    /// repeating itself across turns. Values higher than 0 turn on this feature (default: 0)
+    pub repetition_blocklist_turns: usize,
+    /// Size of the n-grams of the previous responses blocked from the next response (default: 3)
+    pub repetition_blocklist_ngram_size: usize,
+    /// Post-processing applied to each output string before it is returned (e.g. trimming or capitalization), after detokenization
+    /// and the removal of special tokens (default: None)
+    pub post_process: Option<PostProcess>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
 }
@@ -135,6 +197,26 @@ impl Default for ConversationConfig {
             num_return_sequences: 1,
             num_beam_groups: None,
             diversity_penalty: None,
+            corpus_penalty: None,
+            reasoning_budget: None,
+            generation_microbatch_size: None,
+            eos_probability_threshold: None,
+            length_bias: None,
+            repetition_penalty_mode: RepetitionPenaltyMode::Multiplicative,
+            warper_order: WarperKind::default_order(),
+            output_top_k_alternatives: None,
+            output_cache_memory: false,
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            source_copy_bias: 0.0,
+            token_healing: false,
+            detokenization_profile: DetokenizationProfile::Natural,
+            end_suffix_ids: None,
+            max_output_bytes: None,
+            max_output_chars: None,
+            repetition_blocklist_turns: 0,
+            repetition_blocklist_ngram_size: 3,
+            post_process: None,
             device: Device::cuda_if_available(),
         }
     }
@@ -161,6 +243,23 @@ impl From<ConversationConfig> for GenerateConfig {
             num_return_sequences: config.num_return_sequences,
             num_beam_groups: config.num_beam_groups,
             diversity_penalty: config.diversity_penalty,
+            corpus_penalty: config.corpus_penalty,
+            reasoning_budget: config.reasoning_budget,
+            generation_microbatch_size: config.generation_microbatch_size,
+            eos_probability_threshold: config.eos_probability_threshold,
+            length_bias: config.length_bias,
+            repetition_penalty_mode: config.repetition_penalty_mode,
+            warper_order: config.warper_order,
+            output_top_k_alternatives: config.output_top_k_alternatives,
+            output_cache_memory: config.output_cache_memory,
+            frequency_penalty: config.frequency_penalty,
+            presence_penalty: config.presence_penalty,
+            source_copy_bias: config.source_copy_bias,
+            token_healing: config.token_healing,
+            detokenization_profile: config.detokenization_profile,
+            end_suffix_ids: config.end_suffix_ids,
+            max_output_bytes: config.max_output_bytes,
+            max_output_chars: config.max_output_chars,
             device: config.device,
         }
     }
@@ -702,6 +801,13 @@ impl ConversationOption {
         }
     }
 
+    /// Interface method to access the generation configuration
+    pub(crate) fn get_generate_config(&self) -> &GenerateConfig {
+        match self {
+            Self::GPT2(model_ref) => model_ref.get_config(),
+        }
+    }
+
     /// Returns the `ModelType` for this ConversationOption
     pub fn model_type(&self) -> ModelType {
         match *self {
@@ -721,6 +827,25 @@ impl ConversationOption {
             }
         }
     }
+
+    /// Interface method to generate_from_ids_and_past_with_blocked_ngrams() of the particular models.
+    pub fn generate_from_ids_and_past_with_blocked_ngrams(
+        &self,
+        input_ids: Tensor,
+        attention_mask: Option<Tensor>,
+        blocked_ngrams: &[Vec<Vec<i64>>],
+    ) -> Result<Vec<Vec<i64>>, RustBertError> {
+        match *self {
+            Self::GPT2(ref model) => model.generate_from_ids_and_past_with_blocked_ngrams(
+                input_ids,
+                attention_mask,
+                blocked_ngrams,
+                None,
+                None,
+                None,
+            ),
+        }
+    }
 }
 
 /// # Conversation model
@@ -729,7 +854,12 @@ pub struct ConversationModel {
     model: ConversationOption,
     eos_token_id: i64,
     max_allowed_context_length: i64,
+    detokenization_profile: DetokenizationProfile,
+    repetition_blocklist_turns: usize,
+    repetition_blocklist_ngram_size: usize,
+    post_process: Option<PostProcess>,
     device: Device,
+    diagnostics_sink: Option<DiagnosticsSink>,
 }
 
 impl ConversationModel {
@@ -750,10 +880,19 @@ impl ConversationModel {
     /// # }
     /// ```
     pub fn new(
-        conversation_config: ConversationConfig,
+        mut conversation_config: ConversationConfig,
     ) -> Result<ConversationModel, RustBertError> {
         let max_allowed_length =
             conversation_config.max_length - conversation_config.min_length_for_response;
+        let detokenization_profile = conversation_config.detokenization_profile;
+        let repetition_blocklist_turns = conversation_config.repetition_blocklist_turns;
+        let repetition_blocklist_ngram_size = conversation_config.repetition_blocklist_ngram_size;
+        let post_process = conversation_config.post_process.take();
+        if (repetition_blocklist_turns > 0) && (repetition_blocklist_ngram_size == 0) {
+            return Err(RustBertError::InvalidConfigurationError(
+                "repetition_blocklist_ngram_size must be strictly greater than 0".to_string(),
+            ));
+        }
         let device = conversation_config.device;
         let model = ConversationOption::new(conversation_config)?;
         let eos_token_id = model.get_eos_id()?;
@@ -761,10 +900,44 @@ impl ConversationModel {
             model,
             eos_token_id,
             max_allowed_context_length: max_allowed_length,
+            detokenization_profile,
+            repetition_blocklist_turns,
+            repetition_blocklist_ngram_size,
+            post_process,
             device,
+            diagnostics_sink: None,
         })
     }
 
+    /// Interface method to access tokenizer
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        self.model.get_tokenizer()
+    }
+
+    /// Sets the sink receiving the diagnostics of the pipeline (generation settings that are not applied, reported at
+    /// each call), or removes it with `None`
+    ///
+    /// # Arguments
+    ///
+    /// * `diagnostics_sink` - `Option<DiagnosticsSink>` callback called with each diagnostic reported by the pipeline
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::conversation::ConversationModel;
+    ///
+    /// let mut conversation_model = ConversationModel::new(Default::default())?;
+    /// conversation_model.set_diagnostics_sink(Some(Box::new(|diagnostic| {
+    ///     eprintln!("{:?}: {}", diagnostic.kind, diagnostic.message)
+    /// })));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_diagnostics_sink(&mut self, diagnostics_sink: Option<DiagnosticsSink>) {
+        self.diagnostics_sink = diagnostics_sink;
+    }
+
     /// Perform a multi-turn conversation based on user input
     ///
     /// # Arguments
@@ -793,6 +966,7 @@ impl ConversationModel {
         &self,
         conversation_manager: &'a mut ConversationManager,
     ) -> HashMap<&'a Uuid, &'a str> {
+        report_generation_diagnostics(&self.diagnostics_sink, self.model.get_generate_config());
         let (active_uuid, active_conversations) = conversation_manager.get_active_conversations();
         if !active_uuid.is_empty() {
             let texts = active_conversations
@@ -809,9 +983,22 @@ impl ConversationModel {
             let (input_tensor, attention_mask) =
                 self.concat_input_history(prompt_ids.as_ref(), history);
             let input_length = *input_tensor.size().last().unwrap() as usize;
-            let mut generated = self
-                .model
-                .generate_from_ids_and_past(input_tensor, Some(attention_mask));
+            let mut generated = if self.repetition_blocklist_turns > 0 {
+                let blocked_ngrams = active_conversations
+                    .iter()
+                    .map(|conversation| self.get_repetition_blocklist(conversation))
+                    .collect::<Vec<Vec<Vec<i64>>>>();
+                self.model
+                    .generate_from_ids_and_past_with_blocked_ngrams(
+                        input_tensor,
+                        Some(attention_mask),
+                        &blocked_ngrams,
+                    )
+                    .expect("A blocklist is built for each active conversation")
+            } else {
+                self.model
+                    .generate_from_ids_and_past(input_tensor, Some(attention_mask))
+            };
             let removed_padding_quantities = self.clean_padding_indices(&mut generated);
 
             let mut output = HashMap::with_capacity(active_uuid.len());
@@ -826,13 +1013,16 @@ impl ConversationModel {
                 .zip(removed_padding_quantities.into_iter())
             {
                 let generated_response = &generated_sequence[input_length - removed_padding.0..];
-                conversation
-                    .generated_responses
-                    .push(self.model.get_tokenizer().decode(
-                        generated_response.to_vec(),
-                        true,
-                        true,
-                    ));
+                let response = self.model.get_tokenizer().decode_with_profile(
+                    generated_response.to_vec(),
+                    true,
+                    self.detokenization_profile,
+                );
+                let response = match &self.post_process {
+                    Some(post_process) => post_process(response),
+                    None => response,
+                };
+                conversation.generated_responses.push(response);
                 conversation.history.push(conversation_promp_ids);
                 conversation.history.push(generated_response.to_vec());
                 conversation.mark_processed();
@@ -844,6 +1034,34 @@ impl ConversationModel {
         }
     }
 
+    /// N-grams of the last `repetition_blocklist_turns` responses of a conversation, blocked from its next response
+    fn get_repetition_blocklist(&self, conversation: &Conversation) -> Vec<Vec<i64>> {
+        //    The history alternates between the user inputs and the generated responses ids
+        let response_ids = conversation
+            .history
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .collect::<Vec<&Vec<i64>>>();
+        let mut seen_ngrams = HashSet::new();
+        let mut blocked_ngrams = vec![];
+        for response_ids in &response_ids[response_ids
+            .len()
+            .saturating_sub(self.repetition_blocklist_turns)..]
+        {
+            let response_ids = match response_ids.split_last() {
+                Some((&last_id, response_ids)) if last_id == self.eos_token_id => response_ids,
+                _ => response_ids.as_slice(),
+            };
+            for ngram in response_ids.windows(self.repetition_blocklist_ngram_size) {
+                if seen_ngrams.insert(ngram) {
+                    blocked_ngrams.push(ngram.to_vec());
+                }
+            }
+        }
+        blocked_ngrams
+    }
+
     fn clean_padding_indices(&self, model_output: &mut Vec<Vec<i64>>) -> Vec<(usize, usize)> {
         // In case inputs are sent as batch, this cleans the padding indices in the history for shorter outputs
         let pad_token = self
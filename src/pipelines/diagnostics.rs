@@ -0,0 +1,196 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Structured diagnostics of the pipelines
+//! Some behaviours of the pipelines silently affect their predictions, for example the truncation of long inputs,
+//! the mapping of out-of-vocabulary words to the unknown token or generation settings that are not applied. Pipelines supporting diagnostics report these events
+//! to an optional sink (a callback registered on the pipeline), allowing callers to collect and surface them.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::diagnostics::Diagnostic;
+//! use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+//! use std::sync::{Arc, Mutex};
+//!
+//! let mut sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+//! let diagnostics: Arc<Mutex<Vec<Diagnostic>>> = Arc::new(Mutex::new(vec![]));
+//! let sink = diagnostics.clone();
+//! sequence_classification_model.set_diagnostics_sink(Some(Box::new(move |diagnostic| {
+//!     sink.lock().unwrap().push(diagnostic)
+//! })));
+//! let output = sequence_classification_model.predict(&["A very long review..."]);
+//! for diagnostic in diagnostics.lock().unwrap().iter() {
+//!     println!("{:?}: {}", diagnostic.kind, diagnostic.message);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::pipelines::generation_utils::GenerateConfig;
+use rust_tokenizers::TokenizedInput;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+/// # Kind of event reported by a pipeline
+pub enum DiagnosticKind {
+    /// Tokens of the input were dropped to fit the maximum input length of the pipeline
+    Truncation,
+    /// Parts of the input were mapped to the unknown token of the vocabulary
+    UnknownTokens,
+    /// A setting of the pipeline configuration does not have the expected effect (e.g. a warper omitted from the
+    /// `warper_order` of a generation configuration)
+    ConfigurationWarning,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+/// # Structured event reported by a pipeline
+pub struct Diagnostic {
+    /// Kind of the event
+    pub kind: DiagnosticKind,
+    /// Human-readable description of the event
+    pub message: String,
+    /// Position of the affected input in the batch passed to the pipeline (0 for `ConfigurationWarning`, affecting all inputs)
+    pub input_index: usize,
+    /// Number of tokens affected (dropped tokens for `Truncation`, unknown tokens for `UnknownTokens`, 0 for `ConfigurationWarning`)
+    pub num_tokens: usize,
+}
+
+/// Callback receiving the diagnostics reported by a pipeline
+pub type DiagnosticsSink = Box<dyn Fn(Diagnostic) + Send + Sync>;
+
+/// Builds the truncation and unknown tokens diagnostics of a batch of tokenized inputs
+pub(crate) fn tokenization_diagnostics(
+    tokenized_input: &[TokenizedInput],
+    unk_id: i64,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    for (input_index, input) in tokenized_input.iter().enumerate() {
+        if input.num_truncated_tokens > 0 {
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::Truncation,
+                message: format!(
+                    "Input {} was truncated to {} tokens, dropping {} tokens",
+                    input_index,
+                    input.token_ids.len(),
+                    input.num_truncated_tokens
+                ),
+                input_index,
+                num_tokens: input.num_truncated_tokens,
+            });
+        }
+        let num_unknown_tokens = input
+            .token_ids
+            .iter()
+            .filter(|&&token_id| token_id == unk_id)
+            .count();
+        if num_unknown_tokens > 0 {
+            diagnostics.push(Diagnostic {
+                kind: DiagnosticKind::UnknownTokens,
+                message: format!(
+                    "Input {} contains {} unknown tokens",
+                    input_index, num_unknown_tokens
+                ),
+                input_index,
+                num_tokens: num_unknown_tokens,
+            });
+        }
+    }
+    diagnostics
+}
+
+/// Reports the diagnostics of a batch of tokenized inputs to a sink, if any
+pub(crate) fn report_tokenization_diagnostics(
+    sink: &Option<DiagnosticsSink>,
+    tokenized_input: &[TokenizedInput],
+    unk_id: i64,
+) {
+    if let Some(sink) = sink {
+        for diagnostic in tokenization_diagnostics(tokenized_input, unk_id) {
+            sink(diagnostic);
+        }
+    }
+}
+
+/// Reports the warnings of a generation configuration to a sink, if any
+pub(crate) fn report_generation_diagnostics(
+    sink: &Option<DiagnosticsSink>,
+    generate_config: &GenerateConfig,
+) {
+    if let Some(sink) = sink {
+        for message in generate_config.warnings() {
+            sink(Diagnostic {
+                kind: DiagnosticKind::ConfigurationWarning,
+                message,
+                input_index: 0,
+                num_tokens: 0,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pipelines::generation_utils::WarperKind;
+    use std::sync::{Arc, Mutex};
+
+    fn tokenized_input(token_ids: Vec<i64>, num_truncated_tokens: usize) -> TokenizedInput {
+        TokenizedInput {
+            token_offsets: vec![None; token_ids.len()],
+            reference_offsets: vec![vec![]; token_ids.len()],
+            mask: vec![],
+            segment_ids: vec![0; token_ids.len()],
+            special_tokens_mask: vec![0; token_ids.len()],
+            overflowing_tokens: vec![],
+            num_truncated_tokens,
+            token_ids,
+        }
+    }
+
+    #[test]
+    fn truncation_and_unknown_tokens() {
+        let diagnostics = tokenization_diagnostics(
+            &[
+                tokenized_input(vec![101, 7, 8, 102], 0),
+                tokenized_input(vec![101, 100, 8, 100, 102], 12),
+            ],
+            100,
+        );
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::Truncation);
+        assert_eq!(diagnostics[0].input_index, 1);
+        assert_eq!(diagnostics[0].num_tokens, 12);
+        assert_eq!(diagnostics[1].kind, DiagnosticKind::UnknownTokens);
+        assert_eq!(diagnostics[1].input_index, 1);
+        assert_eq!(diagnostics[1].num_tokens, 2);
+    }
+
+    #[test]
+    fn generation_configuration_warnings() {
+        let diagnostics = Arc::new(Mutex::new(vec![]));
+        let collected_diagnostics = diagnostics.clone();
+        let sink: Option<DiagnosticsSink> = Some(Box::new(move |diagnostic| {
+            collected_diagnostics.lock().unwrap().push(diagnostic)
+        }));
+        let generate_config = GenerateConfig {
+            top_k: 10,
+            warper_order: vec![WarperKind::RepetitionPenalty, WarperKind::TopP],
+            ..Default::default()
+        };
+        report_generation_diagnostics(&sink, &generate_config);
+        report_generation_diagnostics(&sink, &GenerateConfig::default());
+
+        let diagnostics = diagnostics.lock().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::ConfigurationWarning);
+        assert!(diagnostics[0].message.contains("TopK"));
+    }
+}
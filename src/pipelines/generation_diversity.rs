@@ -0,0 +1,227 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Diversity metrics for generated candidates
+//! Standard diversity metrics computed over a set of candidates returned by a generation call
+//! (e.g. with `num_return_sequences` > 1), to tune decoding parameters such as `temperature` or
+//! `diversity_penalty`:
+//! - distinct-1 and distinct-2: ratio of unique unigrams and bigrams over all the candidates,
+//! - self-BLEU: mean BLEU-4 of each candidate against the other candidates (lower is more diverse),
+//! - mean pairwise cosine similarity of the candidate embeddings, if embeddings are provided.
+//!
+//! Candidates are split on whitespace.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::generation_diversity::generation_diversity;
+//!
+//! let candidates = [
+//!     "The dog ran across the park.",
+//!     "The dog ran across the field.",
+//!     "A cat slept on the warm windowsill.",
+//! ];
+//! let report = generation_diversity(&candidates);
+//! ```
+
+use crate::RustBertError;
+use std::cmp::min;
+use std::collections::{HashMap, HashSet};
+use tch::kind::Kind::Float;
+use tch::Tensor;
+
+/// Highest n-gram order used for the self-BLEU
+const MAX_BLEU_ORDER: usize = 4;
+
+#[derive(Debug, Clone, PartialEq)]
+/// # Diversity metrics of a set of generated candidates
+pub struct DiversityReport {
+    /// Number of distinct unigrams divided by the total number of unigrams over all candidates
+    pub distinct_1: f64,
+    /// Number of distinct bigrams divided by the total number of bigrams over all candidates
+    pub distinct_2: f64,
+    /// Mean smoothed BLEU-4 of each candidate, using the other candidates as references (0 for fewer than 2 candidates)
+    pub self_bleu: f64,
+    /// Mean cosine similarity between the embeddings of all pairs of candidates, if embeddings were provided for at least 2 candidates
+    pub mean_pairwise_cosine: Option<f64>,
+}
+
+/// Computes the distinct-n and self-BLEU diversity metrics of a set of generated candidates
+///
+/// # Arguments
+///
+/// * `candidates` - candidate texts returned by a generation call
+///
+/// # Returns
+/// * `DiversityReport` diversity metrics of the candidates, without the embedding similarity
+pub fn generation_diversity<S: AsRef<str>>(candidates: &[S]) -> DiversityReport {
+    let tokenized = candidates
+        .iter()
+        .map(|candidate| candidate.as_ref().split_whitespace().collect::<Vec<&str>>())
+        .collect::<Vec<Vec<&str>>>();
+
+    let self_bleu = if tokenized.len() < 2 {
+        0f64
+    } else {
+        tokenized
+            .iter()
+            .enumerate()
+            .map(|(candidate_index, hypothesis)| {
+                let references = tokenized
+                    .iter()
+                    .enumerate()
+                    .filter(|(reference_index, _)| *reference_index != candidate_index)
+                    .map(|(_, reference)| reference.as_slice())
+                    .collect::<Vec<&[&str]>>();
+                sentence_bleu(hypothesis, &references)
+            })
+            .sum::<f64>()
+            / tokenized.len() as f64
+    };
+
+    DiversityReport {
+        distinct_1: distinct_n(&tokenized, 1),
+        distinct_2: distinct_n(&tokenized, 2),
+        self_bleu,
+        mean_pairwise_cosine: None,
+    }
+}
+
+/// Computes the diversity metrics of a set of generated candidates, including the mean pairwise cosine similarity
+/// of their embeddings (e.g. the pooled output of an encoder for each candidate)
+///
+/// # Arguments
+///
+/// * `candidates` - candidate texts returned by a generation call
+/// * `embeddings` - `Tensor` of shape (*number of candidates*, *embedding dimension*) with the embeddings of the candidates
+///
+/// # Returns
+/// * `DiversityReport` diversity metrics of the candidates
+pub fn generation_diversity_with_embeddings<S: AsRef<str>>(
+    candidates: &[S],
+    embeddings: &Tensor,
+) -> Result<DiversityReport, RustBertError> {
+    let num_candidates = candidates.len() as i64;
+    if (embeddings.dim() != 2) || (embeddings.size()[0] != num_candidates) {
+        return Err(RustBertError::ValueError(format!(
+            "Expected embeddings of shape ({}, embedding dimension), got {:?}",
+            num_candidates,
+            embeddings.size()
+        )));
+    }
+    let mut report = generation_diversity(candidates);
+    if num_candidates >= 2 {
+        let embeddings = embeddings.to_kind(Float);
+        let embeddings = &embeddings / embeddings.norm2(2, &[-1], true).clamp_min(1e-12);
+        let similarities = embeddings.matmul(&embeddings.transpose(0, 1));
+        let off_diagonal_sum = similarities.sum(Float) - similarities.trace();
+        report.mean_pairwise_cosine = Some(
+            off_diagonal_sum.double_value(&[]) / (num_candidates * (num_candidates - 1)) as f64,
+        );
+    }
+    Ok(report)
+}
+
+fn ngram_counts<'a, 'b>(tokens: &'a [&'b str], order: usize) -> HashMap<&'a [&'b str], usize> {
+    let mut counts = HashMap::new();
+    for ngram in tokens.windows(order) {
+        *counts.entry(ngram).or_insert(0) += 1;
+    }
+    counts
+}
+
+fn distinct_n(tokenized: &[Vec<&str>], order: usize) -> f64 {
+    let mut total = 0usize;
+    let mut distinct = HashSet::new();
+    for tokens in tokenized {
+        for ngram in tokens.windows(order) {
+            total += 1;
+            distinct.insert(ngram);
+        }
+    }
+    if total == 0 {
+        0f64
+    } else {
+        distinct.len() as f64 / total as f64
+    }
+}
+
+/// Sentence-level BLEU-4 with add-one smoothing of the precisions of order above 1 (Lin and Och, 2004)
+fn sentence_bleu(hypothesis: &[&str], references: &[&[&str]]) -> f64 {
+    if hypothesis.is_empty() {
+        return 0f64;
+    }
+    let mut log_precision_sum = 0f64;
+    for order in 1..=MAX_BLEU_ORDER {
+        let mut max_reference_counts: HashMap<&[&str], usize> = HashMap::new();
+        for reference in references {
+            for (ngram, count) in ngram_counts(reference, order) {
+                let max_count = max_reference_counts.entry(ngram).or_insert(0);
+                *max_count = (*max_count).max(count);
+            }
+        }
+        let hypothesis_counts = ngram_counts(hypothesis, order);
+        let total: usize = hypothesis_counts.values().sum();
+        let clipped: usize = hypothesis_counts
+            .iter()
+            .map(|(ngram, count)| min(*count, *max_reference_counts.get(ngram).unwrap_or(&0)))
+            .sum();
+        let precision = if order == 1 {
+            clipped as f64 / total as f64
+        } else {
+            (clipped + 1) as f64 / (total + 1) as f64
+        };
+        if precision == 0f64 {
+            return 0f64;
+        }
+        log_precision_sum += precision.ln();
+    }
+    //        The brevity penalty uses the reference length closest to the hypothesis length (the shortest on ties)
+    let hypothesis_length = hypothesis.len() as f64;
+    let reference_length = references
+        .iter()
+        .map(|reference| reference.len() as f64)
+        .min_by(|a, b| {
+            ((a - hypothesis_length).abs(), *a)
+                .partial_cmp(&((b - hypothesis_length).abs(), *b))
+                .unwrap()
+        })
+        .unwrap_or(hypothesis_length);
+    let brevity_penalty = if hypothesis_length > reference_length {
+        1f64
+    } else {
+        (1f64 - reference_length / hypothesis_length).exp()
+    };
+    brevity_penalty * (log_precision_sum / MAX_BLEU_ORDER as f64).exp()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn distinct_n_ratios() {
+        let report = generation_diversity(&["a b a", "a b c"]);
+        assert!((report.distinct_1 - 3.0 / 6.0).abs() < 1e-9);
+        assert!((report.distinct_2 - 3.0 / 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn self_bleu_bounds() {
+        let identical = generation_diversity(&["the dog ran across the park"; 3]);
+        assert!((identical.self_bleu - 1.0).abs() < 1e-9);
+
+        let disjoint = generation_diversity(&["the dog ran fast", "a cat slept soundly"]);
+        assert_eq!(disjoint.self_bleu, 0.0);
+
+        let single = generation_diversity(&["the dog ran across the park"]);
+        assert_eq!(single.self_bleu, 0.0);
+        assert_eq!(single.mean_pairwise_cosine, None);
+    }
+}
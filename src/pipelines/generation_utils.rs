@@ -50,23 +50,30 @@
 //! # }
 //! ```
 //!
-//! Example output: \
+//! Example output (`text` of the generated outputs, grouped by prompt): \
 //! ```no_run
 //! # let output =
 //! [
-//!     "The dog's owners, however, did not want to be named. According to the lawsuit, the animal's owner, a 29-year",
-//!     "The dog has always been part of the family. \"He was always going to be my dog and he was always looking out for me",
-//!     "The dog has been able to stay in the home for more than three months now. \"It's a very good dog. She's",
-//!     "The cat was discovered earlier this month in the home of a relative of the deceased. The cat\'s owner, who wished to remain anonymous,",
-//!     "The cat was pulled from the street by two-year-old Jazmine.\"I didn't know what to do,\" she said",
-//!     "The cat was attacked by two stray dogs and was taken to a hospital. Two other cats were also injured in the attack and are being treated."
+//!     [
+//!         "The dog's owners, however, did not want to be named. According to the lawsuit, the animal's owner, a 29-year",
+//!         "The dog has always been part of the family. \"He was always going to be my dog and he was always looking out for me",
+//!         "The dog has been able to stay in the home for more than three months now. \"It's a very good dog. She's",
+//!     ],
+//!     [
+//!         "The cat was discovered earlier this month in the home of a relative of the deceased. The cat\'s owner, who wished to remain anonymous,",
+//!         "The cat was pulled from the street by two-year-old Jazmine.\"I didn't know what to do,\" she said",
+//!         "The cat was attacked by two stray dogs and was taken to a hospital. Two other cats were also injured in the attack and are being treated.",
+//!     ],
 //! ]
 //! # ;
 //! ```
 
 use rust_tokenizers::tokenizer::Tokenizer;
 use rust_tokenizers::vocab::Vocab;
-use tch::kind::Kind::Int64;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use tch::kind::Kind::{Double, Int64};
 use tch::{no_grad, Device, Tensor};
 
 use crate::bart::LayerState as BartLayerState;
@@ -75,9 +82,11 @@ use crate::common::resources::{RemoteResource, Resource};
 use crate::gpt2::{
     Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources, Gpt2VocabResources,
 };
-use crate::pipelines::generation_utils::private_generation_utils::{
-    GenerateOptions, PrivateLanguageGenerator,
+use crate::pipelines::common::{
+    assert_no_grad, build_padding_attention_mask, reload_var_store, DetokenizationProfile,
+    PaddingSide,
 };
+use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
 use crate::prophetnet::LayerState as ProphetNetLayerState;
 use crate::reformer::LayerState as ReformerLayerState;
 use crate::t5::LayerState as T5LayerState;
@@ -113,7 +122,8 @@ pub struct GenerateConfig {
     pub top_k: i64,
     /// Top_p value for [Nucleus sampling, Holtzman et al.](http://arxiv.org/abs/1904.09751). Keep top tokens until cumulative probability reaches top_p (default: 0.9)
     pub top_p: f64,
-    /// Repetition penalty (mostly useful for CTRL decoders). Values higher than 1 will penalize tokens that have been already generated. (default: 1.0)
+    /// Repetition penalty (mostly useful for CTRL decoders). Values higher than 1 will penalize tokens that have been already generated.
+    /// The value is interpreted according to the `repetition_penalty_mode` (default: 1.0)
     pub repetition_penalty: f64,
     /// Exponential penalty based on the length of the hypotheses generated (default: 1.0)
     pub length_penalty: f64,
@@ -125,6 +135,66 @@ pub struct GenerateConfig {
     pub num_beam_groups: Option<i64>,
     /// Diversity penalty for diverse beam search. High values will enforce more difference between beam groups (default: 5.5)
     pub diversity_penalty: Option<f64>,
+    /// Corpus-derived additive logits adjustments, keyed by token id. These are added to the logits at every generation step, on top of
+    /// the dynamic repetition penalty, and are intended to steer generation away from tokens over-represented in a corpus (e.g. clichés)
+    /// relative to a reference distribution. A typical choice is `penalty(t) = -alpha * ln(f_corpus(t) / f_reference(t))` for tokens
+    /// with a frequency ratio above 1, where `f_corpus` and `f_reference` are the (smoothed) relative token frequencies in the
+    /// corpus to avoid and in a reference corpus, and `alpha` controls the strength. Negative values penalize a token, positive values
+    /// promote it. Token ids must be lower than the model vocabulary size, or the generator creation fails (default: None)
+    pub corpus_penalty: Option<HashMap<i64, f64>>,
+    /// Reasoning budget for models emitting a reasoning block delimited by special tokens before their answer (e.g. `<think>...</think>`).
+    /// If provided, the number of tokens generated inside the reasoning block is capped and the closing delimiter is forced once the
+    /// budget is exhausted, after which generation continues with the answer (default: None)
+    pub reasoning_budget: Option<ReasoningBudget>,
+    /// Maximum number of sequences decoded simultaneously. If provided, the sequences to generate (batched inputs x `num_return_sequences` x `num_beams`)
+    /// are split into micro-batches decoded one after the other, bounding the peak memory at the cost of additional sequential passes. Beam
+    /// hypotheses of a given input are always kept in the same micro-batch. Deterministic decoding results are unaffected (default: None)
+    pub generation_microbatch_size: Option<i64>,
+    /// EOS probability threshold for confidence-based early stopping. If provided, a sequence is ended as soon as the probability of any EOS
+    /// token exceeds the threshold, even if EOS is not the most likely token. Cannot trigger before `min_length` is reached (default: None)
+    pub eos_probability_threshold: Option<f64>,
+    /// Soft length control: a bias varying with the sequence length is added to the logits of the EOS tokens at each step, encouraging
+    /// the sequences to end near a target length (see `LengthBias`). `min_length` still prevents EOS before it is reached (default: None)
+    pub length_bias: Option<LengthBias>,
+    /// Form of the repetition penalty applied to previously generated tokens (default: `RepetitionPenaltyMode::Multiplicative`)
+    pub repetition_penalty_mode: RepetitionPenaltyMode,
+    /// Order in which the logits warpers are applied. Warpers omitted from the list are not applied
+    /// (default: `WarperKind::default_order()`, i.e. repetition penalty, temperature, top-k and top-p)
+    pub warper_order: Vec<WarperKind>,
+    /// Number of most likely candidate tokens to report, with their probabilities, for each generated position. The alternatives are
+    /// returned in the `top_k_alternatives` of `generate_indices_with_output` (default: None)
+    pub output_top_k_alternatives: Option<usize>,
+    /// Report the memory footprint (in bytes) of the key/value cache after each decoding step, returned in the `cache_memory` of
+    /// `generate_indices_with_output`. Measuring the cache at each step has a cost and is disabled by default (default: false)
+    pub output_cache_memory: bool,
+    /// Frequency penalty (OpenAI-style): subtracted from the logits of each token, multiplied by the number of times the token was
+    /// already generated. Negative values encourage repetitions (default: 0.0)
+    pub frequency_penalty: f64,
+    /// Presence penalty (OpenAI-style): subtracted from the logits of each token already generated at least once.
+    /// Negative values encourage repetitions (default: 0.0)
+    pub presence_penalty: f64,
+    /// Source copy bias: added to the logits of the tokens present in the encoder input (or prompt for decoder-only models) at each step,
+    /// a soft prior towards copying from the source improving the faithfulness of abstractive summaries. High values push the generation
+    /// towards extraction (default: 0.0)
+    pub source_copy_bias: f64,
+    /// Token healing for decoder-only models: the last token of the prompt is removed and the first generated token is restricted to
+    /// the tokens starting with its surface form, avoiding unnatural continuations of prompts ending mid-word. Prompts of a single token
+    /// are not healed (default: false)
+    pub token_healing: bool,
+    /// Detokenization profile used to convert the generated tokens to text. `DetokenizationProfile::Code` preserves the literal output
+    /// of the model (e.g. indentation and spacing around operators) (default: `DetokenizationProfile::Natural`)
+    pub detokenization_profile: DetokenizationProfile,
+    /// Suffix forced at the end of the generated sequences, before the EOS token (e.g. a closing tag). Hypotheses cannot end before
+    /// emitting the full suffix, and the remaining suffix tokens are forced once the positions left before `max_length` are just enough
+    /// to emit them followed by EOS. A suffix emitted by the model on its own before that point allows the sequence to end (default: None)
+    pub end_suffix_ids: Option<Vec<i64>>,
+    /// Maximum size in bytes of the generated text (excluding the prompt), for outputs subject to downstream length limits. The running
+    /// output is decoded at each step and a sequence ends before the token that would exceed the budget. Trailing tokens decoding to an
+    /// incomplete character are removed, so that multi-byte characters are never split. Decoding at each step is costly: this is meant for
+    /// short outputs. Only supported for greedy decoding and sampling (default: None)
+    pub max_output_bytes: Option<usize>,
+    /// Maximum number of characters of the generated text (excluding the prompt), see `max_output_bytes` (default: None)
+    pub max_output_chars: Option<usize>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
 }
@@ -158,22 +228,94 @@ impl Default for GenerateConfig {
             num_return_sequences: 1,
             num_beam_groups: None,
             diversity_penalty: None,
+            corpus_penalty: None,
+            reasoning_budget: None,
+            generation_microbatch_size: None,
+            eos_probability_threshold: None,
+            length_bias: None,
+            repetition_penalty_mode: RepetitionPenaltyMode::Multiplicative,
+            warper_order: WarperKind::default_order(),
+            output_top_k_alternatives: None,
+            output_cache_memory: false,
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            source_copy_bias: 0.0,
+            token_healing: false,
+            detokenization_profile: DetokenizationProfile::Natural,
+            end_suffix_ids: None,
+            max_output_bytes: None,
+            max_output_chars: None,
             device: Device::cuda_if_available(),
         }
     }
 }
 
 impl GenerateConfig {
+    /// Returns the warnings of the configuration: settings that are valid but will not have the expected effect,
+    /// e.g. warpers that are configured but omitted from `warper_order` and will therefore not be applied.
+    ///
+    /// # Returns
+    /// * `Vec<String>` warning messages, empty if the configuration raises no warning
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::generation_utils::{GenerateConfig, WarperKind};
+    ///
+    /// let generate_config = GenerateConfig {
+    ///     top_k: 10,
+    ///     warper_order: vec![WarperKind::RepetitionPenalty, WarperKind::Temperature],
+    ///     ..Default::default()
+    /// };
+    /// for warning in generate_config.warnings() {
+    ///     println!("{}", warning);
+    /// }
+    /// ```
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = vec![];
+        for (warper, is_enabled) in &[
+            (
+                WarperKind::RepetitionPenalty,
+                self.repetition_penalty_mode
+                    .is_enabled(self.repetition_penalty),
+            ),
+            (WarperKind::Temperature, self.temperature > 1f64),
+            (WarperKind::TopK, self.top_k > 0),
+            (WarperKind::TopP, self.top_p < 1f64),
+        ] {
+            if *is_enabled && !self.warper_order.contains(warper) {
+                warnings.push(format!(
+                    "{:?} is configured but omitted from warper_order and will not be applied",
+                    warper
+                ));
+            }
+        }
+        warnings
+    }
+
     pub(crate) fn validate(&self) {
         assert!(self.temperature > 0f64, "temperature must positive");
         assert!(
             (self.top_p >= 0f64) & (self.top_p <= 1f64),
             "top_p must be 0 and 1"
         );
-        assert!(
-            self.repetition_penalty >= 1f64,
-            "repetition_penalty must be greater than 1"
-        );
+        match self.repetition_penalty_mode {
+            RepetitionPenaltyMode::Multiplicative => assert!(
+                self.repetition_penalty >= 1f64,
+                "repetition_penalty must be greater than 1"
+            ),
+            RepetitionPenaltyMode::Additive => assert!(
+                self.repetition_penalty >= 0f64,
+                "repetition_penalty must be positive for the additive repetition penalty"
+            ),
+        }
+        for (position, warper) in self.warper_order.iter().enumerate() {
+            assert!(
+                !self.warper_order[..position].contains(warper),
+                "{:?} must appear at most once in warper_order",
+                warper
+            );
+        }
         assert!(
             self.length_penalty > 0f64,
             "length_penalty must be strictly greater than 0"
@@ -200,6 +342,52 @@ impl GenerateConfig {
                 )
             }
         }
+        if let Some(generation_microbatch_size) = self.generation_microbatch_size {
+            assert!(
+                generation_microbatch_size > 0,
+                "generation_microbatch_size must be strictly greater than 0"
+            );
+        }
+        if let Some(output_top_k_alternatives) = self.output_top_k_alternatives {
+            assert!(
+                output_top_k_alternatives > 0,
+                "output_top_k_alternatives must be strictly greater than 0"
+            );
+        }
+        if let Some(eos_probability_threshold) = self.eos_probability_threshold {
+            assert!(
+                (eos_probability_threshold > 0f64) && (eos_probability_threshold <= 1f64),
+                "eos_probability_threshold must be between 0 and 1"
+            );
+        }
+        if let Some(length_bias) = &self.length_bias {
+            assert!(
+                length_bias.strength.is_finite(),
+                "length_bias strength must be finite"
+            );
+        }
+        if let Some(reasoning_budget) = &self.reasoning_budget {
+            assert!(
+                reasoning_budget.max_reasoning_tokens >= 0,
+                "max_reasoning_tokens must be positive"
+            );
+            assert_ne!(
+                reasoning_budget.open_token_id, reasoning_budget.close_token_id,
+                "reasoning budget open and close token ids must be different"
+            );
+        }
+        if let Some(end_suffix_ids) = &self.end_suffix_ids {
+            assert!(
+                !end_suffix_ids.is_empty(),
+                "end_suffix_ids must contain at least one token"
+            );
+        }
+        if self.max_output_bytes.is_some() || self.max_output_chars.is_some() {
+            assert_eq!(
+                self.num_beams, 1,
+                "max_output_bytes and max_output_chars are only supported for greedy decoding and sampling"
+            );
+        }
         if let Some(num_beam_groups_value) = self.num_beam_groups {
             if num_beam_groups_value > 1 {
                 assert_eq!(
@@ -210,6 +398,100 @@ impl GenerateConfig {
             }
         }
     }
+
+    pub(crate) fn validate_corpus_penalty(&self, vocab_size: i64) -> Result<(), RustBertError> {
+        if let Some(corpus_penalty) = &self.corpus_penalty {
+            for token_id in corpus_penalty.keys() {
+                if (*token_id < 0) || (*token_id >= vocab_size) {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "corpus_penalty token id {} is outside of the vocabulary range [0, {})",
+                        token_id, vocab_size
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// # Reasoning budget for models emitting a delimited reasoning block
+/// Reasoning models may emit a reasoning block enclosed in special delimiter tokens (e.g. `<think>...</think>`) before their answer.
+/// The generation loop tracks whether each hypothesis is inside such a block and, once `max_reasoning_tokens` tokens have been
+/// generated within it, forces the closing delimiter so that the model proceeds with its answer. Tokens following an unclosed
+/// opening delimiter in the prompt count towards the budget.
+#[derive(Debug, Clone, Copy)]
+pub struct ReasoningBudget {
+    /// Token id opening the reasoning block (e.g. the id of `<think>`)
+    pub open_token_id: i64,
+    /// Token id closing the reasoning block (e.g. the id of `</think>`)
+    pub close_token_id: i64,
+    /// Maximum number of tokens allowed inside the reasoning block before the closing delimiter is forced
+    pub max_reasoning_tokens: i64,
+}
+
+/// # Soft length control through the EOS logits
+/// At each step, `strength * (current_length - target)` is added to the logits of the EOS tokens, where `current_length` is the
+/// length of the sequences counted as for `min_length` and `max_length` (including the prompt for decoder-only models). With a
+/// positive strength, EOS is discouraged before the target length and increasingly encouraged after it, giving a smoother length
+/// control than `min_length` and `max_length`. The bias is applied before the `min_length` constraint, which still prevents EOS
+/// until the minimum length is reached.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LengthBias {
+    /// Length at which the bias on the EOS logits is 0
+    pub target: usize,
+    /// Bias added to the EOS logits per token of difference between the current and target lengths
+    pub strength: f64,
+}
+
+/// # Form of the repetition penalty
+/// Controls how the logits of tokens that were already generated are penalized when a repetition penalty is set.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RepetitionPenaltyMode {
+    /// Divides positive logits and multiplies negative logits by the penalty ([Keskar et al.](http://arxiv.org/abs/1909.05858)).
+    /// The penalty must be greater than or equal to 1. As the effect scales with the magnitude of the logit, tokens with a logit
+    /// close to 0 are barely penalized.
+    Multiplicative,
+    /// Subtracts the penalty (in logit units) from the logits of previously generated tokens, regardless of their sign. Each token
+    /// is penalized once, however many times it was generated. The penalty must be greater than or equal to 0.
+    Additive,
+}
+
+impl RepetitionPenaltyMode {
+    fn is_enabled(self, repetition_penalty: f64) -> bool {
+        match self {
+            RepetitionPenaltyMode::Multiplicative => repetition_penalty > 1f64,
+            RepetitionPenaltyMode::Additive => repetition_penalty > 0f64,
+        }
+    }
+}
+
+/// # Logits warper
+/// Transformation of the next token logits whose order of application is set by the `warper_order` of the generation configuration.
+/// For greedy decoding, the repetition penalty is always applied along with the other logits processors (n-gram blocking, minimum length).
+/// For beam search, the temperature and the repetition penalty are applied to the logits while top-k and top-p filtering are applied to the
+/// beam scores: the order is only respected within each of these stages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WarperKind {
+    /// Penalty on previously generated tokens (`repetition_penalty` and `repetition_penalty_mode`)
+    RepetitionPenalty,
+    /// Division of the logits by the `temperature`
+    Temperature,
+    /// Top-k filtering (`top_k`)
+    TopK,
+    /// Nucleus filtering (`top_p`)
+    TopP,
+}
+
+impl WarperKind {
+    /// Default order of application, matching the Python Transformers library: repetition penalty, temperature, top-k and top-p
+    pub fn default_order() -> Vec<WarperKind> {
+        vec![
+            WarperKind::RepetitionPenalty,
+            WarperKind::Temperature,
+            WarperKind::TopK,
+            WarperKind::TopP,
+        ]
+    }
 }
 
 #[derive(Debug)]
@@ -223,6 +505,640 @@ pub enum Cache {
     None,
 }
 
+impl Cache {
+    /// Returns the memory footprint (in bytes) of the tensors held by the cache, summed over the
+    /// self-attention and cross-attention (for encoder-decoder models) states of all layers.
+    ///
+    /// # Returns
+    ///
+    /// * `usize` total size in bytes of the cached tensors
+    pub fn memory_size(&self) -> usize {
+        match self {
+            Cache::GPT2Cache(Some(layer_states)) => {
+                layer_states.iter().map(tensor_memory_size).sum()
+            }
+            Cache::BARTCache(Some(layer_states)) => layer_states
+                .iter()
+                .map(|(self_attention_state, cross_attention_state)| {
+                    layer_state_bytes(
+                        self_attention_state
+                            .as_ref()
+                            .map(|state| (&state.prev_key, &state.prev_value)),
+                        cross_attention_state
+                            .as_ref()
+                            .map(|state| (&state.prev_key, &state.prev_value)),
+                    )
+                })
+                .sum(),
+            Cache::T5Cache(Some(layer_states)) => layer_states
+                .iter()
+                .map(|(self_attention_state, cross_attention_state)| {
+                    layer_state_bytes(
+                        self_attention_state
+                            .as_ref()
+                            .map(|state| (&state.prev_key, &state.prev_value)),
+                        cross_attention_state
+                            .as_ref()
+                            .map(|state| (&state.prev_key, &state.prev_value)),
+                    )
+                })
+                .sum(),
+            Cache::ProphetNetCache(Some(layer_states)) => layer_states
+                .iter()
+                .map(|(self_attention_state, cross_attention_state)| {
+                    layer_state_bytes(
+                        self_attention_state
+                            .as_ref()
+                            .map(|state| (&state.prev_key, &state.prev_value)),
+                        cross_attention_state
+                            .as_ref()
+                            .map(|state| (&state.prev_key, &state.prev_value)),
+                    )
+                })
+                .sum(),
+            Cache::XLNetCache(Some(layer_states)) => layer_states
+                .iter()
+                .flatten()
+                .map(|state| tensor_memory_size(&state.prev_content))
+                .sum(),
+            Cache::ReformerCache(Some(layer_states)) => layer_states
+                .iter()
+                .flatten()
+                .map(|state| {
+                    tensor_memory_size(&state.prev_states)
+                        + state.prev_buckets.as_ref().map_or(0, tensor_memory_size)
+                })
+                .sum(),
+            _ => 0,
+        }
+    }
+
+    /// Returns the tensors held by the cache, named after the model family, layer and attention block they belong to
+    /// (e.g. `bart.3.cross.prev_key`). Used to persist the cache of a generation snapshot.
+    pub(crate) fn named_tensors(&self) -> Vec<(String, Tensor)> {
+        let mut named_tensors = vec![];
+        match self {
+            Cache::GPT2Cache(Some(layer_states)) => {
+                for (layer_index, layer_state) in layer_states.iter().enumerate() {
+                    named_tensors.push((format!("gpt2.{}", layer_index), layer_state.copy()));
+                }
+            }
+            Cache::BARTCache(Some(layer_states)) => push_key_value_tensors(
+                &mut named_tensors,
+                "bart",
+                layer_states
+                    .iter()
+                    .map(|(self_attention_state, cross_attention_state)| {
+                        (
+                            self_attention_state
+                                .as_ref()
+                                .map(|state| (&state.prev_key, &state.prev_value)),
+                            cross_attention_state
+                                .as_ref()
+                                .map(|state| (&state.prev_key, &state.prev_value)),
+                        )
+                    }),
+            ),
+            Cache::T5Cache(Some(layer_states)) => push_key_value_tensors(
+                &mut named_tensors,
+                "t5",
+                layer_states
+                    .iter()
+                    .map(|(self_attention_state, cross_attention_state)| {
+                        (
+                            self_attention_state
+                                .as_ref()
+                                .map(|state| (&state.prev_key, &state.prev_value)),
+                            cross_attention_state
+                                .as_ref()
+                                .map(|state| (&state.prev_key, &state.prev_value)),
+                        )
+                    }),
+            ),
+            Cache::ProphetNetCache(Some(layer_states)) => push_key_value_tensors(
+                &mut named_tensors,
+                "prophetnet",
+                layer_states
+                    .iter()
+                    .map(|(self_attention_state, cross_attention_state)| {
+                        (
+                            self_attention_state
+                                .as_ref()
+                                .map(|state| (&state.prev_key, &state.prev_value)),
+                            cross_attention_state
+                                .as_ref()
+                                .map(|state| (&state.prev_key, &state.prev_value)),
+                        )
+                    }),
+            ),
+            Cache::XLNetCache(Some(layer_states)) => {
+                for (layer_index, layer_state) in layer_states.iter().enumerate() {
+                    if let Some(layer_state) = layer_state {
+                        named_tensors.push((
+                            format!("xlnet.{}.prev_content", layer_index),
+                            layer_state.prev_content.copy(),
+                        ));
+                    }
+                }
+            }
+            Cache::ReformerCache(Some(layer_states)) => {
+                for (layer_index, layer_state) in layer_states.iter().enumerate() {
+                    if let Some(layer_state) = layer_state {
+                        named_tensors.push((
+                            format!("reformer.{}.prev_states", layer_index),
+                            layer_state.prev_states.copy(),
+                        ));
+                        if let Some(prev_buckets) = &layer_state.prev_buckets {
+                            named_tensors.push((
+                                format!("reformer.{}.prev_buckets", layer_index),
+                                prev_buckets.copy(),
+                            ));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        named_tensors
+    }
+
+    /// Rebuilds a cache from its named tensors (see `named_tensors`). Returns `Cache::None` if no tensors are provided.
+    pub(crate) fn from_named_tensors(
+        named_tensors: Vec<(String, Tensor)>,
+    ) -> Result<Cache, RustBertError> {
+        let mut model_family = None;
+        let mut num_layers = 0;
+        let mut tensors = HashMap::new();
+        for (name, tensor) in named_tensors {
+            let mut name_parts = name.splitn(3, '.');
+            let (family, layer_index) = match (name_parts.next(), name_parts.next()) {
+                (Some(family), Some(layer_index)) => (family, layer_index.parse::<usize>()),
+                _ => (name.as_str(), Ok(0)),
+            };
+            let layer_index = layer_index.map_err(|_| {
+                RustBertError::ValueError(format!("Invalid cache tensor name {}", name))
+            })?;
+            if *model_family.get_or_insert_with(|| family.to_string()) != family {
+                return Err(RustBertError::ValueError(format!(
+                    "Cache tensor {} does not belong to a {} cache",
+                    name,
+                    model_family.unwrap()
+                )));
+            }
+            num_layers = num_layers.max(layer_index + 1);
+            tensors.insert(name, tensor);
+        }
+        let model_family = match model_family {
+            Some(model_family) => model_family,
+            None => return Ok(Cache::None),
+        };
+        let mut take = |name: String| {
+            tensors
+                .remove(&name)
+                .ok_or_else(|| RustBertError::ValueError(format!("Missing cache tensor {}", name)))
+        };
+        Ok(match model_family.as_str() {
+            "gpt2" => Cache::GPT2Cache(Some(
+                (0..num_layers)
+                    .map(|layer_index| take(format!("gpt2.{}", layer_index)))
+                    .collect::<Result<Vec<Tensor>, RustBertError>>()?,
+            )),
+            "bart" => Cache::BARTCache(Some(take_key_value_states(
+                &mut tensors,
+                "bart",
+                num_layers,
+                |prev_key, prev_value| BartLayerState {
+                    prev_key,
+                    prev_value,
+                },
+            ))),
+            "t5" => Cache::T5Cache(Some(take_key_value_states(
+                &mut tensors,
+                "t5",
+                num_layers,
+                |prev_key, prev_value| T5LayerState {
+                    prev_key,
+                    prev_value,
+                },
+            ))),
+            "prophetnet" => Cache::ProphetNetCache(Some(take_key_value_states(
+                &mut tensors,
+                "prophetnet",
+                num_layers,
+                |prev_key, prev_value| ProphetNetLayerState {
+                    prev_key,
+                    prev_value,
+                },
+            ))),
+            "xlnet" => Cache::XLNetCache(Some(
+                (0..num_layers)
+                    .map(|layer_index| {
+                        tensors
+                            .remove(&format!("xlnet.{}.prev_content", layer_index))
+                            .map(|prev_content| XLNetLayerState { prev_content })
+                    })
+                    .collect(),
+            )),
+            "reformer" => Cache::ReformerCache(Some(
+                (0..num_layers)
+                    .map(|layer_index| {
+                        tensors
+                            .remove(&format!("reformer.{}.prev_states", layer_index))
+                            .map(|prev_states| ReformerLayerState {
+                                prev_buckets: tensors
+                                    .remove(&format!("reformer.{}.prev_buckets", layer_index)),
+                                prev_states,
+                            })
+                    })
+                    .collect(),
+            )),
+            _ => {
+                return Err(RustBertError::ValueError(format!(
+                    "Unknown cache type {}",
+                    model_family
+                )));
+            }
+        })
+    }
+}
+
+fn tensor_memory_size(tensor: &Tensor) -> usize {
+    tensor.numel() * tensor.kind().elt_size_in_bytes()
+}
+
+/// Memory footprint (in bytes) of the keys and values of the self-attention and cross-attention states of a layer
+fn layer_state_bytes(
+    self_attention_state: Option<(&Tensor, &Tensor)>,
+    cross_attention_state: Option<(&Tensor, &Tensor)>,
+) -> usize {
+    self_attention_state
+        .iter()
+        .chain(cross_attention_state.iter())
+        .map(|(prev_key, prev_value)| tensor_memory_size(prev_key) + tensor_memory_size(prev_value))
+        .sum()
+}
+
+/// Appends the keys and values of the self-attention and cross-attention states of each layer to a list of named tensors
+fn push_key_value_tensors<'a>(
+    named_tensors: &mut Vec<(String, Tensor)>,
+    model_family: &str,
+    layer_states: impl Iterator<
+        Item = (
+            Option<(&'a Tensor, &'a Tensor)>,
+            Option<(&'a Tensor, &'a Tensor)>,
+        ),
+    >,
+) {
+    for (layer_index, (self_attention_state, cross_attention_state)) in layer_states.enumerate() {
+        for (block, state) in [
+            ("self", self_attention_state),
+            ("cross", cross_attention_state),
+        ]
+        .iter()
+        {
+            if let Some((prev_key, prev_value)) = state {
+                named_tensors.push((
+                    format!("{}.{}.{}.prev_key", model_family, layer_index, block),
+                    prev_key.copy(),
+                ));
+                named_tensors.push((
+                    format!("{}.{}.{}.prev_value", model_family, layer_index, block),
+                    prev_value.copy(),
+                ));
+            }
+        }
+    }
+}
+
+/// Rebuilds the self-attention and cross-attention states of each layer from their named keys and values
+fn take_key_value_states<T>(
+    tensors: &mut HashMap<String, Tensor>,
+    model_family: &str,
+    num_layers: usize,
+    build_state: impl Fn(Tensor, Tensor) -> T,
+) -> Vec<(Option<T>, Option<T>)> {
+    let mut take_state = |layer_index: usize, block: &str| match (
+        tensors.remove(&format!(
+            "{}.{}.{}.prev_key",
+            model_family, layer_index, block
+        )),
+        tensors.remove(&format!(
+            "{}.{}.{}.prev_value",
+            model_family, layer_index, block
+        )),
+    ) {
+        (Some(prev_key), Some(prev_value)) => Some(build_state(prev_key, prev_value)),
+        _ => None,
+    };
+    (0..num_layers)
+        .map(|layer_index| {
+            (
+                take_state(layer_index, "self"),
+                take_state(layer_index, "cross"),
+            )
+        })
+        .collect()
+}
+
+/// # Generated token indices along with generation statistics
+pub struct GeneratedIndicesOutput {
+    /// Generated token indices, with one vector for each output sequence (*number_of_prompts* x *num_return_sequences*)
+    pub indices: Vec<Vec<i64>>,
+    /// Score of each output sequence (*number_of_prompts* x *num_return_sequences*): the length-normalized score of the beam
+    /// hypothesis for beam search, the sum of the log-probabilities of the generated tokens otherwise. Empty sequences have a score of 0.
+    pub scores: Vec<f64>,
+    /// Memory footprint (in bytes) of the key/value cache after each decoding step, summed over all layers for the entire
+    /// (expanded) batch. For encoder-decoder models this includes both the self-attention and cross-attention caches.
+    /// Empty unless `output_cache_memory` is set in the generation configuration.
+    pub cache_memory: Vec<usize>,
+    /// Reasoning and answer portions of each generated sequence, populated if a `reasoning_budget` is set in the generation configuration
+    pub reasoning_splits: Option<Vec<ReasoningSplit>>,
+    /// Prompt and completion token counts for each output sequence (*number_of_prompts* x *num_return_sequences*)
+    pub usage: Vec<GenerationUsage>,
+    /// Most likely candidate tokens considered at each generated position of each output sequence, populated if
+    /// `output_top_k_alternatives` is set in the generation configuration. For beam search, the alternatives along
+    /// the path of the returned beam are reported.
+    pub top_k_alternatives: Option<Vec<Vec<TokenAlternatives>>>,
+}
+
+/// # Snapshot of the state of an interrupted generation
+/// Contains the tokens generated so far and the decoding state of each sequence (or beam), so that the generation can be
+/// resumed later with `LanguageGenerator::resume_generation`, possibly by another process loading the same model and
+/// generation configuration. The decoding state is serialized with serde, the tensors of the snapshot (key/value cache of
+/// the model and, for encoder-decoder models, encoder outputs) are persisted separately with `save_tensors` and
+/// `load_tensors`. A decoder-only snapshot resumed without its tensors rebuilds the cache with a forward pass over the
+/// tokens generated so far. Sampling generations derive the random state of each decoding step from the `rng_seed` of
+/// the snapshot, so that a resumed generation samples the same tokens as an uninterrupted one.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct GenerationSnapshot {
+    /// Token ids of each sequence (*number_of_prompts* x *num_return_sequences*, x *num_beams* until a beam search is
+    /// complete): the left-padded prompt (the decoder start token for encoder-decoder models) followed by the generated tokens
+    pub token_ids: Vec<Vec<i64>>,
+    /// Number of leading positions of the `token_ids` that were not generated
+    pub prompt_length: usize,
+    /// Attention mask of the (left-padded) prompt of each sequence, of the encoder input for encoder-decoder models
+    pub prompt_attention_mask: Vec<Vec<i64>>,
+    /// Score of each sequence: sum of the log-probabilities of the generated tokens for greedy decoding and sampling,
+    /// beam score for beam search (length-normalized score of the hypothesis once complete)
+    pub scores: Vec<f64>,
+    /// Flag indicating for each sequence if it has ended (EOS generated, or beam search done for its input)
+    pub finished: Vec<bool>,
+    /// Finished hypotheses (length-normalized score and token ids) of each input of a beam search in progress
+    pub beam_hypotheses: Vec<Vec<(f64, Vec<i64>)>>,
+    /// Seed from which the random state of each sampling step is derived (`None` without sampling)
+    pub rng_seed: Option<i64>,
+    /// Minimum length of the sequences
+    pub min_length: i64,
+    /// Maximum length of the sequences
+    pub max_length: i64,
+    #[serde(skip)]
+    tensors: Vec<(String, Tensor)>,
+}
+
+impl Clone for GenerationSnapshot {
+    fn clone(&self) -> Self {
+        GenerationSnapshot {
+            token_ids: self.token_ids.clone(),
+            prompt_length: self.prompt_length,
+            prompt_attention_mask: self.prompt_attention_mask.clone(),
+            scores: self.scores.clone(),
+            finished: self.finished.clone(),
+            beam_hypotheses: self.beam_hypotheses.clone(),
+            rng_seed: self.rng_seed,
+            min_length: self.min_length,
+            max_length: self.max_length,
+            tensors: self
+                .tensors
+                .iter()
+                .map(|(name, tensor)| (name.clone(), tensor.copy()))
+                .collect(),
+        }
+    }
+}
+
+impl GenerationSnapshot {
+    /// Returns the number of decoding steps run so far
+    pub fn step(&self) -> usize {
+        self.token_ids.first().map_or(0, |token_ids| {
+            token_ids.len().saturating_sub(self.prompt_length)
+        })
+    }
+
+    /// Returns `true` if the generation is complete: all sequences have ended or reached the maximum length
+    pub fn is_complete(&self) -> bool {
+        let reached_max_length = match self.token_ids.first() {
+            Some(token_ids) => token_ids.len() as i64 >= self.max_length,
+            None => true,
+        };
+        self.finished.iter().all(|finished| *finished) || reached_max_length
+    }
+
+    /// Saves the tensors of the snapshot (key/value cache and encoder outputs) to a file
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path of the file to write
+    pub fn save_tensors<P: AsRef<Path>>(&self, path: P) -> Result<(), RustBertError> {
+        Tensor::save_multi(&self.tensors, path)?;
+        Ok(())
+    }
+
+    /// Loads the tensors of the snapshot (key/value cache and encoder outputs) saved with `save_tensors`
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - path of the file to read
+    /// * `device` - device to load the tensors to
+    pub fn load_tensors<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        device: Device,
+    ) -> Result<(), RustBertError> {
+        self.tensors = Tensor::load_multi_with_device(path, device)?;
+        Ok(())
+    }
+
+    fn tensor(&self, name: &str, device: Device) -> Option<Tensor> {
+        self.tensors
+            .iter()
+            .find(|(tensor_name, _)| tensor_name == name)
+            .map(|(_, tensor)| tensor.to(device))
+    }
+
+    fn cache(&self, device: Device) -> Result<Cache, RustBertError> {
+        Cache::from_named_tensors(
+            self.tensors
+                .iter()
+                .filter(|(name, _)| name.starts_with("cache."))
+                .map(|(name, tensor)| (name["cache.".len()..].to_string(), tensor.to(device)))
+                .collect(),
+        )
+    }
+}
+
+/// Candidate tokens and their probabilities for a generated position, sorted by decreasing probability.
+/// The probabilities are computed after the logits processors (e.g. repetition penalty, n-gram blocking)
+/// and before the sampling warpers (temperature, top-k and top-p filtering).
+pub type TokenAlternatives = Vec<(String, f32)>;
+
+/// Post-processing function applied by the generation pipelines to each output string.
+/// It runs last on the decoded text, after detokenization and the removal of special tokens, so that any
+/// truncation of the sequence at generation time (EOS, `end_suffix_ids`) is already reflected in its input.
+pub type PostProcess = Box<dyn Fn(String) -> String + Send + Sync>;
+
+/// Reranking function scoring each generated output string, applied by the generation pipelines to order the sequences
+/// generated for a prompt. Higher scores are ranked first.
+pub type Rerank = Box<dyn Fn(&str) -> f64 + Send + Sync>;
+
+/// Callback of a generation trigger, receiving the text generated so far by a sequence that generated the trigger token
+/// and returning the token ids to splice after it (e.g. the encoded retrieved context)
+pub type TriggerCallback = Box<dyn FnMut(&str) -> Vec<i64>>;
+
+/// Applies an optional post-processing function to generated texts
+pub(crate) fn apply_post_process(
+    post_process: Option<&PostProcess>,
+    texts: Vec<String>,
+) -> Vec<String> {
+    match post_process {
+        Some(post_process) => texts.into_iter().map(post_process).collect(),
+        None => texts,
+    }
+}
+
+/// # Generated text along with its score
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeneratedText {
+    /// Generated text
+    pub text: String,
+    /// Score of the generated sequence (see `GeneratedIndicesOutput::scores`)
+    pub score: f64,
+    /// Score of the generated text given by the reranking function, if provided
+    pub rerank_score: Option<f64>,
+}
+
+/// Groups the generated texts by input (*number_of_prompts* x *num_return_sequences*), sorting the texts of each input by decreasing
+/// score, or by decreasing reranking score if a reranking function is provided
+pub(crate) fn group_generated_texts(
+    texts: Vec<String>,
+    scores: Vec<f64>,
+    num_return_sequences: usize,
+    rerank: Option<&Rerank>,
+) -> Vec<Vec<GeneratedText>> {
+    let generated_texts = texts
+        .into_iter()
+        .zip(scores)
+        .map(|(text, score)| {
+            let rerank_score = rerank.map(|rerank| rerank(&text));
+            GeneratedText {
+                text,
+                score,
+                rerank_score,
+            }
+        })
+        .collect::<Vec<GeneratedText>>();
+    generated_texts
+        .chunks(num_return_sequences)
+        .map(|input_texts| {
+            let mut input_texts = input_texts.to_vec();
+            input_texts.sort_by(|text_1, text_2| {
+                let (score_1, score_2) = match (text_1.rerank_score, text_2.rerank_score) {
+                    (Some(rerank_score_1), Some(rerank_score_2)) => {
+                        (rerank_score_1, rerank_score_2)
+                    }
+                    _ => (text_1.score, text_2.score),
+                };
+                score_2
+                    .partial_cmp(&score_1)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+            input_texts
+        })
+        .collect()
+}
+
+/// # Token usage of a generated sequence
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GenerationUsage {
+    /// Number of tokens in the encoded prompt, excluding padding
+    pub prompt_tokens: i64,
+    /// Number of generated tokens, up to and including the first end of sequence token
+    pub completion_tokens: i64,
+    /// Sum of the prompt and completion tokens
+    pub total_tokens: i64,
+}
+
+impl GenerationUsage {
+    fn from_sequence(
+        prompt_tokens: i64,
+        generated_ids: &[i64],
+        eos_token_ids: Option<&Vec<i64>>,
+    ) -> GenerationUsage {
+        let completion_tokens = match eos_token_ids.and_then(|eos_token_ids| {
+            generated_ids
+                .iter()
+                .position(|token_id| eos_token_ids.contains(token_id))
+        }) {
+            Some(eos_position) => eos_position + 1,
+            None => generated_ids.len(),
+        } as i64;
+        GenerationUsage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: prompt_tokens + completion_tokens,
+        }
+    }
+}
+
+/// # Generated sequence split into its reasoning and answer portions
+#[derive(Debug, Clone)]
+pub struct ReasoningSplit {
+    /// Token indices inside the reasoning block, excluding the delimiters
+    pub reasoning: Vec<i64>,
+    /// Token indices following the reasoning block. Contains the entire sequence if no reasoning block was found.
+    pub answer: Vec<i64>,
+}
+
+impl ReasoningSplit {
+    /// Splits a generated sequence into its reasoning and answer portions. The reasoning block starts after the last occurrence
+    /// of the opening delimiter and ends at the first closing delimiter following it. If the reasoning block was not closed,
+    /// the answer is empty.
+    ///
+    /// # Arguments
+    ///
+    /// * `sequence` - token indices of a generated sequence
+    /// * `reasoning_budget` - `ReasoningBudget` providing the delimiter token ids
+    ///
+    /// # Returns
+    /// * `ReasoningSplit` reasoning and answer portions of the sequence
+    pub fn from_sequence(sequence: &[i64], reasoning_budget: &ReasoningBudget) -> ReasoningSplit {
+        match sequence
+            .iter()
+            .rposition(|token_id| *token_id == reasoning_budget.open_token_id)
+        {
+            Some(open_position) => {
+                let reasoning_block = &sequence[open_position + 1..];
+                match reasoning_block
+                    .iter()
+                    .position(|token_id| *token_id == reasoning_budget.close_token_id)
+                {
+                    Some(close_position) => ReasoningSplit {
+                        reasoning: reasoning_block[..close_position].to_vec(),
+                        answer: reasoning_block[close_position + 1..].to_vec(),
+                    },
+                    None => ReasoningSplit {
+                        reasoning: reasoning_block.to_vec(),
+                        answer: vec![],
+                    },
+                }
+            }
+            None => ReasoningSplit {
+                reasoning: vec![],
+                answer: sequence.to_vec(),
+            },
+        }
+    }
+}
+
 pub(crate) mod private_generation_utils {
     use std::cmp::{max, min};
     use std::collections::HashMap;
@@ -230,11 +1146,18 @@ pub(crate) mod private_generation_utils {
     use rust_tokenizers::tokenizer::{truncate_sequences, Tokenizer, TruncationStrategy};
     use rust_tokenizers::vocab::Vocab;
     use rust_tokenizers::TokenIdsWithOffsets;
-    use tch::kind::Kind::{Bool, Float, Int64};
-    use tch::{nn, Device, Tensor};
+    use tch::kind::Kind::{Bool, Double, Float, Int64};
+    use tch::{manual_seed, nn, no_grad, Device, Tensor};
 
-    use crate::pipelines::common::TokenizerOption;
-    use crate::pipelines::generation_utils::{BeamHypotheses, Cache, GenerateConfig, LMHeadModel};
+    use crate::common::error::RustBertError;
+    use crate::pipelines::common::{
+        assert_no_grad, build_padding_attention_mask, PaddingSide, TokenizerOption,
+    };
+    use crate::pipelines::generation_utils::{
+        BeamHypotheses, Cache, GenerateConfig, GeneratedIndicesOutput, GenerationSnapshot,
+        GenerationUsage, LMHeadModel, LengthBias, ReasoningBudget, ReasoningSplit,
+        RepetitionPenaltyMode, TriggerCallback, WarperKind,
+    };
 
     use super::ordered_float::OrderedFloat;
 
@@ -246,6 +1169,12 @@ pub(crate) mod private_generation_utils {
         pub top_k: i64,
         pub top_p: f64,
         pub repetition_penalty: f64,
+        pub repetition_penalty_mode: RepetitionPenaltyMode,
+        pub warper_order: Vec<WarperKind>,
+        pub output_top_k_alternatives: Option<usize>,
+        pub output_cache_memory: bool,
+        pub frequency_penalty: f64,
+        pub presence_penalty: f64,
         pub no_repeat_ngram_size: i64,
         pub pad_token_id: Option<i64>,
         pub eos_token_ids: Option<Vec<i64>>,
@@ -255,6 +1184,104 @@ pub(crate) mod private_generation_utils {
         pub length_penalty: f64,
         pub num_beam_groups: Option<i64>,
         pub diversity_penalty: Option<f64>,
+        pub corpus_penalty: Option<Tensor>,
+        pub reasoning_budget: Option<ReasoningBudget>,
+        pub eos_probability_threshold: Option<f64>,
+        pub length_bias: Option<LengthBias>,
+        pub end_suffix_ids: Option<Vec<i64>>,
+        pub max_output_bytes: Option<usize>,
+        pub max_output_chars: Option<usize>,
+        pub on_trigger: Option<(i64, TriggerCallback)>,
+    }
+
+    /// Logits biases specific to each row of the expanded batch, of shape (*batch size x num beams*, *vocab size*)
+    pub struct RowLogitsBiases {
+        /// Bias towards the tokens of the source, added at every step
+        pub source_copy_bias: Option<Tensor>,
+        /// Mask restricting the first generated token to the completions of the healed prompt token
+        pub token_healing_mask: Option<Tensor>,
+        /// N-grams that cannot be generated, for each row
+        pub blocked_ngrams: Option<Vec<Vec<Vec<i64>>>>,
+    }
+
+    impl RowLogitsBiases {
+        fn narrow(&self, start: i64, length: i64) -> RowLogitsBiases {
+            RowLogitsBiases {
+                source_copy_bias: self
+                    .source_copy_bias
+                    .as_ref()
+                    .map(|source_copy_bias| source_copy_bias.narrow(0, start, length)),
+                token_healing_mask: self
+                    .token_healing_mask
+                    .as_ref()
+                    .map(|token_healing_mask| token_healing_mask.narrow(0, start, length)),
+                blocked_ngrams: self.blocked_ngrams.as_ref().map(|blocked_ngrams| {
+                    blocked_ngrams[start as usize..(start + length) as usize].to_vec()
+                }),
+            }
+        }
+    }
+
+    /// State of the decoding loops, used to pause a generation and to resume it from a snapshot
+    pub struct DecodingState {
+        /// Cache of the model for the tokens processed so far (`Cache::None` to rebuild it with the first forward pass)
+        pub past: Cache,
+        /// Sum of the log-probabilities of the tokens generated so far for each sequence (beam scores for beam search)
+        pub scores: Tensor,
+        /// Flag (1 or 0) indicating for each sequence if it is still being generated
+        pub unfinished_sentences: Tensor,
+        /// Finished hypotheses of each input (beam search)
+        pub(crate) hypotheses: Vec<BeamHypotheses>,
+        /// Flag indicating for each input if its beam search is done
+        pub done: Vec<bool>,
+        /// Maximum number of decoding steps to run before stopping (until completion if `None`)
+        pub max_steps: Option<i64>,
+        /// Seed from which the random state of each sampling step is derived
+        pub rng_seed: Option<i64>,
+    }
+
+    /// Initial scores of the beams (flattened *batch size* x *num beams*): only the first beam of each group is
+    /// considered at the first step, as all beams of an input start from the same tokens
+    pub fn initial_beam_scores(
+        batch_size: i64,
+        num_beams: i64,
+        num_beam_groups: i64,
+        device: Device,
+    ) -> Tensor {
+        let num_sub_beams = num_beams / num_beam_groups;
+        let beam_scores = Tensor::ones(&[batch_size, num_beams], (Float, device)) * -1e9;
+        let _ = beam_scores.slice(1, 0, num_beams, num_sub_beams).fill_(0);
+        beam_scores.view_(&[-1])
+    }
+
+    pub struct GeneratedOutput {
+        pub indices: Tensor,
+        pub scores: Tensor,
+        pub cache_memory: Vec<usize>,
+        pub top_k_alternatives: Option<Vec<SequenceAlternatives>>,
+        /// State of a decoding loop started from an initial state, if the generation can be resumed
+        pub state: Option<DecodingState>,
+    }
+
+    /// Candidate token ids and probabilities for each generated position of a sequence
+    pub type SequenceAlternatives = Vec<Vec<(i64, f32)>>;
+
+    /// Converts the token ids and probabilities of the alternatives of a sequence (*steps* x *k*) to vectors
+    fn collect_alternatives(token_ids: &Tensor, probabilities: &Tensor) -> SequenceAlternatives {
+        let num_alternatives = *token_ids.size().last().unwrap() as usize;
+        let token_ids = Vec::<i64>::from(token_ids.contiguous().view(-1));
+        let probabilities = Vec::<f32>::from(probabilities.to_kind(Float).contiguous().view(-1));
+        token_ids
+            .chunks(num_alternatives)
+            .zip(probabilities.chunks(num_alternatives))
+            .map(|(token_ids, probabilities)| {
+                token_ids
+                    .iter()
+                    .copied()
+                    .zip(probabilities.iter().copied())
+                    .collect()
+            })
+            .collect()
     }
 
     pub struct PreparedInput<'a> {
@@ -381,7 +1408,17 @@ pub(crate) mod private_generation_utils {
             num_beams: i64,
             prev_output_tokens: &Tensor,
             repetition_penalty: f64,
+            repetition_penalty_mode: RepetitionPenaltyMode,
         ) {
+            if repetition_penalty_mode == RepetitionPenaltyMode::Additive {
+                let seen_tokens = next_token_logits.zeros_like().scatter1(
+                    1,
+                    prev_output_tokens,
+                    repetition_penalty,
+                );
+                *next_token_logits -= seen_tokens;
+                return;
+            }
             for i in 0..(batch_size * num_beams as i64) {
                 for token_position in 0..prev_output_tokens.get(i).size()[0] {
                     let token = prev_output_tokens.get(i).int64_value(&[token_position]);
@@ -454,41 +1491,345 @@ pub(crate) mod private_generation_utils {
             }
         }
 
-        fn top_k_top_p_filtering(
+        fn enforce_reasoning_budget(
             &self,
-            logits: &mut Tensor,
-            top_k: i64,
-            top_p: f64,
-            min_tokens_to_keep: i64,
+            scores: &mut Tensor,
+            input_ids: &Tensor,
+            reasoning_budget: &ReasoningBudget,
         ) {
-            //        Nucleus and top-k filtering introduced by Holtzman et al. (http://arxiv.org/abs/1904.09751)
-            //        Ported from https://gist.github.com/thomwolf/1a5a29f6962089e871b94cbd09daf317
-            let vocab_size = *logits.size().last().unwrap();
-            if top_k > 0 {
-                let top_k = vocab_size - min(max(top_k, min_tokens_to_keep), vocab_size);
-                let (_, indices_to_remove) = logits.topk(top_k, -1, false, false);
-                for index in 0..*logits.size().first().unwrap() {
-                    let _ = logits.get(index).index_fill_(
-                        0,
-                        &indices_to_remove.get(index),
-                        f64::NEG_INFINITY,
-                    );
+            let input_ids = input_ids.to(Device::Cpu);
+            let num_hypothesis = *input_ids.size().first().unwrap();
+            for hypothesis_index in 0..num_hypothesis {
+                let hypothesis_input_ids = input_ids
+                    .get(hypothesis_index)
+                    .iter::<i64>()
+                    .unwrap()
+                    .collect::<Vec<i64>>();
+                let open_position = hypothesis_input_ids
+                    .iter()
+                    .rposition(|token_id| *token_id == reasoning_budget.open_token_id);
+                if let Some(open_position) = open_position {
+                    let reasoning_block = &hypothesis_input_ids[open_position + 1..];
+                    if !reasoning_block.contains(&reasoning_budget.close_token_id)
+                        && (reasoning_block.len() as i64 >= reasoning_budget.max_reasoning_tokens)
+                    {
+                        let mut hypothesis_scores = scores.get(hypothesis_index);
+                        let _ = hypothesis_scores.fill_(f64::NEG_INFINITY);
+                        let _ = hypothesis_scores
+                            .get(reasoning_budget.close_token_id)
+                            .fill_(0.0);
+                    }
                 }
             }
-            if top_p < 1f64 {
-                let (sorted_logits, sorted_indices) = logits.sort(-1, true);
-                let cumulative_probabilities = sorted_logits.softmax(-1, Float).cumsum(-1, Float);
-                let mut sorted_indices_to_remove =
-                    cumulative_probabilities.ge(top_p).to_kind(Int64);
-                if min_tokens_to_keep > 1 {
-                    let _ = sorted_indices_to_remove.index_fill_(
-                        1,
-                        &Tensor::arange1(0, min_tokens_to_keep + 1, (Int64, logits.device())),
-                        0,
-                    );
-                }
-                let _ = sorted_indices_to_remove.index_copy_(
-                    1,
+        }
+
+        /// Returns, for each unfinished sequence whose generated text would exceed the output byte or character budget with its next
+        /// token, the number of generated tokens to keep. Trailing tokens decoding to an incomplete character (e.g. a byte-level token
+        /// holding the first bytes of a multi-byte character) are not kept, so that characters are never split.
+        fn get_output_budget_lengths(
+            &self,
+            input_ids: &Tensor,
+            next_tokens: &Tensor,
+            unfinished_sentences: &Tensor,
+            prompt_length: i64,
+            max_output_bytes: Option<usize>,
+            max_output_chars: Option<usize>,
+        ) -> Vec<Option<usize>> {
+            let detokenization_profile = self.get_config().detokenization_profile;
+            let decode = |token_ids: &[i64]| {
+                self.get_tokenizer().decode_with_profile(
+                    token_ids.to_vec(),
+                    true,
+                    detokenization_profile,
+                )
+            };
+            let exceeds = |limit: Option<usize>, length: usize| match limit {
+                Some(limit) => length > limit,
+                None => false,
+            };
+            let input_ids = input_ids.to(Device::Cpu);
+            let current_length = input_ids.size()[1];
+            let next_tokens = Vec::<i64>::from(next_tokens.to(Device::Cpu));
+            let unfinished_sentences = Vec::<i64>::from(unfinished_sentences.to(Device::Cpu));
+            next_tokens
+                .into_iter()
+                .zip(unfinished_sentences)
+                .enumerate()
+                .map(|(sequence_index, (next_token, unfinished))| {
+                    if unfinished == 0 {
+                        return None;
+                    }
+                    let mut token_ids = input_ids
+                        .get(sequence_index as i64)
+                        .slice(0, prompt_length, current_length, 1)
+                        .iter::<i64>()
+                        .unwrap()
+                        .collect::<Vec<i64>>();
+                    token_ids.push(next_token);
+                    let text = decode(&token_ids);
+                    if !exceeds(max_output_bytes, text.len())
+                        && !exceeds(max_output_chars, text.chars().count())
+                    {
+                        return None;
+                    }
+                    token_ids.pop();
+                    while !token_ids.is_empty()
+                        && decode(&token_ids).ends_with(char::REPLACEMENT_CHARACTER)
+                    {
+                        token_ids.pop();
+                    }
+                    Some(token_ids.len())
+                })
+                .collect()
+        }
+
+        /// Forces the end suffix before EOS. For each hypothesis, `progress` is the length of the longest prefix of the suffix
+        /// ending its generated tokens. EOS is banned until the full suffix is emitted, and the next suffix token is forced once the
+        /// positions left before `max_length` are just enough to emit the rest of the suffix followed by EOS.
+        fn enforce_end_suffix(
+            &self,
+            scores: &mut Tensor,
+            input_ids: &Tensor,
+            prompt_length: i64,
+            max_length: i64,
+            end_suffix_ids: &[i64],
+            eos_token_ids: Option<&Vec<i64>>,
+        ) {
+            let input_ids = input_ids.to(Device::Cpu);
+            let num_hypothesis = *input_ids.size().first().unwrap();
+            let current_length = input_ids.size()[1];
+            let remaining_length = max_length - current_length;
+            let eos_length = if eos_token_ids.is_some() { 1 } else { 0 };
+            let suffix_length = end_suffix_ids.len();
+            for hypothesis_index in 0..num_hypothesis {
+                let generated_ids = input_ids
+                    .get(hypothesis_index)
+                    .slice(0, prompt_length, current_length, 1)
+                    .iter::<i64>()
+                    .unwrap()
+                    .collect::<Vec<i64>>();
+                let progress = (0..=suffix_length)
+                    .rev()
+                    .find(|length| generated_ids.ends_with(&end_suffix_ids[..*length]))
+                    .unwrap_or(0);
+                let mut hypothesis_scores = scores.get(hypothesis_index);
+                let forced_token_id = if progress == suffix_length {
+                    eos_token_ids
+                        .filter(|_| remaining_length <= eos_length)
+                        .map(|eos_token_ids| eos_token_ids[0])
+                } else {
+                    if let Some(eos_token_ids) = eos_token_ids {
+                        let _ = hypothesis_scores.index_fill_(
+                            0,
+                            &Tensor::of_slice(eos_token_ids).to(hypothesis_scores.device()),
+                            f64::NEG_INFINITY,
+                        );
+                    }
+                    if remaining_length <= (suffix_length - progress) as i64 + eos_length {
+                        Some(end_suffix_ids[progress])
+                    } else {
+                        None
+                    }
+                };
+                if let Some(forced_token_id) = forced_token_id {
+                    let _ = hypothesis_scores.fill_(f64::NEG_INFINITY);
+                    let _ = hypothesis_scores.get(forced_token_id).fill_(0.0);
+                }
+            }
+        }
+
+        /// Bans the tokens completing a blocked n-gram, given the tokens generated by each hypothesis
+        fn ban_blocked_ngrams(
+            &self,
+            scores: &mut Tensor,
+            input_ids: &Tensor,
+            prompt_length: i64,
+            blocked_ngrams: &[Vec<Vec<i64>>],
+        ) {
+            let current_length = input_ids.size()[1];
+            let generated_ids = Vec::<Vec<i64>>::from(
+                input_ids
+                    .slice(1, prompt_length, current_length, 1)
+                    .to(Device::Cpu),
+            );
+            for (hypothesis_index, (hypothesis_blocked_ngrams, generated_ids)) in
+                blocked_ngrams.iter().zip(generated_ids.iter()).enumerate()
+            {
+                let banned_tokens = hypothesis_blocked_ngrams
+                    .iter()
+                    .filter_map(|ngram| ngram.split_last())
+                    .filter(|(_, ngram_prefix)| generated_ids.ends_with(ngram_prefix))
+                    .map(|(token_id, _)| *token_id)
+                    .collect::<Vec<i64>>();
+                if !banned_tokens.is_empty() {
+                    let _ = scores.get(hypothesis_index as i64).index_fill_(
+                        0,
+                        &Tensor::of_slice(&banned_tokens).to_device(scores.device()),
+                        f64::NEG_INFINITY,
+                    );
+                }
+            }
+        }
+
+        /// Removes the last token of each (left-padded) prompt and returns a mask of shape (*batch size*, *vocab size*)
+        /// restricting the first generated token to the tokens starting with the surface form of the removed token.
+        /// Prompts of a single token (e.g. a BOS token) are not healed and keep their token: a padding position is removed
+        /// instead. Returns `None` if no prompt of the batch has more than one token.
+        fn prepare_token_healing(
+            &self,
+            input_ids: &Tensor,
+            attention_mask: &Tensor,
+        ) -> Option<(Tensor, Tensor, Tensor)> {
+            let healed_rows = Vec::<i64>::from(attention_mask.sum1(&[1], false, Int64))
+                .into_iter()
+                .map(|unpadded_length| unpadded_length > 1)
+                .collect::<Vec<bool>>();
+            if !healed_rows.contains(&true) {
+                return None;
+            }
+            let tokenizer = self.get_tokenizer();
+            let vocab_size = self.get_vocab_size();
+            let vocab_tokens = (0..vocab_size)
+                .map(|token_id| tokenizer.id_to_token(token_id))
+                .collect::<Vec<Option<String>>>();
+            let prompt_length = input_ids.size()[1];
+            let token_healing_masks = Vec::<i64>::from(input_ids.select(1, -1))
+                .into_iter()
+                .zip(healed_rows.iter())
+                .map(|(healed_token_id, healed)| {
+                    if !healed {
+                        return Tensor::of_slice(&vec![true; vocab_size as usize]);
+                    }
+                    let healed_token = tokenizer.id_to_token(healed_token_id);
+                    let allowed_tokens = vocab_tokens
+                        .iter()
+                        .enumerate()
+                        .map(|(token_id, token)| match (token, &healed_token) {
+                            (Some(token), Some(healed_token)) => token.starts_with(healed_token),
+                            _ => token_id as i64 == healed_token_id,
+                        })
+                        .collect::<Vec<bool>>();
+                    Tensor::of_slice(&allowed_tokens)
+                })
+                .collect::<Vec<Tensor>>();
+            let token_healing_mask = Tensor::stack(&token_healing_masks, 0)
+                .to(input_ids.device())
+                .to_kind(Float)
+                .log();
+            let healed_rows = Tensor::of_slice(&healed_rows)
+                .to(input_ids.device())
+                .unsqueeze(1);
+            let remove_position = |tensor: &Tensor| {
+                let healed = tensor.slice(1, 0, prompt_length - 1, 1);
+                let unhealed = Tensor::cat(
+                    &[
+                        tensor.slice(1, 0, prompt_length - 2, 1),
+                        tensor.slice(1, prompt_length - 1, prompt_length, 1),
+                    ],
+                    1,
+                );
+                healed.where1(&healed_rows, &unhealed)
+            };
+            Some((
+                remove_position(input_ids),
+                remove_position(attention_mask),
+                token_healing_mask,
+            ))
+        }
+
+        fn enforce_frequency_presence_penalty(
+            &self,
+            next_token_logits: &mut Tensor,
+            generated_tokens: &Tensor,
+            frequency_penalty: f64,
+            presence_penalty: f64,
+        ) {
+            if generated_tokens.size()[1] > 0 {
+                let token_counts = next_token_logits.zeros_like().scatter_add(
+                    1,
+                    generated_tokens,
+                    &generated_tokens
+                        .ones_like()
+                        .to_kind(next_token_logits.kind()),
+                );
+                let penalty = &token_counts * frequency_penalty
+                    + token_counts.gt(0).to_kind(next_token_logits.kind()) * presence_penalty;
+                *next_token_logits -= penalty;
+            }
+        }
+
+        fn apply_length_bias(
+            &self,
+            logits: &mut Tensor,
+            eos_token_ids: &[i64],
+            length_bias: &LengthBias,
+            current_length: i64,
+        ) {
+            let bias = length_bias.strength * (current_length - length_bias.target as i64) as f64;
+            let eos_bias = Tensor::zeros(&[logits.size()[1]], (logits.kind(), logits.device()))
+                .index_fill(
+                    0,
+                    &Tensor::of_slice(eos_token_ids).to(logits.device()),
+                    bias,
+                );
+            *logits += eos_bias;
+        }
+
+        fn enforce_eos_probability_threshold(
+            &self,
+            scores: &mut Tensor,
+            eos_token_ids: &[i64],
+            eos_probability_threshold: f64,
+        ) {
+            let eos_token_ids = Tensor::of_slice(eos_token_ids).to(scores.device());
+            let confident_eos = scores
+                .softmax(-1, Float)
+                .index_select(1, &eos_token_ids)
+                .ge(eos_probability_threshold)
+                .any1(-1, true);
+            let non_eos_mask = confident_eos
+                .expand_as(scores)
+                .to_kind(Int64)
+                .index_fill(1, &eos_token_ids, 0)
+                .to_kind(Bool);
+            let _ = scores.masked_fill_(&non_eos_mask, f64::NEG_INFINITY);
+        }
+
+        fn top_k_top_p_filtering(
+            &self,
+            logits: &mut Tensor,
+            top_k: i64,
+            top_p: f64,
+            min_tokens_to_keep: i64,
+        ) {
+            //        Nucleus and top-k filtering introduced by Holtzman et al. (http://arxiv.org/abs/1904.09751)
+            //        Ported from https://gist.github.com/thomwolf/1a5a29f6962089e871b94cbd09daf317
+            let vocab_size = *logits.size().last().unwrap();
+            if top_k > 0 {
+                let top_k = vocab_size - min(max(top_k, min_tokens_to_keep), vocab_size);
+                let (_, indices_to_remove) = logits.topk(top_k, -1, false, false);
+                for index in 0..*logits.size().first().unwrap() {
+                    let _ = logits.get(index).index_fill_(
+                        0,
+                        &indices_to_remove.get(index),
+                        f64::NEG_INFINITY,
+                    );
+                }
+            }
+            if top_p < 1f64 {
+                let (sorted_logits, sorted_indices) = logits.sort(-1, true);
+                let cumulative_probabilities = sorted_logits.softmax(-1, Float).cumsum(-1, Float);
+                let mut sorted_indices_to_remove =
+                    cumulative_probabilities.ge(top_p).to_kind(Int64);
+                if min_tokens_to_keep > 1 {
+                    let _ = sorted_indices_to_remove.index_fill_(
+                        1,
+                        &Tensor::arange1(0, min_tokens_to_keep + 1, (Int64, logits.device())),
+                        0,
+                    );
+                }
+                let _ = sorted_indices_to_remove.index_copy_(
+                    1,
                     &Tensor::arange1(1, vocab_size, (Int64, logits.device())),
                     &sorted_indices_to_remove
                         .slice(1, 0, vocab_size - 1, 1)
@@ -508,6 +1849,47 @@ pub(crate) mod private_generation_utils {
             }
         }
 
+        fn apply_warpers(
+            &self,
+            logits: &mut Tensor,
+            warpers: &[WarperKind],
+            gen_opt: &GenerateOptions,
+            batch_size: i64,
+            prev_output_tokens: &Tensor,
+            min_tokens_to_keep: i64,
+        ) {
+            for warper in warpers {
+                match warper {
+                    WarperKind::RepetitionPenalty => {
+                        if gen_opt
+                            .repetition_penalty_mode
+                            .is_enabled(gen_opt.repetition_penalty)
+                        {
+                            self.enforce_repetition_penalty(
+                                logits,
+                                batch_size,
+                                1,
+                                prev_output_tokens,
+                                gen_opt.repetition_penalty,
+                                gen_opt.repetition_penalty_mode,
+                            )
+                        }
+                    }
+                    WarperKind::Temperature => {
+                        if gen_opt.temperature > 1f64 {
+                            *logits /= gen_opt.temperature;
+                        }
+                    }
+                    WarperKind::TopK => {
+                        self.top_k_top_p_filtering(logits, gen_opt.top_k, 1f64, min_tokens_to_keep)
+                    }
+                    WarperKind::TopP => {
+                        self.top_k_top_p_filtering(logits, 0, gen_opt.top_p, min_tokens_to_keep)
+                    }
+                }
+            }
+        }
+
         fn run_hamming_diversity_penalty(
             &self,
             scores: &mut Tensor,
@@ -549,20 +1931,57 @@ pub(crate) mod private_generation_utils {
             cur_len: i64,
             batch_size: i64,
             attention_mask: Tensor,
-            gen_opt: GenerateOptions,
-        ) -> Tensor {
-            let mut unfinished_sentences =
-                Tensor::ones(&[batch_size], (Int64, self.get_var_store().device()));
+            row_logits_biases: RowLogitsBiases,
+            gen_opt: &mut GenerateOptions,
+            initial_state: Option<DecodingState>,
+        ) -> GeneratedOutput {
             let mut sentence_lengths: Tensor =
                 Tensor::ones(&[batch_size], (Int64, self.get_var_store().device()))
                     * gen_opt.max_length as i64;
+            let resumable = initial_state.is_some();
+            let (mut unfinished_sentences, mut sequence_scores, mut past, max_steps, rng_seed) =
+                match initial_state {
+                    Some(initial_state) => (
+                        initial_state
+                            .unfinished_sentences
+                            .to(self.get_var_store().device()),
+                        initial_state.scores.to(self.get_var_store().device()),
+                        initial_state.past,
+                        initial_state.max_steps,
+                        initial_state.rng_seed,
+                    ),
+                    None => (
+                        Tensor::ones(&[batch_size], (Int64, self.get_var_store().device())),
+                        Tensor::zeros(&[batch_size], (Float, self.get_var_store().device())),
+                        Cache::None,
+                        None,
+                        None,
+                    ),
+                };
             let mut attention_mask = attention_mask.copy();
             let mut input_ids = input_ids.copy();
-            let mut past: Cache = Cache::None;
             let mut outputs: Tensor;
-            let mut current_length = cur_len;
+            let mut current_length = *input_ids.size().last().unwrap();
+            let stop_length = max_steps.map_or(gen_opt.max_length, |max_steps| {
+                min(current_length + max_steps, gen_opt.max_length)
+            });
+            let mut cache_memory = vec![];
+            //        The repetition penalty is applied along with the other logits processors for greedy decoding or if it comes first
+            let (processor_warpers, sampling_warpers): (Vec<WarperKind>, Vec<WarperKind>) =
+                gen_opt.warper_order.iter().copied().partition(|warper| {
+                    (*warper == WarperKind::RepetitionPenalty)
+                        && (!gen_opt.do_sample || (gen_opt.warper_order.first() == Some(warper)))
+                });
+            let num_alternatives = gen_opt
+                .output_top_k_alternatives
+                .map(|num_alternatives| min(num_alternatives as i64, self.get_vocab_size()));
+            let mut step_alternatives = vec![];
 
-            while current_length < gen_opt.max_length {
+            while current_length < stop_length {
+                //            Derive the random state of the step from the seed of the snapshot
+                if let Some(rng_seed) = rng_seed {
+                    manual_seed(rng_seed.wrapping_add(current_length));
+                }
                 let prepared_input = self.prepare_inputs_for_generation(
                     input_ids.copy(),
                     encoder_outputs.as_ref(),
@@ -584,18 +2003,43 @@ pub(crate) mod private_generation_utils {
                     )
                     .unwrap();
                 outputs = temp.lm_logits;
+                assert_no_grad(&outputs);
                 past = temp.cache;
+                if gen_opt.output_cache_memory {
+                    cache_memory.push(past.memory_size());
+                }
 
                 let mut next_token_logits = outputs.select(1, -1);
                 //            Reduce probability for repeated inputs
-                if gen_opt.repetition_penalty > 1f64 {
-                    self.enforce_repetition_penalty(
+                self.apply_warpers(
+                    &mut next_token_logits,
+                    &processor_warpers,
+                    gen_opt,
+                    batch_size,
+                    &input_ids,
+                    1,
+                );
+                //            Penalize generated tokens according to their number of occurrences
+                if (gen_opt.frequency_penalty != 0f64) || (gen_opt.presence_penalty != 0f64) {
+                    self.enforce_frequency_presence_penalty(
                         &mut next_token_logits,
-                        batch_size,
-                        1,
-                        &input_ids,
-                        gen_opt.repetition_penalty,
-                    )
+                        &input_ids.slice(1, cur_len, current_length, 1),
+                        gen_opt.frequency_penalty,
+                        gen_opt.presence_penalty,
+                    );
+                }
+                //            Apply the static corpus-derived bias
+                if let Some(corpus_penalty) = &gen_opt.corpus_penalty {
+                    next_token_logits += corpus_penalty;
+                }
+                //            Bias towards the tokens of the source and complete the healed prompt token
+                if let Some(source_copy_bias) = &row_logits_biases.source_copy_bias {
+                    next_token_logits += source_copy_bias;
+                }
+                if current_length == cur_len {
+                    if let Some(token_healing_mask) = &row_logits_biases.token_healing_mask {
+                        next_token_logits += token_healing_mask;
+                    }
                 }
                 //            Get banned tokens and set their probability to 0
                 if gen_opt.no_repeat_ngram_size > 0 {
@@ -615,7 +2059,26 @@ pub(crate) mod private_generation_utils {
                         );
                     }
                 }
+                if let Some(blocked_ngrams) = &row_logits_biases.blocked_ngrams {
+                    self.ban_blocked_ngrams(
+                        &mut next_token_logits,
+                        &input_ids,
+                        cur_len,
+                        blocked_ngrams,
+                    );
+                }
 
+                //            Bias the EOS logits towards the target length
+                if let (Some(eos_token_ids), Some(length_bias)) =
+                    (&gen_opt.eos_token_ids, &gen_opt.length_bias)
+                {
+                    self.apply_length_bias(
+                        &mut next_token_logits,
+                        eos_token_ids,
+                        length_bias,
+                        current_length,
+                    );
+                }
                 //            Do not allow eos token if min length is not reached
                 if (gen_opt.eos_token_ids.is_some()) & (current_length < gen_opt.min_length) {
                     let _ = next_token_logits.index_fill_(
@@ -625,6 +2088,16 @@ pub(crate) mod private_generation_utils {
                         f64::NEG_INFINITY,
                     );
                 }
+                //            End sequences for which the EOS probability exceeds the threshold
+                if let (Some(eos_token_ids), Some(eos_probability_threshold)) =
+                    (&gen_opt.eos_token_ids, gen_opt.eos_probability_threshold)
+                {
+                    self.enforce_eos_probability_threshold(
+                        &mut next_token_logits,
+                        eos_token_ids,
+                        eos_probability_threshold,
+                    );
+                }
                 if self.is_encoder_decoder() & !gen_opt.do_sample {
                     self.prepare_scores_for_generation(
                         &mut next_token_logits,
@@ -633,15 +2106,44 @@ pub(crate) mod private_generation_utils {
                     );
                 }
 
+                //            Force the end of the reasoning block if the reasoning budget is exhausted
+                if let Some(reasoning_budget) = &gen_opt.reasoning_budget {
+                    self.enforce_reasoning_budget(
+                        &mut next_token_logits,
+                        &input_ids,
+                        reasoning_budget,
+                    );
+                }
+
+                //            Complete the end suffix before reaching the maximum length
+                if let Some(end_suffix_ids) = &gen_opt.end_suffix_ids {
+                    self.enforce_end_suffix(
+                        &mut next_token_logits,
+                        &input_ids,
+                        cur_len,
+                        gen_opt.max_length,
+                        end_suffix_ids,
+                        gen_opt.eos_token_ids.as_ref(),
+                    );
+                }
+
+                //            Record the most likely candidate tokens
+                if let Some(num_alternatives) = num_alternatives {
+                    let (probabilities, token_ids) =
+                        next_token_logits
+                            .softmax(-1, Float)
+                            .topk(num_alternatives, -1, true, true);
+                    step_alternatives.push((token_ids, probabilities));
+                }
+
                 //            Top-k and top-p sampling
                 let next_token = if gen_opt.do_sample {
-                    if gen_opt.temperature > 1f64 {
-                        next_token_logits /= gen_opt.temperature;
-                    }
-                    self.top_k_top_p_filtering(
+                    self.apply_warpers(
                         &mut next_token_logits,
-                        gen_opt.top_k as i64,
-                        gen_opt.top_p,
+                        &sampling_warpers,
+                        gen_opt,
+                        batch_size,
+                        &input_ids,
                         1,
                     );
                     let probabilities = next_token_logits.softmax(-1, Float);
@@ -649,6 +2151,35 @@ pub(crate) mod private_generation_utils {
                 } else {
                     next_token_logits.argmax(-1, false)
                 };
+                //            End the sequences whose generated text would exceed the output budget with the next token
+                if (gen_opt.max_output_bytes.is_some() || gen_opt.max_output_chars.is_some())
+                    && gen_opt.eos_token_ids.is_some()
+                {
+                    let budget_lengths = self.get_output_budget_lengths(
+                        &input_ids,
+                        &next_token,
+                        &unfinished_sentences,
+                        cur_len,
+                        gen_opt.max_output_bytes,
+                        gen_opt.max_output_chars,
+                    );
+                    for (sequence_index, budget_length) in budget_lengths.into_iter().enumerate() {
+                        if let Some(budget_length) = budget_length {
+                            let budget_end = cur_len + budget_length as i64;
+                            let _ = input_ids
+                                .get(sequence_index as i64)
+                                .narrow(0, budget_end, current_length - budget_end)
+                                .fill_(gen_opt.pad_token_id.unwrap());
+                            let _ = unfinished_sentences.get(sequence_index as i64).fill_(0);
+                        }
+                    }
+                }
+                //            Accumulate the log-probabilities of the tokens added to unfinished sentences
+                sequence_scores += next_token_logits
+                    .log_softmax(-1, Float)
+                    .gather(1, &next_token.unsqueeze(-1), false)
+                    .squeeze1(1)
+                    * unfinished_sentences.to_kind(Float);
 
                 //            Add tokens to unfinished sentences
                 let tokens_to_add = match &gen_opt.eos_token_ids {
@@ -690,8 +2221,118 @@ pub(crate) mod private_generation_utils {
                     );
                 }
                 current_length += 1;
+                //            Splice the token ids returned by the trigger callback after the trigger tokens. Sequences
+                //            with fewer spliced tokens are padded with masked positions. The cache does not cover the
+                //            spliced tokens: it is dropped and rebuilt by the next forward pass over the full sequences
+                if let Some((trigger_token_id, on_trigger)) = gen_opt.on_trigger.as_mut() {
+                    let triggered = Vec::<i64>::from(
+                        (tokens_to_add.eq(*trigger_token_id).to_kind(Int64)
+                            * &unfinished_sentences)
+                            .to(Device::Cpu),
+                    );
+                    let mut spliced_ids = vec![vec![]; triggered.len()];
+                    for (sequence_index, _) in triggered
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, triggered)| **triggered == 1)
+                    {
+                        let output = self.get_tokenizer().decode_with_profile(
+                            Vec::<i64>::from(
+                                input_ids
+                                    .get(sequence_index as i64)
+                                    .slice(0, cur_len, current_length, 1)
+                                    .to(Device::Cpu),
+                            ),
+                            true,
+                            PrivateLanguageGenerator::get_config(self).detokenization_profile,
+                        );
+                        spliced_ids[sequence_index] = on_trigger(&output);
+                    }
+                    let splice_length = min(
+                        spliced_ids.iter().map(Vec::len).max().unwrap_or(0) as i64,
+                        gen_opt.max_length - current_length,
+                    );
+                    if splice_length > 0 {
+                        let mut splice = vec![
+                            gen_opt.pad_token_id.unwrap_or(0);
+                            (batch_size * splice_length) as usize
+                        ];
+                        let mut splice_mask = vec![0i64; (batch_size * splice_length) as usize];
+                        for (sequence_index, sequence_spliced_ids) in spliced_ids.iter().enumerate()
+                        {
+                            for (position, token_id) in sequence_spliced_ids
+                                .iter()
+                                .take(splice_length as usize)
+                                .enumerate()
+                            {
+                                splice[sequence_index * splice_length as usize + position] =
+                                    *token_id;
+                                splice_mask[sequence_index * splice_length as usize + position] = 1;
+                            }
+                        }
+                        input_ids = Tensor::cat(
+                            &[
+                                input_ids,
+                                Tensor::of_slice(&splice)
+                                    .view((batch_size, splice_length))
+                                    .to(attention_mask.device()),
+                            ],
+                            -1,
+                        );
+                        if !self.is_encoder_decoder() {
+                            attention_mask = Tensor::cat(
+                                &[
+                                    attention_mask,
+                                    Tensor::of_slice(&splice_mask)
+                                        .view((batch_size, splice_length))
+                                        .to(input_ids.device()),
+                                ],
+                                -1,
+                            );
+                        }
+                        current_length += splice_length;
+                        past = Cache::None;
+                    }
+                }
+            }
+            let top_k_alternatives = num_alternatives.map(|_| {
+                let num_sequences = input_ids.size()[0];
+                if step_alternatives.is_empty() {
+                    return vec![vec![]; num_sequences as usize];
+                }
+                let (token_ids, probabilities): (Vec<Tensor>, Vec<Tensor>) =
+                    step_alternatives.into_iter().unzip();
+                let token_ids = Tensor::stack(&token_ids, 1);
+                let probabilities = Tensor::stack(&probabilities, 1);
+                (0..num_sequences)
+                    .map(|sequence_index| {
+                        collect_alternatives(
+                            &token_ids.get(sequence_index),
+                            &probabilities.get(sequence_index),
+                        )
+                    })
+                    .collect()
+            });
+            let state = if resumable {
+                Some(DecodingState {
+                    past,
+                    scores: sequence_scores.shallow_clone(),
+                    unfinished_sentences,
+                    hypotheses: vec![],
+                    done: vec![],
+                    max_steps,
+                    rng_seed,
+                })
+            } else {
+                None
+            };
+            GeneratedOutput {
+                indices: input_ids,
+                scores: sequence_scores,
+                cache_memory,
+                top_k_alternatives,
+                state,
             }
-            input_ids
         }
 
         fn generate_beam_search(
@@ -701,33 +2342,60 @@ pub(crate) mod private_generation_utils {
             cur_len: i64,
             batch_size: i64,
             mut attention_mask: Tensor,
-            gen_opt: GenerateOptions,
-        ) -> Tensor {
+            row_logits_biases: RowLogitsBiases,
+            gen_opt: &GenerateOptions,
+            initial_state: Option<DecodingState>,
+        ) -> GeneratedOutput {
             let num_beam_groups = gen_opt.num_beam_groups.unwrap_or(1);
             let num_sub_beams = gen_opt.num_beams / num_beam_groups;
             let diversity_penalty = gen_opt.diversity_penalty.unwrap_or(5.5);
+            //        The repetition penalty is applied before the corpus penalty if it comes first. Top-k and top-p filtering
+            //        are applied to the beam scores, the other warpers to the logits
+            let (processor_warpers, other_warpers): (Vec<WarperKind>, Vec<WarperKind>) =
+                gen_opt.warper_order.iter().copied().partition(|warper| {
+                    (*warper == WarperKind::RepetitionPenalty)
+                        && (gen_opt.warper_order.first() == Some(warper))
+                });
+            let (score_warpers, logits_warpers): (Vec<WarperKind>, Vec<WarperKind>) = other_warpers
+                .into_iter()
+                .partition(|warper| (*warper == WarperKind::TopK) || (*warper == WarperKind::TopP));
 
-            let mut hypotheses = (0..batch_size)
-                .map(|_| {
-                    BeamHypotheses::new(
-                        gen_opt.num_beams,
-                        gen_opt.max_length,
-                        gen_opt.length_penalty,
-                        gen_opt.early_stopping,
-                    )
-                })
-                .collect::<Vec<BeamHypotheses>>();
+            let resumable = initial_state.is_some();
+            let (mut hypotheses, mut beam_scores, mut past, mut done, max_steps, rng_seed) =
+                match initial_state {
+                    Some(initial_state) => (
+                        initial_state.hypotheses,
+                        initial_state.scores.to(self.get_var_store().device()),
+                        initial_state.past,
+                        initial_state.done,
+                        initial_state.max_steps,
+                        initial_state.rng_seed,
+                    ),
+                    None => (
+                        (0..batch_size)
+                            .map(|_| {
+                                BeamHypotheses::new(
+                                    gen_opt.num_beams,
+                                    gen_opt.max_length,
+                                    gen_opt.length_penalty,
+                                    gen_opt.early_stopping,
+                                )
+                            })
+                            .collect::<Vec<BeamHypotheses>>(),
+                        initial_beam_scores(
+                            batch_size,
+                            gen_opt.num_beams,
+                            num_beam_groups,
+                            self.get_var_store().device(),
+                        ),
+                        Cache::None,
+                        vec![false; batch_size as usize],
+                        None,
+                        None,
+                    ),
+                };
 
             let vocab_size = self.get_vocab_size();
-            let beam_scores = Tensor::ones(
-                &[batch_size, gen_opt.num_beams],
-                (Float, self.get_var_store().device()),
-            ) * -1e9;
-            let _ = beam_scores
-                .slice(1, 0, *beam_scores.size().last().unwrap(), num_sub_beams)
-                .fill_(0);
-
-            let mut beam_scores = beam_scores.view_(&[-1]);
             let mut beam_tokens = Tensor::zeros(
                 &[batch_size * gen_opt.num_beams],
                 (Int64, self.get_var_store().device()),
@@ -737,15 +2405,37 @@ pub(crate) mod private_generation_utils {
                 (Int64, self.get_var_store().device()),
             );
             let mut current_tokens = Tensor::new();
-
-            let mut past: Cache = Cache::None;
-            let mut done = vec![false; batch_size as usize];
+            //        Candidate tokens considered along the path of each beam (*batch size x num beams* x *steps* x *k*)
+            let num_alternatives = gen_opt
+                .output_top_k_alternatives
+                .map(|num_alternatives| min(num_alternatives as i64, vocab_size));
+            let (mut alternative_ids, mut alternative_probabilities) = match num_alternatives {
+                Some(num_alternatives) => (
+                    Some(Tensor::zeros(
+                        &[batch_size * gen_opt.num_beams, 0, num_alternatives],
+                        (Int64, self.get_var_store().device()),
+                    )),
+                    Some(Tensor::zeros(
+                        &[batch_size * gen_opt.num_beams, 0, num_alternatives],
+                        (Float, self.get_var_store().device()),
+                    )),
+                ),
+                None => (None, None),
+            };
 
             let mut outputs: Tensor;
             let mut encoder_outputs = encoder_outputs;
-            let mut current_length = cur_len;
+            let mut current_length = *input_ids.size().last().unwrap();
+            let stop_length = max_steps.map_or(gen_opt.max_length, |max_steps| {
+                min(current_length + max_steps, gen_opt.max_length)
+            });
+            let mut cache_memory = vec![];
 
-            while current_length < gen_opt.max_length {
+            while current_length < stop_length {
+                //            Derive the random state of the step from the seed of the snapshot
+                if let Some(rng_seed) = rng_seed {
+                    manual_seed(rng_seed.wrapping_add(current_length));
+                }
                 if num_beam_groups > 1 {
                     current_tokens = Tensor::zeros(
                         &[batch_size * gen_opt.num_beams],
@@ -773,8 +2463,24 @@ pub(crate) mod private_generation_utils {
                     )
                     .unwrap();
                 outputs = temp.lm_logits;
+                assert_no_grad(&outputs);
                 past = temp.cache;
+                if gen_opt.output_cache_memory {
+                    cache_memory.push(past.memory_size());
+                }
 
+                let mut step_alternatives = num_alternatives.map(|num_alternatives| {
+                    (
+                        Tensor::zeros(
+                            &[batch_size * gen_opt.num_beams, num_alternatives],
+                            (Int64, input_ids.device()),
+                        ),
+                        Tensor::zeros(
+                            &[batch_size * gen_opt.num_beams, num_alternatives],
+                            (Float, input_ids.device()),
+                        ),
+                    )
+                });
                 for beam_group_index in 0..num_beam_groups {
                     let group_start_index = beam_group_index * num_sub_beams;
                     let group_end_index = min(group_start_index + num_sub_beams, gen_opt.num_beams);
@@ -807,18 +2513,70 @@ pub(crate) mod private_generation_utils {
                             .index_select(0, batch_group_indices.as_ref().unwrap())
                     };
                     //            Reduce probability for repeated inputs
-                    if gen_opt.repetition_penalty > 1f64 {
-                        self.enforce_repetition_penalty(
+                    self.apply_warpers(
+                        &mut next_token_logits,
+                        &processor_warpers,
+                        gen_opt,
+                        batch_size,
+                        group_input_ids.as_ref().unwrap_or(&input_ids),
+                        1,
+                    );
+                    //            Penalize generated tokens according to their number of occurrences
+                    if (gen_opt.frequency_penalty != 0f64) || (gen_opt.presence_penalty != 0f64) {
+                        self.enforce_frequency_presence_penalty(
                             &mut next_token_logits,
-                            batch_size,
-                            1,
-                            group_input_ids.as_ref().unwrap_or(&input_ids),
-                            gen_opt.repetition_penalty,
-                        )
+                            &group_input_ids.as_ref().unwrap_or(&input_ids).slice(
+                                1,
+                                cur_len,
+                                current_length,
+                                1,
+                            ),
+                            gen_opt.frequency_penalty,
+                            gen_opt.presence_penalty,
+                        );
+                    }
+                    //            Apply the static corpus-derived bias
+                    if let Some(corpus_penalty) = &gen_opt.corpus_penalty {
+                        next_token_logits += corpus_penalty;
+                    }
+                    //            Bias towards the tokens of the source and complete the healed prompt token
+                    let first_step = current_length == cur_len;
+                    for row_bias in [
+                        row_logits_biases.source_copy_bias.as_ref(),
+                        row_logits_biases
+                            .token_healing_mask
+                            .as_ref()
+                            .filter(|_| first_step),
+                    ]
+                    .iter()
+                    .flatten()
+                    {
+                        match &batch_group_indices {
+                            Some(batch_group_indices) => {
+                                next_token_logits += row_bias.index_select(0, batch_group_indices)
+                            }
+                            None => next_token_logits += *row_bias,
+                        }
                     }
 
-                    if gen_opt.temperature > 1f64 {
-                        next_token_logits /= gen_opt.temperature;
+                    self.apply_warpers(
+                        &mut next_token_logits,
+                        &logits_warpers,
+                        gen_opt,
+                        batch_size,
+                        group_input_ids.as_ref().unwrap_or(&input_ids),
+                        1,
+                    );
+                    //            Bias the EOS logits towards the target length
+                    if let (Some(eos_token_ids), Some(length_bias)) =
+                        (&gen_opt.eos_token_ids, &gen_opt.length_bias)
+                    {
+                        self.apply_length_bias(
+                            &mut next_token_logits,
+                            eos_token_ids,
+                            length_bias,
+                            current_length,
+                        );
                     }
                     if self.is_encoder_decoder() & !gen_opt.do_sample {
                         self.prepare_scores_for_generation(
@@ -837,8 +2595,18 @@ pub(crate) mod private_generation_utils {
                             f64::NEG_INFINITY,
                         );
                     }
-                    //            Get banned tokens and set their probability to 0
-                    if gen_opt.no_repeat_ngram_size > 0 {
+                    //            End hypotheses for which the EOS probability exceeds the threshold
+                    if let (Some(eos_token_ids), Some(eos_probability_threshold)) =
+                        (&gen_opt.eos_token_ids, gen_opt.eos_probability_threshold)
+                    {
+                        self.enforce_eos_probability_threshold(
+                            &mut scores,
+                            eos_token_ids,
+                            eos_probability_threshold,
+                        );
+                    }
+                    //            Get banned tokens and set their probability to 0
+                    if gen_opt.no_repeat_ngram_size > 0 {
                         let banned_tokens = self.get_banned_tokens(
                             group_input_ids.as_ref().unwrap_or(&input_ids),
                             gen_opt.no_repeat_ngram_size,
@@ -855,6 +2623,46 @@ pub(crate) mod private_generation_utils {
                             );
                         }
                     }
+                    if let Some(blocked_ngrams) = &row_logits_biases.blocked_ngrams {
+                        match &batch_group_indices {
+                            Some(batch_group_indices) => self.ban_blocked_ngrams(
+                                &mut scores,
+                                group_input_ids.as_ref().unwrap(),
+                                cur_len,
+                                &Vec::<i64>::from(batch_group_indices)
+                                    .into_iter()
+                                    .map(|row_index| blocked_ngrams[row_index as usize].clone())
+                                    .collect::<Vec<Vec<Vec<i64>>>>(),
+                            ),
+                            None => self.ban_blocked_ngrams(
+                                &mut scores,
+                                &input_ids,
+                                cur_len,
+                                blocked_ngrams,
+                            ),
+                        }
+                    }
+
+                    //            Force the end of the reasoning block if the reasoning budget is exhausted
+                    if let Some(reasoning_budget) = &gen_opt.reasoning_budget {
+                        self.enforce_reasoning_budget(
+                            &mut scores,
+                            group_input_ids.as_ref().unwrap_or(&input_ids),
+                            reasoning_budget,
+                        );
+                    }
+
+                    //            Complete the end suffix before reaching the maximum length
+                    if let Some(end_suffix_ids) = &gen_opt.end_suffix_ids {
+                        self.enforce_end_suffix(
+                            &mut scores,
+                            group_input_ids.as_ref().unwrap_or(&input_ids),
+                            cur_len,
+                            gen_opt.max_length,
+                            end_suffix_ids,
+                            gen_opt.eos_token_ids.as_ref(),
+                        );
+                    }
 
                     //            Update scores with diversity penalty
                     if num_beam_groups > 1 {
@@ -869,6 +2677,29 @@ pub(crate) mod private_generation_utils {
                         );
                     }
 
+                    //            Record the most likely candidate tokens
+                    if let (Some(num_alternatives), Some((step_ids, step_probabilities))) =
+                        (num_alternatives, step_alternatives.as_mut())
+                    {
+                        let (log_probabilities, token_ids) =
+                            scores.topk(num_alternatives, -1, true, true);
+                        if num_beam_groups > 1 {
+                            let _ = step_ids.index_copy_(
+                                0,
+                                batch_group_indices.as_ref().unwrap(),
+                                &token_ids,
+                            );
+                            let _ = step_probabilities.index_copy_(
+                                0,
+                                batch_group_indices.as_ref().unwrap(),
+                                &log_probabilities.exp(),
+                            );
+                        } else {
+                            step_ids.copy_(&token_ids);
+                            step_probabilities.copy_(&log_probabilities.exp());
+                        }
+                    }
+
                     let mut next_scores: Tensor = &scores
                         + (if num_beam_groups > 1 {
                             beam_scores
@@ -880,10 +2711,12 @@ pub(crate) mod private_generation_utils {
                         });
 
                     let (next_scores, next_tokens) = if gen_opt.do_sample {
-                        self.top_k_top_p_filtering(
+                        self.apply_warpers(
                             &mut next_scores,
-                            gen_opt.top_k,
-                            gen_opt.top_p,
+                            &score_warpers,
+                            gen_opt,
+                            batch_size,
+                            &input_ids,
                             2,
                         );
                         let _scores = next_scores
@@ -939,8 +2772,38 @@ pub(crate) mod private_generation_utils {
                                 .int64_value(&[batch_index, beam_index_pos]);
                             let beam_token_score =
                                 next_scores.double_value(&[batch_index, beam_index_pos]);
-                            hypotheses[batch_index as usize]
-                                .add(input_ids.get(effective_beam_id).copy(), beam_token_score);
+                            let hypothesis_alternatives = match (
+                                &alternative_ids,
+                                &alternative_probabilities,
+                                &step_alternatives,
+                            ) {
+                                (
+                                    Some(alternative_ids),
+                                    Some(alternative_probabilities),
+                                    Some((step_ids, step_probabilities)),
+                                ) => Some((
+                                    Tensor::cat(
+                                        &[
+                                            alternative_ids.get(effective_beam_id),
+                                            step_ids.get(effective_beam_id).unsqueeze(0),
+                                        ],
+                                        0,
+                                    ),
+                                    Tensor::cat(
+                                        &[
+                                            alternative_probabilities.get(effective_beam_id),
+                                            step_probabilities.get(effective_beam_id).unsqueeze(0),
+                                        ],
+                                        0,
+                                    ),
+                                )),
+                                _ => None,
+                            };
+                            hypotheses[batch_index as usize].add(
+                                input_ids.get(effective_beam_id).copy(),
+                                hypothesis_alternatives,
+                                beam_token_score,
+                            );
                         }
                     }
 
@@ -1005,6 +2868,20 @@ pub(crate) mod private_generation_utils {
                     -1,
                 );
                 encoder_outputs = self.reorder_cache(&mut past, encoder_outputs, &beam_indices);
+                if let Some((step_ids, step_probabilities)) = step_alternatives {
+                    alternative_ids = alternative_ids.map(|alternative_ids| {
+                        Tensor::cat(&[alternative_ids, step_ids.unsqueeze(1)], 1)
+                            .index_select(0, &beam_indices)
+                    });
+                    alternative_probabilities =
+                        alternative_probabilities.map(|alternative_probabilities| {
+                            Tensor::cat(
+                                &[alternative_probabilities, step_probabilities.unsqueeze(1)],
+                                1,
+                            )
+                            .index_select(0, &beam_indices)
+                        });
+                }
 
                 if !self.is_encoder_decoder() {
                     attention_mask = Tensor::cat(
@@ -1023,6 +2900,31 @@ pub(crate) mod private_generation_utils {
                 current_length += 1;
             }
 
+            //        A paused beam search returns its state, the hypotheses are selected once it is complete
+            if resumable && (current_length < gen_opt.max_length) && !done.iter().all(|&x| x) {
+                let unfinished_sentences = Tensor::of_slice(
+                    &done
+                        .iter()
+                        .flat_map(|done| vec![!*done as i64; gen_opt.num_beams as usize])
+                        .collect::<Vec<i64>>(),
+                );
+                return GeneratedOutput {
+                    indices: input_ids,
+                    scores: beam_scores.shallow_clone(),
+                    cache_memory,
+                    top_k_alternatives: None,
+                    state: Some(DecodingState {
+                        past,
+                        scores: beam_scores,
+                        unfinished_sentences,
+                        hypotheses,
+                        done,
+                        max_steps,
+                        rng_seed,
+                    }),
+                };
+            }
+
             let mut batch_index = 0i64;
 
             loop {
@@ -1037,7 +2939,20 @@ pub(crate) mod private_generation_utils {
                     let effective_beam_id = batch_index * gen_opt.num_beams + beam_index;
                     let final_score = f64::from(beam_scores.get(effective_beam_id));
                     let final_tokens = input_ids.get(effective_beam_id);
-                    hypotheses[batch_index as usize].add(final_tokens, final_score);
+                    let final_alternatives = alternative_ids
+                        .as_ref()
+                        .zip(alternative_probabilities.as_ref())
+                        .map(|(alternative_ids, alternative_probabilities)| {
+                            (
+                                alternative_ids.get(effective_beam_id),
+                                alternative_probabilities.get(effective_beam_id),
+                            )
+                        });
+                    hypotheses[batch_index as usize].add(
+                        final_tokens,
+                        final_alternatives,
+                        final_score,
+                    );
                 }
                 batch_index += 1;
             }
@@ -1053,16 +2968,25 @@ pub(crate) mod private_generation_utils {
             let mut sentence_lengths =
                 Tensor::zeros(&[output_batch_size], (Int64, input_ids.device()));
             let mut best_ids = vec![];
+            let mut best_scores = vec![];
+            let mut top_k_alternatives = num_alternatives.map(|_| vec![]);
 
             for (hypothesis_index, hypothesis) in hypotheses.iter().enumerate() {
                 let mut sorted_hypotheses = hypothesis.clone();
                 sorted_hypotheses
                     .beams
-                    .sort_by_key(|(score, _)| OrderedFloat(*score));
+                    .sort_by_key(|(score, _, _)| OrderedFloat(*score));
                 for j in 0..output_num_return_sequences_per_batch {
                     let effective_batch_index =
                         output_num_return_sequences_per_batch * hypothesis_index as i64 + j;
-                    let (_, best_hyp) = sorted_hypotheses.beams.pop().unwrap();
+                    let (best_score, best_hyp, best_alternatives) =
+                        sorted_hypotheses.beams.pop().unwrap();
+                    best_scores.push(best_score);
+                    if let (Some(top_k_alternatives), Some((token_ids, probabilities))) =
+                        (top_k_alternatives.as_mut(), best_alternatives)
+                    {
+                        top_k_alternatives.push(collect_alternatives(&token_ids, &probabilities));
+                    }
                     let _ = sentence_lengths.index_fill_(
                         0,
                         &Tensor::of_slice(&[effective_batch_index]).to(sentence_lengths.device()),
@@ -1103,7 +3027,13 @@ pub(crate) mod private_generation_utils {
                     );
                 }
             }
-            decoded
+            GeneratedOutput {
+                indices: decoded,
+                scores: Tensor::of_slice(&best_scores),
+                cache_memory,
+                top_k_alternatives,
+                state: None,
+            }
         }
 
         fn reorder_cache(
@@ -1119,6 +3049,669 @@ pub(crate) mod private_generation_utils {
                 }
             }
         }
+
+        /// Bias towards the (non-padding) tokens of the source for each input, of shape (*batch size*, *vocab size*).
+        /// Returns `None` if the `source_copy_bias` of the configuration is 0.
+        fn get_source_copy_bias(
+            &self,
+            input_ids: &Tensor,
+            attention_mask: &Tensor,
+            eos_token_ids: Option<&Vec<i64>>,
+        ) -> Option<Tensor> {
+            let source_copy_bias = PrivateLanguageGenerator::get_config(self).source_copy_bias;
+            if source_copy_bias == 0f64 {
+                return None;
+            }
+            let source_tokens = Tensor::zeros(
+                &[input_ids.size()[0], self.get_vocab_size()],
+                (Float, input_ids.device()),
+            )
+            .scatter_add(1, input_ids, &attention_mask.to_kind(Float));
+            if let Some(eos_token_ids) = eos_token_ids {
+                for eos_token_id in eos_token_ids {
+                    let _ = source_tokens.select(1, *eos_token_id).fill_(0.0);
+                }
+            }
+            Some(source_tokens.gt(0).to_kind(Float) * source_copy_bias)
+        }
+
+        /// Resumes a generation from a snapshot (see `LanguageGenerator::resume_generation`)
+        fn resume_from_snapshot(
+            &self,
+            snapshot: &GenerationSnapshot,
+            max_steps: Option<i64>,
+        ) -> Result<GenerationSnapshot, RustBertError> {
+            let config = PrivateLanguageGenerator::get_config(self);
+            if config.token_healing {
+                return Err(RustBertError::InvalidConfigurationError(
+                    "Generation snapshots are not available with token healing".into(),
+                ));
+            }
+            let num_sequences = snapshot.token_ids.len();
+            let prompt_length = snapshot.prompt_length;
+            let mask_length = snapshot
+                .prompt_attention_mask
+                .first()
+                .map_or(0, |prompt_attention_mask| prompt_attention_mask.len());
+            let sequence_length = snapshot
+                .token_ids
+                .first()
+                .map_or(0, |token_ids| token_ids.len());
+            if (num_sequences == 0)
+                || (prompt_length == 0)
+                || (mask_length == 0)
+                || (!self.is_encoder_decoder() && (mask_length != prompt_length))
+                || (sequence_length < prompt_length)
+                || (snapshot.prompt_attention_mask.len() != num_sequences)
+                || (snapshot.scores.len() != num_sequences)
+                || (snapshot.finished.len() != num_sequences)
+                || snapshot
+                    .token_ids
+                    .iter()
+                    .any(|token_ids| token_ids.len() != sequence_length)
+                || snapshot
+                    .prompt_attention_mask
+                    .iter()
+                    .any(|prompt_attention_mask| prompt_attention_mask.len() != mask_length)
+            {
+                return Err(RustBertError::ValueError(
+                    "Invalid generation snapshot: the token ids, prompt attention mask, score and state of all sequences must be provided with consistent lengths".into(),
+                ));
+            }
+            if snapshot.is_complete() {
+                return Ok(snapshot.clone());
+            }
+            let num_beams = config.num_beams as usize;
+            if (num_beams > 1) && (snapshot.beam_hypotheses.len() * num_beams != num_sequences) {
+                return Err(RustBertError::ValueError(
+                    "Invalid generation snapshot: the beams and finished hypotheses of each input must be provided for beam search".into(),
+                ));
+            }
+
+            let device = self.get_var_store().device();
+            let mut gen_opt =
+                self.get_generate_options(snapshot.min_length, snapshot.max_length, device);
+            //        Snapshots only contain the token ids of the sequences
+            gen_opt.output_top_k_alternatives = None;
+            let (num_sequences, prompt_length, mask_length, sequence_length) = (
+                num_sequences as i64,
+                prompt_length as i64,
+                mask_length as i64,
+                sequence_length as i64,
+            );
+            let input_ids = Tensor::of_slice(&snapshot.token_ids.concat())
+                .view((num_sequences, sequence_length))
+                .to(device);
+            let prompt_attention_mask = Tensor::of_slice(&snapshot.prompt_attention_mask.concat())
+                .view((num_sequences, mask_length))
+                .to(device);
+            let (encoder_outputs, attention_mask, source_copy_bias) = if self.is_encoder_decoder() {
+                let encoder_outputs = snapshot.tensor("encoder_outputs", device).ok_or_else(|| {
+                    RustBertError::ValueError(
+                        "The encoder outputs of the generation snapshot are missing: load its tensors with `GenerationSnapshot::load_tensors`".into(),
+                    )
+                })?;
+                (
+                    Some(encoder_outputs),
+                    prompt_attention_mask,
+                    snapshot.tensor("source_copy_bias", device),
+                )
+            } else {
+                let source_copy_bias = self.get_source_copy_bias(
+                    &input_ids.narrow(1, 0, prompt_length),
+                    &prompt_attention_mask,
+                    gen_opt.eos_token_ids.as_ref(),
+                );
+                let attention_mask = Tensor::cat(
+                    &[
+                        prompt_attention_mask,
+                        Tensor::ones(
+                            &[num_sequences, sequence_length - prompt_length],
+                            (Int64, device),
+                        ),
+                    ],
+                    1,
+                );
+                (None, attention_mask, source_copy_bias)
+            };
+            let row_logits_biases = RowLogitsBiases {
+                source_copy_bias: source_copy_bias.as_ref().map(Tensor::shallow_clone),
+                token_healing_mask: None,
+                blocked_ngrams: None,
+            };
+            let initial_state = DecodingState {
+                past: snapshot.cache(device)?,
+                scores: Tensor::of_slice(&snapshot.scores).to_kind(Float),
+                unfinished_sentences: Tensor::of_slice(
+                    &snapshot
+                        .finished
+                        .iter()
+                        .map(|finished| !*finished as i64)
+                        .collect::<Vec<i64>>(),
+                ),
+                hypotheses: snapshot
+                    .beam_hypotheses
+                    .iter()
+                    .map(|beams| {
+                        BeamHypotheses::from_snapshot(
+                            beams,
+                            gen_opt.num_beams,
+                            gen_opt.max_length,
+                            gen_opt.length_penalty,
+                            gen_opt.early_stopping,
+                            device,
+                        )
+                    })
+                    .collect(),
+                done: snapshot
+                    .finished
+                    .iter()
+                    .step_by(num_beams)
+                    .copied()
+                    .collect(),
+                max_steps,
+                rng_seed: snapshot.rng_seed,
+            };
+
+            let batch_size = num_sequences / num_beams as i64;
+            let generated_output = no_grad(|| {
+                if num_beams > 1 {
+                    self.generate_beam_search(
+                        input_ids,
+                        encoder_outputs.as_ref().map(Tensor::shallow_clone),
+                        prompt_length,
+                        batch_size,
+                        attention_mask,
+                        row_logits_biases,
+                        &gen_opt,
+                        Some(initial_state),
+                    )
+                } else {
+                    self.generate_no_beam_search(
+                        input_ids,
+                        encoder_outputs.as_ref().map(Tensor::shallow_clone),
+                        prompt_length,
+                        batch_size,
+                        attention_mask,
+                        row_logits_biases,
+                        &mut gen_opt,
+                        Some(initial_state),
+                    )
+                }
+            });
+            let token_ids = Vec::<Vec<i64>>::from(generated_output.indices.to(Device::Cpu));
+            let scores = Vec::<f64>::from(generated_output.scores.to_kind(Double).to(Device::Cpu));
+            let resumed_snapshot = match generated_output.state {
+                Some(state) => {
+                    let mut tensors = state
+                        .past
+                        .named_tensors()
+                        .into_iter()
+                        .map(|(name, tensor)| (format!("cache.{}", name), tensor))
+                        .collect::<Vec<(String, Tensor)>>();
+                    if let Some(encoder_outputs) = encoder_outputs {
+                        tensors.push(("encoder_outputs".to_string(), encoder_outputs));
+                        if let Some(source_copy_bias) = source_copy_bias {
+                            tensors.push(("source_copy_bias".to_string(), source_copy_bias));
+                        }
+                    }
+                    GenerationSnapshot {
+                        token_ids,
+                        prompt_length: snapshot.prompt_length,
+                        prompt_attention_mask: snapshot.prompt_attention_mask.clone(),
+                        scores,
+                        finished: Vec::<i64>::from(state.unfinished_sentences.to(Device::Cpu))
+                            .into_iter()
+                            .map(|unfinished| unfinished == 0)
+                            .collect(),
+                        beam_hypotheses: state
+                            .hypotheses
+                            .iter()
+                            .map(BeamHypotheses::to_snapshot)
+                            .collect(),
+                        rng_seed: snapshot.rng_seed,
+                        min_length: snapshot.min_length,
+                        max_length: snapshot.max_length,
+                        tensors,
+                    }
+                }
+                //            The beam search is complete: the sequences are the selected hypotheses of each input
+                None => {
+                    let sequences_per_input = token_ids.len() / batch_size as usize;
+                    GenerationSnapshot {
+                        prompt_length: snapshot.prompt_length,
+                        prompt_attention_mask: (0..token_ids.len())
+                            .map(|sequence_index| {
+                                snapshot.prompt_attention_mask
+                                    [sequence_index / sequences_per_input * num_beams]
+                                    .clone()
+                            })
+                            .collect(),
+                        scores,
+                        finished: vec![true; token_ids.len()],
+                        token_ids,
+                        beam_hypotheses: vec![],
+                        rng_seed: snapshot.rng_seed,
+                        min_length: snapshot.min_length,
+                        max_length: snapshot.max_length,
+                        tensors: vec![],
+                    }
+                }
+            };
+            //        The tensors of a complete generation are not needed anymore
+            Ok(if resumed_snapshot.is_complete() {
+                GenerationSnapshot {
+                    tensors: vec![],
+                    ..resumed_snapshot
+                }
+            } else {
+                resumed_snapshot
+            })
+        }
+
+        /// Builds the decoding options from the generation configuration
+        fn get_generate_options(
+            &self,
+            min_length: i64,
+            max_length: i64,
+            device: Device,
+        ) -> GenerateOptions {
+            let eos_token_ids = PrivateLanguageGenerator::get_eos_ids(self).clone();
+
+            let config = PrivateLanguageGenerator::get_config(self);
+            let do_sample = config.do_sample;
+            let num_return_sequences = config.num_return_sequences;
+            let num_beams = config.num_beams;
+            let early_stopping = config.early_stopping;
+            let temperature = config.temperature;
+            let top_k = config.top_k;
+            let top_p = config.top_p;
+            let repetition_penalty = config.repetition_penalty;
+            let repetition_penalty_mode = config.repetition_penalty_mode;
+            let warper_order = config.warper_order.clone();
+            let output_top_k_alternatives = config.output_top_k_alternatives;
+            let output_cache_memory = config.output_cache_memory;
+            let frequency_penalty = config.frequency_penalty;
+            let presence_penalty = config.presence_penalty;
+            let length_penalty = config.length_penalty;
+            let no_repeat_ngram_size = config.no_repeat_ngram_size;
+            let num_beam_groups = config.num_beam_groups;
+            let diversity_penalty = config.diversity_penalty;
+            let reasoning_budget = config.reasoning_budget;
+            let eos_probability_threshold = config.eos_probability_threshold;
+            let length_bias = config.length_bias;
+            let end_suffix_ids = config.end_suffix_ids.clone();
+            let max_output_bytes = config.max_output_bytes;
+            let max_output_chars = config.max_output_chars;
+            let corpus_penalty = config.corpus_penalty.as_ref().map(|corpus_penalty| {
+                let vocab_size = self.get_vocab_size();
+                let (token_ids, penalties): (Vec<i64>, Vec<f64>) = corpus_penalty
+                    .iter()
+                    .map(|(token_id, penalty)| (*token_id, *penalty))
+                    .unzip();
+                Tensor::zeros(&[vocab_size], (Float, device)).index_copy(
+                    0,
+                    &Tensor::of_slice(&token_ids).to(device),
+                    &Tensor::of_slice(&penalties).to_kind(Float).to(device),
+                )
+            });
+
+            let pad_token_id = match self.get_pad_id() {
+                Some(value) => Some(*value),
+                None => match &eos_token_ids {
+                    Some(eos_ids) => Some(eos_ids[0]),
+                    None => None,
+                },
+            };
+
+            GenerateOptions {
+                min_length,
+                max_length,
+                do_sample,
+                temperature,
+                top_k,
+                top_p,
+                repetition_penalty,
+                repetition_penalty_mode,
+                warper_order,
+                output_top_k_alternatives,
+                output_cache_memory,
+                frequency_penalty,
+                presence_penalty,
+                no_repeat_ngram_size,
+                pad_token_id,
+                eos_token_ids,
+                num_return_sequences,
+                early_stopping,
+                num_beams,
+                length_penalty,
+                num_beam_groups,
+                diversity_penalty,
+                corpus_penalty,
+                reasoning_budget,
+                eos_probability_threshold,
+                length_bias,
+                end_suffix_ids,
+                max_output_bytes,
+                max_output_chars,
+                on_trigger: None,
+            }
+        }
+
+        /// Generates token indices from the prompt token ids. For encoder-decoder models, pre-computed (non-expanded)
+        /// encoder outputs may be provided to avoid encoding the same input several times.
+        fn generate_from_encoder_outputs(
+            &self,
+            input_ids: Tensor,
+            encoder_outputs: Option<Tensor>,
+            attention_mask: Option<Tensor>,
+            min_length: Option<i64>,
+            max_length: Option<i64>,
+            decoder_start_token_id: Option<i64>,
+            blocked_ngrams: Option<&[Vec<Vec<i64>>]>,
+            on_trigger: Option<(i64, TriggerCallback)>,
+        ) -> GeneratedIndicesOutput {
+            let config = PrivateLanguageGenerator::get_config(self);
+            let mut gen_opt = self.get_generate_options(
+                min_length.unwrap_or(config.min_length),
+                max_length.unwrap_or(config.max_length),
+                input_ids.device(),
+            );
+            gen_opt.on_trigger = on_trigger;
+            let eos_token_ids = gen_opt.eos_token_ids.clone();
+            let do_sample = gen_opt.do_sample;
+            let num_return_sequences = gen_opt.num_return_sequences;
+            let num_beams = gen_opt.num_beams;
+            let output_top_k_alternatives = gen_opt.output_top_k_alternatives;
+            let reasoning_budget = gen_opt.reasoning_budget;
+            let token_healing = config.token_healing;
+            let generation_microbatch_size = config.generation_microbatch_size;
+
+            let attention_mask = match attention_mask {
+                Some(value) => value,
+                None => {
+                    build_padding_attention_mask(&input_ids, *self.get_pad_id(), PaddingSide::Left)
+                }
+            };
+
+            //        Token healing: the last prompt token is removed and the first generated token must start with its surface form
+            let (input_ids, attention_mask, token_healing_mask) =
+                match token_healing && !self.is_encoder_decoder() {
+                    true => match self.prepare_token_healing(&input_ids, &attention_mask) {
+                        Some((input_ids, attention_mask, token_healing_mask)) => {
+                            (input_ids, attention_mask, Some(token_healing_mask))
+                        }
+                        None => (input_ids, attention_mask, None),
+                    },
+                    false => (input_ids, attention_mask, None),
+                };
+
+            let input_ids_len = *input_ids.size().last().unwrap();
+            let cur_len = if !self.is_encoder_decoder() {
+                *input_ids.size().last().unwrap()
+            } else {
+                1
+            };
+            let batch_size = *input_ids.size().first().unwrap();
+
+            let (effective_batch_size, effective_batch_mult) = match do_sample {
+                true => (
+                    batch_size * num_return_sequences as i64,
+                    num_return_sequences as i64,
+                ),
+                false => (batch_size, 1),
+            };
+
+            let prompt_tokens = Vec::<i64>::from(attention_mask.sum1(&[1], false, Int64));
+
+            let expanded_batch_indices = Tensor::arange(batch_size, (Int64, input_ids.device()))
+                .view((-1, 1))
+                .repeat(&[1, num_beams as i64 * effective_batch_mult])
+                .view(-1);
+
+            //        Bias towards the (non-padding) tokens of the source, computed once and shared by all beams and sequences of an input
+            let source_copy_bias = self
+                .get_source_copy_bias(&input_ids, &attention_mask, eos_token_ids.as_ref())
+                .map(|source_copy_bias| source_copy_bias.index_select(0, &expanded_batch_indices));
+            let row_logits_biases = RowLogitsBiases {
+                source_copy_bias,
+                token_healing_mask: token_healing_mask.map(|token_healing_mask| {
+                    token_healing_mask.index_select(0, &expanded_batch_indices)
+                }),
+                blocked_ngrams: blocked_ngrams.map(|blocked_ngrams| {
+                    Vec::<i64>::from(&expanded_batch_indices)
+                        .into_iter()
+                        .map(|batch_index| blocked_ngrams[batch_index as usize].clone())
+                        .collect()
+                }),
+            };
+
+            let encoder_outputs = if self.is_encoder_decoder() {
+                let encoder_outputs = encoder_outputs.unwrap_or_else(|| {
+                    no_grad(|| self.encode(&input_ids, Some(&attention_mask)).unwrap())
+                });
+                assert_no_grad(&encoder_outputs);
+                Some(encoder_outputs.index_select(0, &expanded_batch_indices))
+            } else {
+                None
+            };
+
+            let (input_ids, attention_mask) = if !self.is_encoder_decoder() {
+                if (num_return_sequences > 1) | (num_beams > 1) {
+                    (
+                        input_ids
+                            .unsqueeze(1)
+                            .expand(
+                                &[batch_size, effective_batch_mult * num_beams as i64, cur_len],
+                                true,
+                            )
+                            .contiguous()
+                            .view((effective_batch_size * num_beams as i64, cur_len)),
+                        attention_mask
+                            .unsqueeze(1)
+                            .expand(
+                                &[batch_size, effective_batch_mult * num_beams as i64, cur_len],
+                                true,
+                            )
+                            .contiguous()
+                            .view((effective_batch_size * num_beams as i64, cur_len)),
+                    )
+                } else {
+                    (input_ids, attention_mask)
+                }
+            } else {
+                let decoder_start_token_id = decoder_start_token_id.unwrap_or_else(|| {
+                    self.get_decoder_start_id()
+                        .expect("decoder start id must be specified for encoder decoders")
+                });
+                let input_ids = Tensor::full(
+                    &[effective_batch_size * num_beams as i64, 1],
+                    decoder_start_token_id,
+                    (Int64, input_ids.device()),
+                );
+                let attention_mask = if (num_return_sequences > 1) | (num_beams > 1) {
+                    attention_mask
+                        .unsqueeze(1)
+                        .expand(
+                            &[
+                                batch_size,
+                                effective_batch_mult * num_beams as i64,
+                                input_ids_len,
+                            ],
+                            true,
+                        )
+                        .contiguous()
+                        .view((effective_batch_size * num_beams as i64, input_ids_len))
+                } else {
+                    attention_mask
+                };
+                (input_ids, attention_mask)
+            };
+
+            //        Split the expanded batch into micro-batches, keeping all beams of an input in the same micro-batch
+            let microbatch_size = match generation_microbatch_size {
+                Some(generation_microbatch_size) => {
+                    std::cmp::max(generation_microbatch_size / num_beams, 1)
+                }
+                None => effective_batch_size,
+            };
+            let generated_output = no_grad(|| {
+                let mut microbatch_outputs = vec![];
+                let mut microbatch_scores = vec![];
+                let mut cache_memory = vec![];
+                let mut top_k_alternatives = output_top_k_alternatives.map(|_| vec![]);
+                for microbatch_start in (0..effective_batch_size).step_by(microbatch_size as usize)
+                {
+                    let microbatch_length =
+                        std::cmp::min(microbatch_size, effective_batch_size - microbatch_start);
+                    let (row_start, row_length) =
+                        (microbatch_start * num_beams, microbatch_length * num_beams);
+                    let microbatch_input_ids = input_ids.narrow(0, row_start, row_length);
+                    let microbatch_attention_mask = attention_mask.narrow(0, row_start, row_length);
+                    let microbatch_encoder_outputs = encoder_outputs
+                        .as_ref()
+                        .map(|encoder_outputs| encoder_outputs.narrow(0, row_start, row_length));
+                    let microbatch_row_logits_biases =
+                        row_logits_biases.narrow(row_start, row_length);
+                    let microbatch_output = if num_beams > 1 {
+                        self.generate_beam_search(
+                            microbatch_input_ids,
+                            microbatch_encoder_outputs,
+                            cur_len,
+                            microbatch_length,
+                            microbatch_attention_mask,
+                            microbatch_row_logits_biases,
+                            &gen_opt,
+                            None,
+                        )
+                    } else {
+                        self.generate_no_beam_search(
+                            microbatch_input_ids,
+                            microbatch_encoder_outputs,
+                            cur_len,
+                            microbatch_length,
+                            microbatch_attention_mask,
+                            microbatch_row_logits_biases,
+                            &mut gen_opt,
+                            None,
+                        )
+                    };
+                    microbatch_outputs.push(microbatch_output.indices);
+                    microbatch_scores
+                        .push(microbatch_output.scores.to_kind(Double).to(Device::Cpu));
+                    cache_memory.extend(microbatch_output.cache_memory);
+                    if let (Some(top_k_alternatives), Some(microbatch_alternatives)) = (
+                        top_k_alternatives.as_mut(),
+                        microbatch_output.top_k_alternatives,
+                    ) {
+                        top_k_alternatives.extend(microbatch_alternatives);
+                    }
+                }
+                //        Pad the micro-batch outputs to a common length before concatenation
+                let max_output_length = microbatch_outputs
+                    .iter()
+                    .map(|output| *output.size().last().unwrap())
+                    .max()
+                    .unwrap();
+                let microbatch_outputs = microbatch_outputs
+                    .into_iter()
+                    .map(|output| {
+                        let (output_batch_size, output_length) = output.size2().unwrap();
+                        if output_length < max_output_length {
+                            Tensor::cat(
+                                &[
+                                    output.shallow_clone(),
+                                    Tensor::full(
+                                        &[output_batch_size, max_output_length - output_length],
+                                        gen_opt.pad_token_id.unwrap_or(0),
+                                        (Int64, output.device()),
+                                    ),
+                                ],
+                                1,
+                            )
+                        } else {
+                            output
+                        }
+                    })
+                    .collect::<Vec<Tensor>>();
+                GeneratedOutput {
+                    indices: Tensor::cat(&microbatch_outputs, 0),
+                    scores: Tensor::cat(&microbatch_scores, 0),
+                    cache_memory,
+                    top_k_alternatives,
+                    state: None,
+                }
+            });
+            let decoded = generated_output.indices;
+            let num_sequences = *decoded.size().first().unwrap();
+            let mut output_ids = Vec::with_capacity(num_sequences as usize);
+            for sequence_index in 0..num_sequences {
+                let sequence_output_ids = decoded
+                    .as_ref()
+                    .get(sequence_index)
+                    .iter::<i64>()
+                    .unwrap()
+                    .collect::<Vec<i64>>();
+                output_ids.push(sequence_output_ids.clone());
+            }
+            let reasoning_splits = reasoning_budget.map(|reasoning_budget| {
+                output_ids
+                    .iter()
+                    .map(|sequence| ReasoningSplit::from_sequence(sequence, &reasoning_budget))
+                    .collect()
+            });
+            let sequences_per_prompt = std::cmp::max(num_sequences / batch_size, 1) as usize;
+            let usage = output_ids
+                .iter()
+                .enumerate()
+                .map(|(sequence_index, sequence)| {
+                    GenerationUsage::from_sequence(
+                        prompt_tokens[sequence_index / sequences_per_prompt],
+                        &sequence[std::cmp::min(cur_len as usize, sequence.len())..],
+                        gen_opt.eos_token_ids.as_ref(),
+                    )
+                })
+                .collect::<Vec<GenerationUsage>>();
+            //        Alternatives are reported for the generated positions, up to and including the first EOS token
+            let top_k_alternatives =
+                generated_output
+                    .top_k_alternatives
+                    .map(|top_k_alternatives| {
+                        top_k_alternatives
+                            .into_iter()
+                            .zip(usage.iter())
+                            .map(|(sequence_alternatives, sequence_usage)| {
+                                sequence_alternatives
+                                    .into_iter()
+                                    .take(sequence_usage.completion_tokens as usize)
+                                    .map(|position_alternatives| {
+                                        position_alternatives
+                                            .into_iter()
+                                            .map(|(token_id, probability)| {
+                                                (
+                                                    self.get_tokenizer().decode(
+                                                        vec![token_id],
+                                                        false,
+                                                        false,
+                                                    ),
+                                                    probability,
+                                                )
+                                            })
+                                            .collect()
+                                    })
+                                    .collect()
+                            })
+                            .collect()
+                    });
+            GeneratedIndicesOutput {
+                indices: output_ids,
+                scores: Vec::<f64>::from(generated_output.scores),
+                cache_memory: generated_output.cache_memory,
+                reasoning_splits,
+                usage,
+                top_k_alternatives,
+            }
+        }
     }
 }
 
@@ -1127,11 +3720,12 @@ pub(crate) mod private_generation_utils {
 pub trait LanguageGenerator<T: LMHeadModel, V: Vocab, U: Tokenizer<V>>:
     PrivateLanguageGenerator<T, V, U>
 {
-    /// Generate text based on a vector of promp texts.
+    /// Generate text based on a vector of prompt texts, returning the generated texts in a flat vector (use `generate` to get them
+    /// grouped by prompt with their scores).
     ///
     /// # Arguments
     ///
-    /// * `prompt_texts` - `Option<Vec<&str>>` Optional vector of text prompts. An empty prompt to the model may be passed if the model implement a `bos_id`.
+    /// * `prompt_texts` - `Option<Vec<&str>>` Optional vector of text prompts. An empty prompt to the model may be passed if the model implement a `bos_id`. Empty or whitespace-only texts in the vector generate empty sequences.
     /// * `attention_mask` - `Option<Tensor>` Optional attention mask to hide portions of the prompt.
     ///
     /// # Returns
@@ -1170,7 +3764,7 @@ pub trait LanguageGenerator<T: LMHeadModel, V: Vocab, U: Tokenizer<V>>:
     /// let max_length = 128;
     /// let decoder_start_token_id = None;
     ///
-    /// let output = gpt2_generator.generate(
+    /// let output = gpt2_generator.generate_flat(
     ///     Some(vec![input_context, second_input_context]),
     ///     attention_mask,
     ///     min_length,
@@ -1193,7 +3787,7 @@ pub trait LanguageGenerator<T: LMHeadModel, V: Vocab, U: Tokenizer<V>>:
     /// ]
     /// # ;
     /// ```
-    fn generate<'a, S>(
+    fn generate_flat<'a, S>(
         &self,
         prompt_texts: Option<S>,
         attention_mask: Option<Tensor>,
@@ -1211,106 +3805,505 @@ pub trait LanguageGenerator<T: LMHeadModel, V: Vocab, U: Tokenizer<V>>:
             max_length,
             decoder_start_token_id,
         );
+        let detokenization_profile = self.get_config().detokenization_profile;
         let mut output = Vec::with_capacity(generated.len());
         for generated_sequence in generated {
-            output.push(self.get_tokenizer().decode(generated_sequence, true, true));
+            output.push(self.get_tokenizer().decode_with_profile(
+                generated_sequence,
+                true,
+                detokenization_profile,
+            ));
         }
         output
     }
 
-    /// Generate token indices without decoding (useful for token-level operations before returning final text or as validation step during training).
+    /// Generate text based on a vector of prompt texts, returning with each generated text the byte offsets of its tokens.
+    /// Detokenization merges tokens and removes their markers: the offsets align each token of the output sequence (including the
+    /// prompt tokens for decoder-only models) to the decoded text, e.g. to highlight generated spans. Special tokens are given an
+    /// empty range.
     ///
     /// # Arguments
     ///
     /// * `prompt_texts` - `Option<Vec<&str>>` Optional vector of text prompts. An empty prompt to the model may be passed if the model implement a `bos_id`.
     /// * `attention_mask` - `Option<Tensor>` Optional attention mask to hide portions of the prompt.
+    /// * `min_length` - `impl Into<Option<i64>>` Optional minimum output sequence length
+    /// * `max_length` - `impl Into<Option<i64>>` Optional maximum output sequence length
+    /// * `decoder_start_token_id` - `impl Into<Option<i64>>` Optional decoder start token id
     ///
     /// # Returns
-    /// * `Vec<Vec<i64>>` Vector of Vector of generated token indices based on the prompts of length *number_of_prompts* x *num_return_sequences*.
+    /// * `Vec<(String, Vec<(usize, usize)>)>` Vector of length *number_of_prompts* x *num_return_sequences* containing the generated texts and the byte offsets (start, end) of each token in the text.
     ///
     /// # Example
     ///
     /// ```no_run
-    /// # use std::path::PathBuf;
-    /// # use tch::Device;
     /// # fn main() -> anyhow::Result<()> {
     /// use rust_bert::gpt2::GPT2Generator;
-    /// use rust_bert::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
-    /// # let mut home: PathBuf = dirs::home_dir().unwrap();
-    /// # home.push("rustbert");
-    /// # home.push("gpt2");
-    /// # let config_path = &home.as_path().join("config.json");
-    /// # let vocab_path = &home.as_path().join("vocab.txt");
-    /// # let merges_path = &home.as_path().join("merges.txt");
-    /// # let weights_path = &home.as_path().join("model.ot");
-    /// let device = Device::cuda_if_available();
-    /// let generate_config = GenerateConfig {
-    ///     max_length: 30,
-    ///     do_sample: true,
-    ///     num_beams: 5,
-    ///     temperature: 1.1,
-    ///     num_return_sequences: 3,
-    ///     ..Default::default()
-    /// };
-    /// let mut gpt2_generator = GPT2Generator::new(generate_config)?;
-    /// let input_context = "The dog";
-    /// let second_input_context = "The cat was";
-    /// let attention_mask = None;
-    /// let min_length = 32;
-    /// let max_length = 128;
-    /// let decoder_start_token_id = None;
+    /// use rust_bert::pipelines::generation_utils::LanguageGenerator;
+    /// let gpt2_generator = GPT2Generator::new(Default::default())?;
     ///
-    /// let output = gpt2_generator.generate_indices(
-    ///     Some(vec![input_context, second_input_context]),
-    ///     attention_mask,
-    ///     min_length,
-    ///     max_length,
-    ///     decoder_start_token_id,
-    /// );
+    /// let output = gpt2_generator.generate_with_offsets(Some(&["The dog"]), None, None, None, None);
+    /// for (text, offsets) in output {
+    ///     for (start, end) in offsets {
+    ///         println!("{}", &text[start..end]);
+    ///     }
+    /// }
     /// # Ok(())
     /// # }
     /// ```
-    fn generate_indices<'a, S>(
+    fn generate_with_offsets<'a, S>(
         &self,
         prompt_texts: Option<S>,
         attention_mask: Option<Tensor>,
         min_length: impl Into<Option<i64>>,
         max_length: impl Into<Option<i64>>,
         decoder_start_token_id: impl Into<Option<i64>>,
-    ) -> Vec<Vec<i64>>
+    ) -> Vec<(String, Vec<(usize, usize)>)>
     where
         S: AsRef<[&'a str]>,
     {
-        let eos_token_ids = PrivateLanguageGenerator::get_eos_ids(self).clone();
-
-        let config = PrivateLanguageGenerator::get_config(self);
-        let max_length = max_length.into().unwrap_or(config.max_length);
-        let encoding_max_len = if self.is_encoder_decoder() {
-            1024i64
-        } else {
-            max_length
-        };
-        let pad_token_id = match self.get_pad_id() {
-            Some(value) => Some(*value),
-            None => match &eos_token_ids {
-                Some(eos_ids) => Some(eos_ids[0]),
-                None => None,
-            },
-        };
-
-        let input_ids = match prompt_texts {
-            Some(text) => self.encode_prompt_text(text, encoding_max_len, pad_token_id),
-            None => match self.get_bos_id() {
-                Some(bos_id) => {
-                    Tensor::ones(&[1, 1], (Int64, self.get_var_store().device())) * *bos_id
-                }
-                None => panic!(
-                    "A model with a BOS token must be used to start generation with an empty input"
-                ),
-            },
-        };
-        self.generate_from_ids_and_past(
-            input_ids,
+        let generated = self.generate_indices(
+            prompt_texts,
+            attention_mask,
+            min_length,
+            max_length,
+            decoder_start_token_id,
+        );
+        let detokenization_profile = self.get_config().detokenization_profile;
+        generated
+            .iter()
+            .map(|generated_sequence| {
+                self.get_tokenizer().decode_with_offsets(
+                    generated_sequence,
+                    true,
+                    detokenization_profile,
+                )
+            })
+            .collect()
+    }
+
+    /// Generate text for the active subset of a batch of prompt texts. Inactive prompts (e.g. conversations awaiting a user input)
+    /// are not passed to the model and are returned unchanged, so that they do not consume any decoding compute. Active sequences
+    /// stop individually when they generate an end of sequence token: finished sequences are padded until all active sequences of
+    /// the batch are finished (or the maximum length is reached).
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt_texts` - `&[&str]` Vector of text prompts. Empty or whitespace-only texts generate empty sequences.
+    /// * `active_mask` - `&[bool]` Flags indicating for each prompt if it should be generated (true) or passed through unchanged (false). Must have the same length as `prompt_texts`.
+    /// * `attention_mask` - `Option<Tensor>` Optional attention mask to hide portions of the prompt, for all prompts (including inactive ones).
+    ///
+    /// # Returns
+    /// * `Result<Vec<String>, RustBertError>` Vector of generated strings of length *number_of_prompts* x *num_return_sequences*, where inactive prompts are repeated as-is.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::Device;
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::gpt2::GPT2Generator;
+    /// use rust_bert::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+    /// let gpt2_generator = GPT2Generator::new(Default::default())?;
+    ///
+    /// let output = gpt2_generator.generate_with_active_mask(
+    ///     &["The dog", "Awaiting user input"],
+    ///     &[true, false],
+    ///     None,
+    ///     None,
+    ///     None,
+    ///     None,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn generate_with_active_mask(
+        &self,
+        prompt_texts: &[&str],
+        active_mask: &[bool],
+        attention_mask: Option<Tensor>,
+        min_length: impl Into<Option<i64>>,
+        max_length: impl Into<Option<i64>>,
+        decoder_start_token_id: impl Into<Option<i64>>,
+    ) -> Result<Vec<String>, RustBertError> {
+        if active_mask.len() != prompt_texts.len() {
+            return Err(RustBertError::ValueError(format!(
+                "The active mask length ({}) must match the number of prompts ({})",
+                active_mask.len(),
+                prompt_texts.len()
+            )));
+        }
+        let num_return_sequences =
+            PrivateLanguageGenerator::get_config(self).num_return_sequences as usize;
+        let active_indices = active_mask
+            .iter()
+            .enumerate()
+            .filter(|(_, active)| **active)
+            .map(|(index, _)| index as i64)
+            .collect::<Vec<i64>>();
+        let generated = if active_indices.is_empty() {
+            vec![]
+        } else {
+            let attention_mask = attention_mask.map(|attention_mask| {
+                attention_mask.index_select(
+                    0,
+                    &Tensor::of_slice(&active_indices).to(attention_mask.device()),
+                )
+            });
+            self.generate_flat(
+                Some(
+                    active_indices
+                        .iter()
+                        .map(|index| prompt_texts[*index as usize])
+                        .collect::<Vec<&str>>(),
+                ),
+                attention_mask,
+                min_length,
+                max_length,
+                decoder_start_token_id,
+            )
+        };
+        let mut generated = generated.into_iter();
+        let mut output = Vec::with_capacity(prompt_texts.len() * num_return_sequences);
+        for (prompt_text, active) in prompt_texts.iter().zip(active_mask) {
+            for _ in 0..num_return_sequences {
+                output.push(match active {
+                    true => generated.next().unwrap(),
+                    false => prompt_text.to_string(),
+                });
+            }
+        }
+        Ok(output)
+    }
+
+    /// Generate text based on a vector of prompt texts, grouping the generated texts by prompt.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt_texts` - `Option<Vec<&str>>` Optional vector of text prompts. An empty prompt to the model may be passed if the model implement a `bos_id`. Empty or whitespace-only texts in the vector generate empty sequences.
+    /// * `attention_mask` - `Option<Tensor>` Optional attention mask to hide portions of the prompt.
+    ///
+    /// # Returns
+    /// * `Vec<Vec<GeneratedText>>` Generated texts with their scores, with one vector for each prompt (in the order of the prompts) holding its *num_return_sequences* generated texts sorted by decreasing score.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::Device;
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::gpt2::GPT2Generator;
+    /// use rust_bert::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+    /// let generate_config = GenerateConfig {
+    ///     max_length: 30,
+    ///     num_beams: 5,
+    ///     num_return_sequences: 3,
+    ///     ..Default::default()
+    /// };
+    /// let gpt2_generator = GPT2Generator::new(generate_config)?;
+    ///
+    /// let output = gpt2_generator.generate(
+    ///     Some(vec!["The dog", "The cat was"]),
+    ///     None,
+    ///     None,
+    ///     None,
+    ///     None,
+    /// );
+    /// let best_cat_continuation = &output[1][0].text;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn generate<'a, S>(
+        &self,
+        prompt_texts: Option<S>,
+        attention_mask: Option<Tensor>,
+        min_length: impl Into<Option<i64>>,
+        max_length: impl Into<Option<i64>>,
+        decoder_start_token_id: impl Into<Option<i64>>,
+    ) -> Vec<Vec<GeneratedText>>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let num_return_sequences =
+            PrivateLanguageGenerator::get_config(self).num_return_sequences as usize;
+        let generated = self.generate_indices_with_output(
+            prompt_texts,
+            attention_mask,
+            min_length,
+            max_length,
+            decoder_start_token_id,
+        );
+        let detokenization_profile = self.get_config().detokenization_profile;
+        let texts = generated
+            .indices
+            .into_iter()
+            .map(|generated_sequence| {
+                self.get_tokenizer().decode_with_profile(
+                    generated_sequence,
+                    true,
+                    detokenization_profile,
+                )
+            })
+            .collect::<Vec<String>>();
+        group_generated_texts(texts, generated.scores, num_return_sequences, None)
+    }
+
+    /// Generate token indices without decoding (useful for token-level operations before returning final text or as validation step during training).
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt_texts` - `Option<Vec<&str>>` Optional vector of text prompts. An empty prompt to the model may be passed if the model implement a `bos_id`. Empty or whitespace-only texts in the vector generate empty sequences.
+    /// * `attention_mask` - `Option<Tensor>` Optional attention mask to hide portions of the prompt.
+    ///
+    /// # Returns
+    /// * `Vec<Vec<i64>>` Vector of Vector of generated token indices based on the prompts of length *number_of_prompts* x *num_return_sequences*.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::path::PathBuf;
+    /// # use tch::Device;
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::gpt2::GPT2Generator;
+    /// use rust_bert::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+    /// # let mut home: PathBuf = dirs::home_dir().unwrap();
+    /// # home.push("rustbert");
+    /// # home.push("gpt2");
+    /// # let config_path = &home.as_path().join("config.json");
+    /// # let vocab_path = &home.as_path().join("vocab.txt");
+    /// # let merges_path = &home.as_path().join("merges.txt");
+    /// # let weights_path = &home.as_path().join("model.ot");
+    /// let device = Device::cuda_if_available();
+    /// let generate_config = GenerateConfig {
+    ///     max_length: 30,
+    ///     do_sample: true,
+    ///     num_beams: 5,
+    ///     temperature: 1.1,
+    ///     num_return_sequences: 3,
+    ///     ..Default::default()
+    /// };
+    /// let mut gpt2_generator = GPT2Generator::new(generate_config)?;
+    /// let input_context = "The dog";
+    /// let second_input_context = "The cat was";
+    /// let attention_mask = None;
+    /// let min_length = 32;
+    /// let max_length = 128;
+    /// let decoder_start_token_id = None;
+    ///
+    /// let output = gpt2_generator.generate_indices(
+    ///     Some(vec![input_context, second_input_context]),
+    ///     attention_mask,
+    ///     min_length,
+    ///     max_length,
+    ///     decoder_start_token_id,
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn generate_indices<'a, S>(
+        &self,
+        prompt_texts: Option<S>,
+        attention_mask: Option<Tensor>,
+        min_length: impl Into<Option<i64>>,
+        max_length: impl Into<Option<i64>>,
+        decoder_start_token_id: impl Into<Option<i64>>,
+    ) -> Vec<Vec<i64>>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        self.generate_indices_with_output(
+            prompt_texts,
+            attention_mask,
+            min_length,
+            max_length,
+            decoder_start_token_id,
+        )
+        .indices
+    }
+
+    /// Generate token indices without decoding, also returning statistics collected during generation
+    /// (e.g. the memory footprint of the key/value cache after each decoding step, useful for capacity planning).
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt_texts` - `Option<Vec<&str>>` Optional vector of text prompts. An empty prompt to the model may be passed if the model implement a `bos_id`. Empty or whitespace-only texts in the vector generate empty sequences.
+    /// * `attention_mask` - `Option<Tensor>` Optional attention mask to hide portions of the prompt.
+    ///
+    /// # Returns
+    /// * `GeneratedIndicesOutput` containing the generated token indices (*number_of_prompts* x *num_return_sequences*) and generation statistics.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::Device;
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::gpt2::GPT2Generator;
+    /// use rust_bert::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+    /// let generate_config = GenerateConfig {
+    ///     max_length: 30,
+    ///     num_beams: 5,
+    ///     output_cache_memory: true,
+    ///     ..Default::default()
+    /// };
+    /// let gpt2_generator = GPT2Generator::new(generate_config)?;
+    /// let input_context = "The dog";
+    ///
+    /// let output = gpt2_generator.generate_indices_with_output(
+    ///     Some(vec![input_context]),
+    ///     None,
+    ///     None,
+    ///     None,
+    ///     None,
+    /// );
+    /// let peak_cache_memory = output.cache_memory.iter().max();
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn generate_indices_with_output<'a, S>(
+        &self,
+        prompt_texts: Option<S>,
+        attention_mask: Option<Tensor>,
+        min_length: impl Into<Option<i64>>,
+        max_length: impl Into<Option<i64>>,
+        decoder_start_token_id: impl Into<Option<i64>>,
+    ) -> GeneratedIndicesOutput
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let eos_token_ids = PrivateLanguageGenerator::get_eos_ids(self).clone();
+
+        let config = PrivateLanguageGenerator::get_config(self);
+        let min_length = min_length.into();
+        let max_length = max_length.into().unwrap_or(config.max_length);
+        let decoder_start_token_id = decoder_start_token_id.into();
+
+        //        Empty or whitespace-only prompts generate empty sequences and are not passed to the model
+        let prompt_texts = prompt_texts.map(|texts| texts.as_ref().to_vec());
+        if let Some(texts) = &prompt_texts {
+            let non_empty_indices = texts
+                .iter()
+                .enumerate()
+                .filter(|(_, text)| !text.trim().is_empty())
+                .map(|(index, _)| index as i64)
+                .collect::<Vec<i64>>();
+            if non_empty_indices.len() < texts.len() {
+                let generated = if non_empty_indices.is_empty() {
+                    GeneratedIndicesOutput {
+                        indices: vec![],
+                        scores: vec![],
+                        cache_memory: vec![],
+                        reasoning_splits: None,
+                        usage: vec![],
+                        top_k_alternatives: None,
+                    }
+                } else {
+                    let attention_mask = attention_mask.map(|attention_mask| {
+                        attention_mask.index_select(
+                            0,
+                            &Tensor::of_slice(&non_empty_indices).to(attention_mask.device()),
+                        )
+                    });
+                    self.generate_indices_with_output(
+                        Some(
+                            non_empty_indices
+                                .iter()
+                                .map(|index| texts[*index as usize])
+                                .collect::<Vec<&str>>(),
+                        ),
+                        attention_mask,
+                        min_length,
+                        max_length,
+                        decoder_start_token_id,
+                    )
+                };
+                let mut generated_indices = generated.indices.into_iter();
+                let mut generated_scores = generated.scores.into_iter();
+                let mut generated_usage = generated.usage.into_iter();
+                let mut generated_reasoning_splits = generated
+                    .reasoning_splits
+                    .map(|reasoning_splits| reasoning_splits.into_iter());
+                let mut generated_top_k_alternatives = generated
+                    .top_k_alternatives
+                    .map(|top_k_alternatives| top_k_alternatives.into_iter());
+                let mut indices = vec![];
+                let mut scores = vec![];
+                let mut usage = vec![];
+                let mut reasoning_splits = config.reasoning_budget.map(|_| vec![]);
+                let mut top_k_alternatives = config.output_top_k_alternatives.map(|_| vec![]);
+                for text in texts {
+                    let is_empty = text.trim().is_empty();
+                    for _ in 0..config.num_return_sequences {
+                        if is_empty {
+                            indices.push(vec![]);
+                            scores.push(0.0);
+                            usage.push(GenerationUsage {
+                                prompt_tokens: 0,
+                                completion_tokens: 0,
+                                total_tokens: 0,
+                            });
+                            if let Some(reasoning_splits) = reasoning_splits.as_mut() {
+                                reasoning_splits.push(ReasoningSplit {
+                                    reasoning: vec![],
+                                    answer: vec![],
+                                });
+                            }
+                            if let Some(top_k_alternatives) = top_k_alternatives.as_mut() {
+                                top_k_alternatives.push(vec![]);
+                            }
+                        } else {
+                            indices.push(generated_indices.next().unwrap());
+                            scores.push(generated_scores.next().unwrap());
+                            usage.push(generated_usage.next().unwrap());
+                            if let (Some(reasoning_splits), Some(generated_reasoning_splits)) = (
+                                reasoning_splits.as_mut(),
+                                generated_reasoning_splits.as_mut(),
+                            ) {
+                                reasoning_splits.push(generated_reasoning_splits.next().unwrap());
+                            }
+                            if let (Some(top_k_alternatives), Some(generated_top_k_alternatives)) = (
+                                top_k_alternatives.as_mut(),
+                                generated_top_k_alternatives.as_mut(),
+                            ) {
+                                top_k_alternatives
+                                    .push(generated_top_k_alternatives.next().unwrap());
+                            }
+                        }
+                    }
+                }
+                return GeneratedIndicesOutput {
+                    indices,
+                    scores,
+                    cache_memory: generated.cache_memory,
+                    reasoning_splits,
+                    usage,
+                    top_k_alternatives,
+                };
+            }
+        }
+
+        let encoding_max_len = if self.is_encoder_decoder() {
+            1024i64
+        } else {
+            max_length
+        };
+        let pad_token_id = match self.get_pad_id() {
+            Some(value) => Some(*value),
+            None => match &eos_token_ids {
+                Some(eos_ids) => Some(eos_ids[0]),
+                None => None,
+            },
+        };
+
+        let input_ids = match prompt_texts {
+            Some(text) => self.encode_prompt_text(text, encoding_max_len, pad_token_id),
+            None => match self.get_bos_id() {
+                Some(bos_id) => {
+                    Tensor::ones(&[1, 1], (Int64, self.get_var_store().device())) * *bos_id
+                }
+                None => panic!(
+                    "A model with a BOS token must be used to start generation with an empty input"
+                ),
+            },
+        };
+        self.generate_from_ids_and_past_with_output(
+            input_ids,
             attention_mask,
             min_length,
             max_length,
@@ -1320,187 +4313,635 @@ pub trait LanguageGenerator<T: LMHeadModel, V: Vocab, U: Tokenizer<V>>:
 
     fn generate_from_ids_and_past(
         &self,
-        input_ids: Tensor,
-        attention_mask: Option<Tensor>,
+        input_ids: Tensor,
+        attention_mask: Option<Tensor>,
+        min_length: impl Into<Option<i64>>,
+        max_length: impl Into<Option<i64>>,
+        decoder_start_token_id: impl Into<Option<i64>>,
+    ) -> Vec<Vec<i64>> {
+        self.generate_from_ids_and_past_with_output(
+            input_ids,
+            attention_mask,
+            min_length,
+            max_length,
+            decoder_start_token_id,
+        )
+        .indices
+    }
+
+    fn generate_from_ids_and_past_with_output(
+        &self,
+        input_ids: Tensor,
+        attention_mask: Option<Tensor>,
+        min_length: impl Into<Option<i64>>,
+        max_length: impl Into<Option<i64>>,
+        decoder_start_token_id: impl Into<Option<i64>>,
+    ) -> GeneratedIndicesOutput {
+        self.generate_from_encoder_outputs(
+            input_ids,
+            None,
+            attention_mask,
+            min_length.into(),
+            max_length.into(),
+            decoder_start_token_id.into(),
+            None,
+            None,
+        )
+    }
+
+    /// Generate token indices given a list of indices (with past), preventing the generation of a list of n-grams for
+    /// each input (e.g. phrases already used in previous turns of a conversation). A token is banned if it completes one of
+    /// the n-grams of its input given the previously generated tokens (n-grams of a single token are always banned).
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - `Tensor` of shape (*batch size*, *sequence length*) with the token indices of the prompts
+    /// * `attention_mask` - `Option<Tensor>` Optional attention mask to hide portions of the prompt.
+    /// * `blocked_ngrams` - `&[Vec<Vec<i64>>]` n-grams that cannot be generated, for each input of the batch
+    /// * `min_length` - `impl Into<Option<i64>>` Optional minimum output sequence length
+    /// * `max_length` - `impl Into<Option<i64>>` Optional maximum output sequence length
+    /// * `decoder_start_token_id` - `impl Into<Option<i64>>` Optional decoder start token id
+    ///
+    /// # Returns
+    /// * `Result<Vec<Vec<i64>>, RustBertError>` Vector of length *number_of_prompts* x *num_return_sequences* containing the generated token indices, or an error if the number of n-gram lists does not match the number of prompts.
+    fn generate_from_ids_and_past_with_blocked_ngrams(
+        &self,
+        input_ids: Tensor,
+        attention_mask: Option<Tensor>,
+        blocked_ngrams: &[Vec<Vec<i64>>],
+        min_length: impl Into<Option<i64>>,
+        max_length: impl Into<Option<i64>>,
+        decoder_start_token_id: impl Into<Option<i64>>,
+    ) -> Result<Vec<Vec<i64>>, RustBertError> {
+        let batch_size = input_ids.size()[0];
+        if blocked_ngrams.len() as i64 != batch_size {
+            return Err(RustBertError::ValueError(format!(
+                "Blocked n-grams must be provided for each input ({} n-gram lists for {} inputs)",
+                blocked_ngrams.len(),
+                batch_size
+            )));
+        }
+        Ok(self
+            .generate_from_encoder_outputs(
+                input_ids,
+                None,
+                attention_mask,
+                min_length.into(),
+                max_length.into(),
+                decoder_start_token_id.into(),
+                Some(blocked_ngrams),
+                None,
+            )
+            .indices)
+    }
+
+    /// Generate token indices for at most `max_steps` decoding steps and return a snapshot of the generation state,
+    /// that can be serialized and resumed later with `resume_generation` (e.g. by another worker). The decoding state is
+    /// serialized with serde, the key/value cache and encoder outputs with `GenerationSnapshot::save_tensors`. Not
+    /// available with token healing.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt_texts` - `Option<Vec<&str>>` Optional vector of text prompts. An empty prompt to the model may be passed if the model implement a `bos_id`.
+    /// * `min_length` - `impl Into<Option<i64>>` optional minimum output sequence length. If not provided, will use the default value set in the generation configuration
+    /// * `max_length` - `impl Into<Option<i64>>` optional maximum output sequence length. If not provided, will use the default value set in the generation configuration
+    /// * `max_steps` - `i64` maximum number of decoding steps to run before returning the snapshot
+    ///
+    /// # Returns
+    /// * `Result<GenerationSnapshot, RustBertError>` snapshot of the generation state
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::gpt2::GPT2Generator;
+    /// use rust_bert::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+    /// use tch::Device;
+    /// let generate_config = GenerateConfig {
+    ///     max_length: 100,
+    ///     ..Default::default()
+    /// };
+    /// let gpt2_generator = GPT2Generator::new(generate_config)?;
+    ///
+    /// let mut snapshot = gpt2_generator.generate_snapshot(Some(&["The dog"]), None, None, 10)?;
+    /// let serialized_snapshot = serde_json::to_string(&snapshot)?;
+    /// snapshot.save_tensors("path/to/snapshot.ot")?;
+    /// // ...
+    /// snapshot = serde_json::from_str(&serialized_snapshot)?;
+    /// snapshot.load_tensors("path/to/snapshot.ot", Device::cuda_if_available())?;
+    /// while !snapshot.is_complete() {
+    ///     snapshot = gpt2_generator.resume_generation(&snapshot, 10)?;
+    /// }
+    /// let output = snapshot.token_ids;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn generate_snapshot<'a, S>(
+        &self,
+        prompt_texts: Option<S>,
         min_length: impl Into<Option<i64>>,
         max_length: impl Into<Option<i64>>,
-        decoder_start_token_id: impl Into<Option<i64>>,
-    ) -> Vec<Vec<i64>> {
-        let eos_token_ids = PrivateLanguageGenerator::get_eos_ids(self).clone();
-
+        max_steps: i64,
+    ) -> Result<GenerationSnapshot, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
         let config = PrivateLanguageGenerator::get_config(self);
-        let do_sample = config.do_sample;
-        let num_return_sequences = config.num_return_sequences;
-        let num_beams = config.num_beams;
         let min_length = min_length.into().unwrap_or(config.min_length);
         let max_length = max_length.into().unwrap_or(config.max_length);
-        let early_stopping = config.early_stopping;
-        let temperature = config.temperature;
-        let top_k = config.top_k;
-        let top_p = config.top_p;
-        let repetition_penalty = config.repetition_penalty;
-        let length_penalty = config.length_penalty;
-        let no_repeat_ngram_size = config.no_repeat_ngram_size;
-        let num_beam_groups = config.num_beam_groups;
-        let diversity_penalty = config.diversity_penalty;
-
         let pad_token_id = match self.get_pad_id() {
             Some(value) => Some(*value),
-            None => match &eos_token_ids {
-                Some(eos_ids) => Some(eos_ids[0]),
-                None => None,
-            },
+            None => PrivateLanguageGenerator::get_eos_ids(self)
+                .as_ref()
+                .map(|eos_ids| eos_ids[0]),
         };
 
-        let input_ids_len = *input_ids.size().last().unwrap();
-        let cur_len = if !self.is_encoder_decoder() {
-            *input_ids.size().last().unwrap()
+        let input_ids = match prompt_texts {
+            Some(texts) => {
+                if texts.as_ref().iter().any(|text| text.trim().is_empty()) {
+                    return Err(RustBertError::ValueError(
+                        "Generation snapshots require non-empty prompts".into(),
+                    ));
+                }
+                self.encode_prompt_text(texts, max_length, pad_token_id)
+            }
+            None => match self.get_bos_id() {
+                Some(bos_id) => {
+                    Tensor::ones(&[1, 1], (Int64, self.get_var_store().device())) * *bos_id
+                }
+                None => return Err(RustBertError::ValueError(
+                    "A model with a BOS token must be used to start generation with an empty input"
+                        .into(),
+                )),
+            },
+        };
+        let attention_mask =
+            build_padding_attention_mask(&input_ids, *self.get_pad_id(), PaddingSide::Left);
+        //        Each prompt is repeated for the beams and sequences sampled from it
+        let sequences_per_prompt = if config.do_sample {
+            config.num_return_sequences * config.num_beams
         } else {
-            1
+            config.num_beams
         };
-        let batch_size = *input_ids.size().first().unwrap();
-
-        let (effective_batch_size, effective_batch_mult) = match do_sample {
-            true => (
-                batch_size * num_return_sequences as i64,
-                num_return_sequences as i64,
-            ),
-            false => (batch_size, 1),
+        let batch_size = input_ids.size()[0];
+        let num_sequences = batch_size * sequences_per_prompt;
+        let expanded_batch_indices = Tensor::arange(batch_size, (Int64, input_ids.device()))
+            .view((-1, 1))
+            .repeat(&[1, sequences_per_prompt])
+            .view(-1);
+        let prompt_attention_mask =
+            Vec::<Vec<i64>>::from(attention_mask.index_select(0, &expanded_batch_indices));
+        let mut tensors = vec![];
+        let (token_ids, prompt_length) = if self.is_encoder_decoder() {
+            let encoder_outputs = no_grad(|| self.encode(&input_ids, Some(&attention_mask)))
+                .ok_or_else(|| {
+                    RustBertError::InvalidConfigurationError(
+                        "The encoder of the model must be available for encoder-decoder generation"
+                            .into(),
+                    )
+                })?;
+            tensors.push((
+                "encoder_outputs".to_string(),
+                encoder_outputs.index_select(0, &expanded_batch_indices),
+            ));
+            if let Some(source_copy_bias) = self.get_source_copy_bias(
+                &input_ids,
+                &attention_mask,
+                PrivateLanguageGenerator::get_eos_ids(self).as_ref(),
+            ) {
+                tensors.push((
+                    "source_copy_bias".to_string(),
+                    source_copy_bias.index_select(0, &expanded_batch_indices),
+                ));
+            }
+            let decoder_start_token_id = self.get_decoder_start_id().ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "decoder start id must be specified for encoder decoders".into(),
+                )
+            })?;
+            (
+                vec![vec![decoder_start_token_id]; num_sequences as usize],
+                1,
+            )
+        } else {
+            (
+                Vec::<Vec<i64>>::from(input_ids.index_select(0, &expanded_batch_indices)),
+                input_ids.size()[1] as usize,
+            )
         };
-
-        let attention_mask = match attention_mask {
-            Some(value) => value,
-            None => match self.get_pad_id() {
-                Some(pad_id) => input_ids.ne(*pad_id).to_kind(Int64),
-                None => input_ids.ones_like().to_kind(Int64),
-            },
+        let scores = if config.num_beams > 1 {
+            Vec::<f64>::from(
+                private_generation_utils::initial_beam_scores(
+                    num_sequences / config.num_beams,
+                    config.num_beams,
+                    config.num_beam_groups.unwrap_or(1),
+                    Device::Cpu,
+                )
+                .to_kind(Double),
+            )
+        } else {
+            vec![0f64; num_sequences as usize]
         };
-
-        let encoder_outputs = if self.is_encoder_decoder() {
-            let encoder_outputs = self.encode(&input_ids, Some(&attention_mask)).unwrap();
-            let expanded_batch_indices = Tensor::arange(batch_size, (Int64, input_ids.device()))
-                .view((-1, 1))
-                .repeat(&[1, num_beams as i64 * effective_batch_mult])
-                .view(-1);
-            Some(encoder_outputs.index_select(0, &expanded_batch_indices))
+        let beam_hypotheses = if config.num_beams > 1 {
+            vec![vec![]; (num_sequences / config.num_beams) as usize]
+        } else {
+            vec![]
+        };
+        //        The random state of each sampling step is derived from a seed drawn from the current random state
+        let rng_seed = if config.do_sample {
+            Some(Tensor::randint(i64::MAX, &[1], (Int64, Device::Cpu)).int64_value(&[0]))
         } else {
             None
         };
+        let snapshot = GenerationSnapshot {
+            token_ids,
+            prompt_length,
+            prompt_attention_mask,
+            scores,
+            finished: vec![false; num_sequences as usize],
+            beam_hypotheses,
+            rng_seed,
+            min_length,
+            max_length,
+            tensors,
+        };
+        self.resume_generation(&snapshot, max_steps)
+    }
 
-        let (input_ids, attention_mask) = if !self.is_encoder_decoder() {
-            if (num_return_sequences > 1) | (num_beams > 1) {
-                (
-                    input_ids
-                        .unsqueeze(1)
-                        .expand(
-                            &[batch_size, effective_batch_mult * num_beams as i64, cur_len],
-                            true,
-                        )
-                        .contiguous()
-                        .view((effective_batch_size * num_beams as i64, cur_len)),
-                    attention_mask
-                        .unsqueeze(1)
-                        .expand(
-                            &[batch_size, effective_batch_mult * num_beams as i64, cur_len],
-                            true,
-                        )
-                        .contiguous()
-                        .view((effective_batch_size * num_beams as i64, cur_len)),
-                )
-            } else {
-                (input_ids, attention_mask)
+    /// Resume a generation from a snapshot, for at most `max_steps` decoding steps (or until completion), and return a
+    /// snapshot of the new generation state. The model and generation configuration must match the ones used to create
+    /// the snapshot. A complete snapshot is returned unchanged.
+    ///
+    /// # Arguments
+    ///
+    /// * `snapshot` - `&GenerationSnapshot` snapshot of the generation state (e.g. created by `generate_snapshot`)
+    /// * `max_steps` - `impl Into<Option<i64>>` optional maximum number of decoding steps to run. The generation runs until completion if not provided
+    ///
+    /// # Returns
+    /// * `Result<GenerationSnapshot, RustBertError>` snapshot of the generation state. Once complete, its `token_ids` are the generated token indices
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::gpt2::GPT2Generator;
+    /// use rust_bert::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+    /// let generate_config = GenerateConfig {
+    ///     do_sample: true,
+    ///     ..Default::default()
+    /// };
+    /// let gpt2_generator = GPT2Generator::new(generate_config)?;
+    ///
+    /// let snapshot = gpt2_generator.generate_snapshot(Some(&["The dog"]), None, None, 5)?;
+    /// let output = gpt2_generator.resume_generation(&snapshot, None)?.token_ids;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn resume_generation(
+        &self,
+        snapshot: &GenerationSnapshot,
+        max_steps: impl Into<Option<i64>>,
+    ) -> Result<GenerationSnapshot, RustBertError> {
+        self.resume_from_snapshot(snapshot, max_steps.into())
+    }
+
+    /// Generate token indices from prompts, calling back into user code each time a sequence generates the trigger
+    /// token, e.g. for retrieval-augmented generation interleaving retrieval with decoding. The callback (`on_trigger`
+    /// hook of the decoding loop) receives the text generated so far by the sequence and returns token ids (e.g. the
+    /// encoded retrieved context) that are spliced into the sequence after the trigger token, before the generation
+    /// continues. Available with greedy decoding and sampling, for batches of prompts with decoder-only models and
+    /// for a single generated sequence with encoder-decoder models. Returns an error for beam search or token healing.
+    ///
+    /// The spliced tokens are part of the context of the following steps and count towards the maximum length (they are
+    /// truncated to the remaining length), but they are not scored. In a batch, the sequences receiving fewer spliced
+    /// tokens (or not triggered) are padded with masked positions. The key/value cache of the model does not cover the
+    /// spliced tokens: it is invalidated by each splice and rebuilt by a forward pass over the full sequences, so that
+    /// each trigger costs a pass over the prompts and all the tokens so far. For encoder-decoder models, the tokens are
+    /// spliced into the decoder input: the encoder outputs are reused, while the decoder self-attention and
+    /// cross-attention caches are dropped and recomputed over all the decoder tokens.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt_texts` - `Option<Vec<&str>>` Optional vector of text prompts. An empty prompt to the model may be passed if the model implement a `bos_id`.
+    /// * `min_length` - `impl Into<Option<i64>>` optional minimum output sequence length. If not provided, will use the default value set in the generation configuration
+    /// * `max_length` - `impl Into<Option<i64>>` optional maximum output sequence length. If not provided, will use the default value set in the generation configuration
+    /// * `on_trigger` - `(i64, TriggerCallback)` trigger token id and callback returning the token ids to splice after it
+    ///
+    /// # Returns
+    /// * `Result<Vec<Vec<i64>>, RustBertError>` token indices (*number_of_prompts* x *num_return_sequences*) of the prompts followed by the generated and spliced tokens
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::gpt2::GPT2Generator;
+    /// use rust_bert::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+    /// let generate_config = GenerateConfig {
+    ///     max_length: 100,
+    ///     num_beams: 1,
+    ///     do_sample: false,
+    ///     ..Default::default()
+    /// };
+    /// # fn retrieve_and_encode(_output: &str) -> Vec<i64> { vec![] }
+    /// let gpt2_generator = GPT2Generator::new(generate_config)?;
+    /// // Token id of `:` in the GPT2 vocabulary
+    /// let trigger_token_id = 25;
+    ///
+    /// let output = gpt2_generator.generate_with_trigger(
+    ///     Some(&["Question: what is the capital of France? Context"]),
+    ///     None,
+    ///     None,
+    ///     (
+    ///         trigger_token_id,
+    ///         Box::new(|output: &str| retrieve_and_encode(output)),
+    ///     ),
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn generate_with_trigger<'a, S>(
+        &self,
+        prompt_texts: Option<S>,
+        min_length: impl Into<Option<i64>>,
+        max_length: impl Into<Option<i64>>,
+        on_trigger: (i64, TriggerCallback),
+    ) -> Result<Vec<Vec<i64>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let config = PrivateLanguageGenerator::get_config(self);
+        if (config.num_beams > 1) || config.token_healing {
+            return Err(RustBertError::InvalidConfigurationError(
+                "Generation triggers are only available for greedy decoding or sampling, without token healing".into(),
+            ));
+        }
+        let max_length = max_length.into().unwrap_or(config.max_length);
+        let pad_token_id = match self.get_pad_id() {
+            Some(value) => Some(*value),
+            None => PrivateLanguageGenerator::get_eos_ids(self)
+                .as_ref()
+                .map(|eos_ids| eos_ids[0]),
+        };
+        let input_ids = match prompt_texts {
+            Some(texts) => {
+                if texts.as_ref().iter().any(|text| text.trim().is_empty()) {
+                    return Err(RustBertError::ValueError(
+                        "Generation with a trigger requires non-empty prompts".into(),
+                    ));
+                }
+                let encoding_max_len = if self.is_encoder_decoder() {
+                    1024i64
+                } else {
+                    max_length
+                };
+                self.encode_prompt_text(texts, encoding_max_len, pad_token_id)
             }
+            None => match self.get_bos_id() {
+                Some(bos_id) => {
+                    Tensor::ones(&[1, 1], (Int64, self.get_var_store().device())) * *bos_id
+                }
+                None => return Err(RustBertError::ValueError(
+                    "A model with a BOS token must be used to start generation with an empty input"
+                        .into(),
+                )),
+            },
+        };
+        //        The decoder input of encoder-decoder models has no attention mask to hide the padding of shorter splices
+        let sequences_per_prompt = if config.do_sample {
+            config.num_return_sequences
         } else {
-            let decoder_start_token_id = decoder_start_token_id.into().unwrap_or_else(|| {
-                self.get_decoder_start_id()
-                    .expect("decoder start id must be specified for encoder decoders")
-            });
-            let input_ids = Tensor::full(
-                &[effective_batch_size * num_beams as i64, 1],
-                decoder_start_token_id,
-                (Int64, input_ids.device()),
-            );
-            let attention_mask = if (num_return_sequences > 1) | (num_beams > 1) {
-                attention_mask
-                    .unsqueeze(1)
-                    .expand(
-                        &[
-                            batch_size,
-                            effective_batch_mult * num_beams as i64,
-                            input_ids_len,
-                        ],
-                        true,
-                    )
-                    .contiguous()
-                    .view((effective_batch_size * num_beams as i64, input_ids_len))
-            } else {
-                attention_mask
-            };
-            (input_ids, attention_mask)
+            1
         };
+        if self.is_encoder_decoder() && (input_ids.size()[0] * sequences_per_prompt > 1) {
+            return Err(RustBertError::InvalidConfigurationError(
+                "Generation triggers are only available for a single generated sequence with encoder-decoder models".into(),
+            ));
+        }
+        Ok(self
+            .generate_from_encoder_outputs(
+                input_ids,
+                None,
+                None,
+                min_length.into(),
+                Some(max_length),
+                None,
+                None,
+                Some(on_trigger),
+            )
+            .indices)
+    }
 
-        let gen_opt = GenerateOptions {
-            min_length,
-            max_length,
-            do_sample,
-            temperature,
-            top_k,
-            top_p,
-            repetition_penalty,
-            no_repeat_ngram_size,
-            pad_token_id,
-            eos_token_ids,
-            num_return_sequences,
-            early_stopping,
-            num_beams,
-            length_penalty,
-            num_beam_groups,
-            diversity_penalty,
+    /// Generate token indices for several length targets at once. For encoder-decoder models, the prompts
+    /// are encoded a single time and the encoder outputs are shared by the decoding passes (one per length
+    /// target, each starting from an empty cache). Decoder-only models run a full generation per target.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt_texts` - `Vec<&str>` vector of text prompts
+    /// * `length_targets` - `&[(i64, i64)]` slice of (`min_length`, `max_length`) targets
+    ///
+    /// # Returns
+    /// * `Vec<Vec<Vec<i64>>>` generated token indices (*number_of_prompts* x *num_return_sequences*) for each length target, in the order of `length_targets`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::bart::BartGenerator;
+    /// use rust_bert::pipelines::generation_utils::LanguageGenerator;
+    ///
+    /// let bart_generator = BartGenerator::new(Default::default())?;
+    /// let input_context = "In findings published Tuesday in Cornell University's arXiv by a team of scientists \
+    /// from the University of Montreal and a separate report published Wednesday in Nature Astronomy by a team \
+    /// from University College London (UCL), the presence of water vapour was confirmed in the atmosphere of K2-18b.";
+    ///
+    /// let output = bart_generator.generate_indices_multi_length(&[input_context], &[(10, 30), (30, 60)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn generate_indices_multi_length<'a, S>(
+        &self,
+        prompt_texts: S,
+        length_targets: &[(i64, i64)],
+    ) -> Vec<Vec<Vec<i64>>>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        if !self.is_encoder_decoder() {
+            return length_targets
+                .iter()
+                .map(|(min_length, max_length)| {
+                    self.generate_indices(
+                        Some(prompt_texts.as_ref()),
+                        None,
+                        *min_length,
+                        *max_length,
+                        None,
+                    )
+                })
+                .collect();
+        }
+
+        let pad_token_id = match self.get_pad_id() {
+            Some(value) => Some(*value),
+            None => self
+                .get_eos_ids()
+                .as_ref()
+                .map(|eos_token_ids| eos_token_ids[0]),
         };
+        let input_ids = self.encode_prompt_text(prompt_texts, 1024, pad_token_id);
+        let attention_mask =
+            build_padding_attention_mask(&input_ids, *self.get_pad_id(), PaddingSide::Left);
+        let encoder_outputs = no_grad(|| self.encode(&input_ids, Some(&attention_mask)).unwrap());
+        assert_no_grad(&encoder_outputs);
 
-        let decoded = no_grad(|| {
-            if num_beams > 1 {
-                self.generate_beam_search(
-                    input_ids,
-                    encoder_outputs,
-                    cur_len,
-                    effective_batch_size,
-                    attention_mask,
-                    gen_opt,
-                )
-            } else {
-                self.generate_no_beam_search(
-                    input_ids,
-                    encoder_outputs,
-                    cur_len,
-                    effective_batch_size,
-                    attention_mask,
-                    gen_opt,
+        length_targets
+            .iter()
+            .map(|(min_length, max_length)| {
+                self.generate_from_encoder_outputs(
+                    input_ids.shallow_clone(),
+                    Some(encoder_outputs.shallow_clone()),
+                    Some(attention_mask.shallow_clone()),
+                    Some(*min_length),
+                    Some(*max_length),
+                    None,
+                    None,
+                    None,
                 )
+                .indices
+            })
+            .collect()
+    }
+
+    /// Computes the log-probability of a continuation given a prompt under a causal language model,
+    /// `sum log p(token_i | token_<i)` over the continuation tokens. The prompt and continuation are tokenized
+    /// separately and scored with a single teacher-forced forward pass: the first continuation token is predicted
+    /// from the last prompt token. Useful for reranking candidate continuations of a shared prompt.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - `&str` prompt conditioning the continuation. If empty, the BOS token is used as a prompt.
+    /// * `continuation` - `&str` continuation to score
+    ///
+    /// # Returns
+    /// * `Result<f64, RustBertError>` log-probability of the continuation (0 for an empty continuation). Returns an error for encoder-decoder models and for models without causal token probabilities (XLNet)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::gpt2::GPT2Generator;
+    /// use rust_bert::pipelines::generation_utils::LanguageGenerator;
+    ///
+    /// let gpt2_generator = GPT2Generator::new(Default::default())?;
+    /// let prompt = "The capital of France is";
+    /// let candidates = [" Paris", " a potato"];
+    /// let scores = candidates
+    ///     .iter()
+    ///     .map(|candidate| gpt2_generator.sequence_logprob(prompt, candidate))
+    ///     .collect::<Result<Vec<f64>, _>>()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn sequence_logprob(&self, prompt: &str, continuation: &str) -> Result<f64, RustBertError> {
+        if self.is_encoder_decoder() {
+            return Err(RustBertError::InvalidConfigurationError(
+                "Sequence log-probabilities can only be computed for causal language models".into(),
+            ));
+        }
+        let tokenizer = self.get_tokenizer();
+        let mut prompt_ids = tokenizer.convert_tokens_to_ids(tokenizer.tokenize(prompt));
+        let continuation_ids = tokenizer.convert_tokens_to_ids(tokenizer.tokenize(continuation));
+        if continuation_ids.is_empty() {
+            return Ok(0f64);
+        }
+        if prompt_ids.is_empty() {
+            match self.get_bos_id() {
+                Some(bos_id) => prompt_ids.push(*bos_id),
+                None => {
+                    return Err(RustBertError::ValueError(
+                        "A model with a BOS token must be used to score a continuation with an empty prompt"
+                            .into(),
+                    ));
+                }
             }
-        });
-        let num_sequences = *decoded.size().first().unwrap();
-        let mut output_ids = Vec::with_capacity(num_sequences as usize);
-        for sequence_index in 0..num_sequences {
-            let sequence_output_ids = decoded
-                .as_ref()
-                .get(sequence_index)
-                .iter::<i64>()
-                .unwrap()
-                .collect::<Vec<i64>>();
-            output_ids.push(sequence_output_ids.clone());
         }
-        output_ids
+        let device = self.get_var_store().device();
+        let prompt_length = prompt_ids.len() as i64;
+        let continuation_length = continuation_ids.len() as i64;
+        let input_ids = Tensor::of_slice(&[prompt_ids, continuation_ids.clone()].concat())
+            .unsqueeze(0)
+            .to(device);
+
+        no_grad(|| {
+            let lm_logits = self
+                .get_model()
+                .forward_t(
+                    &Some(input_ids),
+                    Cache::None,
+                    &None,
+                    &None,
+                    &None,
+                    &None,
+                    None,
+                    &None,
+                    false,
+                )?
+                .lm_logits;
+            assert_no_grad(&lm_logits);
+            //        The logits at position i predict the token at position i + 1
+            Ok(lm_logits
+                .get(0)
+                .narrow(0, prompt_length - 1, continuation_length)
+                .log_softmax(-1, Double)
+                .gather(
+                    1,
+                    &Tensor::of_slice(&continuation_ids).to(device).unsqueeze(-1),
+                    false,
+                )
+                .sum(Double)
+                .double_value(&[]))
+        })
+    }
+
+    /// Replaces the weights of the model in place with the weights from a resource (see `pipelines::common::reload_var_store`).
+    ///
+    /// # Arguments
+    ///
+    /// * `weights_resource` - `Resource` pointing to the new model weights
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::gpt2::GPT2Generator;
+    /// use rust_bert::pipelines::generation_utils::LanguageGenerator;
+    /// use rust_bert::resources::{LocalResource, Resource};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut gpt2_generator = GPT2Generator::new(Default::default())?;
+    /// let weights_resource = Resource::Local(LocalResource {
+    ///     local_path: PathBuf::from("path/to/updated/rust_model.ot"),
+    /// });
+    /// gpt2_generator.reload_weights(&weights_resource)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn reload_weights(&mut self, weights_resource: &Resource) -> Result<(), RustBertError> {
+        reload_var_store(self.get_var_store(), weights_resource)
     }
 }
 
+/// Score, token ids and (optionally) candidate token ids and probabilities along the path of a finished beam
+type BeamHypothesis = (f64, Tensor, Option<(Tensor, Tensor)>);
+
 #[derive(Debug)]
-struct BeamHypotheses {
+pub(crate) struct BeamHypotheses {
     max_length: i64,
     length_penalty: f64,
     early_stopping: bool,
     num_beams: i64,
-    beams: Vec<(f64, Tensor)>,
+    beams: Vec<BeamHypothesis>,
     worst_score: f64,
 }
 
@@ -1514,8 +4955,16 @@ impl Clone for BeamHypotheses {
             beams: self
                 .beams
                 .iter()
-                .map(|(score, tensor)| (*score, tensor.copy()))
-                .collect::<Vec<(f64, Tensor)>>(),
+                .map(|(score, tensor, alternatives)| {
+                    (
+                        *score,
+                        tensor.copy(),
+                        alternatives.as_ref().map(|(token_ids, probabilities)| {
+                            (token_ids.copy(), probabilities.copy())
+                        }),
+                    )
+                })
+                .collect::<Vec<BeamHypothesis>>(),
             worst_score: self.worst_score,
         }
     }
@@ -1538,28 +4987,61 @@ impl BeamHypotheses {
         }
     }
 
+    /// Rebuilds the hypotheses of an input from their (length-normalized) scores and token ids stored in a generation snapshot
+    fn from_snapshot(
+        beams: &[(f64, Vec<i64>)],
+        num_beams: i64,
+        max_length: i64,
+        length_penalty: f64,
+        early_stopping: bool,
+        device: Device,
+    ) -> BeamHypotheses {
+        let mut hypotheses =
+            BeamHypotheses::new(num_beams, max_length, length_penalty, early_stopping);
+        for (score, token_ids) in beams {
+            hypotheses
+                .beams
+                .push((*score, Tensor::of_slice(token_ids).to(device), None));
+            hypotheses.worst_score = hypotheses.worst_score.min(*score);
+        }
+        hypotheses
+    }
+
+    /// Returns the (length-normalized) scores and token ids of the hypotheses, to be stored in a generation snapshot
+    fn to_snapshot(&self) -> Vec<(f64, Vec<i64>)> {
+        self.beams
+            .iter()
+            .map(|(score, hypothesis, _)| (*score, Vec::<i64>::from(hypothesis.to(Device::Cpu))))
+            .collect()
+    }
+
     fn len(&self) -> i64 {
         self.beams.len() as i64
     }
 
-    fn add(&mut self, hypothesis: Tensor, sum_log_probabilities: f64) {
+    fn add(
+        &mut self,
+        hypothesis: Tensor,
+        alternatives: Option<(Tensor, Tensor)>,
+        sum_log_probabilities: f64,
+    ) {
         let score =
             sum_log_probabilities / ((hypothesis.size()[0] as f64).powf(self.length_penalty));
         if (self.len() < self.num_beams) | (score > self.worst_score) {
-            self.beams.push((score, hypothesis));
+            self.beams.push((score, hypothesis, alternatives));
             if self.len() > self.num_beams {
                 let (worst_score_position, _) = self
                     .beams
                     .iter()
                     .enumerate()
-                    .min_by_key(|(_, (score, _))| OrderedFloat(*score))
+                    .min_by_key(|(_, (score, _, _))| OrderedFloat(*score))
                     .unwrap();
                 let _ = self.beams.remove(worst_score_position);
             }
             self.worst_score = self
                 .beams
                 .iter()
-                .min_by_key(|(score, _)| OrderedFloat(*score))
+                .min_by_key(|(score, _, _)| OrderedFloat(*score))
                 .unwrap()
                 .0;
         }
@@ -173,16 +173,20 @@
 //! # Ok(())
 //! # }
 //! ```
-//! Example output: \
+//! Example output (`text` of the generated outputs, grouped by prompt): \
 //! ```no_run
 //! # let output =
 //! [
-//!     "The dog's owners, however, did not want to be named. According to the lawsuit, the animal's owner, a 29-year",
-//!     "The dog has always been part of the family. \"He was always going to be my dog and he was always looking out for me",
-//!     "The dog has been able to stay in the home for more than three months now. \"It's a very good dog. She's",
-//!     "The cat was discovered earlier this month in the home of a relative of the deceased. The cat\'s owner, who wished to remain anonymous,",
-//!     "The cat was pulled from the street by two-year-old Jazmine.\"I didn't know what to do,\" she said",
-//!     "The cat was attacked by two stray dogs and was taken to a hospital. Two other cats were also injured in the attack and are being treated."
+//!     [
+//!         "The dog's owners, however, did not want to be named. According to the lawsuit, the animal's owner, a 29-year",
+//!         "The dog has always been part of the family. \"He was always going to be my dog and he was always looking out for me",
+//!         "The dog has been able to stay in the home for more than three months now. \"It's a very good dog. She's",
+//!     ],
+//!     [
+//!         "The cat was discovered earlier this month in the home of a relative of the deceased. The cat\'s owner, who wished to remain anonymous,",
+//!         "The cat was pulled from the street by two-year-old Jazmine.\"I didn't know what to do,\" she said",
+//!         "The cat was attacked by two stray dogs and was taken to a hospital. Two other cats were also injured in the attack and are being treated.",
+//!     ],
 //! ]
 //! # ;
 //! ```
@@ -216,24 +220,28 @@
 //!             score: 0.972,
 //!             id: 0,
 //!             sentence: 0,
+//!             margin: 0.94,
 //!         },
 //!         Label {
 //!             text: "public health".to_string(),
 //!             score: 0.032,
 //!             id: 1,
 //!             sentence: 0,
+//!             margin: -0.94,
 //!         },
 //!         Label {
 //!             text: "economics".to_string(),
 //!             score: 0.006,
 //!             id: 2,
 //!             sentence: 0,
+//!             margin: -0.966,
 //!         },
 //!         Label {
 //!             text: "sports".to_string(),
 //!             score: 0.004,
 //!             id: 3,
 //!             sentence: 0,
+//!             margin: -0.968,
 //!         },
 //!     ],
 //!     [
@@ -242,24 +250,28 @@
 //!             score: 0.975,
 //!             id: 0,
 //!             sentence: 1,
+//!             margin: 0.123,
 //!         },
 //!         Label {
 //!             text: "economics".to_string(),
 //!             score: 0.852,
 //!             id: 2,
 //!             sentence: 1,
+//!             margin: -0.123,
 //!         },
 //!         Label {
 //!             text: "public health".to_string(),
 //!             score: 0.0818,
 //!             id: 1,
 //!             sentence: 1,
+//!             margin: -0.8932,
 //!         },
 //!         Label {
 //!             text: "sports".to_string(),
 //!             score: 0.001,
 //!             id: 3,
 //!             sentence: 1,
+//!             margin: -0.974,
 //!         },
 //!     ],
 //! ]
@@ -397,14 +409,19 @@
 
 pub mod common;
 pub mod conversation;
+pub mod diagnostics;
+pub mod generation_diversity;
 pub mod generation_utils;
 pub mod ner;
 pub mod pos_tagging;
 pub mod question_answering;
+pub mod sentence_importance;
+pub mod sentence_splitter;
 pub mod sentiment;
 pub mod sequence_classification;
 pub mod summarization;
 pub mod text_generation;
 pub mod token_classification;
+pub mod tool_calls;
 pub mod translation;
 pub mod zero_shot_classification;
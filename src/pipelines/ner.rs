@@ -121,7 +121,9 @@
 //! Dutch| XLM_ROBERTA_NER_NL |
 
 use crate::common::error::RustBertError;
-use crate::pipelines::token_classification::{TokenClassificationConfig, TokenClassificationModel};
+use crate::pipelines::token_classification::{
+    TokenClassificationConfig, TokenClassificationModel, TokenLabelDistribution,
+};
 
 #[derive(Debug)]
 /// # Entity generated by a `NERModel`
@@ -211,6 +213,85 @@ impl NERModel {
             })
             .collect::<Vec<Vec<Entity>>>()
     }
+
+    /// Returns the probability of each entity tag (e.g. B-PER, I-ORG, O) for the tokens of the input texts, before the
+    /// aggregation of sub-tokens into words, e.g. for confidence thresholding or probabilistic entity linking.
+    /// Special tokens are excluded and the tokens are mapped to their character offsets in the input.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<TokenLabelDistribution>>` containing the tokens of each input with their tag probabilities
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = ["My name is Amy. I live in Paris."];
+    /// let output = ner_model.predict_tag_distributions(&input);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_tag_distributions<'a, S>(&self, input: S) -> Vec<Vec<TokenLabelDistribution>>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        self.token_classification_model
+            .predict_label_distributions(input)
+    }
+
+    /// Explains a predicted entity by attributing it to the input tokens, to help diagnosing false positives.
+    /// The attribution method is gradient×input with respect to the logits of the entity label at the
+    /// positions of the entity (see `TokenClassificationModel::explain_token`).
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&str` text to extract entities from
+    /// * `entity_index` - index of the entity to explain in the entities predicted for the input (as returned by `predict`)
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<(String, f32)>` containing the input tokens with their importance for the entity, in order of appearance
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::NERModel;
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = "My name is Amy. I live in Paris.";
+    /// let importance = ner_model.explain_entity(input, 1)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn explain_entity(
+        &self,
+        input: &str,
+        entity_index: usize,
+    ) -> Result<Vec<(String, f32)>, RustBertError> {
+        let entity_token = self
+            .token_classification_model
+            .predict([input], true, false)
+            .remove(0)
+            .into_iter()
+            .filter(|token| token.label != "O")
+            .nth(entity_index)
+            .ok_or_else(|| {
+                RustBertError::ValueError(format!(
+                    "Entity index {} out of range of the entities predicted for the input",
+                    entity_index
+                ))
+            })?;
+        self.token_classification_model
+            .explain_token(input, &entity_token)
+    }
 }
 #[cfg(test)]
 mod test {
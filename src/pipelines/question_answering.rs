@@ -53,7 +53,10 @@ use crate::distilbert::{
 };
 use crate::longformer::LongformerForQuestionAnswering;
 use crate::mobilebert::MobileBertForQuestionAnswering;
-use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
+use crate::pipelines::common::{
+    assert_no_grad, reload_var_store, ConfigOption, ModelType, TokenizerOption, WindowBoundary,
+    Windower,
+};
 use crate::reformer::ReformerForQuestionAnswering;
 use crate::roberta::RobertaForQuestionAnswering;
 use crate::xlnet::XLNetForQuestionAnswering;
@@ -133,18 +136,20 @@ pub struct QuestionAnsweringConfig {
     pub model_type: ModelType,
     /// Flag indicating if the model expects a lower casing of the input
     pub lower_case: bool,
-    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models
+    /// Flag indicating if the tokenizer should strip accents (normalization), overriding the `lower_case` default. Only used for BERT / ALBERT / XLNet / ProphetNet models
     pub strip_accents: Option<bool>,
     /// Flag indicating if the tokenizer should add a white space before each tokenized input (needed for some Roberta models)
     pub add_prefix_space: Option<bool>,
     /// Maximum sequence length for the combined query and context
     pub max_seq_length: usize,
-    /// Stride to apply if the context needs to be broken down due to a large length. Represents the number of overlapping tokens between sliding windows.
+    /// Stride to apply if the context needs to be broken down due to a large length. Represents the maximum number of overlapping tokens between sliding windows, which are snapped to the `window_boundary`.
     pub doc_stride: usize,
     /// Maximum length for the query
     pub max_query_length: usize,
     /// Maximum length for the answer
     pub max_answer_length: usize,
+    /// Boundaries at which the sliding windows over long contexts may start and end (default: sentence boundaries, falling back to word boundaries)
+    pub window_boundary: WindowBoundary,
 }
 
 impl QuestionAnsweringConfig {
@@ -182,6 +187,7 @@ impl QuestionAnsweringConfig {
             doc_stride: 128,
             max_query_length: 64,
             max_answer_length: 15,
+            window_boundary: WindowBoundary::Sentence,
         }
     }
 
@@ -227,6 +233,7 @@ impl QuestionAnsweringConfig {
             doc_stride: doc_stride.into().unwrap_or(128),
             max_query_length: max_query_length.into().unwrap_or(64),
             max_answer_length: max_answer_length.into().unwrap_or(15),
+            window_boundary: WindowBoundary::Sentence,
         }
     }
 }
@@ -253,6 +260,7 @@ impl Default for QuestionAnsweringConfig {
             doc_stride: 128,
             max_query_length: 64,
             max_answer_length: 15,
+            window_boundary: WindowBoundary::Sentence,
         }
     }
 }
@@ -504,6 +512,7 @@ pub struct QuestionAnsweringModel {
     doc_stride: usize,
     max_query_length: usize,
     max_answer_len: usize,
+    window_boundary: WindowBoundary,
     qa_model: QuestionAnsweringOption,
     var_store: VarStore,
 }
@@ -591,11 +600,43 @@ impl QuestionAnsweringModel {
             doc_stride: question_answering_config.doc_stride,
             max_query_length: question_answering_config.max_query_length,
             max_answer_len: question_answering_config.max_answer_length,
+            window_boundary: question_answering_config.window_boundary,
             qa_model,
             var_store,
         })
     }
 
+    /// Interface method to access tokenizer
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+
+    /// Replaces the weights of the model in place with the weights from a resource (see `pipelines::common::reload_var_store`).
+    ///
+    /// # Arguments
+    ///
+    /// * `weights_resource` - `Resource` pointing to the new model weights
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::question_answering::QuestionAnsweringModel;
+    /// use rust_bert::resources::{LocalResource, Resource};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut model = QuestionAnsweringModel::new(Default::default())?;
+    /// let weights_resource = Resource::Local(LocalResource {
+    ///     local_path: PathBuf::from("path/to/updated/rust_model.ot"),
+    /// });
+    /// model.reload_weights(&weights_resource)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reload_weights(&mut self, weights_resource: &Resource) -> Result<(), RustBertError> {
+        reload_var_store(&self.var_store, weights_resource)
+    }
+
     /// Perform extractive question answering given a list of `QaInputs`
     ///
     /// # Arguments
@@ -605,7 +646,7 @@ impl QuestionAnsweringModel {
     /// * `batch_size` - maximum batch size for the model forward pass.
     ///
     /// # Returns
-    /// * `Vec<Vec<Answer>>` Vector (same length as `qa_inputs`) of vectors (each of length `top_k`) containing the extracted answers.
+    /// * `Vec<Vec<Answer>>` Vector (same length as `qa_inputs`) of vectors (each of length `top_k`) containing the extracted answers. No answer is returned for empty questions or contexts.
     ///
     /// # Example
     ///
@@ -639,7 +680,7 @@ impl QuestionAnsweringModel {
         top_k: i64,
         batch_size: usize,
     ) -> Vec<Vec<Answer>> {
-        let mut features: Vec<QaFeature> = qa_inputs
+        let features: Vec<QaFeature> = qa_inputs
             .iter()
             .enumerate()
             .map(|(example_index, qa_example)| {
@@ -653,7 +694,78 @@ impl QuestionAnsweringModel {
             })
             .flatten()
             .collect();
+        let contexts = qa_inputs
+            .iter()
+            .map(|qa_input| qa_input.context.as_str())
+            .collect::<Vec<&str>>();
+
+        self.predict_from_features(features, &contexts, top_k, batch_size)
+    }
+
+    /// Perform extractive question answering for many questions about a single context. The context is tokenized
+    /// once and its token spans are reused across all questions, only the question tokens vary between inputs.
+    /// This is significantly faster than `predict` for document-centric question answering with many questions.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - `&str` Context shared by all questions
+    /// * `questions` - `&[&str]` Array of questions about the context
+    /// * `top_k` - return the top-k answers for each question. Set to 1 to return only the best answer.
+    /// * `batch_size` - maximum batch size for the model forward pass.
+    ///
+    /// # Returns
+    /// * `Vec<Vec<Answer>>` Vector (same length as `questions`) of vectors (each of length `top_k`) containing the extracted answers. No answer is returned for empty questions or contexts.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::question_answering::QuestionAnsweringModel;
+    ///
+    /// let qa_model = QuestionAnsweringModel::new(Default::default())?;
+    ///
+    /// let context = "While Amy lives in Amsterdam, Eric is in The Hague.";
+    /// let questions = ["Where does Amy live ?", "Where does Eric live ?"];
+    /// let answers = qa_model.answer_batch_shared_context(context, &questions, 1, 32);
+    ///
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn answer_batch_shared_context(
+        &self,
+        context: &str,
+        questions: &[&str],
+        top_k: i64,
+        batch_size: usize,
+    ) -> Vec<Vec<Answer>> {
+        let encoded_context = self.encode_context(context);
+        let features: Vec<QaFeature> = questions
+            .iter()
+            .enumerate()
+            .flat_map(|(example_index, question)| {
+                let encoded_query = self.encode_query(question, self.max_query_length);
+                self.generate_spans(
+                    &encoded_query,
+                    &encoded_context,
+                    context,
+                    self.max_seq_len,
+                    self.doc_stride,
+                    example_index as i64,
+                )
+            })
+            .collect();
+        let contexts = vec![context; questions.len()];
 
+        self.predict_from_features(features, &contexts, top_k, batch_size)
+    }
+
+    fn predict_from_features(
+        &self,
+        mut features: Vec<QaFeature>,
+        contexts: &[&str],
+        top_k: i64,
+        batch_size: usize,
+    ) -> Vec<Vec<Answer>> {
         let mut example_top_k_answers_map: HashMap<usize, Vec<Answer>> = HashMap::new();
         let mut start = 0usize;
         let len_features = features.len();
@@ -668,6 +780,8 @@ impl QuestionAnsweringModel {
                     self.qa_model
                         .forward_t(Some(input_ids), Some(attention_masks), None, false);
 
+                assert_no_grad(&start_logits);
+                assert_no_grad(&end_logits);
                 let start_logits = start_logits.detach();
                 let end_logits = end_logits.detach();
                 let example_index_to_feature_end_position: Vec<(usize, i64)> = batch_features
@@ -682,7 +796,7 @@ impl QuestionAnsweringModel {
 
                 for (example_id, max_feature_id) in example_index_to_feature_end_position {
                     let mut answers: Vec<Answer> = vec![];
-                    let example = &qa_inputs[example_id];
+                    let context = contexts[example_id];
                     for feature_idx in feature_id_start..max_feature_id {
                         let feature = &batch_features[feature_idx as usize];
                         let p_mask = (Tensor::of_slice(&feature.p_mask) - 1)
@@ -705,7 +819,7 @@ impl QuestionAnsweringModel {
                             let end_pos = feature.offsets[ends[idx] as usize]
                                 .unwrap_or(Offset { begin: 0, end: 0 })
                                 .end as usize;
-                            let answer = example.context[start_pos..end_pos].to_string();
+                            let answer = context[start_pos..end_pos].to_string();
 
                             answers.push(Answer {
                                 score: scores[idx],
@@ -725,7 +839,7 @@ impl QuestionAnsweringModel {
             start = end;
         }
         let mut all_answers = vec![];
-        for example_id in 0..qa_inputs.len() {
+        for example_id in 0..contexts.len() {
             if let Some(answers) = example_top_k_answers_map.get_mut(&example_id) {
                 remove_duplicates(answers).sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
                 all_answers.push(answers[..min(answers.len(), top_k as usize)].to_vec());
@@ -771,18 +885,57 @@ impl QuestionAnsweringModel {
         max_query_length: usize,
         example_index: i64,
     ) -> Vec<QaFeature> {
-        let mut encoded_query = self.tokenizer.tokenize_with_offsets(&qa_example.question);
+        let encoded_query = self.encode_query(&qa_example.question, max_query_length);
+        let encoded_context = self.encode_context(&qa_example.context);
+        self.generate_spans(
+            &encoded_query,
+            &encoded_context,
+            &qa_example.context,
+            max_seq_length,
+            doc_stride,
+            example_index,
+        )
+    }
+
+    fn encode_query(&self, question: &str, max_query_length: usize) -> TokenIdsWithOffsets {
+        let mut encoded_query = self.tokenizer.tokenize_with_offsets(question);
         encoded_query.tokens.truncate(max_query_length);
         encoded_query.offsets.truncate(max_query_length);
         encoded_query.reference_offsets.truncate(max_query_length);
         encoded_query.masks.truncate(max_query_length);
-        let encoded_query = TokenIdsWithOffsets {
+        TokenIdsWithOffsets {
             ids: self.tokenizer.convert_tokens_to_ids(&encoded_query.tokens),
             offsets: encoded_query.offsets,
             reference_offsets: encoded_query.reference_offsets,
             masks: encoded_query.masks,
-        };
+        }
+    }
 
+    fn encode_context(&self, context: &str) -> TokenIdsWithOffsets {
+        let tokenized_context = self.tokenizer.tokenize_with_offsets(context);
+        TokenIdsWithOffsets {
+            ids: self
+                .tokenizer
+                .convert_tokens_to_ids(&tokenized_context.tokens),
+            offsets: tokenized_context.offsets,
+            reference_offsets: tokenized_context.reference_offsets,
+            masks: tokenized_context.masks,
+        }
+    }
+
+    fn generate_spans(
+        &self,
+        encoded_query: &TokenIdsWithOffsets,
+        encoded_context: &TokenIdsWithOffsets,
+        context: &str,
+        max_seq_length: usize,
+        doc_stride: usize,
+        example_index: i64,
+    ) -> Vec<QaFeature> {
+        //        Empty (or whitespace-only) questions and contexts have no answer
+        if encoded_query.ids.is_empty() || encoded_context.ids.is_empty() {
+            return vec![];
+        }
         let sequence_pair_added_tokens = self
             .tokenizer
             .build_input_with_special_tokens(
@@ -802,48 +955,33 @@ impl QuestionAnsweringModel {
             .token_ids
             .len();
 
-        let mut spans: Vec<QaFeature> = vec![];
-
-        let tokenized_context = self.tokenizer.tokenize_with_offsets(&qa_example.context);
-        let encoded_context = TokenIdsWithOffsets {
-            ids: self
-                .tokenizer
-                .convert_tokens_to_ids(&tokenized_context.tokens),
-            offsets: tokenized_context.offsets,
-            reference_offsets: tokenized_context.reference_offsets,
-            masks: tokenized_context.masks,
-        };
         let max_context_length =
             max_seq_length - sequence_pair_added_tokens - encoded_query.ids.len();
 
-        let mut start_token = 0_usize;
-        while (spans.len() * doc_stride as usize) < encoded_context.ids.len() {
-            let end_token = min(start_token + max_context_length, encoded_context.ids.len());
-            let sub_encoded_context = TokenIdsWithOffsets {
-                ids: encoded_context.ids[start_token..end_token].to_vec(),
-                offsets: encoded_context.offsets[start_token..end_token].to_vec(),
-                reference_offsets: encoded_context.reference_offsets[start_token..end_token]
-                    .to_vec(),
-                masks: encoded_context.masks[start_token..end_token].to_vec(),
-            };
-
-            let encoded_span = self
-                .tokenizer
-                .build_input_with_special_tokens(encoded_query.clone(), Some(sub_encoded_context));
-            let p_mask = self.get_mask(&encoded_span);
-            let qa_feature = QaFeature {
-                input_ids: encoded_span.token_ids,
-                offsets: encoded_span.token_offsets,
-                p_mask,
-                example_index,
-            };
-            spans.push(qa_feature);
-            if end_token == encoded_context.ids.len() {
-                break;
-            }
-            start_token = end_token - doc_stride;
-        }
-        spans
+        Windower::new(max_context_length, doc_stride, self.window_boundary)
+            .windows(encoded_context, context)
+            .into_iter()
+            .map(|window| {
+                let sub_encoded_context = TokenIdsWithOffsets {
+                    ids: encoded_context.ids[window.clone()].to_vec(),
+                    offsets: encoded_context.offsets[window.clone()].to_vec(),
+                    reference_offsets: encoded_context.reference_offsets[window.clone()].to_vec(),
+                    masks: encoded_context.masks[window].to_vec(),
+                };
+
+                let encoded_span = self.tokenizer.build_input_with_special_tokens(
+                    encoded_query.clone(),
+                    Some(sub_encoded_context),
+                );
+                let p_mask = self.get_mask(&encoded_span);
+                QaFeature {
+                    input_ids: encoded_span.token_ids,
+                    offsets: encoded_span.token_offsets,
+                    p_mask,
+                    example_index,
+                }
+            })
+            .collect()
     }
 
     fn pad_features(&self, features: &mut [QaFeature]) -> (Tensor, Tensor) {
@@ -0,0 +1,536 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Attention-based sentence importance pipeline
+//! Scores the sentences of a text by the attention an encoder pays to them, providing an attention-based
+//! alternative for extractive tasks (e.g. extractive summarization). The text is split into sentences using a
+//! `SentenceSplitter`, the encoder attention weights (averaged over layers and heads) are aggregated from the
+//! `[CLS]` token (or averaged over all tokens) onto the tokens of each sentence. Special tokens are excluded.
+//! The scores of the sentences of a text sum to 1.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::sentence_importance::SentenceImportanceModel;
+//! let sentence_importance_model = SentenceImportanceModel::new(Default::default())?;
+//!
+//! let input = ["The Eiffel tower is located in Paris. It is 324 metres tall. Tourists love it."];
+//! let output = sentence_importance_model.predict(&input)?;
+//! # Ok(())
+//! # }
+//! ```
+//! Output: \
+//! ```no_run
+//! # use rust_bert::pipelines::sentence_importance::SentenceImportance;
+//! # let output =
+//! [[
+//!     SentenceImportance {
+//!         text: String::from("The Eiffel tower is located in Paris."),
+//!         start: 0,
+//!         end: 37,
+//!         score: 0.45,
+//!     },
+//!     SentenceImportance {
+//!         text: String::from("It is 324 metres tall."),
+//!         start: 38,
+//!         end: 60,
+//!         score: 0.32,
+//!     },
+//!     SentenceImportance {
+//!         text: String::from("Tourists love it."),
+//!         start: 61,
+//!         end: 78,
+//!         score: 0.23,
+//!     },
+//! ]]
+//! # ;
+//! ```
+
+use crate::bert::{BertEmbeddings, BertModel};
+use crate::common::error::RustBertError;
+use crate::common::resources::{RemoteResource, Resource};
+use crate::distilbert::{
+    DistilBertConfigResources, DistilBertModel, DistilBertModelResources, DistilBertVocabResources,
+};
+use crate::pipelines::common::{
+    assert_no_grad, build_padding_attention_mask, reload_var_store, ConfigOption, ModelType,
+    PaddingSide, TokenizerOption,
+};
+use crate::pipelines::sentence_splitter::{RuleBasedSentenceSplitter, SentenceSplitter};
+use crate::roberta::RobertaEmbeddings;
+use rust_tokenizers::tokenizer::TruncationStrategy;
+use rust_tokenizers::TokenizedInput;
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use tch::kind::Kind::Float;
+use tch::nn::VarStore;
+use tch::{nn, no_grad, Device, Tensor};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # Sentence importance generated by a `SentenceImportanceModel`
+pub struct SentenceImportance {
+    /// Sentence text
+    pub text: String,
+    /// Sentence start position (byte offset) in the input text
+    pub start: usize,
+    /// Sentence end position (byte offset) in the input text
+    pub end: usize,
+    /// Importance score (share of the aggregated attention received by the sentence)
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// # Attention pooling strategy for sentence importance
+pub enum AttentionPooling {
+    /// Attention paid by the first token (e.g. `[CLS]`) to the other tokens
+    Cls,
+    /// Attention averaged over all (non-padding) tokens of the input
+    Mean,
+}
+
+/// # Configuration for SentenceImportanceModel
+/// Contains information regarding the model to load and device to place the model on.
+pub struct SentenceImportanceConfig {
+    /// Model type
+    pub model_type: ModelType,
+    /// Model weights resource (default: pretrained DistilBERT model)
+    pub model_resource: Resource,
+    /// Config resource (default: pretrained DistilBERT model)
+    pub config_resource: Resource,
+    /// Vocab resource (default: pretrained DistilBERT model)
+    pub vocab_resource: Resource,
+    /// Merges resource (default: None)
+    pub merges_resource: Option<Resource>,
+    /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
+    pub lower_case: bool,
+    /// Flag indicating if the tokenizer should strip accents (normalization), overriding the `lower_case` default. Only used for BERT / ALBERT / XLNet / ProphetNet models
+    pub strip_accents: Option<bool>,
+    /// Flag indicating if the tokenizer should add a white space before each tokenized input (needed for some Roberta models)
+    pub add_prefix_space: Option<bool>,
+    /// Attention pooling strategy (default: `AttentionPooling::Cls`)
+    pub pooling: AttentionPooling,
+    /// Maximum input length in tokens. Sentences beyond this length receive a score of 0 (default: 512)
+    pub max_length: usize,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+}
+
+impl SentenceImportanceConfig {
+    /// Instantiate a new sentence importance configuration of the supplied type.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_type` - `ModelType` indicating the model type to load (must match with the actual data to be loaded!)
+    /// * model - The `Resource` pointing to the model to load (e.g.  model.ot)
+    /// * config - The `Resource' pointing to the model configuration to load (e.g. config.json)
+    /// * vocab - The `Resource' pointing to the tokenizer's vocabulary to load (e.g.  vocab.txt/vocab.json)
+    /// * merges - An optional `Resource` tuple (`Option<Resource>`) pointing to the tokenizer's merge file to load (e.g.  merges.txt), needed only for Roberta.
+    /// * lower_case - A `bool' indicating whether the tokenizer should lower case all input (in case of a lower-cased model)
+    pub fn new(
+        model_type: ModelType,
+        model_resource: Resource,
+        config_resource: Resource,
+        vocab_resource: Resource,
+        merges_resource: Option<Resource>,
+        lower_case: bool,
+        strip_accents: impl Into<Option<bool>>,
+        add_prefix_space: impl Into<Option<bool>>,
+    ) -> SentenceImportanceConfig {
+        SentenceImportanceConfig {
+            model_type,
+            model_resource,
+            config_resource,
+            vocab_resource,
+            merges_resource,
+            lower_case,
+            strip_accents: strip_accents.into(),
+            add_prefix_space: add_prefix_space.into(),
+            pooling: AttentionPooling::Cls,
+            max_length: 512,
+            device: Device::cuda_if_available(),
+        }
+    }
+}
+
+impl Default for SentenceImportanceConfig {
+    /// Provides a pretrained DistilBERT encoder (English)
+    fn default() -> SentenceImportanceConfig {
+        SentenceImportanceConfig::new(
+            ModelType::DistilBert,
+            Resource::Remote(RemoteResource::from_pretrained(
+                DistilBertModelResources::DISTIL_BERT,
+            )),
+            Resource::Remote(RemoteResource::from_pretrained(
+                DistilBertConfigResources::DISTIL_BERT,
+            )),
+            Resource::Remote(RemoteResource::from_pretrained(
+                DistilBertVocabResources::DISTIL_BERT,
+            )),
+            None,
+            true,
+            None,
+            None,
+        )
+    }
+}
+
+/// # Abstraction that holds one particular encoder model, for any of the supported models
+pub enum SentenceImportanceOption {
+    /// Bert encoder
+    Bert(BertModel<BertEmbeddings>),
+    /// DistilBert encoder
+    DistilBert(DistilBertModel),
+    /// Roberta encoder
+    Roberta(BertModel<RobertaEmbeddings>),
+}
+
+impl SentenceImportanceOption {
+    /// Instantiate a new encoder of the supplied type, returning attention weights.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_type` - `ModelType` indicating the model type to load (must match with the actual data to be loaded)
+    /// * `p` - `tch::nn::Path` path to the model file to load (e.g. model.ot)
+    /// * `config` - A configuration (the model type of the configuration must be compatible with the value for `model_type`)
+    pub fn new<'p, P>(
+        model_type: ModelType,
+        p: P,
+        config: &mut ConfigOption,
+    ) -> Result<Self, RustBertError>
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        match (model_type, config) {
+            (ModelType::Bert, ConfigOption::Bert(config)) => {
+                config.output_attentions = Some(true);
+                Ok(SentenceImportanceOption::Bert(BertModel::new(
+                    p / "bert",
+                    config,
+                )))
+            }
+            (ModelType::DistilBert, ConfigOption::DistilBert(config)) => {
+                config.output_attentions = Some(true);
+                Ok(SentenceImportanceOption::DistilBert(DistilBertModel::new(
+                    p, config,
+                )))
+            }
+            (ModelType::Roberta, ConfigOption::Bert(config)) => {
+                config.output_attentions = Some(true);
+                Ok(SentenceImportanceOption::Roberta(BertModel::new(
+                    p / "roberta",
+                    config,
+                )))
+            }
+            (ModelType::Bert, _) | (ModelType::DistilBert, _) | (ModelType::Roberta, _) => {
+                Err(RustBertError::InvalidConfigurationError(format!(
+                    "Invalid configuration provided for {:?}!",
+                    model_type
+                )))
+            }
+            _ => Err(RustBertError::InvalidConfigurationError(format!(
+                "Sentence importance not implemented for {:?}!",
+                model_type
+            ))),
+        }
+    }
+
+    /// Returns the attention weights of all layers, each of shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    pub fn forward_attentions(
+        &self,
+        input_ids: &Tensor,
+        mask: &Tensor,
+    ) -> Result<Vec<Tensor>, RustBertError> {
+        let all_attentions = match self {
+            Self::Bert(model) => {
+                model
+                    .forward_t(
+                        Some(input_ids.copy()),
+                        Some(mask.copy()),
+                        None,
+                        None,
+                        None,
+                        &None,
+                        &None,
+                        false,
+                    )?
+                    .all_attentions
+            }
+            Self::Roberta(model) => {
+                model
+                    .forward_t(
+                        Some(input_ids.copy()),
+                        Some(mask.copy()),
+                        None,
+                        None,
+                        None,
+                        &None,
+                        &None,
+                        false,
+                    )?
+                    .all_attentions
+            }
+            Self::DistilBert(model) => {
+                model
+                    .forward_t(Some(input_ids.copy()), Some(mask.copy()), None, false)?
+                    .all_attentions
+            }
+        };
+        all_attentions.ok_or_else(|| {
+            RustBertError::ValueError("The encoder did not return attention weights".into())
+        })
+    }
+}
+
+/// # SentenceImportanceModel scoring sentences by the attention they receive
+pub struct SentenceImportanceModel {
+    tokenizer: TokenizerOption,
+    encoder: SentenceImportanceOption,
+    sentence_splitter: Box<dyn SentenceSplitter>,
+    pooling: AttentionPooling,
+    max_length: usize,
+    pad_idx: i64,
+    var_store: VarStore,
+}
+
+impl SentenceImportanceModel {
+    /// Build a new `SentenceImportanceModel` using the default rule-based sentence splitter
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `SentenceImportanceConfig` object containing the resource references (model, vocabulary, configuration), pooling strategy and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sentence_importance::SentenceImportanceModel;
+    ///
+    /// let model = SentenceImportanceModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(config: SentenceImportanceConfig) -> Result<SentenceImportanceModel, RustBertError> {
+        SentenceImportanceModel::new_with_splitter(
+            config,
+            Box::new(RuleBasedSentenceSplitter::default()),
+        )
+    }
+
+    /// Build a new `SentenceImportanceModel` with a custom sentence splitter
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `SentenceImportanceConfig` object containing the resource references (model, vocabulary, configuration), pooling strategy and device placement (CPU/GPU)
+    /// * `sentence_splitter` - `Box<dyn SentenceSplitter>` used to identify the sentence spans
+    pub fn new_with_splitter(
+        config: SentenceImportanceConfig,
+        sentence_splitter: Box<dyn SentenceSplitter>,
+    ) -> Result<SentenceImportanceModel, RustBertError> {
+        let config_path = config.config_resource.get_local_path()?;
+        let vocab_path = config.vocab_resource.get_local_path()?;
+        let weights_path = config.model_resource.get_local_path()?;
+        let merges_path = if let Some(merges_resource) = &config.merges_resource {
+            Some(merges_resource.get_local_path()?)
+        } else {
+            None
+        };
+        let device = config.device;
+
+        let tokenizer = TokenizerOption::from_file(
+            config.model_type,
+            vocab_path.to_str().unwrap(),
+            merges_path.as_deref().map(|path| path.to_str().unwrap()),
+            config.lower_case,
+            config.strip_accents,
+            config.add_prefix_space,
+        )?;
+        let pad_idx = tokenizer.get_pad_id().ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(
+                "The Tokenizer used for sentence importance should contain a PAD id".into(),
+            )
+        })?;
+        let mut var_store = VarStore::new(device);
+        let mut model_config = ConfigOption::from_file(config.model_type, config_path);
+        let encoder =
+            SentenceImportanceOption::new(config.model_type, var_store.root(), &mut model_config)?;
+        var_store.load(weights_path)?;
+        Ok(SentenceImportanceModel {
+            tokenizer,
+            encoder,
+            sentence_splitter,
+            pooling: config.pooling,
+            max_length: config.max_length,
+            pad_idx,
+            var_store,
+        })
+    }
+
+    /// Interface method to access tokenizer
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+
+    /// Replaces the weights of the model in place with the weights from a resource (see `pipelines::common::reload_var_store`).
+    ///
+    /// # Arguments
+    ///
+    /// * `weights_resource` - `Resource` pointing to the new model weights
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sentence_importance::SentenceImportanceModel;
+    /// use rust_bert::resources::{LocalResource, Resource};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut model = SentenceImportanceModel::new(Default::default())?;
+    /// let weights_resource = Resource::Local(LocalResource {
+    ///     local_path: PathBuf::from("path/to/updated/rust_model.ot"),
+    /// });
+    /// model.reload_weights(&weights_resource)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reload_weights(&mut self, weights_resource: &Resource) -> Result<(), RustBertError> {
+        reload_var_store(&self.var_store, weights_resource)
+    }
+
+    /// Scores the sentences of the input texts by the attention they receive from the encoder
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to process.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Vec<SentenceImportance>>, RustBertError>` containing the sentences of each input text with their importance score, in order of appearance
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sentence_importance::SentenceImportanceModel;
+    /// let sentence_importance_model = SentenceImportanceModel::new(Default::default())?;
+    /// let input = ["The Eiffel tower is located in Paris. It is 324 metres tall."];
+    /// let output = sentence_importance_model.predict(&input)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict<'a, S>(&self, input: S) -> Result<Vec<Vec<SentenceImportance>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let input = input.as_ref();
+        if input.is_empty() {
+            return Ok(vec![]);
+        }
+        let tokenized_input: Vec<TokenizedInput> = self.tokenizer.encode_list(
+            input,
+            self.max_length,
+            &TruncationStrategy::LongestFirst,
+            0,
+        );
+        let max_len = tokenized_input
+            .iter()
+            .map(|input| input.token_ids.len())
+            .max()
+            .unwrap();
+        let input_ids = tokenized_input
+            .iter()
+            .map(|input| {
+                let mut token_ids = input.token_ids.clone();
+                token_ids.resize(max_len, self.pad_idx);
+                Tensor::of_slice(&token_ids)
+            })
+            .collect::<Vec<Tensor>>();
+        let input_ids = Tensor::stack(&input_ids, 0).to(self.var_store.device());
+        let attention_mask =
+            build_padding_attention_mask(&input_ids, Some(self.pad_idx), PaddingSide::Right);
+
+        //        Token-level attention received, averaged over layers and heads (batch size x sequence length)
+        let token_attention = no_grad(|| -> Result<Tensor, RustBertError> {
+            let all_attentions = self
+                .encoder
+                .forward_attentions(&input_ids, &attention_mask)?;
+            all_attentions.iter().for_each(assert_no_grad);
+            let attention = Tensor::stack(&all_attentions, 0)
+                .mean1(&[0, 2], false, Float)
+                .to(Device::Cpu);
+            Ok(match self.pooling {
+                AttentionPooling::Cls => attention.select(1, 0),
+                AttentionPooling::Mean => {
+                    let query_mask = attention_mask.to_kind(Float).to(Device::Cpu).unsqueeze(-1);
+                    (attention * &query_mask).sum1(&[1], false, Float)
+                        / query_mask.sum1(&[1], false, Float)
+                }
+            })
+        })?;
+
+        Ok(input
+            .iter()
+            .zip(tokenized_input.iter())
+            .enumerate()
+            .map(|(input_index, (text, tokenized))| {
+                let sentence_ranges = self.sentence_splitter.split_offsets(text);
+                //        Token offsets are expressed in characters, sentence offsets in bytes
+                let char_ranges = sentence_ranges
+                    .iter()
+                    .map(|range| {
+                        let start = text[..range.start].chars().count() as u32;
+                        let end = start + text[range.clone()].chars().count() as u32;
+                        (start, end)
+                    })
+                    .collect::<Vec<(u32, u32)>>();
+                let mut sentence_scores = vec![0f64; sentence_ranges.len()];
+                let text_attention = token_attention.get(input_index as i64);
+                for (token_index, offset) in tokenized.token_offsets.iter().enumerate() {
+                    //        Special tokens do not have offsets and are excluded
+                    if let Some(offset) = offset {
+                        if let Some(sentence_index) = char_ranges.iter().position(|(start, end)| {
+                            (offset.begin >= *start) && (offset.end <= *end)
+                        }) {
+                            sentence_scores[sentence_index] +=
+                                text_attention.double_value(&[token_index as i64]);
+                        }
+                    }
+                }
+                let total_score: f64 = sentence_scores.iter().sum();
+                sentence_ranges
+                    .into_iter()
+                    .zip(sentence_scores)
+                    .map(|(range, score)| SentenceImportance {
+                        text: text[range.clone()].to_string(),
+                        start: range.start,
+                        end: range.end,
+                        score: if total_score > 0f64 {
+                            score / total_score
+                        } else {
+                            0f64
+                        },
+                    })
+                    .collect::<Vec<SentenceImportance>>()
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = SentenceImportanceConfig::default();
+        let _: Box<dyn Send> = Box::new(SentenceImportanceModel::new(config));
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Sentence splitting utilities
+//! Splits raw text into sentences. Pipelines that operate at the sentence level accept any
+//! implementation of the `SentenceSplitter` trait, allowing a custom splitter to be plugged in.
+//! A rule-based default (`RuleBasedSentenceSplitter`) is provided, handling common abbreviations,
+//! initials, decimal numbers and ellipses.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::sentence_splitter::{RuleBasedSentenceSplitter, SentenceSplitter};
+//! let splitter = RuleBasedSentenceSplitter::default();
+//!
+//! let input = "Dr. Smith paid $3.50 for the book. He left... Was it worth it?";
+//! let output = splitter.split(input);
+//! ```
+//! Output: \
+//! ```no_run
+//! # let output =
+//! [
+//!     "Dr. Smith paid $3.50 for the book.",
+//!     "He left...",
+//!     "Was it worth it?",
+//! ]
+//! # ;
+//! ```
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+/// # Common interface for sentence splitters
+pub trait SentenceSplitter: Send + Sync {
+    /// Returns the byte offsets of the sentences found in the input text. Leading and trailing
+    /// whitespace are excluded from the sentence spans.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - `&str` text to split
+    ///
+    /// # Returns
+    /// * `Vec<Range<usize>>` byte ranges of the sentences in the input text
+    fn split_offsets(&self, text: &str) -> Vec<Range<usize>>;
+
+    /// Splits the input text into sentences.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - `&str` text to split
+    ///
+    /// # Returns
+    /// * `Vec<&str>` sentences borrowed from the input text
+    fn split<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        self.split_offsets(text)
+            .into_iter()
+            .map(|range| &text[range])
+            .collect()
+    }
+}
+
+const DEFAULT_ABBREVIATIONS: [&str; 24] = [
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "mt", "vs", "e.g", "i.e", "cf", "etc",
+    "approx", "fig", "no", "inc", "ltd", "co", "jan", "feb", "aug", "sept",
+];
+
+/// # Rule-based sentence splitter
+/// Splits text on sentence-final punctuation (`.`, `!`, `?`, `…`), followed by optional closing
+/// quotes or brackets and whitespace. No boundary is inserted:
+/// - within numbers (e.g. `3.50`), as the punctuation must be followed by whitespace
+/// - if the following word starts with a lowercase character
+/// - after a known abbreviation (e.g. `Dr.`) or a single uppercase initial (e.g. `J.`)
+///
+/// A run of terminal punctuation (e.g. an ellipsis `...` or `?!`) is treated as a single boundary.
+pub struct RuleBasedSentenceSplitter {
+    abbreviations: HashSet<String>,
+}
+
+impl RuleBasedSentenceSplitter {
+    /// Creates a new rule-based sentence splitter with a custom set of abbreviations.
+    ///
+    /// # Arguments
+    ///
+    /// * `abbreviations` - abbreviations (without trailing period, case-insensitive) after which no boundary is inserted
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::sentence_splitter::RuleBasedSentenceSplitter;
+    ///
+    /// let splitter = RuleBasedSentenceSplitter::new(&["dr", "e.g", "approx"]);
+    /// ```
+    pub fn new<S: AsRef<str>>(abbreviations: &[S]) -> RuleBasedSentenceSplitter {
+        let abbreviations = abbreviations
+            .iter()
+            .map(|abbreviation| abbreviation.as_ref().to_lowercase())
+            .collect();
+        RuleBasedSentenceSplitter { abbreviations }
+    }
+
+    /// Adds abbreviations to the existing set of abbreviations of the splitter.
+    ///
+    /// # Arguments
+    ///
+    /// * `abbreviations` - abbreviations (without trailing period, case-insensitive) to add
+    pub fn add_abbreviations<S: AsRef<str>>(&mut self, abbreviations: &[S]) {
+        self.abbreviations.extend(
+            abbreviations
+                .iter()
+                .map(|abbreviation| abbreviation.as_ref().to_lowercase()),
+        );
+    }
+
+    fn is_abbreviation(&self, chars: &[(usize, char)], period_position: usize) -> bool {
+        let word_start = chars[..period_position]
+            .iter()
+            .rposition(|(_, c)| !(c.is_alphabetic() || *c == '.'))
+            .map_or(0, |position| position + 1);
+        let word = chars[word_start..period_position]
+            .iter()
+            .map(|(_, c)| *c)
+            .collect::<String>();
+        let mut word_chars = word.chars();
+        match (word_chars.next(), word_chars.next()) {
+            (None, _) => false,
+            (Some(initial), None) if initial.is_uppercase() => true,
+            _ => self.abbreviations.contains(&word.to_lowercase()),
+        }
+    }
+}
+
+impl Default for RuleBasedSentenceSplitter {
+    fn default() -> Self {
+        RuleBasedSentenceSplitter::new(&DEFAULT_ABBREVIATIONS)
+    }
+}
+
+fn is_terminal(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '…')
+}
+
+fn is_closing(c: char) -> bool {
+    matches!(c, '"' | '\'' | ')' | ']' | '}' | '”' | '’' | '»')
+}
+
+impl SentenceSplitter for RuleBasedSentenceSplitter {
+    fn split_offsets(&self, text: &str) -> Vec<Range<usize>> {
+        let chars = text.char_indices().collect::<Vec<(usize, char)>>();
+        let char_end = |position: usize| chars[position].0 + chars[position].1.len_utf8();
+        let next_non_whitespace = |from: usize| {
+            (from..chars.len())
+                .find(|&position| !chars[position].1.is_whitespace())
+                .unwrap_or(chars.len())
+        };
+
+        let mut sentences = vec![];
+        let mut start = next_non_whitespace(0);
+        let mut position = start;
+        while position < chars.len() {
+            if !is_terminal(chars[position].1) {
+                position += 1;
+                continue;
+            }
+            let mut end = position;
+            while end < chars.len() && is_terminal(chars[end].1) {
+                end += 1;
+            }
+            let single_period = end - position == 1 && chars[position].1 == '.';
+            while end < chars.len() && is_closing(chars[end].1) {
+                end += 1;
+            }
+            if end < chars.len() && !chars[end].1.is_whitespace() {
+                position = end;
+                continue;
+            }
+            let next_start = next_non_whitespace(end);
+            let next_is_lowercase = next_start < chars.len() && chars[next_start].1.is_lowercase();
+            if next_is_lowercase || (single_period && self.is_abbreviation(&chars, position)) {
+                position = end;
+                continue;
+            }
+            sentences.push(chars[start].0..char_end(end - 1));
+            start = next_start;
+            position = next_start;
+        }
+        if start < chars.len() {
+            let last_non_whitespace = (start..chars.len())
+                .rev()
+                .find(|&position| !chars[position].1.is_whitespace())
+                .unwrap();
+            sentences.push(chars[start].0..char_end(last_non_whitespace));
+        }
+        sentences
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn abbreviations_and_decimals() {
+        let splitter = RuleBasedSentenceSplitter::default();
+        assert_eq!(
+            splitter.split("Dr. Smith paid $3.50. He left."),
+            vec!["Dr. Smith paid $3.50.", "He left."]
+        );
+        assert_eq!(
+            splitter.split("Use a solvent, e.g. acetone. It works."),
+            vec!["Use a solvent, e.g. acetone.", "It works."]
+        );
+    }
+
+    #[test]
+    fn ellipses_and_punctuation_runs() {
+        let splitter = RuleBasedSentenceSplitter::default();
+        assert_eq!(
+            splitter.split("Wait... What happened?! Nothing!"),
+            vec!["Wait...", "What happened?!", "Nothing!"]
+        );
+        assert_eq!(
+            splitter.split("I think… maybe not."),
+            vec!["I think… maybe not."]
+        );
+    }
+
+    #[test]
+    fn quotes_and_initials() {
+        let splitter = RuleBasedSentenceSplitter::default();
+        assert_eq!(
+            splitter.split("He said \"Stop.\" Then he left."),
+            vec!["He said \"Stop.\"", "Then he left."]
+        );
+        assert_eq!(
+            splitter.split("J. K. Rowling wrote it. It sold well"),
+            vec!["J. K. Rowling wrote it.", "It sold well"]
+        );
+    }
+
+    #[test]
+    fn offsets_and_degenerate_inputs() {
+        let splitter = RuleBasedSentenceSplitter::default();
+        assert_eq!(
+            splitter.split_offsets("  Héllo.  Bye!  "),
+            vec![2..9, 11..15]
+        );
+        assert!(splitter.split("").is_empty());
+        assert!(splitter.split("   ").is_empty());
+
+        let custom = RuleBasedSentenceSplitter::new(&["Approx"]);
+        assert_eq!(
+            custom.split("It costs approx. 3 dollars. Dr. Who."),
+            vec!["It costs approx. 3 dollars.", "Dr.", "Who."]
+        );
+    }
+}
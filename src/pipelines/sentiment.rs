@@ -55,9 +55,11 @@
 //! ```
 
 use crate::common::error::RustBertError;
+use crate::pipelines::sentence_splitter::{RuleBasedSentenceSplitter, SentenceSplitter};
 use crate::pipelines::sequence_classification::{
-    SequenceClassificationConfig, SequenceClassificationModel,
+    Label, SequenceClassificationConfig, SequenceClassificationModel,
 };
+use std::ops::Range;
 
 #[derive(Debug, PartialEq)]
 /// Enum with the possible sentiment polarities. Note that the pre-trained SST2 model does not include neutral sentiment.
@@ -75,11 +77,57 @@ pub struct Sentiment {
     pub score: f64,
 }
 
+impl Sentiment {
+    fn from_label(label: &Label) -> Sentiment {
+        let polarity = if label.id == 1 {
+            SentimentPolarity::Positive
+        } else {
+            SentimentPolarity::Negative
+        };
+        Sentiment {
+            polarity,
+            score: label.score,
+        }
+    }
+
+    /// Probability of the positive polarity
+    fn positive_probability(&self) -> f64 {
+        match self.polarity {
+            SentimentPolarity::Positive => self.score,
+            SentimentPolarity::Negative => 1.0 - self.score,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Sentiment of a sentence of a longer text
+pub struct SentenceSentiment {
+    /// Sentence text
+    pub sentence: String,
+    /// Byte offsets of the sentence in the original text
+    pub span: Range<usize>,
+    /// Sentiment of the sentence
+    pub sentiment: Sentiment,
+}
+
+#[derive(Debug)]
+/// Per-sentence sentiments of a text, with their aggregate.
+pub struct SentencesSentiment {
+    /// Sentiments of the sentences, in the order of the text
+    pub sentences: Vec<SentenceSentiment>,
+    /// Overall sentiment, averaging the probability of the positive polarity over the sentences (`None` for a text without sentences)
+    pub overall: Option<Sentiment>,
+}
+
+/// Sentences with fewer words are merged with their neighbour before classification
+const MIN_SENTENCE_WORDS: usize = 3;
+
 type SentimentConfig = SequenceClassificationConfig;
 
 /// # SentimentClassifier to perform sentiment analysis
 pub struct SentimentModel {
     sequence_classification_model: SequenceClassificationModel,
+    sentence_splitter: Box<dyn SentenceSplitter>,
 }
 
 impl SentimentModel {
@@ -100,9 +148,24 @@ impl SentimentModel {
     /// # }
     /// ```
     pub fn new(sentiment_config: SentimentConfig) -> Result<SentimentModel, RustBertError> {
+        SentimentModel::new_with_splitter(
+            sentiment_config,
+            Box::new(RuleBasedSentenceSplitter::default()),
+        )
+    }
+
+    /// Build a new `SentimentModel` with a custom sentence splitter, used by `predict_by_sentence`
+    /// # Arguments
+    /// * `sentiment_config` - `SentimentConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    /// * `sentence_splitter` - `Box<dyn SentenceSplitter>` used to identify the sentence spans
+    pub fn new_with_splitter(
+        sentiment_config: SentimentConfig,
+        sentence_splitter: Box<dyn SentenceSplitter>,
+    ) -> Result<SentimentModel, RustBertError> {
         let sequence_classification_model = SequenceClassificationModel::new(sentiment_config)?;
         Ok(SentimentModel {
             sequence_classification_model,
+            sentence_splitter,
         })
     }
 
@@ -138,19 +201,91 @@ impl SentimentModel {
         S: AsRef<[&'a str]>,
     {
         let labels = self.sequence_classification_model.predict(input);
-        let mut sentiments = Vec::with_capacity(labels.len());
-        for label in labels {
-            let polarity = if label.id == 1 {
-                SentimentPolarity::Positive
+        labels.iter().map(Sentiment::from_label).collect()
+    }
+
+    /// Extract the sentiment of each sentence of a text. The sentences are classified in a single batch.
+    /// Very short sentences (fewer than 3 words, e.g. "Wow!") carry little signal on their own and are merged
+    /// with the following sentence (or the previous one for the last sentence of the text).
+    /// # Arguments
+    /// * `text` - `&str` text to split into sentences and extract the sentiments from.
+    /// # Returns
+    /// * `SentencesSentiment` sentiments of the sentences with their spans in the text, and the overall sentiment
+    /// # Example
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sentiment::SentimentModel;
+    /// let sentiment_classifier = SentimentModel::new(Default::default())?;
+    /// let input = "The acting was superb and the soundtrack is memorable. \
+    /// Sadly, the last hour drags on and the ending is a letdown.";
+    /// let output = sentiment_classifier.predict_by_sentence(input);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_by_sentence(&self, text: &str) -> SentencesSentiment {
+        let mut spans: Vec<Range<usize>> = Vec::new();
+        let mut pending_start: Option<usize> = None;
+        for span in self.sentence_splitter.split_offsets(text) {
+            let span = pending_start.take().unwrap_or(span.start)..span.end;
+            if text[span.clone()].split_whitespace().count() < MIN_SENTENCE_WORDS {
+                pending_start = Some(span.start);
             } else {
-                SentimentPolarity::Negative
-            };
-            sentiments.push(Sentiment {
-                polarity,
-                score: label.score,
-            })
+                spans.push(span);
+            }
+        }
+        if let Some(start) = pending_start {
+            match spans.last_mut() {
+                Some(last_span) => last_span.end = text.len(),
+                None => spans.push(start..text.len()),
+            }
         }
-        sentiments
+        let spans = spans
+            .into_iter()
+            .map(|span| span.start..span.start + text[span].trim_end().len())
+            .collect::<Vec<Range<usize>>>();
+
+        let sentences = spans
+            .iter()
+            .map(|span| &text[span.clone()])
+            .collect::<Vec<&str>>();
+        let sentiments = if sentences.is_empty() {
+            vec![]
+        } else {
+            self.predict(&sentences)
+        };
+
+        let overall = if sentiments.is_empty() {
+            None
+        } else {
+            let positive_probability = sentiments
+                .iter()
+                .map(Sentiment::positive_probability)
+                .sum::<f64>()
+                / sentiments.len() as f64;
+            Some(if positive_probability >= 0.5 {
+                Sentiment {
+                    polarity: SentimentPolarity::Positive,
+                    score: positive_probability,
+                }
+            } else {
+                Sentiment {
+                    polarity: SentimentPolarity::Negative,
+                    score: 1.0 - positive_probability,
+                }
+            })
+        };
+
+        let sentences = sentences
+            .into_iter()
+            .zip(spans)
+            .zip(sentiments)
+            .map(|((sentence, span), sentiment)| SentenceSentiment {
+                sentence: sentence.to_string(),
+                span,
+                sentiment,
+            })
+            .collect();
+        SentencesSentiment { sentences, overall }
     }
 }
 #[cfg(test)]
@@ -51,9 +51,9 @@
 //! # use rust_bert::pipelines::sequence_classification::Label;
 //! let output =
 //! [
-//!    Label { text: String::from("POSITIVE"), score: 0.9986, id: 1, sentence: 0},
-//!    Label { text: String::from("NEGATIVE"), score: 0.9985, id: 0, sentence: 1},
-//!    Label { text: String::from("POSITIVE"), score: 0.9988, id: 1, sentence: 12},
+//!    Label { text: String::from("POSITIVE"), score: 0.9986, id: 1, sentence: 0, margin: 0.9972},
+//!    Label { text: String::from("NEGATIVE"), score: 0.9985, id: 0, sentence: 1, margin: 0.997},
+//!    Label { text: String::from("POSITIVE"), score: 0.9988, id: 1, sentence: 12, margin: 0.9976},
 //! ]
 //! # ;
 //! ```
@@ -68,7 +68,11 @@ use crate::distilbert::{
 };
 use crate::longformer::LongformerForSequenceClassification;
 use crate::mobilebert::MobileBertForSequenceClassification;
-use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
+use crate::pipelines::common::{
+    assert_no_grad, build_padding_attention_mask, reload_var_store, ConfigOption, InputTruncation,
+    ModelType, PaddingSide, TokenizerOption,
+};
+use crate::pipelines::diagnostics::{report_tokenization_diagnostics, DiagnosticsSink};
 use crate::reformer::ReformerForSequenceClassification;
 use crate::roberta::RobertaForSequenceClassification;
 use crate::xlnet::XLNetForSequenceClassification;
@@ -76,6 +80,7 @@ use rust_tokenizers::tokenizer::TruncationStrategy;
 use rust_tokenizers::TokenizedInput;
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
+use std::cmp::min;
 use std::collections::HashMap;
 use tch::nn::VarStore;
 use tch::{nn, no_grad, Device, Kind, Tensor};
@@ -92,6 +97,26 @@ pub struct Label {
     /// Sentence index
     #[serde(default)]
     pub sentence: usize,
+    /// Margin between the label score and the highest score among the other labels for the sentence. For single-label
+    /// classification, this is the difference between the two highest probabilities: a small margin signals an ambiguous
+    /// prediction, often a better abstention signal than the score itself. Negative for labels other than the top label.
+    #[serde(default)]
+    pub margin: f64,
+}
+
+/// Returns the margin between each score and the highest score among the other labels of the same row,
+/// for scores of shape (*batch size*, *number of labels*). Tied top scores have a margin of 0.
+pub(crate) fn label_margins(scores: &Tensor) -> Tensor {
+    let num_labels = *scores.size().last().unwrap();
+    let (top_scores, _) = scores.topk(min(num_labels, 2), -1, true, true);
+    let top_1 = top_scores.select(-1, 0).unsqueeze(-1);
+    let top_2 = if num_labels > 1 {
+        top_scores.select(-1, 1).unsqueeze(-1)
+    } else {
+        top_1.zeros_like()
+    };
+    let highest_other_scores = top_2.where1(&scores.eq1(&top_1), &top_1);
+    scores - highest_other_scores
 }
 
 /// # Configuration for SequenceClassificationModel
@@ -109,10 +134,13 @@ pub struct SequenceClassificationConfig {
     pub merges_resource: Option<Resource>,
     /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
     pub lower_case: bool,
-    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models
+    /// Flag indicating if the tokenizer should strip accents (normalization), overriding the `lower_case` default. Only used for BERT / ALBERT / XLNet / ProphetNet models
     pub strip_accents: Option<bool>,
     /// Flag indicating if the tokenizer should add a white space before each tokenized input (needed for some Roberta models)
     pub add_prefix_space: Option<bool>,
+    /// Truncation of the inputs longer than the maximum input length of the pipeline (128 tokens, including the special
+    /// tokens). With `InputTruncation::HeadTail`, `head + tail` plus the special tokens must fit in this length (default: `InputTruncation::Head`)
+    pub truncation: InputTruncation,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
 }
@@ -147,6 +175,7 @@ impl SequenceClassificationConfig {
             lower_case,
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
+            truncation: InputTruncation::Head,
             device: Device::cuda_if_available(),
         }
     }
@@ -170,11 +199,68 @@ impl Default for SequenceClassificationConfig {
             lower_case: true,
             strip_accents: None,
             add_prefix_space: None,
+            truncation: InputTruncation::Head,
             device: Device::cuda_if_available(),
         }
     }
 }
 
+/// # Classification head applied on top of the encoder hidden states
+/// Allows attaching a custom head architecture (e.g. attention pooling or recurrent layers) to the encoder of a
+/// `SequenceClassificationModel`. The encoder produces hidden states that are consumed by the head.
+pub trait ClassificationHead: Send {
+    /// Computes the classification logits from the encoder hidden states.
+    ///
+    /// # Arguments
+    ///
+    /// * `hidden_states` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*) containing the last hidden states of the encoder
+    /// * `attention_mask` - `Tensor` of shape (*batch size*, *sequence_length*) with 1 for tokens to attend to and 0 for padding positions
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*batch size*, *num_labels*) containing the logits for each label
+    fn forward(&self, hidden_states: &Tensor, attention_mask: &Tensor) -> Tensor;
+}
+
+/// # Linear classification head
+/// Projects the hidden state of the first token (e.g. `[CLS]`) to the label logits with a single linear layer.
+pub struct LinearClassificationHead {
+    classifier: nn::Linear,
+}
+
+impl LinearClassificationHead {
+    /// Build a new `LinearClassificationHead`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the head
+    /// * `hidden_size` - dimension of the encoder hidden states
+    /// * `num_labels` - number of output labels
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::sequence_classification::LinearClassificationHead;
+    /// use tch::{nn, Device};
+    ///
+    /// let vs = nn::VarStore::new(Device::Cpu);
+    /// let head = LinearClassificationHead::new(&vs.root() / "classifier", 768, 3);
+    /// ```
+    pub fn new<'p, P>(p: P, hidden_size: i64, num_labels: i64) -> LinearClassificationHead
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let classifier = nn::linear(p, hidden_size, num_labels, Default::default());
+        LinearClassificationHead { classifier }
+    }
+}
+
+impl ClassificationHead for LinearClassificationHead {
+    fn forward(&self, hidden_states: &Tensor, _attention_mask: &Tensor) -> Tensor {
+        hidden_states.select(1, 0).apply(&self.classifier)
+    }
+}
+
 /// # Abstraction that holds one particular sequence classification model, for any of the supported models
 pub enum SequenceClassificationOption {
     /// Bert for Sequence Classification
@@ -464,16 +550,122 @@ impl SequenceClassificationOption {
             }
         }
     }
+
+    /// Returns the last hidden states of the encoder, skipping the model classification head. Used to run custom
+    /// classification heads (`ClassificationHead`) on top of the encoder. Available for Bert, DistilBert, MobileBert,
+    /// Roberta, XLMRoberta, Albert and XLNet models.
+    pub fn forward_hidden_states(
+        &self,
+        input_ids: Option<Tensor>,
+        mask: Option<Tensor>,
+        token_type_ids: Option<Tensor>,
+        position_ids: Option<Tensor>,
+        input_embeds: Option<Tensor>,
+        train: bool,
+    ) -> Result<Tensor, RustBertError> {
+        Ok(match *self {
+            Self::Bert(ref model) => {
+                model
+                    .bert
+                    .forward_t(
+                        input_ids,
+                        mask,
+                        token_type_ids,
+                        position_ids,
+                        input_embeds,
+                        &None,
+                        &None,
+                        train,
+                    )?
+                    .hidden_state
+            }
+            Self::DistilBert(ref model) => {
+                model
+                    .distil_bert_model
+                    .forward_t(input_ids, mask, input_embeds, train)?
+                    .hidden_state
+            }
+            Self::MobileBert(ref model) => {
+                model
+                    .mobilebert
+                    .forward_t(
+                        input_ids.as_ref(),
+                        token_type_ids.as_ref(),
+                        position_ids.as_ref(),
+                        input_embeds,
+                        mask.as_ref(),
+                        train,
+                    )?
+                    .hidden_state
+            }
+            Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
+                model
+                    .roberta
+                    .forward_t(
+                        input_ids,
+                        mask,
+                        token_type_ids,
+                        position_ids,
+                        input_embeds,
+                        &None,
+                        &None,
+                        train,
+                    )?
+                    .hidden_state
+            }
+            Self::Albert(ref model) => {
+                model
+                    .albert
+                    .forward_t(
+                        input_ids,
+                        mask,
+                        token_type_ids,
+                        position_ids,
+                        input_embeds,
+                        train,
+                    )?
+                    .hidden_state
+            }
+            Self::XLNet(ref model) => {
+                model
+                    .base_model
+                    .forward_t(
+                        input_ids.as_ref(),
+                        mask.as_ref(),
+                        None,
+                        None,
+                        None,
+                        token_type_ids.as_ref(),
+                        input_embeds,
+                        train,
+                    )?
+                    .hidden_state
+            }
+            Self::Bart(_) | Self::Reformer(_) | Self::Longformer(_) => {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Custom classification heads are not supported for {:?}!",
+                    self.model_type()
+                )));
+            }
+        })
+    }
 }
 
 /// # SequenceClassificationModel for Classification (e.g. Sentiment Analysis)
 pub struct SequenceClassificationModel {
     tokenizer: TokenizerOption,
     sequence_classifier: SequenceClassificationOption,
+    classification_head: Option<Box<dyn ClassificationHead>>,
     label_mapping: HashMap<i64, String>,
     var_store: VarStore,
+    calibration_temperature: f64,
+    truncation: InputTruncation,
+    diagnostics_sink: Option<DiagnosticsSink>,
 }
 
+/// Maximum length of the inputs of the sequence classification pipeline, including the special tokens
+const MAX_INPUT_LENGTH: usize = 128;
+
 impl SequenceClassificationModel {
     /// Build a new `SequenceClassificationModel`
     ///
@@ -512,6 +704,15 @@ impl SequenceClassificationModel {
             config.strip_accents,
             config.add_prefix_space,
         )?;
+        if let InputTruncation::HeadTail { head, tail } = config.truncation {
+            let num_special_tokens = tokenizer.num_special_tokens();
+            if head + tail + num_special_tokens > MAX_INPUT_LENGTH {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Head-tail truncation keeps {} + {} tokens and {} special tokens, exceeding the maximum input length of {}",
+                    head, tail, num_special_tokens, MAX_INPUT_LENGTH
+                )));
+            }
+        }
         let mut var_store = VarStore::new(device);
         let model_config = ConfigOption::from_file(config.model_type, config_path);
         let sequence_classifier =
@@ -521,18 +722,245 @@ impl SequenceClassificationModel {
         Ok(SequenceClassificationModel {
             tokenizer,
             sequence_classifier,
+            classification_head: None,
             label_mapping,
             var_store,
+            calibration_temperature: 1.0,
+            truncation: config.truncation,
+            diagnostics_sink: None,
         })
     }
 
+    /// Build a new `SequenceClassificationModel` with a custom classification head. The pretrained encoder is loaded
+    /// from the configuration resources, and the hidden states it produces are passed to the provided head instead of
+    /// the classification head of the pretrained model. The weights of the custom head are managed by the caller.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `SequenceClassificationConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    /// * `classification_head` - `Box<dyn ClassificationHead>` head computing the label logits from the encoder hidden states
+    /// * `label_mapping` - Optional `HashMap<i64, String>` mapping the head output indices to label names. Defaults to the label mapping of the model configuration.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sequence_classification::{
+    ///     LinearClassificationHead, SequenceClassificationModel,
+    /// };
+    /// use std::collections::HashMap;
+    /// use tch::{nn, Device};
+    ///
+    /// let head_var_store = nn::VarStore::new(Device::cuda_if_available());
+    /// let head = LinearClassificationHead::new(&head_var_store.root(), 768, 2);
+    /// let label_mapping: HashMap<i64, String> =
+    ///     [(0, "spam".to_string()), (1, "ham".to_string())].iter().cloned().collect();
+    ///
+    /// let model = SequenceClassificationModel::new_with_head(
+    ///     Default::default(),
+    ///     Box::new(head),
+    ///     label_mapping,
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_with_head(
+        config: SequenceClassificationConfig,
+        classification_head: Box<dyn ClassificationHead>,
+        label_mapping: impl Into<Option<HashMap<i64, String>>>,
+    ) -> Result<SequenceClassificationModel, RustBertError> {
+        match config.model_type {
+            ModelType::Bart | ModelType::Reformer | ModelType::Longformer => {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Custom classification heads are not supported for {:?}!",
+                    config.model_type
+                )));
+            }
+            _ => {}
+        }
+        let label_mapping = label_mapping.into();
+        let mut model = SequenceClassificationModel::new(config)?;
+        model.classification_head = Some(classification_head);
+        if let Some(label_mapping) = label_mapping {
+            model.label_mapping = label_mapping;
+        }
+        Ok(model)
+    }
+
+    /// Interface method to access tokenizer
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+
+    /// Replaces the weights of the model in place with the weights from a resource (see `pipelines::common::reload_var_store`).
+    ///
+    /// # Arguments
+    ///
+    /// * `weights_resource` - `Resource` pointing to the new model weights
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// use rust_bert::resources::{LocalResource, Resource};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut model = SequenceClassificationModel::new(Default::default())?;
+    /// let weights_resource = Resource::Local(LocalResource {
+    ///     local_path: PathBuf::from("path/to/updated/rust_model.ot"),
+    /// });
+    /// model.reload_weights(&weights_resource)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reload_weights(&mut self, weights_resource: &Resource) -> Result<(), RustBertError> {
+        reload_var_store(&self.var_store, weights_resource)
+    }
+
+    /// Sets the temperature dividing the label logits before the softmax (or sigmoid for multi-label classification),
+    /// calibrating the scores returned by the pipeline ([Guo et al.](https://arxiv.org/abs/1706.04599)). A temperature
+    /// higher than 1 softens the scores and a temperature lower than 1 sharpens them. The predicted label (argmax) is
+    /// unchanged, but the labels passing the threshold of a multi-label classification may differ. See `fit_temperature`
+    /// to fit the temperature on a validation set (default: 1.0, no calibration).
+    ///
+    /// # Arguments
+    ///
+    /// * `temperature` - `f64` strictly positive calibration temperature
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let mut sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// sequence_classification_model.set_calibration_temperature(1.5)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_calibration_temperature(&mut self, temperature: f64) -> Result<(), RustBertError> {
+        if !(temperature.is_finite() && temperature > 0f64) {
+            return Err(RustBertError::ValueError(format!(
+                "The calibration temperature must be strictly positive, got {}",
+                temperature
+            )));
+        }
+        self.calibration_temperature = temperature;
+        Ok(())
+    }
+
+    /// Sets the sink receiving the diagnostics of the pipeline (truncated inputs and unknown tokens), or removes it with `None`
+    ///
+    /// # Arguments
+    ///
+    /// * `diagnostics_sink` - `Option<DiagnosticsSink>` callback called with each diagnostic reported by the pipeline
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let mut sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// sequence_classification_model.set_diagnostics_sink(Some(Box::new(|diagnostic| {
+    ///     eprintln!("{:?}: {}", diagnostic.kind, diagnostic.message)
+    /// })));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_diagnostics_sink(&mut self, diagnostics_sink: Option<DiagnosticsSink>) {
+        self.diagnostics_sink = diagnostics_sink;
+    }
+
+    /// Finds the temperature minimizing the negative log-likelihood of the labels of a validation set
+    /// given the label logits, using a golden-section search over the logarithm of the temperature
+    /// (between 0.01 and 100). The result can be passed to `set_calibration_temperature`.
+    ///
+    /// # Arguments
+    ///
+    /// * `logits` - `Tensor` of shape (*number of examples*, *number of labels*) with the uncalibrated logits, e.g. from `predict_logits`
+    /// * `labels` - `Tensor` of shape (*number of examples*) with the index of the true label of each example
+    ///
+    /// # Returns
+    ///
+    /// * `f64` calibration temperature
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// use tch::Tensor;
+    ///
+    /// let mut sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let validation_texts = ["This movie was a masterpiece.", "What a waste of time."];
+    /// let validation_labels = Tensor::of_slice(&[1i64, 0]);
+    ///
+    /// let logits = sequence_classification_model.predict_logits(&validation_texts);
+    /// let temperature = SequenceClassificationModel::fit_temperature(&logits, &validation_labels);
+    /// sequence_classification_model.set_calibration_temperature(temperature)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn fit_temperature(logits: &Tensor, labels: &Tensor) -> f64 {
+        let logits = logits.to_kind(Kind::Double).to(Device::Cpu);
+        let labels = labels.to_kind(Kind::Int64).to(Device::Cpu).unsqueeze(-1);
+        let negative_log_likelihood = |log_temperature: f64| {
+            -(&logits / log_temperature.exp())
+                .log_softmax(-1, Kind::Double)
+                .gather(1, &labels, false)
+                .mean(Kind::Double)
+                .double_value(&[])
+        };
+        //        The negative log-likelihood is convex in the inverse temperature, and therefore unimodal in its logarithm
+        let inverse_golden_ratio = (5f64.sqrt() - 1f64) / 2f64;
+        let (mut lower, mut upper) = (0.01f64.ln(), 100f64.ln());
+        let mut left = upper - inverse_golden_ratio * (upper - lower);
+        let mut right = lower + inverse_golden_ratio * (upper - lower);
+        let (mut left_value, mut right_value) = (
+            negative_log_likelihood(left),
+            negative_log_likelihood(right),
+        );
+        while upper - lower > 1e-6 {
+            if left_value < right_value {
+                upper = right;
+                right = left;
+                right_value = left_value;
+                left = upper - inverse_golden_ratio * (upper - lower);
+                left_value = negative_log_likelihood(left);
+            } else {
+                lower = left;
+                left = right;
+                left_value = right_value;
+                right = lower + inverse_golden_ratio * (upper - lower);
+                right_value = negative_log_likelihood(right);
+            }
+        }
+        ((lower + upper) / 2f64).exp()
+    }
+
     fn prepare_for_model<'a, S>(&self, input: S) -> Tensor
     where
         S: AsRef<[&'a str]>,
     {
-        let tokenized_input: Vec<TokenizedInput> =
-            self.tokenizer
-                .encode_list(input.as_ref(), 128, &TruncationStrategy::LongestFirst, 0);
+        let tokenized_input: Vec<TokenizedInput> = match self.truncation {
+            InputTruncation::Head => self.tokenizer.encode_list(
+                input.as_ref(),
+                MAX_INPUT_LENGTH,
+                &TruncationStrategy::LongestFirst,
+                0,
+            ),
+            InputTruncation::HeadTail { head, tail } => {
+                self.tokenizer
+                    .encode_list_head_tail(input.as_ref(), head, tail)
+            }
+        };
+        report_tokenization_diagnostics(
+            &self.diagnostics_sink,
+            &tokenized_input,
+            self.tokenizer.get_unk_id(),
+        );
         let max_len = tokenized_input
             .iter()
             .map(|input| input.token_ids.len())
@@ -555,6 +983,42 @@ impl SequenceClassificationModel {
         Tensor::stack(tokenized_input_tensors.as_slice(), 0).to(self.var_store.device())
     }
 
+    fn build_attention_mask(&self, input_tensor: &Tensor) -> Tensor {
+        build_padding_attention_mask(
+            input_tensor,
+            self.tokenizer.get_pad_id(),
+            PaddingSide::Right,
+        )
+    }
+
+    fn forward(&self, input_tensor: &Tensor) -> Tensor {
+        let attention_mask = self.build_attention_mask(input_tensor);
+        match &self.classification_head {
+            Some(classification_head) => {
+                let hidden_states = self
+                    .sequence_classifier
+                    .forward_hidden_states(
+                        Some(input_tensor.copy()),
+                        Some(attention_mask.copy()),
+                        None,
+                        None,
+                        None,
+                        false,
+                    )
+                    .unwrap();
+                classification_head.forward(&hidden_states, &attention_mask)
+            }
+            None => self.sequence_classifier.forward_t(
+                Some(input_tensor.copy()),
+                Some(attention_mask),
+                None,
+                None,
+                None,
+                false,
+            ),
+        }
+    }
+
     /// Classify texts
     ///
     /// # Arguments
@@ -569,7 +1033,7 @@ impl SequenceClassificationModel {
     ///
     /// ```no_run
     /// # fn main() -> anyhow::Result<()> {
-    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
     ///
     /// let sequence_classification_model =  SequenceClassificationModel::new(Default::default())?;
     /// let input = [
@@ -587,22 +1051,23 @@ impl SequenceClassificationModel {
     {
         let input_tensor = self.prepare_for_model(input.as_ref());
         let output = no_grad(|| {
-            let output = self.sequence_classifier.forward_t(
-                Some(input_tensor.copy()),
-                None,
-                None,
-                None,
-                None,
-                false,
-            );
-            output.softmax(-1, Kind::Float).detach().to(Device::Cpu)
+            let output = self.forward(&input_tensor);
+            assert_no_grad(&output);
+            (output / self.calibration_temperature)
+                .softmax(-1, Kind::Float)
+                .detach()
+                .to(Device::Cpu)
         });
         let label_indices = output.as_ref().argmax(-1, true).squeeze1(1);
         let scores = output
             .gather(1, &label_indices.unsqueeze(-1), false)
             .squeeze1(1);
+        let margins = label_margins(&output)
+            .gather(1, &label_indices.unsqueeze(-1), false)
+            .squeeze1(1);
         let label_indices = label_indices.iter::<i64>().unwrap().collect::<Vec<i64>>();
         let scores = scores.iter::<f64>().unwrap().collect::<Vec<f64>>();
+        let margins = margins.iter::<f64>().unwrap().collect::<Vec<f64>>();
 
         let mut labels: Vec<Label> = vec![];
         for sentence_idx in 0..label_indices.len() {
@@ -616,12 +1081,66 @@ impl SequenceClassificationModel {
                 score: scores[sentence_idx],
                 id: label_indices[sentence_idx],
                 sentence: sentence_idx,
+                margin: margins[sentence_idx],
             };
             labels.push(label)
         }
         labels
     }
 
+    /// Returns the label logits for texts, before calibration
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*number of texts*, *number of labels*) containing the label logits, placed on the CPU
+    pub fn predict_logits<'a, S>(&self, input: S) -> Tensor
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let input_tensor = self.prepare_for_model(input.as_ref());
+        no_grad(|| {
+            let output = self.forward(&input_tensor);
+            assert_no_grad(&output);
+            output.detach().to(Device::Cpu)
+        })
+    }
+
+    /// Returns the attention mask built by the pipeline for texts, after truncation and padding.
+    /// This debugging method returns the exact mask passed to the model by `predict`,
+    /// `predict_logits` and `predict_multilabel` for the same batch of texts.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*number of texts*, *sequence_length*) with 1 for tokens attended to and 0 for padding positions, placed on the CPU
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let input = ["A short text.", "A slightly longer text, requiring less padding."];
+    /// let attention_mask = sequence_classification_model.get_attention_mask(&input);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_attention_mask<'a, S>(&self, input: S) -> Tensor
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let input_tensor = self.prepare_for_model(input.as_ref());
+        self.build_attention_mask(&input_tensor).to(Device::Cpu)
+    }
+
     /// Multi-label classification of texts
     ///
     /// # Arguments
@@ -637,7 +1156,7 @@ impl SequenceClassificationModel {
     ///
     /// ```no_run
     /// # fn main() -> anyhow::Result<()> {
-    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
     ///
     /// let sequence_classification_model =  SequenceClassificationModel::new(Default::default())?;
     /// let input = [
@@ -656,17 +1175,15 @@ impl SequenceClassificationModel {
     ) -> Result<Vec<Vec<Label>>, RustBertError> {
         let input_tensor = self.prepare_for_model(input.to_vec());
         let output = no_grad(|| {
-            let output = self.sequence_classifier.forward_t(
-                Some(input_tensor.copy()),
-                None,
-                None,
-                None,
-                None,
-                false,
-            );
-            output.sigmoid().detach().to(Device::Cpu)
+            let output = self.forward(&input_tensor);
+            assert_no_grad(&output);
+            (output / self.calibration_temperature)
+                .sigmoid()
+                .detach()
+                .to(Device::Cpu)
         });
         let label_indices = output.as_ref().ge(threshold).nonzero();
+        let margins = label_margins(&output);
 
         let mut labels: Vec<Vec<Label>> = vec![];
         let mut sequence_labels: Vec<Label> = vec![];
@@ -683,12 +1200,14 @@ impl SequenceClassificationModel {
                 sequence_labels = vec![];
             }
             let score = output.double_value(sentence_label.as_slice());
+            let margin = margins.double_value(sentence_label.as_slice());
             let label_string = self.label_mapping.get(&id).unwrap().to_owned();
             let label = Label {
                 text: label_string,
                 score,
                 id,
                 sentence: sentence as usize,
+                margin,
             };
             sequence_labels.push(label);
         }
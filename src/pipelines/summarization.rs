@@ -62,7 +62,12 @@
 //! # ;
 //! ```
 
-use tch::{Device, Tensor};
+use rust_tokenizers::tokenizer::TruncationStrategy;
+use rust_tokenizers::Offset;
+use std::collections::HashMap;
+use std::ops::Range;
+use tch::kind::Kind::Float;
+use tch::{no_grad, Device, Tensor};
 
 use crate::bart::{
     BartConfigResources, BartGenerator, BartMergesResources, BartModelResources, BartVocabResources,
@@ -70,8 +75,14 @@ use crate::bart::{
 use crate::common::error::RustBertError;
 use crate::common::resources::{RemoteResource, Resource};
 use crate::pegasus::PegasusConditionalGenerator;
-use crate::pipelines::common::ModelType;
-use crate::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+use crate::pipelines::common::{DetokenizationProfile, ModelType, TokenizerOption};
+use crate::pipelines::diagnostics::{report_generation_diagnostics, DiagnosticsSink};
+use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
+use crate::pipelines::generation_utils::{
+    apply_post_process, GenerateConfig, LanguageGenerator, LengthBias, PostProcess,
+    ReasoningBudget, RepetitionPenaltyMode, WarperKind,
+};
+use crate::pipelines::sentence_splitter::{RuleBasedSentenceSplitter, SentenceSplitter};
 use crate::prophetnet::ProphetNetConditionalGenerator;
 use crate::t5::T5Generator;
 
@@ -117,6 +128,59 @@ pub struct SummarizationConfig {
     pub num_beam_groups: Option<i64>,
     /// Diversity penalty for diverse beam search. High values will enforce more difference between beam groups (default: 5.5)
     pub diversity_penalty: Option<f64>,
+    /// Corpus-derived additive logits adjustments, keyed by token id. Applied as a static bias on top of the dynamic repetition penalty (default: None)
+    pub corpus_penalty: Option<HashMap<i64, f64>>,
+    /// Reasoning budget capping the number of tokens generated inside a delimited reasoning block before the answer (default: None)
+    pub reasoning_budget: Option<ReasoningBudget>,
+    /// Maximum number of sequences decoded simultaneously, splitting generation into sequential micro-batches to bound peak memory (default: None)
+    pub generation_microbatch_size: Option<i64>,
+    /// EOS probability threshold above which a sequence is ended, even if EOS is not the most likely token (default: None)
+    pub eos_probability_threshold: Option<f64>,
+    /// Soft length control: a bias varying with the sequence length is added to the logits of the EOS tokens at each step, encouraging
+    /// the sequences to end near a target length (see `LengthBias`). `min_length` still prevents EOS before it is reached (default: None)
+    pub length_bias: Option<LengthBias>,
+    /// Form of the repetition penalty applied to previously generated tokens (default: `RepetitionPenaltyMode::Multiplicative`)
+    pub repetition_penalty_mode: RepetitionPenaltyMode,
+    /// Order in which the logits warpers are applied. Warpers omitted from the list are not applied
+    /// (default: `WarperKind::default_order()`, i.e. repetition penalty, temperature, top-k and top-p)
+    pub warper_order: Vec<WarperKind>,
+    /// Number of most likely candidate tokens to report, with their probabilities, for each generated position. The alternatives are
+    /// returned in the `top_k_alternatives` of `generate_indices_with_output` (default: None)
+    pub output_top_k_alternatives: Option<usize>,
+    /// Report the memory footprint (in bytes) of the key/value cache after each decoding step, returned in the `cache_memory` of
+    /// `generate_indices_with_output`. Measuring the cache at each step has a cost and is disabled by default (default: false)
+    pub output_cache_memory: bool,
+    /// Frequency penalty (OpenAI-style): subtracted from the logits of each token, multiplied by the number of times the token was
+    /// already generated. Negative values encourage repetitions (default: 0.0)
+    pub frequency_penalty: f64,
+    /// Presence penalty (OpenAI-style): subtracted from the logits of each token already generated at least once.
+    /// Negative values encourage repetitions (default: 0.0)
+    pub presence_penalty: f64,
+    /// Source copy bias: added to the logits of the tokens present in the encoder input (or prompt for decoder-only models) at each step,
+    /// a soft prior towards copying from the source improving the faithfulness of abstractive summaries. High values push the generation
+    /// towards extraction (default: 0.0)
+    pub source_copy_bias: f64,
+    /// Token healing for decoder-only models: the last token of the prompt is removed and the first generated token is restricted to
+    /// the tokens starting with its surface form, avoiding unnatural continuations of prompts ending mid-word. Prompts of a single token
+    /// are not healed (default: false)
+    pub token_healing: bool,
+    /// Detokenization profile used to convert the generated tokens to text. `DetokenizationProfile::Code` preserves the literal output
+    /// of the model (e.g. indentation and spacing around operators) (default: `DetokenizationProfile::Natural`)
+    pub detokenization_profile: DetokenizationProfile,
+    /// Suffix forced at the end of the generated sequences, before the EOS token (e.g. a closing tag). Hypotheses cannot end before
+    /// emitting the full suffix, and the remaining suffix tokens are forced once the positions left before `max_length` are just enough
+    /// to emit them followed by EOS. A suffix emitted by the model on its own before that point allows the sequence to end (default: None)
+    pub end_suffix_ids: Option<Vec<i64>>,
+    /// Maximum size in bytes of the generated text (excluding the prompt), for outputs subject to downstream length limits. The running
+    /// output is decoded at each step and a sequence ends before the token that would exceed the budget. Trailing tokens decoding to an
+    /// incomplete character are removed, so that multi-byte characters are never split. Decoding at each step is costly: this is meant for
+    /// short outputs. Only supported for greedy decoding and sampling (default: None)
+    pub max_output_bytes: Option<usize>,
+    /// Maximum number of characters of the generated text (excluding the prompt), see `max_output_bytes` (default: None)
+    pub max_output_chars: Option<usize>,
+    /// Post-processing applied to each output string before it is returned (e.g. trimming or capitalization), after detokenization
+    /// and the removal of special tokens (default: None)
+    pub post_process: Option<PostProcess>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
 }
@@ -180,6 +244,24 @@ impl Default for SummarizationConfig {
             num_return_sequences: 1,
             num_beam_groups: None,
             diversity_penalty: None,
+            corpus_penalty: None,
+            reasoning_budget: None,
+            generation_microbatch_size: None,
+            eos_probability_threshold: None,
+            length_bias: None,
+            repetition_penalty_mode: RepetitionPenaltyMode::Multiplicative,
+            warper_order: WarperKind::default_order(),
+            output_top_k_alternatives: None,
+            output_cache_memory: false,
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            source_copy_bias: 0.0,
+            token_healing: false,
+            detokenization_profile: DetokenizationProfile::Natural,
+            end_suffix_ids: None,
+            max_output_bytes: None,
+            max_output_chars: None,
+            post_process: None,
             device: Device::cuda_if_available(),
         }
     }
@@ -206,6 +288,23 @@ impl From<SummarizationConfig> for GenerateConfig {
             num_return_sequences: config.num_return_sequences,
             num_beam_groups: config.num_beam_groups,
             diversity_penalty: config.diversity_penalty,
+            corpus_penalty: config.corpus_penalty,
+            reasoning_budget: config.reasoning_budget,
+            generation_microbatch_size: config.generation_microbatch_size,
+            eos_probability_threshold: config.eos_probability_threshold,
+            length_bias: config.length_bias,
+            repetition_penalty_mode: config.repetition_penalty_mode,
+            warper_order: config.warper_order,
+            output_top_k_alternatives: config.output_top_k_alternatives,
+            output_cache_memory: config.output_cache_memory,
+            frequency_penalty: config.frequency_penalty,
+            presence_penalty: config.presence_penalty,
+            source_copy_bias: config.source_copy_bias,
+            token_healing: config.token_healing,
+            detokenization_profile: config.detokenization_profile,
+            end_suffix_ids: config.end_suffix_ids,
+            max_output_bytes: config.max_output_bytes,
+            max_output_chars: config.max_output_chars,
             device: config.device,
         }
     }
@@ -253,7 +352,27 @@ impl SummarizationOption {
         }
     }
 
-    /// Interface method to generate() of the particular models.
+    /// Interface method to access tokenizer
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        match self {
+            Self::Bart(model_ref) => model_ref.get_tokenizer(),
+            Self::T5(model_ref) => model_ref.get_tokenizer(),
+            Self::ProphetNet(model_ref) => model_ref.get_tokenizer(),
+            Self::Pegasus(model_ref) => model_ref.get_tokenizer(),
+        }
+    }
+
+    /// Interface method to access the generation configuration
+    pub(crate) fn get_generate_config(&self) -> &GenerateConfig {
+        match self {
+            Self::Bart(model_ref) => model_ref.get_config(),
+            Self::T5(model_ref) => model_ref.get_config(),
+            Self::ProphetNet(model_ref) => model_ref.get_config(),
+            Self::Pegasus(model_ref) => model_ref.get_config(),
+        }
+    }
+
+    /// Interface method to generate_flat() of the particular models.
     pub fn generate<'a, S>(
         &self,
         prompt_texts: Option<S>,
@@ -263,24 +382,213 @@ impl SummarizationOption {
         S: AsRef<[&'a str]>,
     {
         match *self {
-            Self::Bart(ref model) => model.generate(prompt_texts, attention_mask, None, None, None),
-            Self::T5(ref model) => model.generate(prompt_texts, attention_mask, None, None, None),
+            Self::Bart(ref model) => {
+                model.generate_flat(prompt_texts, attention_mask, None, None, None)
+            }
+            Self::T5(ref model) => {
+                model.generate_flat(prompt_texts, attention_mask, None, None, None)
+            }
             Self::ProphetNet(ref model) => {
-                model.generate(prompt_texts, attention_mask, None, None, None)
+                model.generate_flat(prompt_texts, attention_mask, None, None, None)
             }
             Self::Pegasus(ref model) => {
-                model.generate(prompt_texts, attention_mask, None, None, None)
+                model.generate_flat(prompt_texts, attention_mask, None, None, None)
             }
         }
     }
+
+    /// Interface method to generate_indices() of the particular models.
+    pub fn generate_indices<'a, S>(
+        &self,
+        prompt_texts: Option<S>,
+        attention_mask: Option<Tensor>,
+    ) -> Vec<Vec<i64>>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        match *self {
+            Self::Bart(ref model) => {
+                model.generate_indices(prompt_texts, attention_mask, None, None, None)
+            }
+            Self::T5(ref model) => {
+                model.generate_indices(prompt_texts, attention_mask, None, None, None)
+            }
+            Self::ProphetNet(ref model) => {
+                model.generate_indices(prompt_texts, attention_mask, None, None, None)
+            }
+            Self::Pegasus(ref model) => {
+                model.generate_indices(prompt_texts, attention_mask, None, None, None)
+            }
+        }
+    }
+
+    /// Interface method to the cross-attention weights of the BART-based models. The generated token ids (starting
+    /// with the decoder start token) are teacher-forced, and the weights are averaged over layers and heads. The
+    /// attention weights are only collected for this forward pass, the model does not need to output attentions.
+    ///
+    /// # Arguments
+    ///
+    /// * `source_ids` - `&[i64]` token ids of the source text
+    /// * `generated_ids` - `&[i64]` token ids generated by the model, including the decoder start token
+    ///
+    /// # Returns
+    /// * `Tensor` of shape (*generated_length - 1*, *source_length*) with the cross-attention weights used to generate each token following the decoder start token
+    pub fn forward_cross_attentions(
+        &self,
+        source_ids: &[i64],
+        generated_ids: &[i64],
+    ) -> Result<Tensor, RustBertError> {
+        if generated_ids.len() < 2 {
+            return Err(RustBertError::ValueError(
+                "Cross-attention weights require a decoder start token followed by at least one generated token"
+                    .into(),
+            ));
+        }
+        let cross_attentions = no_grad(|| match *self {
+            Self::Bart(ref model) => {
+                let (input_ids, decoder_input_ids) = teacher_forcing_inputs(
+                    source_ids,
+                    generated_ids,
+                    model.get_var_store().device(),
+                );
+                Ok(model
+                    .get_model()
+                    .forward_cross_attentions(&input_ids, &decoder_input_ids))
+            }
+            Self::Pegasus(ref model) => {
+                let (input_ids, decoder_input_ids) = teacher_forcing_inputs(
+                    source_ids,
+                    generated_ids,
+                    model.get_var_store().device(),
+                );
+                Ok(model
+                    .get_model()
+                    .forward_cross_attentions(&input_ids, &decoder_input_ids))
+            }
+            Self::T5(_) | Self::ProphetNet(_) => {
+                Err(RustBertError::InvalidConfigurationError(format!(
+                    "Cross-attention weights are not available for {:?}",
+                    self.model_type()
+                )))
+            }
+        })?;
+        Ok(Tensor::stack(&cross_attentions, 0)
+            .mean1(&[0, 1, 2], false, Float)
+            .to(Device::Cpu))
+    }
+
+    /// Interface method to generate_indices_multi_length() of the particular models.
+    pub fn generate_multi_length<'a, S>(
+        &self,
+        prompt_texts: S,
+        length_targets: &[(i64, i64)],
+    ) -> Vec<Vec<Vec<i64>>>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        match *self {
+            Self::Bart(ref model) => {
+                model.generate_indices_multi_length(prompt_texts, length_targets)
+            }
+            Self::T5(ref model) => {
+                model.generate_indices_multi_length(prompt_texts, length_targets)
+            }
+            Self::ProphetNet(ref model) => {
+                model.generate_indices_multi_length(prompt_texts, length_targets)
+            }
+            Self::Pegasus(ref model) => {
+                model.generate_indices_multi_length(prompt_texts, length_targets)
+            }
+        }
+    }
+}
+
+/// Returns the index of the sentence containing each token (`None` for tokens without offsets or outside of the sentences)
+fn token_sentence_indices(
+    text: &str,
+    sentences: &[Range<usize>],
+    token_offsets: &[Option<Offset>],
+) -> Vec<Option<usize>> {
+    //        Token offsets are expressed in characters, sentence offsets in bytes
+    let char_ranges = sentences
+        .iter()
+        .map(|range| {
+            let start = text[..range.start].chars().count() as u32;
+            let end = start + text[range.clone()].chars().count() as u32;
+            (start, end)
+        })
+        .collect::<Vec<(u32, u32)>>();
+    token_offsets
+        .iter()
+        .map(|offset| {
+            offset.and_then(|offset| {
+                char_ranges
+                    .iter()
+                    .position(|(start, end)| (offset.begin >= *start) && (offset.end <= *end))
+            })
+        })
+        .collect()
+}
+
+/// Builds the encoder and teacher-forced decoder inputs (generated tokens, without the last one)
+fn teacher_forcing_inputs(
+    source_ids: &[i64],
+    generated_ids: &[i64],
+    device: Device,
+) -> (Tensor, Tensor) {
+    (
+        Tensor::of_slice(source_ids).unsqueeze(0).to(device),
+        Tensor::of_slice(&generated_ids[..generated_ids.len() - 1])
+            .unsqueeze(0)
+            .to(device),
+    )
 }
 
 /// # SummarizationModel to perform summarization
 pub struct SummarizationModel {
     model: SummarizationOption,
     prefix: Option<String>,
+    detokenization_profile: DetokenizationProfile,
+    post_process: Option<PostProcess>,
+    sentence_splitter: Box<dyn SentenceSplitter>,
+    diagnostics_sink: Option<DiagnosticsSink>,
+}
+
+#[derive(Debug, Clone)]
+/// Source sentence a summary sentence is attributed to
+pub struct SourceAttribution {
+    /// Source sentence text
+    pub text: String,
+    /// Byte offsets of the sentence in the source text
+    pub span: Range<usize>,
+    /// Share of the cross-attention of the summary sentence received by the source sentence. The weights
+    /// of the source sentences of a summary sentence sum to 1.
+    pub weight: f64,
+}
+
+#[derive(Debug, Clone)]
+/// Summary sentence with the source sentences it was derived from
+pub struct SummarySentenceAttribution {
+    /// Summary sentence text
+    pub text: String,
+    /// Byte offsets of the sentence in the summary
+    pub span: Range<usize>,
+    /// Source sentences, ranked by decreasing attribution weight
+    pub sources: Vec<SourceAttribution>,
 }
 
+#[derive(Debug, Clone)]
+/// Summary with the attribution of its sentences to the source sentences
+pub struct AttributedSummary {
+    /// Summary text, decoded from the generated tokens (without the post-processing of the pipeline)
+    pub summary: String,
+    /// Attribution of the summary sentences, in the order of the summary
+    pub sentences: Vec<SummarySentenceAttribution>,
+}
+
+/// Maximum number of source tokens encoded for generation (and attribution) with encoder-decoder models
+const MAX_SOURCE_LENGTH: usize = 1024;
+
 impl SummarizationModel {
     /// Build a new `SummarizationModel`
     ///
@@ -300,14 +608,68 @@ impl SummarizationModel {
     /// ```
     pub fn new(
         summarization_config: SummarizationConfig,
+    ) -> Result<SummarizationModel, RustBertError> {
+        SummarizationModel::new_with_splitter(
+            summarization_config,
+            Box::new(RuleBasedSentenceSplitter::default()),
+        )
+    }
+
+    /// Build a new `SummarizationModel` with a custom sentence splitter, used by `summarize_with_attribution`
+    ///
+    /// # Arguments
+    ///
+    /// * `summarization_config` - `SummarizationConfig` object containing the resource references (model, vocabulary, configuration), summarization options and device placement (CPU/GPU)
+    /// * `sentence_splitter` - `Box<dyn SentenceSplitter>` used to identify the sentence spans of the sources and summaries
+    pub fn new_with_splitter(
+        mut summarization_config: SummarizationConfig,
+        sentence_splitter: Box<dyn SentenceSplitter>,
     ) -> Result<SummarizationModel, RustBertError> {
         let prefix = match summarization_config.model_type {
             ModelType::T5 => Some("summarize: ".to_string()),
             _ => None,
         };
+        let detokenization_profile = summarization_config.detokenization_profile;
+        let post_process = summarization_config.post_process.take();
         let model = SummarizationOption::new(summarization_config)?;
 
-        Ok(SummarizationModel { model, prefix })
+        Ok(SummarizationModel {
+            model,
+            prefix,
+            detokenization_profile,
+            post_process,
+            sentence_splitter,
+            diagnostics_sink: None,
+        })
+    }
+
+    /// Interface method to access tokenizer
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        self.model.get_tokenizer()
+    }
+
+    /// Sets the sink receiving the diagnostics of the pipeline (generation settings that are not applied, reported at
+    /// each call), or removes it with `None`
+    ///
+    /// # Arguments
+    ///
+    /// * `diagnostics_sink` - `Option<DiagnosticsSink>` callback called with each diagnostic reported by the pipeline
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::summarization::SummarizationModel;
+    ///
+    /// let mut summarization_model = SummarizationModel::new(Default::default())?;
+    /// summarization_model.set_diagnostics_sink(Some(Box::new(|diagnostic| {
+    ///     eprintln!("{:?}: {}", diagnostic.kind, diagnostic.message)
+    /// })));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_diagnostics_sink(&mut self, diagnostics_sink: Option<DiagnosticsSink>) {
+        self.diagnostics_sink = diagnostics_sink;
     }
 
     /// Summarize texts provided
@@ -358,21 +720,283 @@ impl SummarizationModel {
     where
         S: AsRef<[&'a str]>,
     {
-        match &self.prefix {
+        report_generation_diagnostics(&self.diagnostics_sink, self.model.get_generate_config());
+        let summaries = match &self.prefix {
             None => self.model.generate(Some(texts), None),
-            Some(prefix) => {
+            Some(_) => {
                 let texts = texts
                     .as_ref()
                     .iter()
-                    .map(|text| format!("{}{}", prefix, text))
+                    .map(|text| self.prefixed_text(text))
                     .collect::<Vec<String>>();
                 self.model.generate(
                     Some(texts.iter().map(|x| &**x).collect::<Vec<&str>>()),
                     None,
                 )
             }
+        };
+        apply_post_process(self.post_process.as_ref(), summaries)
+    }
+
+    /// Source text passed to the model, with the prefix of the pipeline if any
+    fn prefixed_text(&self, text: &str) -> String {
+        match &self.prefix {
+            //        Empty inputs are not prefixed so that they are not summarized
+            Some(prefix) if !text.trim().is_empty() => format!("{}{}", prefix, text),
+            _ => text.to_string(),
         }
     }
+
+    /// Summarize texts and attribute each summary sentence to the source sentences it was derived from.
+    /// The cross-attention weights of the decoder over the source tokens, averaged over layers and heads,
+    /// are aggregated from the tokens of each summary sentence onto the tokens of each source sentence.
+    /// The generated tokens are teacher-forced in a second forward pass, the only one collecting attention weights.
+    /// This is only supported for the BART-based models (BART and Pegasus). The sentence spans refer to the decoded
+    /// summary, which is therefore returned without the `post_process` step of the pipeline.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to summarize.
+    ///
+    /// # Returns
+    /// * `Vec<AttributedSummary>` Summaries with the ranked source sentences of each summary sentence
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::summarization::SummarizationModel;
+    /// let model = SummarizationModel::new(Default::default())?;
+    ///
+    /// let input = ["In findings published Tuesday in Cornell University's arXiv by a team of scientists
+    /// from the University of Montreal and a separate report published Wednesday in Nature Astronomy by a team
+    /// from University College London (UCL), the presence of water vapour was confirmed in the atmosphere of K2-18b,
+    /// a planet circling a star in the constellation Leo. This is the first such discovery in a planet in its star's
+    /// habitable zone — not too hot and not too cold for liquid water to exist."];
+    ///
+    /// let output = model.summarize_with_attribution(&input)?;
+    /// for sentence in &output[0].sentences {
+    ///     println!("{} <- {}", sentence.text, sentence.sources[0].text);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn summarize_with_attribution<'a, S>(
+        &self,
+        texts: S,
+    ) -> Result<Vec<AttributedSummary>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        report_generation_diagnostics(&self.diagnostics_sink, self.model.get_generate_config());
+        match self.model.model_type() {
+            ModelType::Bart | ModelType::Pegasus => {}
+            model_type => {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Summary attribution is not implemented for {:?}",
+                    model_type
+                )));
+            }
+        }
+        let texts = texts.as_ref();
+        let prefixed_texts = texts
+            .iter()
+            .map(|text| self.prefixed_text(text))
+            .collect::<Vec<String>>();
+        let generated = self.model.generate_indices(
+            Some(
+                prefixed_texts
+                    .iter()
+                    .map(|text| text.as_str())
+                    .collect::<Vec<&str>>(),
+            ),
+            None,
+        );
+        texts
+            .iter()
+            .zip(prefixed_texts.iter())
+            .zip(generated)
+            .map(|((text, prefixed_text), generated_ids)| {
+                self.attribute_summary(text, prefixed_text, generated_ids)
+            })
+            .collect()
+    }
+
+    fn attribute_summary(
+        &self,
+        text: &str,
+        prefixed_text: &str,
+        mut generated_ids: Vec<i64>,
+    ) -> Result<AttributedSummary, RustBertError> {
+        let tokenizer = self.model.get_tokenizer();
+        if let Some(pad_id) = tokenizer.get_pad_id() {
+            while (generated_ids.len() > 1) && (generated_ids.last() == Some(&pad_id)) {
+                generated_ids.pop();
+            }
+        }
+        let (summary, summary_offsets) =
+            tokenizer.decode_with_offsets(&generated_ids, true, self.detokenization_profile);
+        let summary_sentences = self.sentence_splitter.split_offsets(&summary);
+
+        //        The source is encoded as for generation (prefixed, with special tokens and truncated),
+        //        its sentences are split from the original text and shifted by the length of the prefix
+        let prefix_length = prefixed_text.len() - text.len();
+        let source_sentences = self
+            .sentence_splitter
+            .split_offsets(text)
+            .into_iter()
+            .map(|span| span.start + prefix_length..span.end + prefix_length)
+            .collect::<Vec<Range<usize>>>();
+        let source_input = tokenizer.encode_pair(
+            prefixed_text,
+            None,
+            MAX_SOURCE_LENGTH,
+            &TruncationStrategy::LongestFirst,
+            0,
+        );
+
+        //        Summary sentence x source sentence aggregated cross-attention
+        let mut sentence_weights =
+            vec![vec![0f64; source_sentences.len()]; summary_sentences.len()];
+        if !source_input.token_ids.is_empty()
+            && (generated_ids.len() > 1)
+            && !source_sentences.is_empty()
+        {
+            let cross_attention = self
+                .model
+                .forward_cross_attentions(&source_input.token_ids, &generated_ids)?;
+            let source_length = source_input.token_ids.len();
+            let cross_attention = Vec::<f64>::from(cross_attention.view(-1));
+
+            let source_sentence_indices = token_sentence_indices(
+                prefixed_text,
+                &source_sentences,
+                &source_input.token_offsets,
+            );
+            //        The attention of row t is used to generate token t + 1 (after the decoder start token)
+            for (target_index, (start, end)) in summary_offsets.iter().skip(1).enumerate() {
+                let summary_sentence_index = if start < end {
+                    summary_sentences
+                        .iter()
+                        .position(|span| (span.start < *end) && (*end <= span.end))
+                } else {
+                    None
+                };
+                if let Some(summary_sentence_index) = summary_sentence_index {
+                    let attention = &cross_attention
+                        [target_index * source_length..(target_index + 1) * source_length];
+                    for (weight, source_sentence_index) in
+                        attention.iter().zip(source_sentence_indices.iter())
+                    {
+                        if let Some(source_sentence_index) = source_sentence_index {
+                            sentence_weights[summary_sentence_index][*source_sentence_index] +=
+                                weight;
+                        }
+                    }
+                }
+            }
+        }
+
+        let sentences = summary_sentences
+            .into_iter()
+            .zip(sentence_weights)
+            .map(|(summary_span, weights)| {
+                let total_weight: f64 = weights.iter().sum();
+                let mut sources = source_sentences
+                    .iter()
+                    .zip(weights)
+                    .filter(|(_, weight)| *weight > 0f64)
+                    .map(|(source_span, weight)| {
+                        let span =
+                            source_span.start - prefix_length..source_span.end - prefix_length;
+                        SourceAttribution {
+                            text: text[span.clone()].to_string(),
+                            span,
+                            weight: weight / total_weight,
+                        }
+                    })
+                    .collect::<Vec<SourceAttribution>>();
+                sources.sort_by(|a, b| {
+                    b.weight
+                        .partial_cmp(&a.weight)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+                SummarySentenceAttribution {
+                    text: summary[summary_span.clone()].to_string(),
+                    span: summary_span,
+                    sources,
+                }
+            })
+            .collect();
+        Ok(AttributedSummary { summary, sentences })
+    }
+
+    /// Summarize a text for several length targets. The text is encoded once and decoded for each
+    /// (`min_length`, `max_length`) target, which is cheaper than calling `summarize` once per target.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - `&str` text to summarize.
+    /// * `length_targets` - `&[(i64, i64)]` slice of (`min_length`, `max_length`) targets, in tokens.
+    ///
+    /// # Returns
+    /// * `Vec<((i64, i64), String)>` Summaries keyed by their length target, in the order of `length_targets`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::summarization::SummarizationModel;
+    /// let model = SummarizationModel::new(Default::default())?;
+    ///
+    /// let input = "In findings published Tuesday in Cornell University's arXiv by a team of scientists
+    /// from the University of Montreal and a separate report published Wednesday in Nature Astronomy by a team
+    /// from University College London (UCL), the presence of water vapour was confirmed in the atmosphere of K2-18b,
+    /// a planet circling a star in the constellation Leo. This is the first such discovery in a planet in its star's
+    /// habitable zone — not too hot and not too cold for liquid water to exist.";
+    ///
+    /// let output = model.summarize_multi_length(input, &[(10, 30), (30, 80)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn summarize_multi_length(
+        &self,
+        text: &str,
+        length_targets: &[(i64, i64)],
+    ) -> Vec<((i64, i64), String)> {
+        report_generation_diagnostics(&self.diagnostics_sink, self.model.get_generate_config());
+        //        Empty inputs are not summarized
+        if text.trim().is_empty() {
+            return length_targets
+                .iter()
+                .map(|length_target| (*length_target, String::new()))
+                .collect();
+        }
+        let text = match &self.prefix {
+            None => text.to_string(),
+            Some(prefix) => format!("{}{}", prefix, text),
+        };
+        let tokenizer = self.model.get_tokenizer();
+        self.model
+            .generate_multi_length(&[text.as_str()], length_targets)
+            .into_iter()
+            .zip(length_targets.iter())
+            .map(|(generated, length_target)| {
+                let summary = generated
+                    .into_iter()
+                    .next()
+                    .map(|token_ids| {
+                        tokenizer.decode_with_profile(token_ids, true, self.detokenization_profile)
+                    })
+                    .unwrap_or_default();
+                let summary = match &self.post_process {
+                    Some(post_process) => post_process(summary),
+                    None => summary,
+                };
+                (*length_target, summary)
+            })
+            .collect()
+    }
 }
 
 #[cfg(test)]
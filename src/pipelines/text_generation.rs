@@ -17,6 +17,7 @@
 //! By default, the dependencies for this model will be downloaded for a GPT2-medium model.
 //! Customized text generation models models can be loaded by overwriting the resources in the configuration.
 //! The dependencies will be downloaded to the user's home directory, under ~/.cache/.rustbert/gpt2
+use std::collections::HashMap;
 use tch::{Device, Tensor};
 
 use crate::common::error::RustBertError;
@@ -25,9 +26,14 @@ use crate::gpt2::{
     GPT2Generator, Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources, Gpt2VocabResources,
 };
 use crate::openai_gpt::OpenAIGenerator;
-use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::common::{DetokenizationProfile, ModelType, TokenizerOption};
+use crate::pipelines::diagnostics::{report_generation_diagnostics, DiagnosticsSink};
 use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
-use crate::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+use crate::pipelines::generation_utils::{
+    apply_post_process, group_generated_texts, GenerateConfig, GeneratedIndicesOutput,
+    GeneratedText, LanguageGenerator, LengthBias, PostProcess, ReasoningBudget,
+    RepetitionPenaltyMode, Rerank, WarperKind,
+};
 use crate::reformer::ReformerGenerator;
 use crate::resources::Resource;
 use crate::xlnet::XLNetGenerator;
@@ -74,6 +80,63 @@ pub struct TextGenerationConfig {
     pub num_beam_groups: Option<i64>,
     /// Diversity penalty for diverse beam search. High values will enforce more difference between beam groups (default: 5.5)
     pub diversity_penalty: Option<f64>,
+    /// Corpus-derived additive logits adjustments, keyed by token id. Applied as a static bias on top of the dynamic repetition penalty (default: None)
+    pub corpus_penalty: Option<HashMap<i64, f64>>,
+    /// Reasoning budget capping the number of tokens generated inside a delimited reasoning block before the answer (default: None)
+    pub reasoning_budget: Option<ReasoningBudget>,
+    /// Maximum number of sequences decoded simultaneously, splitting generation into sequential micro-batches to bound peak memory (default: None)
+    pub generation_microbatch_size: Option<i64>,
+    /// EOS probability threshold above which a sequence is ended, even if EOS is not the most likely token (default: None)
+    pub eos_probability_threshold: Option<f64>,
+    /// Soft length control: a bias varying with the sequence length is added to the logits of the EOS tokens at each step, encouraging
+    /// the sequences to end near a target length (see `LengthBias`). `min_length` still prevents EOS before it is reached (default: None)
+    pub length_bias: Option<LengthBias>,
+    /// Form of the repetition penalty applied to previously generated tokens (default: `RepetitionPenaltyMode::Multiplicative`)
+    pub repetition_penalty_mode: RepetitionPenaltyMode,
+    /// Order in which the logits warpers are applied. Warpers omitted from the list are not applied
+    /// (default: `WarperKind::default_order()`, i.e. repetition penalty, temperature, top-k and top-p)
+    pub warper_order: Vec<WarperKind>,
+    /// Number of most likely candidate tokens to report, with their probabilities, for each generated position. The alternatives are
+    /// returned in the `top_k_alternatives` of `generate_indices_with_output` (default: None)
+    pub output_top_k_alternatives: Option<usize>,
+    /// Report the memory footprint (in bytes) of the key/value cache after each decoding step, returned in the `cache_memory` of
+    /// `generate_indices_with_output`. Measuring the cache at each step has a cost and is disabled by default (default: false)
+    pub output_cache_memory: bool,
+    /// Frequency penalty (OpenAI-style): subtracted from the logits of each token, multiplied by the number of times the token was
+    /// already generated. Negative values encourage repetitions (default: 0.0)
+    pub frequency_penalty: f64,
+    /// Presence penalty (OpenAI-style): subtracted from the logits of each token already generated at least once.
+    /// Negative values encourage repetitions (default: 0.0)
+    pub presence_penalty: f64,
+    /// Source copy bias: added to the logits of the tokens present in the encoder input (or prompt for decoder-only models) at each step,
+    /// a soft prior towards copying from the source improving the faithfulness of abstractive summaries. High values push the generation
+    /// towards extraction (default: 0.0)
+    pub source_copy_bias: f64,
+    /// Token healing for decoder-only models: the last token of the prompt is removed and the first generated token is restricted to
+    /// the tokens starting with its surface form, avoiding unnatural continuations of prompts ending mid-word. Prompts of a single token
+    /// are not healed (default: false)
+    pub token_healing: bool,
+    /// Detokenization profile used to convert the generated tokens to text. `DetokenizationProfile::Code` preserves the literal output
+    /// of the model (e.g. indentation and spacing around operators) (default: `DetokenizationProfile::Natural`)
+    pub detokenization_profile: DetokenizationProfile,
+    /// Suffix forced at the end of the generated sequences, before the EOS token (e.g. a closing tag). Hypotheses cannot end before
+    /// emitting the full suffix, and the remaining suffix tokens are forced once the positions left before `max_length` are just enough
+    /// to emit them followed by EOS. A suffix emitted by the model on its own before that point allows the sequence to end (default: None)
+    pub end_suffix_ids: Option<Vec<i64>>,
+    /// Maximum size in bytes of the generated text (excluding the prompt), for outputs subject to downstream length limits. The running
+    /// output is decoded at each step and a sequence ends before the token that would exceed the budget. Trailing tokens decoding to an
+    /// incomplete character are removed, so that multi-byte characters are never split. Decoding at each step is costly: this is meant for
+    /// short outputs. Only supported for greedy decoding and sampling (default: None)
+    pub max_output_bytes: Option<usize>,
+    /// Maximum number of characters of the generated text (excluding the prompt), see `max_output_bytes` (default: None)
+    pub max_output_chars: Option<usize>,
+    /// Post-processing applied to each output string before it is returned (e.g. trimming or capitalization), after detokenization
+    /// and the removal of special tokens (default: None)
+    pub post_process: Option<PostProcess>,
+    /// Reranking function scoring each generated text (after post-processing), used to order the sequences generated for each
+    /// prompt instead of the model score (e.g. a classifier or keyword coverage score). For beam search, all `num_beams` finished
+    /// hypotheses are reranked before keeping the best `num_return_sequences` (default: None)
+    pub rerank: Option<Rerank>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
 }
@@ -137,6 +200,25 @@ impl Default for TextGenerationConfig {
             num_return_sequences: 1,
             num_beam_groups: None,
             diversity_penalty: None,
+            corpus_penalty: None,
+            reasoning_budget: None,
+            generation_microbatch_size: None,
+            eos_probability_threshold: None,
+            length_bias: None,
+            repetition_penalty_mode: RepetitionPenaltyMode::Multiplicative,
+            warper_order: WarperKind::default_order(),
+            output_top_k_alternatives: None,
+            output_cache_memory: false,
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            source_copy_bias: 0.0,
+            token_healing: false,
+            detokenization_profile: DetokenizationProfile::Natural,
+            end_suffix_ids: None,
+            max_output_bytes: None,
+            max_output_chars: None,
+            post_process: None,
+            rerank: None,
             device: Device::cuda_if_available(),
         }
     }
@@ -163,6 +245,23 @@ impl From<TextGenerationConfig> for GenerateConfig {
             num_return_sequences: config.num_return_sequences,
             num_beam_groups: config.num_beam_groups,
             diversity_penalty: config.diversity_penalty,
+            corpus_penalty: config.corpus_penalty,
+            reasoning_budget: config.reasoning_budget,
+            generation_microbatch_size: config.generation_microbatch_size,
+            eos_probability_threshold: config.eos_probability_threshold,
+            length_bias: config.length_bias,
+            repetition_penalty_mode: config.repetition_penalty_mode,
+            warper_order: config.warper_order,
+            output_top_k_alternatives: config.output_top_k_alternatives,
+            output_cache_memory: config.output_cache_memory,
+            frequency_penalty: config.frequency_penalty,
+            presence_penalty: config.presence_penalty,
+            source_copy_bias: config.source_copy_bias,
+            token_healing: config.token_healing,
+            detokenization_profile: config.detokenization_profile,
+            end_suffix_ids: config.end_suffix_ids,
+            max_output_bytes: config.max_output_bytes,
+            max_output_chars: config.max_output_chars,
             device: config.device,
         }
     }
@@ -222,6 +321,16 @@ impl TextGenerationOption {
         }
     }
 
+    /// Interface method to access the generation configuration
+    pub(crate) fn get_generate_config(&self) -> &GenerateConfig {
+        match self {
+            Self::GPT2(model_ref) => model_ref.get_config(),
+            Self::GPT(model_ref) => model_ref.get_config(),
+            Self::XLNet(model_ref) => model_ref.get_config(),
+            Self::Reformer(model_ref) => model_ref.get_config(),
+        }
+    }
+
     /// Interface method to generate() of the particular models.
     pub fn generate_indices<'a, S>(
         &self,
@@ -230,22 +339,53 @@ impl TextGenerationOption {
         min_length: Option<i64>,
         max_length: Option<i64>,
     ) -> Vec<Vec<i64>>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        self.generate_indices_with_output(prompt_texts, attention_mask, min_length, max_length)
+            .indices
+    }
+
+    /// Interface method to generate_indices_with_output() of the particular models.
+    pub fn generate_indices_with_output<'a, S>(
+        &self,
+        prompt_texts: Option<S>,
+        attention_mask: Option<Tensor>,
+        min_length: Option<i64>,
+        max_length: Option<i64>,
+    ) -> GeneratedIndicesOutput
     where
         S: AsRef<[&'a str]>,
     {
         match *self {
-            Self::GPT2(ref model) => {
-                model.generate_indices(prompt_texts, attention_mask, min_length, max_length, None)
-            }
-            Self::GPT(ref model) => {
-                model.generate_indices(prompt_texts, attention_mask, min_length, max_length, None)
-            }
-            Self::XLNet(ref model) => {
-                model.generate_indices(prompt_texts, attention_mask, min_length, max_length, None)
-            }
-            Self::Reformer(ref model) => {
-                model.generate_indices(prompt_texts, attention_mask, min_length, max_length, None)
-            }
+            Self::GPT2(ref model) => model.generate_indices_with_output(
+                prompt_texts,
+                attention_mask,
+                min_length,
+                max_length,
+                None,
+            ),
+            Self::GPT(ref model) => model.generate_indices_with_output(
+                prompt_texts,
+                attention_mask,
+                min_length,
+                max_length,
+                None,
+            ),
+            Self::XLNet(ref model) => model.generate_indices_with_output(
+                prompt_texts,
+                attention_mask,
+                min_length,
+                max_length,
+                None,
+            ),
+            Self::Reformer(ref model) => model.generate_indices_with_output(
+                prompt_texts,
+                attention_mask,
+                min_length,
+                max_length,
+                None,
+            ),
         }
     }
 }
@@ -257,6 +397,12 @@ pub struct TextGenerationModel {
     prefix_length: Option<i64>,
     min_length: i64,
     max_length: i64,
+    num_return_sequences: i64,
+    num_candidate_sequences: i64,
+    detokenization_profile: DetokenizationProfile,
+    post_process: Option<PostProcess>,
+    rerank: Option<Rerank>,
+    diagnostics_sink: Option<DiagnosticsSink>,
 }
 
 impl TextGenerationModel {
@@ -279,7 +425,7 @@ impl TextGenerationModel {
     /// # }
     /// ```
     pub fn new(
-        generation_config: TextGenerationConfig,
+        mut generation_config: TextGenerationConfig,
     ) -> Result<TextGenerationModel, RustBertError> {
         let prefix = match generation_config.model_type {
             ModelType::XLNet => Some(
@@ -300,6 +446,18 @@ with people, even a bishop, begging for his blessing. <eod> </s> <eos>"
 
         let min_length = generation_config.min_length;
         let max_length = generation_config.max_length;
+        let num_return_sequences = generation_config.num_return_sequences;
+        let detokenization_profile = generation_config.detokenization_profile;
+        let post_process = generation_config.post_process.take();
+        let rerank = generation_config.rerank.take();
+        //        Beam search returns all its finished hypotheses to the reranking function, truncated after reranking
+        if rerank.is_some()
+            && !generation_config.do_sample
+            && (generation_config.num_beams > num_return_sequences)
+        {
+            generation_config.num_return_sequences = generation_config.num_beams;
+        }
+        let num_candidate_sequences = generation_config.num_return_sequences;
         let model = TextGenerationOption::new(generation_config)?;
         let prefix_length = if let Some(prefix) = &prefix {
             Some(model.get_tokenizer().tokenize(prefix).len() as i64)
@@ -313,24 +471,58 @@ with people, even a bishop, begging for his blessing. <eod> </s> <eos>"
             prefix_length,
             min_length,
             max_length,
+            num_return_sequences,
+            num_candidate_sequences,
+            detokenization_profile,
+            post_process,
+            rerank,
+            diagnostics_sink: None,
         })
     }
 
-    /// Generate texts from provided prompts
+    /// Interface method to access tokenizer
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        self.model.get_tokenizer()
+    }
+
+    /// Sets the sink receiving the diagnostics of the pipeline (generation settings that are not applied, reported at
+    /// each call), or removes it with `None`
     ///
     /// # Arguments
     ///
-    /// * `input` - `&[&str]` Array of texts to summarize.
+    /// * `diagnostics_sink` - `Option<DiagnosticsSink>` callback called with each diagnostic reported by the pipeline
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let mut text_generation_model = TextGenerationModel::new(Default::default())?;
+    /// text_generation_model.set_diagnostics_sink(Some(Box::new(|diagnostic| {
+    ///     eprintln!("{:?}: {}", diagnostic.kind, diagnostic.message)
+    /// })));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_diagnostics_sink(&mut self, diagnostics_sink: Option<DiagnosticsSink>) {
+        self.diagnostics_sink = diagnostics_sink;
+    }
+
+    /// Generate texts from provided prompts, grouped by prompt
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of prompts.
     /// * `prefix` - `impl Into<Option<&'a str>>`: Optional string to pass as a prefix for generation. Will be excluded from generated sequences.
     ///
     /// # Returns
-    /// * `Vec<String>` Generated texts
+    /// * `Vec<Vec<GeneratedText>>` Generated texts with their scores, with one vector for each prompt (in the order of the prompts) holding its *num_return_sequences* generated texts sorted by decreasing score (or decreasing reranking score if a `rerank` function is set).
     ///
     /// # Example
     ///
     /// ```no_run
     /// # fn main() -> anyhow::Result<()> {
-    /// use rust_bert::pipelines::common::ModelType;
     /// use rust_bert::pipelines::text_generation::TextGenerationModel;
     ///
     /// let model = TextGenerationModel::new(Default::default())?;
@@ -339,10 +531,80 @@ with people, even a bishop, begging for his blessing. <eod> </s> <eos>"
     /// let prefix = None;
     ///
     /// let output = model.generate(&input, prefix);
+    /// let best_cat_continuation = &output[1][0].text;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn generate<'a, S>(
+        &self,
+        texts: S,
+        prefix: impl Into<Option<&'a str>>,
+    ) -> Vec<Vec<GeneratedText>>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        report_generation_diagnostics(&self.diagnostics_sink, self.model.get_generate_config());
+        let (texts, scores) = self.generate_with_scores(texts, prefix);
+        group_generated_texts(
+            texts,
+            scores,
+            self.num_candidate_sequences as usize,
+            self.rerank.as_ref(),
+        )
+        .into_iter()
+        .map(|mut input_texts| {
+            input_texts.truncate(self.num_return_sequences as usize);
+            input_texts
+        })
+        .collect()
+    }
+
+    /// Generate texts from provided prompts, returned in a flat vector
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of prompts.
+    /// * `prefix` - `impl Into<Option<&'a str>>`: Optional string to pass as a prefix for generation. Will be excluded from generated sequences.
+    ///
+    /// # Returns
+    /// * `Vec<String>` Generated texts of length *number_of_prompts* x *num_return_sequences* (ordered by decreasing reranking score for each prompt if a `rerank` function is set)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::text_generation::TextGenerationModel;
+    ///
+    /// let model = TextGenerationModel::new(Default::default())?;
+    ///
+    /// let input = ["The dog", "The cat was"];
+    /// let prefix = None;
+    ///
+    /// let output = model.generate_flat(&input, prefix);
     /// # Ok(())
     /// # }
     /// ```
-    pub fn generate<'a, S>(&self, texts: S, prefix: impl Into<Option<&'a str>>) -> Vec<String>
+    pub fn generate_flat<'a, S>(&self, texts: S, prefix: impl Into<Option<&'a str>>) -> Vec<String>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        if self.rerank.is_some() {
+            self.generate(texts, prefix)
+                .into_iter()
+                .flatten()
+                .map(|generated_text| generated_text.text)
+                .collect()
+        } else {
+            report_generation_diagnostics(&self.diagnostics_sink, self.model.get_generate_config());
+            self.generate_with_scores(texts, prefix).0
+        }
+    }
+
+    fn generate_with_scores<'a, S>(
+        &self,
+        texts: S,
+        prefix: impl Into<Option<&'a str>>,
+    ) -> (Vec<String>, Vec<f64>)
     where
         S: AsRef<[&'a str]>,
     {
@@ -354,15 +616,24 @@ with people, even a bishop, begging for his blessing. <eod> </s> <eos>"
             (None, Some(pipeline_prefix)) => (Some(pipeline_prefix.as_str()), self.prefix_length),
             (None, None) => (None, None),
         };
-        let generated_indices = match (prefix, prefix_length) {
-            (None, _) => self.model.generate_indices(Some(texts), None, None, None),
+        let generated = match (prefix, prefix_length) {
+            (None, _) => self
+                .model
+                .generate_indices_with_output(Some(texts), None, None, None),
             (Some(prefix), Some(prefix_length)) => {
                 let texts = texts
                     .as_ref()
                     .iter()
-                    .map(|text| format!("{} {}", prefix, text))
+                    .map(|text| {
+                        //        Empty inputs are not prefixed so that no text is generated for them
+                        if text.trim().is_empty() {
+                            text.to_string()
+                        } else {
+                            format!("{} {}", prefix, text)
+                        }
+                    })
                     .collect::<Vec<String>>();
-                self.model.generate_indices(
+                self.model.generate_indices_with_output(
                     Some(texts.iter().map(|x| &**x).collect::<Vec<&str>>()),
                     None,
                     Some(self.min_length + prefix_length),
@@ -372,9 +643,9 @@ with people, even a bishop, begging for his blessing. <eod> </s> <eos>"
             _ => panic!("Prefix length not defined but prefix provided!"),
         };
 
-        let mut output = Vec::with_capacity(generated_indices.len());
-        for generated_sequence in generated_indices {
-            output.push(self.model.get_tokenizer().decode(
+        let mut output = Vec::with_capacity(generated.indices.len());
+        for generated_sequence in generated.indices {
+            output.push(self.model.get_tokenizer().decode_with_profile(
                 if prefix_length.is_some() {
                     generated_sequence
                         .into_iter()
@@ -384,10 +655,13 @@ with people, even a bishop, begging for his blessing. <eod> </s> <eos>"
                     generated_sequence
                 },
                 true,
-                true,
+                self.detokenization_profile,
             ));
         }
-        output
+        (
+            apply_post_process(self.post_process.as_ref(), output),
+            generated.scores,
+        )
     }
 }
 
@@ -120,7 +120,10 @@ use crate::distilbert::DistilBertForTokenClassification;
 use crate::electra::ElectraForTokenClassification;
 use crate::longformer::LongformerForTokenClassification;
 use crate::mobilebert::MobileBertForTokenClassification;
-use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
+use crate::pipelines::common::{
+    assert_no_grad, reload_var_store, ConfigOption, ModelType, TokenizerOption,
+};
+use crate::pipelines::diagnostics::{report_tokenization_diagnostics, DiagnosticsSink};
 use crate::roberta::RobertaForTokenClassification;
 use crate::xlnet::XLNetForTokenClassification;
 use rust_tokenizers::tokenizer::{Tokenizer, TruncationStrategy};
@@ -178,6 +181,17 @@ impl ConsolidatableTokens<Token> for Vec<Token> {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Label probability distribution of a token, generated by a `TokenClassificationModel`
+pub struct TokenLabelDistribution {
+    /// String representation of the token
+    pub text: String,
+    /// Token offsets (character positions in the input text)
+    pub offset: Offset,
+    /// Probability of each label, in the order of the label indices
+    pub scores: Vec<(String, f64)>,
+}
+
 type LabelAggregationFunction = Box<fn(&[Token]) -> (i64, String)>;
 
 /// # Enum defining the label aggregation method for sub tokens
@@ -208,7 +222,7 @@ pub struct TokenClassificationConfig {
     pub merges_resource: Option<Resource>,
     /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
     pub lower_case: bool,
-    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models
+    /// Flag indicating if the tokenizer should strip accents (normalization), overriding the `lower_case` default. Only used for BERT / ALBERT / XLNet / ProphetNet models
     pub strip_accents: Option<bool>,
     /// Flag indicating if the tokenizer should add a white space before each tokenized input (needed for some Roberta models)
     pub add_prefix_space: Option<bool>,
@@ -556,6 +570,7 @@ pub struct TokenClassificationModel {
     label_mapping: HashMap<i64, String>,
     var_store: VarStore,
     label_aggregation_function: LabelAggregationOption,
+    diagnostics_sink: Option<DiagnosticsSink>,
 }
 
 impl TokenClassificationModel {
@@ -609,9 +624,64 @@ impl TokenClassificationModel {
             label_mapping,
             var_store,
             label_aggregation_function,
+            diagnostics_sink: None,
         })
     }
 
+    /// Interface method to access tokenizer
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+
+    /// Replaces the weights of the model in place with the weights from a resource (see `pipelines::common::reload_var_store`).
+    ///
+    /// # Arguments
+    ///
+    /// * `weights_resource` - `Resource` pointing to the new model weights
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    /// use rust_bert::resources::{LocalResource, Resource};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut model = TokenClassificationModel::new(Default::default())?;
+    /// let weights_resource = Resource::Local(LocalResource {
+    ///     local_path: PathBuf::from("path/to/updated/rust_model.ot"),
+    /// });
+    /// model.reload_weights(&weights_resource)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reload_weights(&mut self, weights_resource: &Resource) -> Result<(), RustBertError> {
+        reload_var_store(&self.var_store, weights_resource)
+    }
+
+    /// Sets the sink receiving the diagnostics of the pipeline (truncated inputs and unknown tokens), or removes it with `None`
+    ///
+    /// # Arguments
+    ///
+    /// * `diagnostics_sink` - `Option<DiagnosticsSink>` callback called with each diagnostic reported by the pipeline
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    ///
+    /// let mut ner_model = TokenClassificationModel::new(Default::default())?;
+    /// ner_model.set_diagnostics_sink(Some(Box::new(|diagnostic| {
+    ///     eprintln!("{:?}: {}", diagnostic.kind, diagnostic.message)
+    /// })));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_diagnostics_sink(&mut self, diagnostics_sink: Option<DiagnosticsSink>) {
+        self.diagnostics_sink = diagnostics_sink;
+    }
+
     fn prepare_for_model<'a, S>(&self, input: S) -> (Vec<TokenizedInput>, Tensor)
     where
         S: AsRef<[&'a str]>,
@@ -619,6 +689,11 @@ impl TokenClassificationModel {
         let tokenized_input: Vec<TokenizedInput> =
             self.tokenizer
                 .encode_list(input.as_ref(), 128, &TruncationStrategy::LongestFirst, 0);
+        report_tokenization_diagnostics(
+            &self.diagnostics_sink,
+            &tokenized_input,
+            self.tokenizer.get_unk_id(),
+        );
         let max_len = tokenized_input
             .iter()
             .map(|input| input.token_ids.len())
@@ -660,7 +735,7 @@ impl TokenClassificationModel {
     ///
     /// ```no_run
     /// # fn main() -> anyhow::Result<()> {
-    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    /// use rust_bert::pipelines::token_classification::TokenClassificationModel;
     ///
     /// let ner_model = TokenClassificationModel::new(Default::default())?;
     /// let input = [
@@ -681,18 +756,7 @@ impl TokenClassificationModel {
         S: AsRef<[&'a str]>,
     {
         let (tokenized_input, input_tensor) = self.prepare_for_model(input.as_ref());
-        let output = no_grad(|| {
-            self.token_sequence_classifier.forward_t(
-                Some(input_tensor.copy()),
-                None,
-                None,
-                None,
-                None,
-                false,
-            )
-        });
-        let output = output.detach().to(Device::Cpu);
-        let score: Tensor = output.exp() / output.exp().sum1(&[-1], true, Float);
+        let score = self.forward_scores(&input_tensor);
         let labels_idx = &score.argmax(-1, true);
         let mut tokens: Vec<Vec<Token>> = vec![];
         for sentence_idx in 0..labels_idx.size()[0] {
@@ -733,6 +797,209 @@ impl TokenClassificationModel {
         tokens
     }
 
+    /// Explains the label predicted for a token by attributing it to the input tokens. The attribution method is
+    /// gradient×input: the logits of the token label at the positions covered by the token (all its sub-tokens for a
+    /// consolidated token) are summed and differentiated with respect to the input word embeddings. The importance of
+    /// an input token is the dot product of the gradient with its embedding: positive values support the prediction,
+    /// negative values oppose it. Special tokens are excluded from the output.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&str` text the token was predicted from
+    /// * `token` - `&Token` predicted for the input text (e.g. returned by `predict`)
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<(String, f32)>` containing the input tokens (as they appear in the text) with their importance, in order of appearance
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    ///
+    /// let ner_model = TokenClassificationModel::new(Default::default())?;
+    /// let input = "My name is Amy. I live in Paris.";
+    /// let tokens = ner_model.predict(&[input], true, false);
+    /// let importance = ner_model.explain_token(input, &tokens[0][3])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn explain_token(
+        &self,
+        input: &str,
+        token: &Token,
+    ) -> Result<Vec<(String, f32)>, RustBertError> {
+        let (tokenized_input, input_tensor) = self.prepare_for_model([input]);
+        let tokenized_input = &tokenized_input[0];
+        let positions = match &token.offset {
+            Some(token_offset) => tokenized_input
+                .token_offsets
+                .iter()
+                .enumerate()
+                .filter_map(|(position, offset)| match offset {
+                    Some(offset)
+                        if (offset.begin >= token_offset.begin)
+                            && (offset.end <= token_offset.end) =>
+                    {
+                        Some(position as i64)
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<i64>>(),
+            None => vec![token.index as i64],
+        };
+        if positions.is_empty()
+            || positions
+                .iter()
+                .any(|&position| position >= input_tensor.size()[1])
+        {
+            return Err(RustBertError::ValueError(format!(
+                "Token {} does not match the tokenized input",
+                token.text
+            )));
+        }
+
+        let word_embeddings = self
+            .var_store
+            .variables()
+            .into_iter()
+            .find(|(name, _)| {
+                name.ends_with("word_embeddings.weight") || name.ends_with("word_embedding.weight")
+            })
+            .map(|(_, weights)| weights)
+            .ok_or_else(|| {
+                RustBertError::ValueError(format!(
+                    "Word embeddings not found for {:?}",
+                    self.token_sequence_classifier.model_type()
+                ))
+            })?;
+        let input_embeds = word_embeddings
+            .index_select(0, &input_tensor.view(-1))
+            .unsqueeze(0)
+            .detach()
+            .set_requires_grad(true);
+        let logits = self.token_sequence_classifier.forward_t(
+            None,
+            None,
+            None,
+            None,
+            Some(input_embeds.shallow_clone()),
+            false,
+        );
+        let target = logits
+            .get(0)
+            .index_select(0, &Tensor::of_slice(&positions).to(logits.device()))
+            .select(1, token.label_index)
+            .sum(Float);
+        let gradients = Tensor::run_backward(&[target], &[&input_embeds], false, false);
+        let importance = (&gradients[0] * input_embeds.detach())
+            .sum1(&[-1], false, Float)
+            .get(0)
+            .to(Device::Cpu);
+
+        let original_chars = input.chars().collect::<Vec<char>>();
+        Ok(tokenized_input
+            .token_offsets
+            .iter()
+            .enumerate()
+            .filter_map(|(position, offset)| {
+                offset.as_ref().map(|offset| {
+                    let end_char = min(offset.end as usize, original_chars.len());
+                    let text = original_chars[offset.begin as usize..end_char]
+                        .iter()
+                        .collect::<String>();
+                    (text, importance.double_value(&[position as i64]) as f32)
+                })
+            })
+            .collect())
+    }
+
+    /// Returns the probability of each label for the tokens of the input texts, before the aggregation of
+    /// sub-tokens into words. Special tokens are excluded.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify the tokens of.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<TokenLabelDistribution>>` containing the tokens of each input, with their offsets in the input text and their label probabilities
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    ///
+    /// let ner_model = TokenClassificationModel::new(Default::default())?;
+    /// let input = ["My name is Amy. I live in Paris."];
+    /// let output = ner_model.predict_label_distributions(&input);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_label_distributions<'a, S>(&self, input: S) -> Vec<Vec<TokenLabelDistribution>>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let (tokenized_input, input_tensor) = self.prepare_for_model(input.as_ref());
+        let score = self.forward_scores(&input_tensor);
+        let labels = (0..*score.size().last().unwrap())
+            .map(|label_index| {
+                self.label_mapping
+                    .get(&label_index)
+                    .expect("Index out of vocabulary bounds.")
+                    .to_owned()
+            })
+            .collect::<Vec<String>>();
+        input
+            .as_ref()
+            .iter()
+            .zip(tokenized_input.iter())
+            .enumerate()
+            .map(|(sentence_idx, (text, sentence_tokens))| {
+                let original_chars = text.chars().collect::<Vec<char>>();
+                let sentence_scores = score.get(sentence_idx as i64);
+                sentence_tokens
+                    .token_offsets
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(position_idx, offset)| {
+                        offset.map(|offset| {
+                            let end_char = min(offset.end as usize, original_chars.len());
+                            let token_scores =
+                                Vec::<f64>::from(sentence_scores.get(position_idx as i64));
+                            TokenLabelDistribution {
+                                text: original_chars[offset.begin as usize..end_char]
+                                    .iter()
+                                    .collect(),
+                                offset,
+                                scores: labels.iter().cloned().zip(token_scores).collect(),
+                            }
+                        })
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Returns the label probabilities of each token (*batch size* x *sequence length* x *number of labels*), on the CPU
+    fn forward_scores(&self, input_tensor: &Tensor) -> Tensor {
+        let output = no_grad(|| {
+            self.token_sequence_classifier.forward_t(
+                Some(input_tensor.copy()),
+                None,
+                None,
+                None,
+                None,
+                false,
+            )
+        });
+        assert_no_grad(&output);
+        let output = output.detach().to(Device::Cpu);
+        output.exp() / output.exp().sum1(&[-1], true, Float)
+    }
+
     fn decode_token(
         &self,
         original_sentence_chars: &[char],
@@ -0,0 +1,242 @@
+// Copyright 2021 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Tool call parsing
+//! Instruction models trained for tool (function) calling emit their calls in a structured format within their
+//! generated text. The `ToolCallParser` extracts these calls from a generated output, separating them from the
+//! remaining prose. The default convention is a JSON object with a `name` and an `arguments` field enclosed in
+//! `<tool_call>` and `</tool_call>` delimiters; custom delimiters can be provided.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::tool_calls::ToolCallParser;
+//!
+//! let parser = ToolCallParser::default();
+//! let output = "Let me check the weather.\n\
+//!     <tool_call>{\"name\": \"get_weather\", \"arguments\": {\"city\": \"Paris\"}}</tool_call>";
+//! let parsed = parser.parse(output)?;
+//! # Ok(())
+//! # }
+//! ```
+//! Output: \
+//! ```no_run
+//! # use rust_bert::pipelines::tool_calls::{ParsedToolCalls, ToolCall};
+//! # let output =
+//! ParsedToolCalls {
+//!     content: "Let me check the weather.".to_string(),
+//!     tool_calls: vec![ToolCall {
+//!         name: "get_weather".to_string(),
+//!         arguments: serde_json::json!({"city": "Paris"}),
+//!     }],
+//! }
+//! # ;
+//! ```
+
+use crate::RustBertError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// # Tool call emitted by a model
+pub struct ToolCall {
+    /// Name of the tool to call
+    pub name: String,
+    /// Arguments of the call. Arguments serialized as a JSON string (e.g. OpenAI-style outputs) are parsed, other values
+    /// are returned unchanged. `Value::Null` if the call has no arguments
+    pub arguments: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// # Generated output split into prose and tool calls
+pub struct ParsedToolCalls {
+    /// Text of the output outside of the tool calls, with leading and trailing whitespace removed
+    pub content: String,
+    /// Tool calls in the order they appear in the output
+    pub tool_calls: Vec<ToolCall>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// # Parser extracting the tool calls from generated outputs
+/// Each tool call is a JSON object with a `name` and an optional `arguments` field, enclosed in an opening and a
+/// closing delimiter.
+pub struct ToolCallParser {
+    open_delimiter: String,
+    close_delimiter: String,
+}
+
+impl Default for ToolCallParser {
+    fn default() -> ToolCallParser {
+        ToolCallParser {
+            open_delimiter: "<tool_call>".to_string(),
+            close_delimiter: "</tool_call>".to_string(),
+        }
+    }
+}
+
+impl ToolCallParser {
+    /// Build a new `ToolCallParser` with custom delimiters
+    ///
+    /// # Arguments
+    ///
+    /// * `open_delimiter` - text opening a tool call (e.g. `<tool_call>`)
+    /// * `close_delimiter` - text closing a tool call (e.g. `</tool_call>`)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::tool_calls::ToolCallParser;
+    ///
+    /// let parser = ToolCallParser::new("<|tool_call|>", "<|/tool_call|>")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        open_delimiter: &str,
+        close_delimiter: &str,
+    ) -> Result<ToolCallParser, RustBertError> {
+        if open_delimiter.is_empty() || close_delimiter.is_empty() {
+            return Err(RustBertError::InvalidConfigurationError(
+                "Tool call delimiters must not be empty".to_string(),
+            ));
+        }
+        Ok(ToolCallParser {
+            open_delimiter: open_delimiter.to_string(),
+            close_delimiter: close_delimiter.to_string(),
+        })
+    }
+
+    /// Extracts the tool calls from a generated output
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - `&str` text generated by the model
+    ///
+    /// # Returns
+    /// * `ParsedToolCalls` prose and tool calls of the output. Returns an error if a tool call is not closed (e.g. generation stopped by the maximum length), is not valid JSON or has no `name`
+    pub fn parse(&self, output: &str) -> Result<ParsedToolCalls, RustBertError> {
+        let mut content = String::new();
+        let mut tool_calls = vec![];
+        let mut remaining = output;
+        while let Some(start) = remaining.find(self.open_delimiter.as_str()) {
+            content.push_str(&remaining[..start]);
+            let call_start = start + self.open_delimiter.len();
+            let call_length = remaining[call_start..]
+                .find(self.close_delimiter.as_str())
+                .ok_or_else(|| {
+                    RustBertError::ValueError(format!(
+                        "Unterminated tool call: missing {} after {}",
+                        self.close_delimiter,
+                        &remaining[start..]
+                    ))
+                })?;
+            tool_calls.push(parse_tool_call(
+                &remaining[call_start..call_start + call_length],
+            )?);
+            remaining = &remaining[call_start + call_length + self.close_delimiter.len()..];
+        }
+        content.push_str(remaining);
+        Ok(ParsedToolCalls {
+            content: content.trim().to_string(),
+            tool_calls,
+        })
+    }
+}
+
+fn parse_tool_call(tool_call: &str) -> Result<ToolCall, RustBertError> {
+    let mut value: Value = serde_json::from_str(tool_call.trim()).map_err(|error| {
+        RustBertError::ValueError(format!(
+            "Malformed tool call {}: {}",
+            tool_call.trim(),
+            error
+        ))
+    })?;
+    let name = match value.get("name") {
+        Some(Value::String(name)) => name.clone(),
+        _ => {
+            return Err(RustBertError::ValueError(format!(
+                "Tool call {} has no name",
+                tool_call.trim()
+            )));
+        }
+    };
+    let arguments = match value.get_mut("arguments").map(Value::take) {
+        Some(Value::String(arguments)) => match serde_json::from_str(&arguments) {
+            Ok(arguments) => arguments,
+            Err(_) => Value::String(arguments),
+        },
+        Some(arguments) => arguments,
+        None => Value::Null,
+    };
+    Ok(ToolCall { name, arguments })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn default_delimiters() {
+        let parser = ToolCallParser::default();
+        let parsed = parser
+            .parse(
+                "Checking both cities. <tool_call>\n{\"name\": \"get_weather\", \"arguments\": {\"city\": \"Paris\"}}\n</tool_call>\
+                 <tool_call>{\"name\": \"get_weather\", \"arguments\": \"{\\\"city\\\": \\\"Rome\\\"}\"}</tool_call>",
+            )
+            .unwrap();
+        assert_eq!(parsed.content, "Checking both cities.");
+        assert_eq!(
+            parsed.tool_calls,
+            vec![
+                ToolCall {
+                    name: "get_weather".to_string(),
+                    arguments: json!({"city": "Paris"}),
+                },
+                ToolCall {
+                    name: "get_weather".to_string(),
+                    arguments: json!({"city": "Rome"}),
+                },
+            ]
+        );
+
+        let parsed = parser.parse("No tool needed.").unwrap();
+        assert_eq!(parsed.content, "No tool needed.");
+        assert!(parsed.tool_calls.is_empty());
+    }
+
+    #[test]
+    fn custom_delimiters() {
+        let parser = ToolCallParser::new("[TOOL]", "[/TOOL]").unwrap();
+        let parsed = parser
+            .parse("[TOOL]{\"name\": \"get_time\"}[/TOOL] Done.")
+            .unwrap();
+        assert_eq!(parsed.content, "Done.");
+        assert_eq!(parsed.tool_calls[0].name, "get_time");
+        assert_eq!(parsed.tool_calls[0].arguments, Value::Null);
+
+        assert!(ToolCallParser::new("", "[/TOOL]").is_err());
+    }
+
+    #[test]
+    fn malformed_tool_calls() {
+        let parser = ToolCallParser::default();
+        assert!(parser
+            .parse("<tool_call>{\"name\": \"get_weather\", \"arguments\": {\"city\"</tool_call>")
+            .is_err());
+        assert!(parser
+            .parse("<tool_call>{\"name\": \"get_weather\", \"arguments\": {}}")
+            .is_err());
+        assert!(parser
+            .parse("<tool_call>{\"arguments\": {}}</tool_call>")
+            .is_err());
+    }
+}
@@ -54,6 +54,7 @@
 //! # ;
 //! ```
 
+use std::collections::HashMap;
 use tch::{Device, Tensor};
 
 use crate::common::error::RustBertError;
@@ -62,8 +63,13 @@ use crate::marian::{
     MarianConfigResources, MarianGenerator, MarianModelResources, MarianPrefix, MarianSpmResources,
     MarianVocabResources,
 };
-use crate::pipelines::common::ModelType;
-use crate::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+use crate::pipelines::common::{DetokenizationProfile, ModelType, TokenizerOption};
+use crate::pipelines::diagnostics::{report_generation_diagnostics, DiagnosticsSink};
+use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
+use crate::pipelines::generation_utils::{
+    apply_post_process, GenerateConfig, LanguageGenerator, LengthBias, PostProcess,
+    ReasoningBudget, RepetitionPenaltyMode, WarperKind,
+};
 use crate::t5::{T5ConfigResources, T5Generator, T5ModelResources, T5Prefix, T5VocabResources};
 
 /// Pretrained languages available for direct use
@@ -423,6 +429,59 @@ pub struct TranslationConfig {
     pub num_beam_groups: Option<i64>,
     /// Diversity penalty for diverse beam search. High values will enforce more difference between beam groups (default: 5.5)
     pub diversity_penalty: Option<f64>,
+    /// Corpus-derived additive logits adjustments, keyed by token id. Applied as a static bias on top of the dynamic repetition penalty (default: None)
+    pub corpus_penalty: Option<HashMap<i64, f64>>,
+    /// Reasoning budget capping the number of tokens generated inside a delimited reasoning block before the answer (default: None)
+    pub reasoning_budget: Option<ReasoningBudget>,
+    /// Maximum number of sequences decoded simultaneously, splitting generation into sequential micro-batches to bound peak memory (default: None)
+    pub generation_microbatch_size: Option<i64>,
+    /// EOS probability threshold above which a sequence is ended, even if EOS is not the most likely token (default: None)
+    pub eos_probability_threshold: Option<f64>,
+    /// Soft length control: a bias varying with the sequence length is added to the logits of the EOS tokens at each step, encouraging
+    /// the sequences to end near a target length (see `LengthBias`). `min_length` still prevents EOS before it is reached (default: None)
+    pub length_bias: Option<LengthBias>,
+    /// Form of the repetition penalty applied to previously generated tokens (default: `RepetitionPenaltyMode::Multiplicative`)
+    pub repetition_penalty_mode: RepetitionPenaltyMode,
+    /// Order in which the logits warpers are applied. Warpers omitted from the list are not applied
+    /// (default: `WarperKind::default_order()`, i.e. repetition penalty, temperature, top-k and top-p)
+    pub warper_order: Vec<WarperKind>,
+    /// Number of most likely candidate tokens to report, with their probabilities, for each generated position. The alternatives are
+    /// returned in the `top_k_alternatives` of `generate_indices_with_output` (default: None)
+    pub output_top_k_alternatives: Option<usize>,
+    /// Report the memory footprint (in bytes) of the key/value cache after each decoding step, returned in the `cache_memory` of
+    /// `generate_indices_with_output`. Measuring the cache at each step has a cost and is disabled by default (default: false)
+    pub output_cache_memory: bool,
+    /// Frequency penalty (OpenAI-style): subtracted from the logits of each token, multiplied by the number of times the token was
+    /// already generated. Negative values encourage repetitions (default: 0.0)
+    pub frequency_penalty: f64,
+    /// Presence penalty (OpenAI-style): subtracted from the logits of each token already generated at least once.
+    /// Negative values encourage repetitions (default: 0.0)
+    pub presence_penalty: f64,
+    /// Source copy bias: added to the logits of the tokens present in the encoder input (or prompt for decoder-only models) at each step,
+    /// a soft prior towards copying from the source improving the faithfulness of abstractive summaries. High values push the generation
+    /// towards extraction (default: 0.0)
+    pub source_copy_bias: f64,
+    /// Token healing for decoder-only models: the last token of the prompt is removed and the first generated token is restricted to
+    /// the tokens starting with its surface form, avoiding unnatural continuations of prompts ending mid-word. Prompts of a single token
+    /// are not healed (default: false)
+    pub token_healing: bool,
+    /// Detokenization profile used to convert the generated tokens to text. `DetokenizationProfile::Code` preserves the literal output
+    /// of the model (e.g. indentation and spacing around operators) (default: `DetokenizationProfile::Natural`)
+    pub detokenization_profile: DetokenizationProfile,
+    /// Suffix forced at the end of the generated sequences, before the EOS token (e.g. a closing tag). Hypotheses cannot end before
+    /// emitting the full suffix, and the remaining suffix tokens are forced once the positions left before `max_length` are just enough
+    /// to emit them followed by EOS. A suffix emitted by the model on its own before that point allows the sequence to end (default: None)
+    pub end_suffix_ids: Option<Vec<i64>>,
+    /// Maximum size in bytes of the generated text (excluding the prompt), for outputs subject to downstream length limits. The running
+    /// output is decoded at each step and a sequence ends before the token that would exceed the budget. Trailing tokens decoding to an
+    /// incomplete character are removed, so that multi-byte characters are never split. Decoding at each step is costly: this is meant for
+    /// short outputs. Only supported for greedy decoding and sampling (default: None)
+    pub max_output_bytes: Option<usize>,
+    /// Maximum number of characters of the generated text (excluding the prompt), see `max_output_bytes` (default: None)
+    pub max_output_chars: Option<usize>,
+    /// Post-processing applied to each output string before it is returned (e.g. trimming or capitalization), after detokenization
+    /// and the removal of special tokens (default: None)
+    pub post_process: Option<PostProcess>,
     /// Model type used for translation
     pub model_type: ModelType,
 }
@@ -527,6 +586,24 @@ impl TranslationConfig {
             prefix,
             num_beam_groups: None,
             diversity_penalty: None,
+            corpus_penalty: None,
+            reasoning_budget: None,
+            generation_microbatch_size: None,
+            eos_probability_threshold: None,
+            length_bias: None,
+            repetition_penalty_mode: RepetitionPenaltyMode::Multiplicative,
+            warper_order: WarperKind::default_order(),
+            output_top_k_alternatives: None,
+            output_cache_memory: false,
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            source_copy_bias: 0.0,
+            token_healing: false,
+            detokenization_profile: DetokenizationProfile::Natural,
+            end_suffix_ids: None,
+            max_output_bytes: None,
+            max_output_chars: None,
+            post_process: None,
             model_type: translation_resource.model_type,
         }
     }
@@ -606,6 +683,24 @@ impl TranslationConfig {
             prefix,
             num_beam_groups: None,
             diversity_penalty: None,
+            corpus_penalty: None,
+            reasoning_budget: None,
+            generation_microbatch_size: None,
+            eos_probability_threshold: None,
+            length_bias: None,
+            repetition_penalty_mode: RepetitionPenaltyMode::Multiplicative,
+            warper_order: WarperKind::default_order(),
+            output_top_k_alternatives: None,
+            output_cache_memory: false,
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            source_copy_bias: 0.0,
+            token_healing: false,
+            detokenization_profile: DetokenizationProfile::Natural,
+            end_suffix_ids: None,
+            max_output_bytes: None,
+            max_output_chars: None,
+            post_process: None,
             model_type,
         }
     }
@@ -632,6 +727,23 @@ impl From<TranslationConfig> for GenerateConfig {
             num_return_sequences: config.num_return_sequences,
             num_beam_groups: config.num_beam_groups,
             diversity_penalty: config.diversity_penalty,
+            corpus_penalty: config.corpus_penalty,
+            reasoning_budget: config.reasoning_budget,
+            generation_microbatch_size: config.generation_microbatch_size,
+            eos_probability_threshold: config.eos_probability_threshold,
+            length_bias: config.length_bias,
+            repetition_penalty_mode: config.repetition_penalty_mode,
+            warper_order: config.warper_order,
+            output_top_k_alternatives: config.output_top_k_alternatives,
+            output_cache_memory: config.output_cache_memory,
+            frequency_penalty: config.frequency_penalty,
+            presence_penalty: config.presence_penalty,
+            source_copy_bias: config.source_copy_bias,
+            token_healing: config.token_healing,
+            detokenization_profile: config.detokenization_profile,
+            end_suffix_ids: config.end_suffix_ids,
+            max_output_bytes: config.max_output_bytes,
+            max_output_chars: config.max_output_chars,
             device: config.device,
         }
     }
@@ -667,7 +779,23 @@ impl TranslationOption {
         }
     }
 
-    /// Interface method to generate() of the particular models.
+    /// Interface method to access tokenizer
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        match self {
+            Self::Marian(model_ref) => model_ref.get_tokenizer(),
+            Self::T5(model_ref) => model_ref.get_tokenizer(),
+        }
+    }
+
+    /// Interface method to access the generation configuration
+    pub(crate) fn get_generate_config(&self) -> &GenerateConfig {
+        match self {
+            Self::Marian(model_ref) => model_ref.get_config(),
+            Self::T5(model_ref) => model_ref.get_config(),
+        }
+    }
+
+    /// Interface method to generate_flat() of the particular models.
     pub fn generate<'a, S>(
         &self,
         prompt_texts: Option<S>,
@@ -678,9 +806,11 @@ impl TranslationOption {
     {
         match *self {
             Self::Marian(ref model) => {
-                model.generate(prompt_texts, attention_mask, None, None, None)
+                model.generate_flat(prompt_texts, attention_mask, None, None, None)
+            }
+            Self::T5(ref model) => {
+                model.generate_flat(prompt_texts, attention_mask, None, None, None)
             }
-            Self::T5(ref model) => model.generate(prompt_texts, attention_mask, None, None, None),
         }
     }
 }
@@ -689,6 +819,8 @@ impl TranslationOption {
 pub struct TranslationModel {
     model: TranslationOption,
     prefix: Option<String>,
+    post_process: Option<PostProcess>,
+    diagnostics_sink: Option<DiagnosticsSink>,
 }
 
 impl TranslationModel {
@@ -711,11 +843,51 @@ impl TranslationModel {
     /// # Ok(())
     /// # }
     /// ```
-    pub fn new(translation_config: TranslationConfig) -> Result<TranslationModel, RustBertError> {
+    pub fn new(
+        mut translation_config: TranslationConfig,
+    ) -> Result<TranslationModel, RustBertError> {
         let prefix = translation_config.prefix.clone();
+        let post_process = translation_config.post_process.take();
         let model = TranslationOption::new(translation_config)?;
 
-        Ok(TranslationModel { model, prefix })
+        Ok(TranslationModel {
+            model,
+            prefix,
+            post_process,
+            diagnostics_sink: None,
+        })
+    }
+
+    /// Interface method to access tokenizer
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        self.model.get_tokenizer()
+    }
+
+    /// Sets the sink receiving the diagnostics of the pipeline (generation settings that are not applied, reported at
+    /// each call), or removes it with `None`
+    ///
+    /// # Arguments
+    ///
+    /// * `diagnostics_sink` - `Option<DiagnosticsSink>` callback called with each diagnostic reported by the pipeline
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::translation::{Language, TranslationConfig, TranslationModel};
+    /// use tch::Device;
+    ///
+    /// let translation_config =
+    ///     TranslationConfig::new(Language::EnglishToFrench, Device::cuda_if_available());
+    /// let mut translation_model = TranslationModel::new(translation_config)?;
+    /// translation_model.set_diagnostics_sink(Some(Box::new(|diagnostic| {
+    ///     eprintln!("{:?}: {}", diagnostic.kind, diagnostic.message)
+    /// })));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_diagnostics_sink(&mut self, diagnostics_sink: Option<DiagnosticsSink>) {
+        self.diagnostics_sink = diagnostics_sink;
     }
 
     /// Translates texts provided
@@ -749,12 +921,20 @@ impl TranslationModel {
     where
         S: AsRef<[&'a str]>,
     {
-        match &self.prefix {
+        report_generation_diagnostics(&self.diagnostics_sink, self.model.get_generate_config());
+        let translations = match &self.prefix {
             Some(value) => {
                 let texts = texts
                     .as_ref()
                     .iter()
-                    .map(|&v| format!("{}{}", value, v))
+                    .map(|&v| {
+                        //        Empty inputs are not prefixed so that they are not translated
+                        if v.trim().is_empty() {
+                            v.to_string()
+                        } else {
+                            format!("{}{}", value, v)
+                        }
+                    })
                     .collect::<Vec<String>>();
                 self.model.generate(
                     Some(texts.iter().map(AsRef::as_ref).collect::<Vec<&str>>()),
@@ -762,7 +942,8 @@ impl TranslationModel {
                 )
             }
             None => self.model.generate(Some(texts), None),
-        }
+        };
+        apply_post_process(self.post_process.as_ref(), translations)
     }
 }
 
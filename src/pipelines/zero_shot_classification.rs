@@ -48,24 +48,28 @@
 //!             score: 0.972,
 //!             id: 0,
 //!             sentence: 0,
+//!             margin: 0.94,
 //!         },
 //!         Label {
 //!             text: "public health".to_string(),
 //!             score: 0.032,
 //!             id: 1,
 //!             sentence: 0,
+//!             margin: -0.94,
 //!         },
 //!         Label {
 //!             text: "economy".to_string(),
 //!             score: 0.006,
 //!             id: 2,
 //!             sentence: 0,
+//!             margin: -0.966,
 //!         },
 //!         Label {
 //!             text: "sports".to_string(),
 //!             score: 0.004,
 //!             id: 3,
 //!             sentence: 0,
+//!             margin: -0.968,
 //!         },
 //!     ],
 //!     [
@@ -74,24 +78,28 @@
 //!             score: 0.943,
 //!             id: 0,
 //!             sentence: 1,
+//!             margin: -0.042,
 //!         },
 //!         Label {
 //!             text: "economy".to_string(),
 //!             score: 0.985,
 //!             id: 2,
 //!             sentence: 1,
+//!             margin: 0.042,
 //!         },
 //!         Label {
 //!             text: "public health".to_string(),
 //!             score: 0.0818,
 //!             id: 1,
 //!             sentence: 1,
+//!             margin: -0.9032,
 //!         },
 //!         Label {
 //!             text: "sports".to_string(),
 //!             score: 0.001,
 //!             id: 3,
 //!             sentence: 1,
+//!             margin: -0.984,
 //!         },
 //!     ],
 //! ]
@@ -107,17 +115,20 @@ use crate::bert::BertForSequenceClassification;
 use crate::distilbert::DistilBertModelClassifier;
 use crate::longformer::LongformerForSequenceClassification;
 use crate::mobilebert::MobileBertForSequenceClassification;
-use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
-use crate::pipelines::sequence_classification::Label;
+use crate::pipelines::common::{
+    assert_no_grad, reload_var_store, ConfigOption, ModelType, TokenizerOption,
+};
+use crate::pipelines::sequence_classification::{label_margins, Label};
 use crate::resources::{RemoteResource, Resource};
 use crate::roberta::RobertaForSequenceClassification;
 use crate::xlnet::XLNetForSequenceClassification;
 use crate::RustBertError;
-use rust_tokenizers::tokenizer::TruncationStrategy;
-use rust_tokenizers::TokenizedInput;
+use rust_tokenizers::tokenizer::{truncate_sequences, TruncationStrategy};
+use rust_tokenizers::{TokenIdsWithOffsets, TokenizedInput};
+use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
-use std::ops::Deref;
-use tch::kind::Kind::{Bool, Float};
+use std::collections::HashMap;
+use tch::kind::Kind::{Bool, Float, Int64};
 use tch::nn::VarStore;
 use tch::{nn, no_grad, Device, Tensor};
 
@@ -136,7 +147,7 @@ pub struct ZeroShotClassificationConfig {
     pub merges_resource: Option<Resource>,
     /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
     pub lower_case: bool,
-    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models
+    /// Flag indicating if the tokenizer should strip accents (normalization), overriding the `lower_case` default. Only used for BERT / ALBERT / XLNet / ProphetNet models
     pub strip_accents: Option<bool>,
     /// Flag indicating if the tokenizer should add a white space before each tokenized input (needed for some Roberta models)
     pub add_prefix_space: Option<bool>,
@@ -478,10 +489,24 @@ impl ZeroShotClassificationOption {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # Raw NLI logits generated by a `ZeroShotClassificationModel`
+pub struct ZeroShotRawOutput {
+    /// Pre-softmax NLI logits of shape (*number of sequences*, *number of labels*, *number of NLI classes*)
+    pub logits: Vec<Vec<Vec<f64>>>,
+    /// Candidate labels, indexed by their position in the second dimension of `logits`
+    pub labels: Vec<String>,
+    /// NLI classes (e.g. contradiction, neutral, entailment), indexed by their position in the last dimension of `logits`
+    pub nli_classes: HashMap<i64, String>,
+    /// Index of the entailment class in the last dimension of `logits`
+    pub entailment_id: i64,
+}
+
 /// # ZeroShotClassificationModel for Zero Shot Classification
 pub struct ZeroShotClassificationModel {
     tokenizer: TokenizerOption,
     zero_shot_classifier: ZeroShotClassificationOption,
+    nli_classes: HashMap<i64, String>,
     var_store: VarStore,
 }
 
@@ -527,14 +552,47 @@ impl ZeroShotClassificationModel {
         let model_config = ConfigOption::from_file(config.model_type, config_path);
         let zero_shot_classifier =
             ZeroShotClassificationOption::new(config.model_type, &var_store.root(), &model_config)?;
+        let nli_classes = model_config.get_label_mapping();
         var_store.load(weights_path)?;
         Ok(ZeroShotClassificationModel {
             tokenizer,
             zero_shot_classifier,
+            nli_classes,
             var_store,
         })
     }
 
+    /// Interface method to access tokenizer
+    pub fn get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+
+    /// Replaces the weights of the model in place with the weights from a resource (see `pipelines::common::reload_var_store`).
+    ///
+    /// # Arguments
+    ///
+    /// * `weights_resource` - `Resource` pointing to the new model weights
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    /// use rust_bert::resources::{LocalResource, Resource};
+    /// use std::path::PathBuf;
+    ///
+    /// let mut model = ZeroShotClassificationModel::new(Default::default())?;
+    /// let weights_resource = Resource::Local(LocalResource {
+    ///     local_path: PathBuf::from("path/to/updated/rust_model.ot"),
+    /// });
+    /// model.reload_weights(&weights_resource)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reload_weights(&mut self, weights_resource: &Resource) -> Result<(), RustBertError> {
+        reload_var_store(&self.var_store, weights_resource)
+    }
+
     fn prepare_for_model<'a, S, T>(
         &self,
         inputs: S,
@@ -559,22 +617,63 @@ impl ZeroShotClassificationModel {
                 .collect(),
         };
 
-        let text_pair_list = inputs
+        //        Inputs and hypotheses are tokenized once and combined into (input, hypothesis) pairs,
+        //        following the truncation and special tokens insertion of the tokenizer pair encoding
+        let tokenize = |text: &str| {
+            let tokens = self.tokenizer.tokenize_with_offsets(text);
+            TokenIdsWithOffsets {
+                ids: self.tokenizer.convert_tokens_to_ids(&tokens.tokens),
+                offsets: tokens.offsets,
+                reference_offsets: tokens.reference_offsets,
+                masks: tokens.masks,
+            }
+        };
+        let tokenized_inputs = inputs
             .as_ref()
             .iter()
-            .flat_map(|input| {
-                label_sentences
+            .map(|input| tokenize(input))
+            .collect::<Vec<TokenIdsWithOffsets>>();
+        let tokenized_label_sentences = label_sentences
+            .iter()
+            .map(|label_sentence| tokenize(label_sentence))
+            .collect::<Vec<TokenIdsWithOffsets>>();
+        let empty_sequence = || TokenIdsWithOffsets {
+            ids: vec![],
+            offsets: vec![],
+            reference_offsets: vec![],
+            masks: vec![],
+        };
+        let num_special_tokens = self
+            .tokenizer
+            .build_input_with_special_tokens(empty_sequence(), Some(empty_sequence()))
+            .token_ids
+            .len();
+
+        let tokenized_input: Vec<TokenizedInput> = tokenized_inputs
+            .iter()
+            .flat_map(|tokenized_input| {
+                tokenized_label_sentences
                     .iter()
-                    .map(move |label_sentence| (input.deref(), label_sentence.as_str()))
+                    .map(move |tokenized_label_sentence| {
+                        let total_length = tokenized_input.ids.len()
+                            + tokenized_label_sentence.ids.len()
+                            + num_special_tokens;
+                        let num_truncated_tokens = total_length.saturating_sub(max_len);
+                        let (tokenized_input, tokenized_label_sentence, _, _) = truncate_sequences(
+                            tokenized_input.clone(),
+                            Some(tokenized_label_sentence.clone()),
+                            num_truncated_tokens,
+                            &TruncationStrategy::LongestFirst,
+                            0,
+                        )
+                        .unwrap();
+                        self.tokenizer.build_input_with_special_tokens(
+                            tokenized_input,
+                            tokenized_label_sentence,
+                        )
+                    })
             })
-            .collect::<Vec<(&str, &str)>>();
-
-        let tokenized_input: Vec<TokenizedInput> = self.tokenizer.encode_pair_list(
-            text_pair_list.as_ref(),
-            max_len,
-            &TruncationStrategy::LongestFirst,
-            0,
-        );
+            .collect();
         let max_len = tokenized_input
             .iter()
             .map(|input| input.token_ids.len())
@@ -606,6 +705,56 @@ impl ZeroShotClassificationModel {
         (tokenized_input_tensors, mask)
     }
 
+    /// Returns the attention mask built by the pipeline for the (input, label hypothesis) pairs,
+    /// after truncation and padding. This debugging method returns the exact mask passed to the
+    /// model when classifying the same inputs and labels.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `labels` - `&[&str]` Possible labels for the inputs.
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*number of inputs* x *number of labels*, *sequence_length*) with 1 for tokens attended to and 0 for padding positions, placed on the CPU. Pairs are ordered by input, then by label.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    ///
+    /// let input_sentence = "Who are you voting for in 2020?";
+    /// let candidate_labels = &["politics", "public health", "economics", "sports"];
+    ///
+    /// let attention_mask = sequence_classification_model.get_attention_mask(
+    ///     &[input_sentence],
+    ///     candidate_labels,
+    ///     None,
+    ///     128,
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn get_attention_mask<'a, S, T>(
+        &self,
+        inputs: S,
+        labels: T,
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+    ) -> Tensor
+    where
+        S: AsRef<[&'a str]>,
+        T: AsRef<[&'a str]>,
+    {
+        let (_, mask) = self.prepare_for_model(inputs.as_ref(), labels, template, max_length);
+        mask.to_kind(Int64).to(Device::Cpu)
+    }
+
     /// Zero shot classification with 1 (and exactly 1) true label.
     ///
     /// # Arguments
@@ -650,12 +799,14 @@ impl ZeroShotClassificationModel {
     ///         score: 0.959,
     ///         id: 0,
     ///         sentence: 0,
+    ///         margin: 0.93,
     ///     },
     ///     Label {
     ///         text: "economy".to_string(),
     ///         score: 0.642,
     ///         id: 2,
     ///         sentence: 1,
+    ///         margin: 0.41,
     ///     },
     /// ]
     /// .to_vec();
@@ -683,16 +834,21 @@ impl ZeroShotClassificationModel {
                 None,
                 false,
             );
+            assert_no_grad(&output);
             output.view((num_inputs as i64, labels.as_ref().len() as i64, -1i64))
         });
 
         let scores = output.softmax(1, Float).select(-1, -1);
         let label_indices = scores.as_ref().argmax(-1, true).squeeze1(1);
+        let margins = label_margins(&scores)
+            .gather(1, &label_indices.unsqueeze(-1), false)
+            .squeeze1(1);
         let scores = scores
             .gather(1, &label_indices.unsqueeze(-1), false)
             .squeeze1(1);
         let label_indices = label_indices.iter::<i64>().unwrap().collect::<Vec<i64>>();
         let scores = scores.iter::<f64>().unwrap().collect::<Vec<f64>>();
+        let margins = margins.iter::<f64>().unwrap().collect::<Vec<f64>>();
 
         let mut output_labels: Vec<Label> = vec![];
         for sentence_idx in 0..label_indices.len() {
@@ -702,6 +858,7 @@ impl ZeroShotClassificationModel {
                 score: scores[sentence_idx],
                 id: label_indices[sentence_idx],
                 sentence: sentence_idx,
+                margin: margins[sentence_idx],
             };
             output_labels.push(label)
         }
@@ -752,24 +909,28 @@ impl ZeroShotClassificationModel {
     ///             score: 0.972,
     ///             id: 0,
     ///             sentence: 0,
+    ///             margin: 0.94,
     ///         },
     ///         Label {
     ///             text: "public health".to_string(),
     ///             score: 0.032,
     ///             id: 1,
     ///             sentence: 0,
+    ///             margin: -0.94,
     ///         },
     ///         Label {
     ///             text: "economy".to_string(),
     ///             score: 0.006,
     ///             id: 2,
     ///             sentence: 0,
+    ///             margin: -0.966,
     ///         },
     ///         Label {
     ///             text: "sports".to_string(),
     ///             score: 0.004,
     ///             id: 3,
     ///             sentence: 0,
+    ///             margin: -0.968,
     ///         },
     ///     ],
     ///     [
@@ -778,24 +939,28 @@ impl ZeroShotClassificationModel {
     ///             score: 0.975,
     ///             id: 0,
     ///             sentence: 1,
+    ///             margin: 0.123,
     ///         },
     ///         Label {
     ///             text: "economy".to_string(),
     ///             score: 0.852,
     ///             id: 2,
     ///             sentence: 1,
+    ///             margin: -0.123,
     ///         },
     ///         Label {
     ///             text: "public health".to_string(),
     ///             score: 0.0818,
     ///             id: 1,
     ///             sentence: 1,
+    ///             margin: -0.8932,
     ///         },
     ///         Label {
     ///             text: "sports".to_string(),
     ///             score: 0.001,
     ///             id: 3,
     ///             sentence: 1,
+    ///             margin: -0.974,
     ///         },
     ///     ],
     /// ]
@@ -824,18 +989,26 @@ impl ZeroShotClassificationModel {
                 None,
                 false,
             );
+            assert_no_grad(&output);
             output.view((num_inputs as i64, labels.as_ref().len() as i64, -1i64))
         });
         let scores = output.slice(-1, 0, 3, 2).softmax(-1, Float).select(-1, -1);
+        let margins = label_margins(&scores);
 
         let mut output_labels = vec![];
         for sentence_idx in 0..num_inputs {
             let mut sentence_labels = vec![];
 
-            for (label_index, score) in scores
+            for (label_index, (score, margin)) in scores
                 .select(0, sentence_idx as i64)
                 .iter::<f64>()
                 .unwrap()
+                .zip(
+                    margins
+                        .select(0, sentence_idx as i64)
+                        .iter::<f64>()
+                        .unwrap(),
+                )
                 .enumerate()
             {
                 let label_string = labels.as_ref()[label_index].to_string();
@@ -844,6 +1017,7 @@ impl ZeroShotClassificationModel {
                     score,
                     id: label_index as i64,
                     sentence: sentence_idx,
+                    margin,
                 };
                 sentence_labels.push(label);
             }
@@ -851,6 +1025,105 @@ impl ZeroShotClassificationModel {
         }
         output_labels
     }
+
+    /// Zero shot classification returning the raw NLI logits for every (input, label) pair, before any aggregation.
+    /// Useful for detailed analysis or ensembling of zero-shot classifiers.
+    ///
+    /// The NLI classes are read from the `id2label` mapping of the model configuration (the inverse of its `label2id`).
+    /// The entailment class is the class whose name starts with `entailment` (case-insensitive). If no such class exists,
+    /// the last class is assumed to correspond to entailment, consistent with `predict` and `predict_multilabel`.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `labels` - `&[&str]` Possible labels for the inputs.
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is about {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    ///
+    /// # Returns
+    ///
+    /// * `ZeroShotRawOutput` containing the logits of shape (*number of inputs*, *number of labels*, *number of NLI classes*) and the label and NLI class mappings
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    ///
+    /// let input_sentence = "Who are you voting for in 2020?";
+    /// let candidate_labels = &["politics", "public health", "economics", "sports"];
+    ///
+    /// let output =
+    ///     sequence_classification_model.predict_raw(&[input_sentence], candidate_labels, None, 128);
+    /// let politics_entailment_logit = output.logits[0][0][output.entailment_id as usize];
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_raw<'a, S, T>(
+        &self,
+        inputs: S,
+        labels: T,
+        template: Option<Box<dyn Fn(&str) -> String>>,
+        max_length: usize,
+    ) -> ZeroShotRawOutput
+    where
+        S: AsRef<[&'a str]>,
+        T: AsRef<[&'a str]>,
+    {
+        let num_inputs = inputs.as_ref().len();
+        let (input_tensor, mask) =
+            self.prepare_for_model(inputs.as_ref(), labels.as_ref(), template, max_length);
+        let output = no_grad(|| {
+            let output = self.zero_shot_classifier.forward_t(
+                Some(input_tensor),
+                Some(mask),
+                None,
+                None,
+                None,
+                false,
+            );
+            assert_no_grad(&output);
+            output
+                .view((num_inputs as i64, labels.as_ref().len() as i64, -1i64))
+                .to_kind(Float)
+                .to(Device::Cpu)
+        });
+        let num_nli_classes = *output.size().last().unwrap();
+
+        let logits = (0..num_inputs as i64)
+            .map(|sentence_idx| {
+                let sentence_logits = output.get(sentence_idx);
+                (0..labels.as_ref().len() as i64)
+                    .map(|label_idx| {
+                        sentence_logits
+                            .get(label_idx)
+                            .iter::<f64>()
+                            .unwrap()
+                            .collect::<Vec<f64>>()
+                    })
+                    .collect::<Vec<Vec<f64>>>()
+            })
+            .collect::<Vec<Vec<Vec<f64>>>>();
+
+        let entailment_id = self
+            .nli_classes
+            .iter()
+            .find(|(_, class_name)| class_name.to_lowercase().starts_with("entailment"))
+            .map_or(num_nli_classes - 1, |(class_id, _)| *class_id);
+
+        ZeroShotRawOutput {
+            logits,
+            labels: labels
+                .as_ref()
+                .iter()
+                .map(|label| label.to_string())
+                .collect(),
+            nli_classes: self.nli_classes.clone(),
+            entailment_id,
+        }
+    }
 }
 #[cfg(test)]
 mod test {
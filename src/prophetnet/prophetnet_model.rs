@@ -962,6 +962,7 @@ impl ProphetNetConditionalGenerator {
         let eos_token_ids = Some(vec![config.eos_token_id]);
         let pad_token_id = Some(config.pad_token_id);
         let vocab_size = config.vocab_size;
+        generate_config.validate_corpus_penalty(vocab_size)?;
         let is_encoder_decoder = true;
         let decoder_start_id = Some(config.decoder_start_token_id);
 
@@ -1087,6 +1087,7 @@ impl ReformerGenerator {
         let eos_token_ids = Some(vec![config.eos_token_id]);
         let pad_token_id = Some(config.pad_token_id);
         let vocab_size = config.vocab_size;
+        generate_config.validate_corpus_penalty(vocab_size)?;
         let is_encoder_decoder = false;
         let decoder_start_id = None;
 
@@ -405,7 +405,7 @@ impl RobertaClassificationHead {
 /// - `roberta`: Base RoBERTa model
 /// - `classifier`: RoBERTa classification head made of 2 linear layers
 pub struct RobertaForSequenceClassification {
-    roberta: BertModel<RobertaEmbeddings>,
+    pub(crate) roberta: BertModel<RobertaEmbeddings>,
     classifier: RobertaClassificationHead,
 }
 
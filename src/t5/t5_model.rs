@@ -752,6 +752,7 @@ impl T5Generator {
         });
         let pad_token_id = Some(config.pad_token_id.unwrap_or(0));
         let vocab_size = config.vocab_size;
+        generate_config.validate_corpus_penalty(vocab_size)?;
         let is_encoder_decoder = true;
         let decoder_start_id = Some(0);
 
@@ -862,7 +862,7 @@ impl LMHeadModel for XLNetLMHeadModel {
 /// - `sequence_summary`: `SequenceSummary` to pool the base model hidden states
 /// - `logits_proj`: Linear layer projecting the hidden layer pooled output to the target space
 pub struct XLNetForSequenceClassification {
-    base_model: XLNetModel,
+    pub(crate) base_model: XLNetModel,
     sequence_summary: SequenceSummary,
     logits_proj: nn::Linear,
 }
@@ -1614,6 +1614,7 @@ impl XLNetGenerator {
         let pad_token_id = Some(config.pad_token_id);
         let is_encoder_decoder = false;
         let vocab_size = config.vocab_size;
+        generate_config.validate_corpus_penalty(vocab_size)?;
         let decoder_start_id = None;
 
         Ok(XLNetGenerator {
@@ -1783,4 +1784,12 @@ impl PrivateLanguageGenerator<XLNetLMHeadModel, XLNetVocab, XLNetTokenizer> for
     }
 }
 
-impl LanguageGenerator<XLNetLMHeadModel, XLNetVocab, XLNetTokenizer> for XLNetGenerator {}
+impl LanguageGenerator<XLNetLMHeadModel, XLNetVocab, XLNetTokenizer> for XLNetGenerator {
+    /// Not supported for XLNet: the model attends to the entire input without a permutation mask,
+    /// so a single teacher-forced forward pass does not provide causal token log-probabilities.
+    fn sequence_logprob(&self, _prompt: &str, _continuation: &str) -> Result<f64, RustBertError> {
+        Err(RustBertError::InvalidConfigurationError(
+            "Sequence log-probabilities are not supported for XLNet".into(),
+        ))
+    }
+}
@@ -1,6 +1,9 @@
 use rust_bert::bart::{
-    BartConfig, BartConfigResources, BartMergesResources, BartModel, BartModelResources,
-    BartVocabResources,
+    BartConfig, BartConfigResources, BartGenerator, BartMergesResources, BartModel,
+    BartModelResources, BartVocabResources, LayerState,
+};
+use rust_bert::pipelines::generation_utils::{
+    Cache, GenerateConfig, GenerationSnapshot, LanguageGenerator,
 };
 use rust_bert::pipelines::summarization::{SummarizationConfig, SummarizationModel};
 use rust_bert::pipelines::zero_shot_classification::{
@@ -9,7 +12,7 @@ use rust_bert::pipelines::zero_shot_classification::{
 use rust_bert::resources::{RemoteResource, Resource};
 use rust_bert::Config;
 use rust_tokenizers::tokenizer::{RobertaTokenizer, Tokenizer, TruncationStrategy};
-use tch::{nn, Device, Tensor};
+use tch::{nn, Device, Kind, Tensor};
 
 #[test]
 fn bart_lm_model() -> anyhow::Result<()> {
@@ -137,6 +140,300 @@ about exoplanets like K2-18b."];
     Ok(())
 }
 
+#[test]
+fn bart_summarization_empty_inputs() -> anyhow::Result<()> {
+    //    Set-up summarization model
+    let config_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartConfigResources::DISTILBART_CNN_6_6,
+    ));
+    let vocab_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartVocabResources::DISTILBART_CNN_6_6,
+    ));
+    let merges_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartMergesResources::DISTILBART_CNN_6_6,
+    ));
+    let model_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartModelResources::DISTILBART_CNN_6_6,
+    ));
+    let summarization_config = SummarizationConfig {
+        model_resource,
+        config_resource,
+        vocab_resource,
+        merges_resource,
+        num_beams: 1,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = SummarizationModel::new(summarization_config)?;
+
+    let output = model.summarize(&["", "   "]);
+
+    assert_eq!(output, vec!["", ""]);
+
+    Ok(())
+}
+
+#[test]
+fn bart_summarization_multi_length() -> anyhow::Result<()> {
+    //    Set-up summarization model
+    let config_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartConfigResources::DISTILBART_CNN_6_6,
+    ));
+    let vocab_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartVocabResources::DISTILBART_CNN_6_6,
+    ));
+    let merges_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartMergesResources::DISTILBART_CNN_6_6,
+    ));
+    let model_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartModelResources::DISTILBART_CNN_6_6,
+    ));
+    let summarization_config = SummarizationConfig {
+        model_resource,
+        config_resource,
+        vocab_resource,
+        merges_resource,
+        num_beams: 1,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = SummarizationModel::new(summarization_config)?;
+
+    let input = "In findings published Tuesday in Cornell University's arXiv by a team of scientists \
+from the University of Montreal and a separate report published Wednesday in Nature Astronomy by a team \
+from University College London (UCL), the presence of water vapour was confirmed in the atmosphere of K2-18b, \
+a planet circling a star in the constellation Leo. This is the first such discovery in a planet in its star's \
+habitable zone — not too hot and not too cold for liquid water to exist. The Montreal team, led by Björn Benneke, \
+used data from the NASA's Hubble telescope to assess changes in the light coming from K2-18b's star as the planet \
+passed between it and Earth.";
+    let length_targets = [(5, 20), (30, 60)];
+
+    let output = model.summarize_multi_length(input, &length_targets);
+    let tokenizer = model.get_tokenizer();
+
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0].0, (5, 20));
+    assert_eq!(output[1].0, (30, 60));
+    assert!(!output[0].1.is_empty());
+    assert!(!output[1].1.is_empty());
+    assert!(tokenizer.tokenize(&output[0].1).len() < tokenizer.tokenize(&output[1].1).len());
+
+    let empty_output = model.summarize_multi_length("  ", &length_targets);
+    assert_eq!(
+        empty_output,
+        vec![((5, 20), String::new()), ((30, 60), String::new())]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bart_generation_snapshot() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = GenerateConfig {
+        model_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartModelResources::DISTILBART_CNN_6_6,
+        )),
+        config_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartConfigResources::DISTILBART_CNN_6_6,
+        )),
+        vocab_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartVocabResources::DISTILBART_CNN_6_6,
+        )),
+        merges_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartMergesResources::DISTILBART_CNN_6_6,
+        )),
+        min_length: 10,
+        max_length: 30,
+        num_beams: 3,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = BartGenerator::new(generate_config)?;
+
+    let input = "In findings published Tuesday in Cornell University's arXiv by a team of scientists \
+from the University of Montreal and a separate report published Wednesday in Nature Astronomy by a team \
+from University College London (UCL), the presence of water vapour was confirmed in the atmosphere of K2-18b, \
+a planet circling a star in the constellation Leo.";
+    let output = model.generate_indices(Some(&[input]), None, None, None, None);
+
+    //    The encoder outputs and cache are saved along with the snapshot
+    let tensors_dir = tempfile::tempdir()?;
+    let tensors_path = tensors_dir.path().join("snapshot.ot");
+    let mut snapshot = model.generate_snapshot(Some(&[input]), None, None, 5)?;
+    assert_eq!(snapshot.step(), 5);
+    while !snapshot.is_complete() {
+        snapshot.save_tensors(&tensors_path)?;
+        let mut restored_snapshot: GenerationSnapshot =
+            serde_json::from_str(&serde_json::to_string(&snapshot)?)?;
+        //    Encoder-decoder snapshots cannot be resumed without their encoder outputs
+        assert!(model.resume_generation(&restored_snapshot, 5).is_err());
+        restored_snapshot.load_tensors(&tensors_path, Device::Cpu)?;
+        snapshot = model.resume_generation(&restored_snapshot, 5)?;
+    }
+    assert_eq!(snapshot.token_ids, output);
+
+    Ok(())
+}
+
+#[test]
+fn bart_generation_with_trigger() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = GenerateConfig {
+        model_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartModelResources::DISTILBART_CNN_6_6,
+        )),
+        config_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartConfigResources::DISTILBART_CNN_6_6,
+        )),
+        vocab_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartVocabResources::DISTILBART_CNN_6_6,
+        )),
+        merges_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartMergesResources::DISTILBART_CNN_6_6,
+        )),
+        max_length: 30,
+        num_beams: 1,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = BartGenerator::new(generate_config)?;
+
+    let input = "In findings published Tuesday in Cornell University's arXiv by a team of scientists \
+from the University of Montreal, the presence of water vapour was confirmed in the atmosphere of K2-18b.";
+    let output = model.generate_indices(Some(&[input]), None, None, None, None);
+
+    //    The tokens are spliced into the decoder input after the trigger token
+    let trigger_token_id = output[0][1];
+    let spliced_ids = vec![133, 1049];
+    let callback_spliced_ids = spliced_ids.clone();
+    let triggered_output = model.generate_with_trigger(
+        Some(&[input]),
+        None,
+        None,
+        (
+            trigger_token_id,
+            Box::new(move |_: &str| callback_spliced_ids.clone()),
+        ),
+    )?;
+    assert_eq!(triggered_output[0][..2], output[0][..2]);
+    assert_eq!(triggered_output[0][2..4], spliced_ids[..]);
+
+    //    Batches of encoder-decoder sequences cannot be padded after the splices
+    assert!(model
+        .generate_with_trigger(
+            Some(&[input, input]),
+            None,
+            None,
+            (trigger_token_id, Box::new(|_: &str| vec![]))
+        )
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn bart_summarization_source_copy_bias() -> anyhow::Result<()> {
+    //    Set-up model
+    let config_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartConfigResources::DISTILBART_CNN_6_6,
+    ));
+    let vocab_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartVocabResources::DISTILBART_CNN_6_6,
+    ));
+    let merges_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartMergesResources::DISTILBART_CNN_6_6,
+    ));
+    let model_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartModelResources::DISTILBART_CNN_6_6,
+    ));
+    let summarization_config = SummarizationConfig {
+        model_resource,
+        config_resource,
+        vocab_resource,
+        merges_resource,
+        num_beams: 1,
+        source_copy_bias: 1000.0,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = SummarizationModel::new(summarization_config)?;
+
+    let input = ["In findings published Tuesday in Cornell University's arXiv by a team of scientists \
+from the University of Montreal and a separate report published Wednesday in Nature Astronomy by a team \
+from University College London (UCL), the presence of water vapour was confirmed in the atmosphere of K2-18b, \
+a planet circling a star in the constellation Leo. This is the first such discovery in a planet in its star's \
+habitable zone — not too hot and not too cold for liquid water to exist. The Montreal team, led by Björn Benneke, \
+used data from the NASA's Hubble telescope to assess changes in the light coming from K2-18b's star as the planet \
+passed between it and Earth."];
+
+    //    A high source copy bias restricts the summary to tokens of the source
+    let output = model.summarize(&input);
+
+    assert_eq!(output.len(), 1);
+    assert!(!output[0].is_empty());
+    for word in output[0].split_whitespace() {
+        assert!(input[0].contains(word), "{} not found in source", word);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bart_summarization_attribution() -> anyhow::Result<()> {
+    //    Set-up summarization model
+    let config_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartConfigResources::DISTILBART_CNN_6_6,
+    ));
+    let vocab_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartVocabResources::DISTILBART_CNN_6_6,
+    ));
+    let merges_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartMergesResources::DISTILBART_CNN_6_6,
+    ));
+    let model_resource = Resource::Remote(RemoteResource::from_pretrained(
+        BartModelResources::DISTILBART_CNN_6_6,
+    ));
+    let summarization_config = SummarizationConfig {
+        model_resource,
+        config_resource,
+        vocab_resource,
+        merges_resource,
+        num_beams: 1,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = SummarizationModel::new(summarization_config)?;
+
+    let input = ["In findings published Tuesday in Cornell University's arXiv by a team of scientists \
+from the University of Montreal and a separate report published Wednesday in Nature Astronomy by a team \
+from University College London (UCL), the presence of water vapour was confirmed in the atmosphere of K2-18b, \
+a planet circling a star in the constellation Leo. This is the first such discovery in a planet in its star's \
+habitable zone — not too hot and not too cold for liquid water to exist. The Montreal team, led by Björn Benneke, \
+used data from the NASA's Hubble telescope to assess changes in the light coming from K2-18b's star as the planet \
+passed between it and Earth."];
+
+    let output = model.summarize_with_attribution(&input)?;
+
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0].summary, model.summarize(&input)[0]);
+    assert!(!output[0].sentences.is_empty());
+    for sentence in output[0].sentences.iter() {
+        assert_eq!(sentence.text, output[0].summary[sentence.span.clone()]);
+        assert!(!sentence.sources.is_empty());
+        let total_weight: f64 = sentence.sources.iter().map(|source| source.weight).sum();
+        assert!((total_weight - 1.0).abs() < 1e-6);
+        for (source, next_source) in sentence.sources.iter().zip(sentence.sources.iter().skip(1)) {
+            assert!(source.weight >= next_source.weight);
+        }
+        for source in sentence.sources.iter() {
+            assert_eq!(source.text, input[0][source.span.clone()]);
+        }
+    }
+
+    Ok(())
+}
+
 #[test]
 fn bart_summarization_beam_search() -> anyhow::Result<()> {
     //    Set-up masked LM model
@@ -232,6 +529,54 @@ fn bart_zero_shot_classification() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn bart_zero_shot_classification_raw() -> anyhow::Result<()> {
+    //    Set-up model model
+    let zero_shot_config = ZeroShotClassificationConfig {
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let sequence_classification_model = ZeroShotClassificationModel::new(zero_shot_config)?;
+
+    let input_sentence = "Who are you voting for in 2020?";
+    let input_sequence_2 = "The prime minister has announced a stimulus package which was widely criticized by the opposition.";
+    let candidate_labels = &["politics", "public health", "economy", "sports"];
+
+    let output = sequence_classification_model.predict_raw(
+        &[input_sentence, input_sequence_2],
+        candidate_labels,
+        None,
+        128,
+    );
+
+    assert_eq!(output.logits.len(), 2);
+    assert_eq!(output.logits[0].len(), 4);
+    assert_eq!(output.logits[0][0].len(), 3);
+    assert_eq!(output.labels, candidate_labels.to_vec());
+    assert_eq!(output.nli_classes.len(), 3);
+    assert_eq!(output.entailment_id, 2);
+    assert_eq!(output.nli_classes[&output.entailment_id], "entailment");
+
+    // Most entailed labels match the aggregated predictions
+    let top_labels = output
+        .logits
+        .iter()
+        .map(|sentence_logits| {
+            (0..sentence_logits.len())
+                .max_by(|&label_1, &label_2| {
+                    sentence_logits[label_1][2]
+                        .partial_cmp(&sentence_logits[label_2][2])
+                        .unwrap()
+                })
+                .unwrap()
+        })
+        .collect::<Vec<usize>>();
+    assert_eq!(output.labels[top_labels[0]], "politics");
+    assert_eq!(output.labels[top_labels[1]], "economy");
+    Ok(())
+}
+
 #[test]
 #[cfg_attr(not(feature = "all-tests"), ignore)]
 fn bart_zero_shot_classification_multilabel() -> anyhow::Result<()> {
@@ -278,3 +623,192 @@ fn bart_zero_shot_classification_multilabel() -> anyhow::Result<()> {
     assert!((output[1][3].score - 0.0004).abs() < 1e-4);
     Ok(())
 }
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn bart_zero_shot_classification_many_sequences() -> anyhow::Result<()> {
+    //    Set-up model model
+    let zero_shot_config = ZeroShotClassificationConfig {
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let sequence_classification_model = ZeroShotClassificationModel::new(zero_shot_config)?;
+
+    let sentences = [
+        "Who are you voting for in 2020?",
+        "The prime minister has announced a stimulus package which was widely criticized by the opposition.",
+        "The team scored in the last minute of the game and qualified for the final.",
+        "Hospitals are preparing for a new wave of infections this winter, and the health minister urged \
+         everyone to get vaccinated as soon as possible to relieve the pressure on intensive care units.",
+    ];
+    let input = sentences
+        .iter()
+        .cycle()
+        .take(16)
+        .copied()
+        .collect::<Vec<&str>>();
+    let candidate_labels = &["politics", "public health", "economy", "sports"];
+
+    //    Batched predictions, with a maximum length truncating the longest sequence
+    let output = sequence_classification_model.predict(&input, candidate_labels, None, 24);
+
+    assert_eq!(output.len(), 16);
+    for (sentence_index, sentence) in sentences.iter().enumerate() {
+        let reference_output =
+            sequence_classification_model.predict(&[*sentence], candidate_labels, None, 24);
+        for label in output.iter().skip(sentence_index).step_by(sentences.len()) {
+            assert_eq!(label.text, reference_output[0].text);
+            assert!((label.score - reference_output[0].score).abs() < 1e-4);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn bart_zero_shot_classification_empty_inputs() -> anyhow::Result<()> {
+    //    Set-up model
+    let zero_shot_config = ZeroShotClassificationConfig {
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let sequence_classification_model = ZeroShotClassificationModel::new(zero_shot_config)?;
+
+    //    Empty inputs still receive a prediction
+    let candidate_labels = &["politics", "public health", "economy", "sports"];
+    let output = sequence_classification_model.predict(&["", "   "], candidate_labels, None, 128);
+
+    assert_eq!(output.len(), 2);
+    assert!(candidate_labels.contains(&output[0].text.as_str()));
+    assert!(candidate_labels.contains(&output[1].text.as_str()));
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn bart_zero_shot_classification_attention_mask() -> anyhow::Result<()> {
+    //    Set-up model
+    let zero_shot_config = ZeroShotClassificationConfig {
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let sequence_classification_model = ZeroShotClassificationModel::new(zero_shot_config)?;
+
+    //    One mask row per (input, label) pair, with truncation to the maximum length
+    let input_sentence = "Who are you voting for in 2020?";
+    let input_sequence_2 = "The prime minister has announced a stimulus package which was widely criticized by the opposition.";
+    let candidate_labels = &["politics", "public health", "economy", "sports"];
+    let attention_mask = sequence_classification_model.get_attention_mask(
+        &[input_sentence, input_sequence_2],
+        candidate_labels,
+        None,
+        16,
+    );
+
+    let (batch_size, sequence_length) = attention_mask.size2()?;
+    assert_eq!(batch_size, 8);
+    assert!(sequence_length <= 16);
+    //    The long input is truncated and all its pairs are fully attended
+    for pair_index in 4..8 {
+        assert_eq!(
+            attention_mask
+                .get(pair_index)
+                .sum(Kind::Int64)
+                .int64_value(&[]),
+            16
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bart_sequence_logprob() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = GenerateConfig {
+        model_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartModelResources::DISTILBART_CNN_6_6,
+        )),
+        config_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartConfigResources::DISTILBART_CNN_6_6,
+        )),
+        vocab_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartVocabResources::DISTILBART_CNN_6_6,
+        )),
+        merges_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartMergesResources::DISTILBART_CNN_6_6,
+        )),
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = BartGenerator::new(generate_config)?;
+
+    //    Sequence log-probabilities are only available for causal language models
+    assert!(model.sequence_logprob("The dog", " was").is_err());
+
+    Ok(())
+}
+
+#[test]
+fn bart_cache_memory() -> anyhow::Result<()> {
+    //    The memory of an encoder-decoder cache includes the cross-attention keys and values
+    let layer_state = |sequence_length: i64| LayerState {
+        prev_key: Tensor::zeros(&[2, 16, sequence_length, 64], (Kind::Float, Device::Cpu)),
+        prev_value: Tensor::zeros(&[2, 16, sequence_length, 64], (Kind::Float, Device::Cpu)),
+    };
+    let self_attention_bytes = 2 * 2 * 16 * 3 * 64 * 4;
+    let cross_attention_bytes = 2 * 2 * 16 * 10 * 64 * 4;
+    let cache = Cache::BARTCache(Some(vec![
+        (Some(layer_state(3)), Some(layer_state(10))),
+        (Some(layer_state(3)), Some(layer_state(10))),
+    ]));
+    assert_eq!(
+        cache.memory_size(),
+        2 * (self_attention_bytes + cross_attention_bytes)
+    );
+    let self_attention_cache = Cache::BARTCache(Some(vec![
+        (Some(layer_state(3)), None),
+        (Some(layer_state(3)), None),
+    ]));
+    assert_eq!(self_attention_cache.memory_size(), 2 * self_attention_bytes);
+
+    //    Set-up model
+    let generate_config = GenerateConfig {
+        model_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartModelResources::DISTILBART_CNN_6_6,
+        )),
+        config_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartConfigResources::DISTILBART_CNN_6_6,
+        )),
+        vocab_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartVocabResources::DISTILBART_CNN_6_6,
+        )),
+        merges_resource: Resource::Remote(RemoteResource::from_pretrained(
+            BartMergesResources::DISTILBART_CNN_6_6,
+        )),
+        min_length: 8,
+        max_length: 12,
+        num_beams: 1,
+        output_cache_memory: true,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = BartGenerator::new(generate_config)?;
+
+    let input = "In findings published Tuesday in Cornell University's arXiv by a team of scientists \
+from the University of Montreal, the presence of water vapour was confirmed in the atmosphere of K2-18b.";
+    let output = model.generate_indices_with_output(Some(&[input]), None, None, None, None);
+
+    //    The self-attention cache grows by the same amount at each step, on top of the constant cross-attention cache
+    let cache_memory = output.cache_memory;
+    assert!(cache_memory.len() > 2);
+    let step_bytes = cache_memory[1] - cache_memory[0];
+    for (memory_1, memory_2) in cache_memory.iter().zip(cache_memory.iter().skip(1)) {
+        assert_eq!(memory_2 - memory_1, step_bytes);
+    }
+    assert!(cache_memory[0] > step_bytes);
+
+    Ok(())
+}
@@ -389,6 +389,64 @@ fn bert_pre_trained_ner() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn bert_pre_trained_ner_explain_entity() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = "My name is Amy. I live in Paris.";
+
+    //    Run model
+    let importance = ner_model.explain_entity(input, 1)?;
+
+    //    Special tokens are excluded, input tokens are returned in order
+    assert_eq!(importance.len(), 10);
+    assert_eq!(importance[0].0, "My");
+    assert_eq!(importance[8].0, "Paris");
+    assert!(importance.iter().all(|(_, score)| score.is_finite()));
+    //    The entity itself is the main driver of its prediction
+    let (most_important, _) = importance
+        .iter()
+        .max_by(|a, b| a.1.abs().partial_cmp(&b.1.abs()).unwrap())
+        .unwrap();
+    assert_eq!(most_important, "Paris");
+
+    assert!(ner_model.explain_entity(input, 2).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn bert_pre_trained_ner_tag_distributions() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Define input
+    let input = ["My name is Amy. I live in Paris."];
+
+    //    Run model
+    let output = ner_model.predict_tag_distributions(&input);
+
+    assert_eq!(output.len(), 1);
+    //    Special tokens are excluded
+    assert_eq!(output[0].len(), 10);
+    let amy = &output[0][3];
+    assert_eq!(amy.text, "Amy");
+    assert_eq!((amy.offset.begin, amy.offset.end), (11, 14));
+    let total_probability: f64 = amy.scores.iter().map(|(_, score)| score).sum();
+    assert!((total_probability - 1.0).abs() < 1e-4);
+    let (label, score) = amy
+        .scores
+        .iter()
+        .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+        .unwrap();
+    assert_eq!(label, "I-PER");
+    assert!((score - 0.9986).abs() < 1e-4);
+
+    Ok(())
+}
+
 #[test]
 fn bert_question_answering() -> anyhow::Result<()> {
     //    Set-up question answering model
@@ -423,3 +481,18 @@ fn bert_question_answering() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn bert_pre_trained_ner_empty_inputs() -> anyhow::Result<()> {
+    //    Set-up model
+    let ner_model = NERModel::new(Default::default())?;
+
+    //    Run model
+    let output = ner_model.predict(&["", "   "]);
+
+    assert_eq!(output.len(), 2);
+    assert!(output[0].is_empty());
+    assert!(output[1].is_empty());
+
+    Ok(())
+}
@@ -3,14 +3,25 @@ use rust_bert::distilbert::{
     DistilBertForTokenClassification, DistilBertModelMaskedLM, DistilBertModelResources,
     DistilBertVocabResources,
 };
+use rust_bert::pipelines::common::{
+    InputTruncation, ModelType, TokenizerOption, WindowBoundary, Windower,
+};
+use rust_bert::pipelines::diagnostics::{Diagnostic, DiagnosticKind};
 use rust_bert::pipelines::question_answering::{QaInput, QuestionAnsweringModel};
+use rust_bert::pipelines::sentence_importance::SentenceImportanceModel;
 use rust_bert::pipelines::sentiment::{SentimentModel, SentimentPolarity};
-use rust_bert::resources::{RemoteResource, Resource};
+use rust_bert::pipelines::sequence_classification::{
+    ClassificationHead, SequenceClassificationConfig, SequenceClassificationModel,
+};
+use rust_bert::quantization::{load_quantized_weights, QuantizationConfig};
+use rust_bert::resources::{LocalResource, RemoteResource, Resource};
 use rust_bert::Config;
 use rust_tokenizers::tokenizer::{BertTokenizer, MultiThreadedTokenizer, TruncationStrategy};
 use rust_tokenizers::vocab::Vocab;
+use rust_tokenizers::{Mask, TokenIdsWithOffsets};
 use std::collections::HashMap;
-use tch::{nn, no_grad, Device, Tensor};
+use std::sync::{Arc, Mutex};
+use tch::{nn, no_grad, Device, Kind, Tensor};
 
 extern crate anyhow;
 
@@ -39,6 +50,419 @@ fn distilbert_sentiment_classifier() -> anyhow::Result<()> {
     Ok(())
 }
 
+struct MeanPoolingHead {
+    classifier: nn::Linear,
+}
+
+impl ClassificationHead for MeanPoolingHead {
+    fn forward(&self, hidden_states: &Tensor, attention_mask: &Tensor) -> Tensor {
+        let mask = attention_mask.unsqueeze(-1).to_kind(hidden_states.kind());
+        let pooled = (hidden_states * &mask).sum1(&[1], false, hidden_states.kind())
+            / mask.sum1(&[1], false, hidden_states.kind());
+        pooled.apply(&self.classifier)
+    }
+}
+
+#[test]
+fn distilbert_sentiment_classifier_empty_inputs() -> anyhow::Result<()> {
+    //    Set-up classifier
+    let sentiment_classifier = SentimentModel::new(Default::default())?;
+
+    //    Empty inputs still receive a prediction
+    let output = sentiment_classifier.predict(&["", "   "]);
+
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0].polarity, output[1].polarity);
+    assert!((output[0].score - output[1].score).abs() < 1e-6);
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sentiment_classifier_by_sentence() -> anyhow::Result<()> {
+    //    Set-up classifier
+    let sentiment_classifier = SentimentModel::new(Default::default())?;
+
+    //    Get sentiments by sentence
+    let input = "The acting was superb and the soundtrack is truly memorable. Wow! \
+    Sadly, the last hour drags on and the ending is a complete letdown.";
+    let output = sentiment_classifier.predict_by_sentence(input);
+
+    //    The short exclamation is merged with the following sentence
+    assert_eq!(output.sentences.len(), 2);
+    assert_eq!(
+        output.sentences[0].sentence,
+        "The acting was superb and the soundtrack is truly memorable."
+    );
+    assert!(output.sentences[1].sentence.starts_with("Wow! Sadly"));
+    for sentence in output.sentences.iter() {
+        assert_eq!(&input[sentence.span.clone()], sentence.sentence);
+    }
+    assert_eq!(
+        output.sentences[0].sentiment.polarity,
+        SentimentPolarity::Positive
+    );
+    assert_eq!(
+        output.sentences[1].sentiment.polarity,
+        SentimentPolarity::Negative
+    );
+    assert!(output.overall.is_some());
+
+    //    Texts without sentences have no overall sentiment
+    let output = sentiment_classifier.predict_by_sentence("   ");
+    assert!(output.sentences.is_empty());
+    assert!(output.overall.is_none());
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sequence_classifier_calibration_temperature() -> anyhow::Result<()> {
+    //    Set-up classifier
+    let mut sequence_classifier = SequenceClassificationModel::new(Default::default())?;
+    let input = [
+        "Probably my all-time favorite movie, a story of selflessness, sacrifice and dedication to a noble cause, but it's not preachy or boring.",
+        "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
+    ];
+    let uncalibrated_output = sequence_classifier.predict(&input);
+
+    //    A temperature higher than 1 softens the scores without changing the predicted labels
+    sequence_classifier.set_calibration_temperature(2.0)?;
+    let calibrated_output = sequence_classifier.predict(&input);
+    for (uncalibrated_label, calibrated_label) in
+        uncalibrated_output.iter().zip(calibrated_output.iter())
+    {
+        assert_eq!(uncalibrated_label.id, calibrated_label.id);
+        assert!(calibrated_label.score < uncalibrated_label.score);
+        assert!(calibrated_label.score > 0.5);
+    }
+    assert!(sequence_classifier
+        .set_calibration_temperature(0.0)
+        .is_err());
+
+    //    With logits (1, 0) and 3 out of 4 examples labelled 0, the optimal temperature is 1 / ln(3)
+    let logits = Tensor::of_slice(&[1f64, 0f64]).repeat(&[4, 1]);
+    let labels = Tensor::of_slice(&[0i64, 0, 0, 1]);
+    let temperature = SequenceClassificationModel::fit_temperature(&logits, &labels);
+    assert!((temperature - 1f64 / 3f64.ln()).abs() < 1e-4);
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sequence_classifier_margin() -> anyhow::Result<()> {
+    //    Set-up classifier
+    let sequence_classifier = SequenceClassificationModel::new(Default::default())?;
+    let input = [
+        "Probably my all-time favorite movie, a story of selflessness, sacrifice and dedication to a noble cause, but it's not preachy or boring.",
+        "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
+    ];
+
+    //    With two classes, the margin is the difference between the score and its complement
+    let output = sequence_classifier.predict(&input);
+    for label in output.iter() {
+        assert!(label.margin > 0.0);
+        assert!((label.margin - (2.0 * label.score - 1.0)).abs() < 1e-4);
+    }
+
+    //    Labels other than the top label have a negative margin
+    let multilabel_output = sequence_classifier.predict_multilabel(&input, 0.0)?;
+    for sentence_labels in multilabel_output.iter() {
+        assert_eq!(sentence_labels.len(), 2);
+        assert!((sentence_labels[0].margin + sentence_labels[1].margin).abs() < 1e-6);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_tokenizer_strip_accents_override() -> anyhow::Result<()> {
+    //    Resources paths
+    let vocab_resource = Resource::Remote(RemoteResource::from_pretrained(
+        DistilBertVocabResources::DISTIL_BERT_SST2,
+    ));
+    let vocab_path = vocab_resource.get_local_path()?;
+    let tokenizer = |strip_accents: bool| {
+        TokenizerOption::from_file(
+            ModelType::DistilBert,
+            vocab_path.to_str().unwrap(),
+            None,
+            true,
+            strip_accents,
+            None,
+        )
+    };
+
+    //    The override takes precedence over the default (stripping accents for lower-cased tokenizers)
+    let stripping_tokenizer = tokenizer(true)?;
+    let accent_preserving_tokenizer = tokenizer(false)?;
+    assert_eq!(
+        stripping_tokenizer.tokenize("Café"),
+        stripping_tokenizer.tokenize("cafe")
+    );
+    assert_ne!(
+        accent_preserving_tokenizer.tokenize("Café"),
+        accent_preserving_tokenizer.tokenize("cafe")
+    );
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sequence_classifier_head_tail_truncation() -> anyhow::Result<()> {
+    //    Resources paths
+    let vocab_resource = Resource::Remote(RemoteResource::from_pretrained(
+        DistilBertVocabResources::DISTIL_BERT_SST2,
+    ));
+    let tokenizer = TokenizerOption::from_file(
+        ModelType::DistilBert,
+        vocab_resource.get_local_path()?.to_str().unwrap(),
+        None,
+        true,
+        None,
+        None,
+    )?;
+    assert_eq!(tokenizer.num_special_tokens(), 2);
+
+    //    Short inputs are left unchanged, long inputs keep their first and last tokens
+    let short_input = "This is a short sentence.";
+    let long_input = "The opening of the movie is great. ".repeat(10) + "The ending is terrible.";
+    let tokenized_input = tokenizer.encode_list_head_tail(&[short_input, &long_input], 8, 5);
+    assert_eq!(tokenized_input[0].num_truncated_tokens, 0);
+    assert_eq!(tokenized_input[0].token_ids.len(), 8);
+    assert_eq!(tokenized_input[1].token_ids.len(), 8 + 5 + 2);
+    assert!(tokenized_input[1].num_truncated_tokens > 0);
+
+    //    The offsets jump over the dropped middle part of the input
+    let offsets = &tokenized_input[1].token_offsets;
+    let last_head_offset = offsets[8].unwrap();
+    let first_tail_offset = offsets[9].unwrap();
+    assert!(first_tail_offset.begin > last_head_offset.end + 1);
+    assert_eq!(
+        &long_input[first_tail_offset.begin as usize..],
+        "The ending is terrible."
+    );
+
+    //    Head and tail must fit in the maximum input length with the special tokens
+    let config = SequenceClassificationConfig {
+        truncation: InputTruncation::HeadTail { head: 64, tail: 63 },
+        ..Default::default()
+    };
+    assert!(SequenceClassificationModel::new(config).is_err());
+
+    //    The conclusion of a long review is kept with head-tail truncation
+    let config = SequenceClassificationConfig {
+        truncation: InputTruncation::HeadTail { head: 8, tail: 16 },
+        ..Default::default()
+    };
+    let sequence_classifier = SequenceClassificationModel::new(config)?;
+    let review = "I watched this film at the cinema last weekend with friends. ".repeat(20)
+        + "It was a boring, awful and terrible waste of time.";
+    let output = sequence_classifier.predict(&[review.as_str()]);
+    assert_eq!(output[0].text, "NEGATIVE");
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sequence_classifier_diagnostics() -> anyhow::Result<()> {
+    //    Set-up classifier
+    let mut sequence_classifier = SequenceClassificationModel::new(Default::default())?;
+    let diagnostics: Arc<Mutex<Vec<Diagnostic>>> = Arc::new(Mutex::new(vec![]));
+    let sink = diagnostics.clone();
+    sequence_classifier.set_diagnostics_sink(Some(Box::new(move |diagnostic| {
+        sink.lock().unwrap().push(diagnostic)
+    })));
+
+    //    Only the long input is reported as truncated
+    let long_input = "This movie is great. ".repeat(50);
+    let input = ["This movie is great.", long_input.as_str()];
+    let _ = sequence_classifier.predict(&input);
+    {
+        let diagnostics = diagnostics.lock().unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].kind, DiagnosticKind::Truncation);
+        assert_eq!(diagnostics[0].input_index, 1);
+        assert_eq!(diagnostics[0].num_tokens, 250 - 126);
+    }
+
+    //    No diagnostics are collected after removing the sink
+    sequence_classifier.set_diagnostics_sink(None);
+    let _ = sequence_classifier.predict(&input);
+    assert_eq!(diagnostics.lock().unwrap().len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sequence_classifier_attention_mask() -> anyhow::Result<()> {
+    //    Set-up classifier
+    let sequence_classifier = SequenceClassificationModel::new(Default::default())?;
+    let input = [
+        "A great movie.",
+        "This film tried to be too many things all at once: stinging political satire, Hollywood blockbuster, sappy romantic comedy, family values promo...",
+    ];
+
+    //    The padding positions of the shorter input are masked
+    let attention_mask = sequence_classifier.get_attention_mask(&input);
+    let (batch_size, sequence_length) = attention_mask.size2()?;
+    assert_eq!(batch_size, 2);
+    let short_input_length = attention_mask.get(0).sum(Kind::Int64).int64_value(&[]);
+    let long_input_length = attention_mask.get(1).sum(Kind::Int64).int64_value(&[]);
+    assert!(short_input_length < sequence_length);
+    assert_eq!(long_input_length, sequence_length);
+    assert_eq!(
+        attention_mask
+            .get(0)
+            .slice(0, 0, short_input_length, 1)
+            .sum(Kind::Int64)
+            .int64_value(&[]),
+        short_input_length
+    );
+
+    //    Masked padding does not affect the prediction for the shorter input
+    let batched_output = sequence_classifier.predict(&input);
+    let single_output = sequence_classifier.predict(&input[..1]);
+    assert_eq!(batched_output[0].id, single_output[0].id);
+    assert!((batched_output[0].score - single_output[0].score).abs() < 1e-4);
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sequence_classifier_custom_head() -> anyhow::Result<()> {
+    //    Set-up classifier with a custom head
+    let head_vs = nn::VarStore::new(Device::cuda_if_available());
+    let head = MeanPoolingHead {
+        classifier: nn::linear(&head_vs.root(), 768, 3, Default::default()),
+    };
+    let label_mapping: HashMap<i64, String> = [
+        (0, "low".to_string()),
+        (1, "medium".to_string()),
+        (2, "high".to_string()),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+    let classifier = SequenceClassificationModel::new_with_head(
+        Default::default(),
+        Box::new(head),
+        label_mapping.clone(),
+    )?;
+
+    //    Get labels
+    let input = [
+        "Short sentence.",
+        "This is a somewhat longer sentence that will require padding of the first one.",
+    ];
+    let output = classifier.predict(&input);
+
+    assert_eq!(output.len(), 2usize);
+    for (sentence_index, label) in output.iter().enumerate() {
+        assert_eq!(label.sentence, sentence_index);
+        assert_eq!(label_mapping[&label.id], label.text);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sequence_classifier_reload_weights() -> anyhow::Result<()> {
+    //    Set-up classifier
+    let mut classifier = SequenceClassificationModel::new(Default::default())?;
+    let input = ["Probably my all-time favorite movie, a story of selflessness, sacrifice and dedication to a noble cause, but it's not preachy or boring."];
+    let output = classifier.predict(&input);
+
+    //    Reload the same weights: predictions are unchanged
+    let weights_resource = Resource::Remote(RemoteResource::from_pretrained(
+        DistilBertModelResources::DISTIL_BERT_SST2,
+    ));
+    classifier.reload_weights(&weights_resource)?;
+    let reloaded_output = classifier.predict(&input);
+
+    assert_eq!(reloaded_output[0].id, output[0].id);
+    assert!((reloaded_output[0].score - output[0].score).abs() < 1e-6);
+
+    //    Weights of a different architecture (question answering head) are rejected
+    let qa_weights_resource = Resource::Remote(RemoteResource::from_pretrained(
+        DistilBertModelResources::DISTIL_BERT_SQUAD,
+    ));
+    assert!(classifier.reload_weights(&qa_weights_resource).is_err());
+    let rejected_output = classifier.predict(&input);
+    assert!((rejected_output[0].score - output[0].score).abs() < 1e-6);
+
+    Ok(())
+}
+
+#[test]
+fn quantized_weights_loading() -> anyhow::Result<()> {
+    //    Set-up a linear layer with 8 input and output features
+    let var_store = nn::VarStore::new(Device::Cpu);
+    let _linear = nn::linear(&var_store.root() / "linear", 8, 8, Default::default());
+
+    //    4-bit weights with a group size of 4: 2 groups of input features, 8 values packed per int32
+    let int_weight = |input: i64, output: i64| (input + output) % 16;
+    let qweight = (0..8)
+        .map(|output| {
+            let packed = (0..8).fold(0i64, |packed, input| {
+                packed | (int_weight(input, output) << (4 * input))
+            });
+            packed as u32 as i32
+        })
+        .collect::<Vec<i32>>();
+    //    Zero-points are stored with an offset of 1 (effective zero-point of 8)
+    let qzeros = [0x7777_7777u32 as i32, 0x7777_7777u32 as i32];
+    let scales = [vec![0.5f32; 8], vec![0.25f32; 8]].concat();
+    let bias = Tensor::of_slice(&[0.1f32; 8]);
+
+    let weights_dir = tempfile::tempdir()?;
+    let weights_path = weights_dir.path().join("rust_model_gptq.ot");
+    Tensor::save_multi(
+        &[
+            ("linear.qweight", &Tensor::of_slice(&qweight).view([1, 8])),
+            ("linear.qzeros", &Tensor::of_slice(&qzeros).view([2, 1])),
+            ("linear.scales", &Tensor::of_slice(&scales).view([2, 8])),
+            ("linear.bias", &bias),
+        ],
+        &weights_path,
+    )?;
+    let weights_resource = Resource::Local(LocalResource {
+        local_path: weights_path,
+    });
+
+    let quantization_config = QuantizationConfig {
+        bits: 4,
+        group_size: 4,
+    };
+    load_quantized_weights(&var_store, &weights_resource, &quantization_config)?;
+
+    let variables = var_store.variables();
+    let weight = &variables["linear.weight"];
+    for output in 0..8 {
+        for input in 0..8 {
+            let scale = if input < 4 { 0.5 } else { 0.25 };
+            let expected = (int_weight(input, output) - 8) as f64 * scale;
+            assert_eq!(weight.double_value(&[output, input]), expected);
+        }
+    }
+    assert!((variables["linear.bias"].double_value(&[0]) - 0.1).abs() < 1e-6);
+
+    //    Group size inconsistent with the checkpoint
+    let invalid_config = QuantizationConfig {
+        bits: 4,
+        group_size: 2,
+    };
+    assert!(load_quantized_weights(&var_store, &weights_resource, &invalid_config).is_err());
+    //    Unsupported bit-width
+    let invalid_config = QuantizationConfig {
+        bits: 3,
+        group_size: 4,
+    };
+    assert!(load_quantized_weights(&var_store, &weights_resource, &invalid_config).is_err());
+
+    Ok(())
+}
+
 #[test]
 fn distilbert_masked_lm() -> anyhow::Result<()> {
     //    Resources paths
@@ -276,3 +700,167 @@ fn distilbert_question_answering() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn distilbert_question_answering_shared_context() -> anyhow::Result<()> {
+    //    Set-up question answering model
+    let qa_model = QuestionAnsweringModel::new(Default::default())?;
+
+    //    Define input
+    let context = "While Amy lives in Amsterdam, Eric is in The Hague.";
+    let questions = ["Where does Amy live ?", "Where does Eric live ?"];
+
+    let answers = qa_model.answer_batch_shared_context(context, &questions, 1, 32);
+    let expected_answers = qa_model.predict(
+        &questions
+            .iter()
+            .map(|question| QaInput {
+                question: question.to_string(),
+                context: context.to_string(),
+            })
+            .collect::<Vec<QaInput>>(),
+        1,
+        32,
+    );
+
+    assert_eq!(answers.len(), 2usize);
+    assert_eq!(answers[0][0].answer, "Amsterdam");
+    assert_eq!(answers[1][0].answer, "The Hague");
+    for (answer, expected_answer) in answers.iter().zip(expected_answers.iter()) {
+        assert_eq!(answer[0], expected_answer[0]);
+        assert!((answer[0].score - expected_answer[0].score).abs() < 1e-6);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_question_answering_windows() -> anyhow::Result<()> {
+    //    Set-up tokenizer
+    let vocab_resource = Resource::Remote(RemoteResource::from_pretrained(
+        DistilBertVocabResources::DISTIL_BERT_SQUAD,
+    ));
+    let vocab_path = vocab_resource.get_local_path()?;
+    let tokenizer: BertTokenizer =
+        BertTokenizer::from_file(vocab_path.to_str().unwrap(), false, false)?;
+
+    //    Define input
+    let context = "Electroencephalography records electrical activity. \
+    Magnetoencephalography measures magnetic fields. \
+    Polysomnography monitors sleep disorders overnight. \
+    Electrocardiography captures heart rhythms. \
+    Spectrophotometry quantifies light absorption precisely.";
+    let tokens = rust_tokenizers::tokenizer::Tokenizer::tokenize_with_offsets(&tokenizer, context);
+    let encoded_context = TokenIdsWithOffsets {
+        ids: rust_tokenizers::tokenizer::Tokenizer::convert_tokens_to_ids(
+            &tokenizer,
+            &tokens.tokens,
+        ),
+        offsets: tokens.offsets,
+        reference_offsets: tokens.reference_offsets,
+        masks: tokens.masks,
+    };
+    let length = encoded_context.ids.len();
+
+    for boundary in &[WindowBoundary::Word, WindowBoundary::Sentence] {
+        let windows = Windower::new(24, 8, *boundary).windows(&encoded_context, context);
+
+        //    Windows cover the full context and overlap by at most the stride
+        assert!(windows.len() > 1);
+        assert_eq!(windows[0].start, 0);
+        assert_eq!(windows.last().unwrap().end, length);
+        for (previous_window, window) in windows.iter().zip(windows.iter().skip(1)) {
+            assert!(window.start > previous_window.start);
+            assert!(window.start <= previous_window.end);
+            assert!(previous_window.end - window.start <= 8);
+        }
+
+        //    No window starts or ends within a word
+        for window in windows.iter() {
+            assert!(window.len() <= 24);
+            assert_ne!(encoded_context.masks[window.start], Mask::Continuation);
+            if window.end < length {
+                assert_ne!(encoded_context.masks[window.end], Mask::Continuation);
+            }
+        }
+
+        //    Sentence boundaries are preferred when available
+        if *boundary == WindowBoundary::Sentence {
+            for window in windows.iter() {
+                assert_eq!(tokens.tokens[window.end - 1], ".");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sentence_importance() -> anyhow::Result<()> {
+    //    Set-up sentence importance model
+    let sentence_importance_model = SentenceImportanceModel::new(Default::default())?;
+
+    //    Define input
+    let input = [
+        "Dr. Smith paid $3.50 for the book. He left... Was it worth it?",
+        "A single sentence without final punctuation",
+    ];
+
+    let output = sentence_importance_model.predict(&input)?;
+
+    assert_eq!(output.len(), 2usize);
+    assert_eq!(output[0].len(), 3usize);
+    assert_eq!(output[0][0].text, "Dr. Smith paid $3.50 for the book.");
+    assert_eq!(output[0][2].text, "Was it worth it?");
+    assert_eq!(output[1].len(), 1usize);
+    for sentences in output.iter() {
+        let total_score: f64 = sentences.iter().map(|sentence| sentence.score).sum();
+        assert!((total_score - 1.0).abs() < 1e-6);
+    }
+    assert!((output[1][0].score - 1.0).abs() < 1e-6);
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_question_answering_empty_inputs() -> anyhow::Result<()> {
+    //    Set-up question answering model
+    let qa_model = QuestionAnsweringModel::new(Default::default())?;
+
+    //    Define input
+    let qa_inputs = [
+        QaInput {
+            question: String::from("Where does Amy live ?"),
+            context: String::from(""),
+        },
+        QaInput {
+            question: String::from("   "),
+            context: String::from("Amy lives in Amsterdam"),
+        },
+    ];
+    let answers = qa_model.predict(&qa_inputs, 1, 32);
+
+    assert_eq!(answers.len(), 2);
+    assert!(answers[0].is_empty());
+    assert!(answers[1].is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn distilbert_sentence_importance_empty_inputs() -> anyhow::Result<()> {
+    //    Set-up model
+    let model = SentenceImportanceModel::new(Default::default())?;
+
+    let output = model.predict(&["", "   "])?;
+
+    assert_eq!(output.len(), 2);
+    assert!(output[0].is_empty());
+    assert!(output[1].is_empty());
+
+    //    An empty batch returns no prediction
+    let empty_input: [&str; 0] = [];
+    assert!(model.predict(empty_input)?.is_empty());
+
+    Ok(())
+}
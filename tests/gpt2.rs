@@ -1,17 +1,26 @@
 use rust_bert::gpt2::{
-    GPT2LMHeadModel, Gpt2Config, Gpt2ConfigResources, Gpt2MergesResources, Gpt2ModelResources,
-    Gpt2VocabResources,
+    GPT2Generator, GPT2LMHeadModel, Gpt2Config, Gpt2ConfigResources, Gpt2MergesResources,
+    Gpt2ModelResources, Gpt2VocabResources,
+};
+use rust_bert::pipelines::common::{
+    build_padding_attention_mask, DetokenizationProfile, ModelType, PaddingSide, TokenizerOption,
 };
-use rust_bert::pipelines::common::ModelType;
 use rust_bert::pipelines::conversation::{
     ConversationConfig, ConversationManager, ConversationModel,
 };
-use rust_bert::pipelines::generation_utils::{Cache, LMHeadModel};
+use rust_bert::pipelines::generation_utils::{
+    Cache, GenerateConfig, GenerationSnapshot, LMHeadModel, LanguageGenerator, LengthBias,
+    RepetitionPenaltyMode, WarperKind,
+};
 use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
-use rust_bert::resources::{RemoteResource, Resource};
+use rust_bert::quantization::{load_quantized_weights, QuantizationConfig};
+use rust_bert::resources::{LocalResource, RemoteResource, Resource};
 use rust_bert::Config;
 use rust_tokenizers::tokenizer::{Gpt2Tokenizer, Tokenizer, TruncationStrategy};
-use tch::{nn, Device, Tensor};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use tch::{nn, Device, Kind, Tensor};
 
 #[test]
 fn gpt2_lm_model() -> anyhow::Result<()> {
@@ -110,6 +119,102 @@ fn gpt2_lm_model() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Quantizes a `GPTConv1D` weight of shape (*in_features*, *out_features*) to 8-bit GPTQ tensors with a single group,
+/// returning the packed weights, packed zero-points and scales
+fn quantize_conv1d_weight(weight: &Tensor) -> (Tensor, Tensor, Tensor) {
+    let size = weight.size();
+    let (in_features, out_features) = (size[0], size[1]);
+    let values = Vec::<Vec<f32>>::from(weight.to_kind(Kind::Float));
+    let scales = (0..out_features as usize)
+        .map(|output| {
+            let max_value = values
+                .iter()
+                .map(|row| row[output].abs())
+                .fold(0f32, f32::max);
+            (max_value / 127.0).max(1e-8)
+        })
+        .collect::<Vec<f32>>();
+    //    Zero-points are stored with an offset of 1 (effective zero-point of 128)
+    let mut qweight = vec![0u32; (in_features / 4 * out_features) as usize];
+    for (input, row) in values.iter().enumerate() {
+        for (output, value) in row.iter().enumerate() {
+            let quantized = ((value / scales[output]).round() as i64 + 128).clamp(0, 255) as u32;
+            qweight[input / 4 * out_features as usize + output] |= quantized << (8 * (input % 4));
+        }
+    }
+    let qweight = qweight
+        .into_iter()
+        .map(|packed| packed as i32)
+        .collect::<Vec<i32>>();
+    let qzeros = vec![0x7f7f_7f7fu32 as i32; (out_features / 4) as usize];
+    (
+        Tensor::of_slice(&qweight).view([in_features / 4, out_features]),
+        Tensor::of_slice(&qzeros).view([1, out_features / 4]),
+        Tensor::of_slice(&scales).view([1, out_features]),
+    )
+}
+
+#[test]
+fn gpt2_quantized_weights_round_trip() -> anyhow::Result<()> {
+    //    Set-up a small GPT2 model
+    let config: Gpt2Config = serde_json::from_str(
+        r#"{"initializer_range": 0.02, "layer_norm_epsilon": 1e-5, "n_ctx": 8, "n_embd": 8, "n_head": 2,
+            "n_layer": 1, "n_positions": 8, "vocab_size": 16}"#,
+    )?;
+    let var_store = nn::VarStore::new(Device::Cpu);
+    let _model = GPT2LMHeadModel::new(&var_store.root(), &config);
+
+    //    Quantize the GPTConv1D layers (c_attn is not square, c_proj and c_fc are stored as (in, out))
+    let mut checkpoint = vec![];
+    let mut num_quantized_layers = 0;
+    for (name, variable) in var_store.variables() {
+        let layer_name = name.strip_suffix(".weight").unwrap_or(&name);
+        if ["c_attn", "c_proj", "c_fc"]
+            .iter()
+            .any(|module_name| layer_name.ends_with(module_name))
+        {
+            let (qweight, qzeros, scales) = quantize_conv1d_weight(&variable);
+            checkpoint.push((format!("{}.qweight", layer_name), qweight));
+            checkpoint.push((format!("{}.qzeros", layer_name), qzeros));
+            checkpoint.push((format!("{}.scales", layer_name), scales));
+            num_quantized_layers += 1;
+        } else {
+            checkpoint.push((name.clone(), variable.copy()));
+        }
+    }
+    assert_eq!(num_quantized_layers, 4);
+    let weights_dir = tempfile::tempdir()?;
+    let weights_path = weights_dir.path().join("rust_model_gptq.ot");
+    Tensor::save_multi(&checkpoint, &weights_path)?;
+    let weights_resource = Resource::Local(LocalResource {
+        local_path: weights_path,
+    });
+
+    //    Load the quantized checkpoint in a new model
+    let quantized_var_store = nn::VarStore::new(Device::Cpu);
+    let _quantized_model = GPT2LMHeadModel::new(&quantized_var_store.root(), &config);
+    let quantization_config = QuantizationConfig {
+        bits: 8,
+        group_size: -1,
+    };
+    load_quantized_weights(
+        &quantized_var_store,
+        &weights_resource,
+        &quantization_config,
+    )?;
+
+    //    The dequantized weights are within half a quantization step of the original weights
+    let original_variables = var_store.variables();
+    for (name, variable) in quantized_var_store.variables() {
+        let original = &original_variables[&name];
+        let tolerance = original.abs().max().double_value(&[]) / 254.0 + 1e-6;
+        let error = (&variable - original).abs().max().double_value(&[]);
+        assert!(error <= tolerance, "{}: {} > {}", name, error, tolerance);
+    }
+
+    Ok(())
+}
+
 #[test]
 fn gpt2_generation_greedy() -> anyhow::Result<()> {
     //    Resources definition
@@ -139,7 +244,7 @@ fn gpt2_generation_greedy() -> anyhow::Result<()> {
     let model = TextGenerationModel::new(generate_config)?;
 
     let input_context = "The cat";
-    let output = model.generate(&[input_context], None);
+    let output = model.generate_flat(&[input_context], None);
 
     assert_eq!(output.len(), 1);
     assert_eq!(output[0], "The cat was found in a field near the town of Keflavik, about 30 miles (48 kilometers) south-east of Moscow.\n\n\n");
@@ -177,7 +282,7 @@ fn gpt2_generation_beam_search() -> anyhow::Result<()> {
     let model = TextGenerationModel::new(generate_config)?;
 
     let input_context = "The dog";
-    let output = model.generate(&[input_context], None);
+    let output = model.generate_flat(&[input_context], None);
 
     assert_eq!(output.len(), 3);
     assert_eq!(
@@ -227,7 +332,7 @@ fn gpt2_generation_beam_search_multiple_prompts_without_padding() -> anyhow::Res
 
     let input_context_1 = "The dog";
     let input_context_2 = "The cat";
-    let output = model.generate(&[input_context_1, input_context_2], None);
+    let output = model.generate_flat(&[input_context_1, input_context_2], None);
 
     assert_eq!(output.len(), 6);
     assert_eq!(
@@ -288,7 +393,7 @@ fn gpt2_generation_beam_search_multiple_prompts_with_padding() -> anyhow::Result
 
     let input_context_1 = "The dog";
     let input_context_2 = "The cat was";
-    let output = model.generate(&[input_context_1, input_context_2], None);
+    let output = model.generate_flat(&[input_context_1, input_context_2], None);
 
     assert_eq!(output.len(), 6);
     assert_eq!(
@@ -320,7 +425,149 @@ fn gpt2_generation_beam_search_multiple_prompts_with_padding() -> anyhow::Result
 }
 
 #[test]
-fn gpt2_diverse_beam_search_multiple_prompts_with_padding() -> anyhow::Result<()> {
+fn gpt2_generation_additive_repetition_penalty() -> anyhow::Result<()> {
+    //    Set-up generator with a large additive penalty, preventing any token from being repeated
+    let generate_config = GenerateConfig {
+        max_length: 20,
+        do_sample: false,
+        num_beams: 1,
+        repetition_penalty: 100.0,
+        repetition_penalty_mode: RepetitionPenaltyMode::Additive,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+
+    let input_context = "The dog";
+    let output = model.generate_indices(Some(&[input_context]), None, None, None, None);
+
+    assert_eq!(output.len(), 1);
+    let mut sorted_indices = output[0].clone();
+    sorted_indices.sort_unstable();
+    sorted_indices.dedup();
+    assert_eq!(sorted_indices.len(), output[0].len());
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_detokenization_profiles() -> anyhow::Result<()> {
+    //    Set-up model
+    let model = TextGenerationModel::new(Default::default())?;
+    let tokenizer = model.get_tokenizer();
+
+    let code = "def get(x , y):\n    return x . y";
+    let tokens = tokenizer.tokenize(code);
+    let token_ids = tokenizer.convert_tokens_to_ids(&tokens);
+
+    //    Natural language clean-up removes the spaces before punctuation
+    let natural =
+        tokenizer.decode_with_profile(token_ids.clone(), true, DetokenizationProfile::Natural);
+    assert_eq!(natural, "def get(x, y):\n    return x. y");
+    //    Code output preserves the literal output of the tokenizer
+    let code_output =
+        tokenizer.decode_with_profile(token_ids.clone(), true, DetokenizationProfile::Code);
+    assert_eq!(code_output, code);
+    //    Raw output concatenates the vocabulary entries, keeping the byte-level space markers
+    let raw = tokenizer.decode_with_profile(token_ids, true, DetokenizationProfile::Raw);
+    assert_eq!(raw, tokens.concat());
+    assert!(raw.contains('Ġ'));
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_token_offsets() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = GenerateConfig {
+        max_length: 20,
+        do_sample: false,
+        num_beams: 1,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+
+    let output = model.generate_with_offsets(Some(&["The dog"]), None, None, None, None);
+    let indices = model.generate_indices(Some(&["The dog"]), None, None, None, None);
+
+    assert_eq!(output.len(), 1);
+    let (text, offsets) = &output[0];
+    assert_eq!(offsets.len(), indices[0].len());
+    assert_eq!(&text[offsets[0].0..offsets[0].1], "The");
+    assert_eq!(&text[offsets[1].0..offsets[1].1], " dog");
+    //    Offsets are contiguous and cover the full text
+    let mut position = 0;
+    for (start, end) in offsets {
+        assert_eq!(*start, position);
+        position = *end;
+    }
+    assert_eq!(position, text.len());
+
+    //    Multi-byte characters spanning several tokens are assigned to the completing token
+    let text_generation_model = TextGenerationModel::new(Default::default())?;
+    let tokenizer = text_generation_model.get_tokenizer();
+    let token_ids = tokenizer.convert_tokens_to_ids(&tokenizer.tokenize("I ❤ tea"));
+    let (text, offsets) =
+        tokenizer.decode_with_offsets(&token_ids, true, DetokenizationProfile::Code);
+    assert_eq!(text, "I ❤ tea");
+    let tokens = offsets
+        .iter()
+        .map(|(start, end)| &text[*start..*end])
+        .collect::<Vec<&str>>();
+    assert!(tokens.contains(&" ❤") || tokens.contains(&"❤"));
+    assert_eq!(tokens.concat(), text);
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_special_tokens() -> anyhow::Result<()> {
+    //    Set-up model
+    let model = TextGenerationModel::new(Default::default())?;
+    let tokenizer = model.get_tokenizer();
+
+    let special_tokens = tokenizer.special_tokens();
+    assert_eq!(special_tokens.bos, Some(50256));
+    assert_eq!(special_tokens.eos, Some(50256));
+    assert_eq!(special_tokens.unk, Some(50256));
+    assert_eq!(special_tokens.pad, None);
+    assert_eq!(special_tokens.mask, None);
+    assert!(special_tokens.additional.is_empty());
+    assert_eq!(tokenizer.vocab_size(), 50257);
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_active_mask() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = GenerateConfig {
+        max_length: 20,
+        do_sample: false,
+        num_beams: 1,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+
+    //    Inactive inputs are returned unchanged, active inputs are generated
+    let input_contexts = ["Awaiting user input", "The cat was"];
+    let output =
+        model.generate_with_active_mask(&input_contexts, &[false, true], None, None, None, None)?;
+    let expected_output = model.generate_flat(Some(&input_contexts[1..]), None, None, None, None);
+
+    assert_eq!(output.len(), 2);
+    assert_eq!(output[0], input_contexts[0]);
+    assert_eq!(output[1], expected_output[0]);
+
+    //    The active mask must match the number of inputs
+    assert!(model
+        .generate_with_active_mask(&input_contexts, &[true], None, None, None, None)
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_grouped_by_input() -> anyhow::Result<()> {
     //    Resources definition
     let config_resource =
         Resource::Remote(RemoteResource::from_pretrained(Gpt2ConfigResources::GPT2));
@@ -331,190 +578,674 @@ fn gpt2_diverse_beam_search_multiple_prompts_with_padding() -> anyhow::Result<()
     let model_resource =
         Resource::Remote(RemoteResource::from_pretrained(Gpt2ModelResources::GPT2));
 
-    //    Set-up masked LM model
+    //    Set-up model
     let generate_config = TextGenerationConfig {
         model_type: ModelType::GPT2,
         model_resource,
         config_resource,
         vocab_resource,
         merges_resource,
-        min_length: 10,
         max_length: 20,
         do_sample: false,
-        num_beams: 6,
-        num_beam_groups: Some(3),
-        diversity_penalty: Some(5.5),
+        num_beams: 5,
         num_return_sequences: 3,
         ..Default::default()
     };
     let model = TextGenerationModel::new(generate_config)?;
 
-    let input_context_1 = "It was a nice and";
-    let input_context_2 = "Language models can generate";
+    let input_context_1 = "The dog";
+    let input_context_2 = "Once upon a time, a cat";
     let output = model.generate(&[input_context_1, input_context_2], None);
 
-    assert_eq!(output.len(), 6);
-    assert_eq!(
-        output[0],
-        "It was a nice and peaceful evening for me,\" he said.\n\n\"It was nice to"
-    );
-    assert_eq!(
-        output[1],
-        "It was a nice and warm day, and I'm glad I did.\n\n\"I'm"
-    );
-    assert_eq!(
-        output[2],
-        "It was a nice and warm day, and I'm glad I did.\n\n\"I was"
-    );
-    assert_eq!(
-        output[3],
-        "Language models can generate more complex models, but they are not the only way to do so."
-    );
-    assert_eq!(
-        output[4],
-        "Language models can generate more complex models, but they are not the only way to do this."
-    );
+    //    One group per input, in the order of the inputs, with texts sorted by decreasing score
+    assert_eq!(output.len(), 2);
+    for (input_context, input_output) in
+        [input_context_1, input_context_2].iter().zip(output.iter())
+    {
+        assert_eq!(input_output.len(), 3);
+        for generated_text in input_output.iter() {
+            assert!(generated_text.text.starts_with(input_context));
+        }
+        for (text_1, text_2) in input_output.iter().zip(input_output.iter().skip(1)) {
+            assert!(text_1.score >= text_2.score);
+        }
+    }
+
+    //    The flat output follows the same layout
+    let flat_output = model.generate_flat(&[input_context_1, input_context_2], None);
     assert_eq!(
-        output[5],
-        "Language models can generate a lot of data, but they're not the only way to do it"
+        flat_output,
+        output
+            .into_iter()
+            .flatten()
+            .map(|generated_text| generated_text.text)
+            .collect::<Vec<String>>()
     );
 
     Ok(())
 }
 
 #[test]
-#[cfg_attr(not(feature = "all-tests"), ignore)]
-fn dialogpt_single_multi_turn_conversation() -> anyhow::Result<()> {
-    //    Set-up conversation model
-    let conversation_config = ConversationConfig {
-        do_sample: false,
-        device: Device::Cpu,
-        ..Default::default()
-    };
-    let conversation_model = ConversationModel::new(conversation_config)?;
-
-    // Set-up conversation manager and add a conversation
-    let mut conversation_manager = ConversationManager::new();
-    let conversation_id =
-        conversation_manager.create("Going to the movies tonight - any suggestions?");
+fn gpt2_generation_token_healing() -> anyhow::Result<()> {
+    //    Resources definition
+    let config_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2ConfigResources::GPT2));
+    let vocab_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2VocabResources::GPT2));
+    let merges_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2MergesResources::GPT2));
+    let model_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2ModelResources::GPT2));
 
-    // Turn 1
-    let output = conversation_model.generate_responses(&mut conversation_manager);
-    assert_eq!(output.len(), 1);
-    assert_eq!(output.get(&conversation_id).unwrap(), &"The Big Lebowski");
+    //    The prompt ends mid-word: the healed prompt token is completed by the first generated token
+    let input_context = "The quick brown fox jum";
+    for num_beams in &[1, 3] {
+        let generate_config = TextGenerationConfig {
+            model_type: ModelType::GPT2,
+            model_resource: model_resource.clone(),
+            config_resource: config_resource.clone(),
+            vocab_resource: vocab_resource.clone(),
+            merges_resource: merges_resource.clone(),
+            max_length: 20,
+            do_sample: false,
+            num_beams: *num_beams,
+            token_healing: true,
+            ..Default::default()
+        };
+        let model = TextGenerationModel::new(generate_config)?;
+        let output = model.generate_flat(&[input_context], None);
 
-    // Turn 2
-    let _ = conversation_manager
-        .get(&conversation_id)
-        .unwrap()
-        .add_user_input("Is it an action movie?");
-    let output = conversation_model.generate_responses(&mut conversation_manager);
-    assert_eq!(output.len(), 1);
-    assert_eq!(output.get(&conversation_id).unwrap(), &"It\'s a comedy.");
+        assert_eq!(output.len(), 1);
+        assert!(output[0].starts_with(input_context));
+        assert!(output[0].len() > input_context.len());
 
-    // Turn 3 (no new user input)
-    let output = conversation_model.generate_responses(&mut conversation_manager);
-    assert_eq!(output.len(), 0);
+        //    Single-token prompts of a batch are not healed and keep their token
+        let output = model.generate_flat([input_context, "Dogs"], None);
+        assert_eq!(output.len(), 2);
+        assert!(output[0].starts_with(input_context));
+        assert!(output[1].starts_with("Dogs"));
+    }
 
     Ok(())
 }
 
 #[test]
-#[cfg_attr(not(feature = "all-tests"), ignore)]
-fn dialogpt_multiple_multi_turn_conversation() -> anyhow::Result<()> {
-    //    Set-up conversation model
-    let conversation_config = ConversationConfig {
-        do_sample: false,
-        device: Device::Cpu,
-        ..Default::default()
-    };
-    let conversation_model = ConversationModel::new(conversation_config)?;
+fn gpt2_generation_end_suffix() -> anyhow::Result<()> {
+    //    Resources definition
+    let vocab_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2VocabResources::GPT2));
+    let merges_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2MergesResources::GPT2));
+    let tokenizer = Gpt2Tokenizer::from_file(
+        vocab_resource.get_local_path()?.to_str().unwrap(),
+        merges_resource.get_local_path()?.to_str().unwrap(),
+        false,
+    )?;
+    let suffix = " The end.";
+    let end_suffix_ids = tokenizer.convert_tokens_to_ids(&tokenizer.tokenize(suffix));
 
-    // Set-up conversation manager and add a conversation
-    let mut conversation_manager = ConversationManager::new();
-    let conversation_1_id =
-        conversation_manager.create("Going to the movies tonight - any suggestions?");
-    let conversation_2_id = conversation_manager.create("What's the last book you have read?");
+    //    Generated sequences end with the suffix, for greedy and beam search decoding
+    let input_context = "The dog";
+    for num_beams in &[1, 3] {
+        let generate_config = TextGenerationConfig {
+            model_type: ModelType::GPT2,
+            vocab_resource: vocab_resource.clone(),
+            merges_resource: merges_resource.clone(),
+            max_length: 20,
+            do_sample: false,
+            num_beams: *num_beams,
+            end_suffix_ids: Some(end_suffix_ids.clone()),
+            ..Default::default()
+        };
+        let model = TextGenerationModel::new(generate_config)?;
+        let output = model.generate_flat(&[input_context], None);
 
-    // Turn 1
-    let output = conversation_model.generate_responses(&mut conversation_manager);
-    assert_eq!(output.len(), 2);
-    assert_eq!(output.get(&conversation_1_id).unwrap(), &"The Big Lebowski");
-    assert_eq!(
-        output.get(&conversation_2_id).unwrap(),
-        &"The Last Question"
-    );
+        assert_eq!(output.len(), 1);
+        assert!(output[0].starts_with(input_context));
+        assert!(output[0].ends_with(suffix));
+    }
 
-    // Turn 2
-    let _ = conversation_manager
-        .get(&conversation_1_id)
-        .unwrap()
-        .add_user_input("Is it an action movie?");
-    let output = conversation_model.generate_responses(&mut conversation_manager);
-    assert_eq!(output.len(), 1);
-    assert_eq!(output.get(&conversation_1_id).unwrap(), &"It\'s a comedy.");
+    Ok(())
+}
 
-    // Turn 3 (no new user input)
-    let output = conversation_model.generate_responses(&mut conversation_manager);
-    assert_eq!(output.len(), 0);
+#[test]
+fn gpt2_generation_frequency_presence_penalties() -> anyhow::Result<()> {
+    let input_context = "The dog";
+    let prompt_length = 2;
+
+    //    Large frequency or presence penalties prevent any generated token from being repeated
+    for (frequency_penalty, presence_penalty) in &[(100.0, 0.0), (0.0, 100.0)] {
+        let generate_config = GenerateConfig {
+            max_length: 20,
+            do_sample: false,
+            num_beams: 1,
+            no_repeat_ngram_size: 0,
+            frequency_penalty: *frequency_penalty,
+            presence_penalty: *presence_penalty,
+            ..Default::default()
+        };
+        let model = GPT2Generator::new(generate_config)?;
+        let output = model.generate_indices(Some(&[input_context]), None, None, None, None);
+
+        assert_eq!(output.len(), 1);
+        let generated_indices = &output[0][prompt_length..];
+        let mut sorted_indices = generated_indices.to_vec();
+        sorted_indices.sort_unstable();
+        sorted_indices.dedup();
+        assert_eq!(sorted_indices.len(), generated_indices.len());
+    }
 
     Ok(())
 }
 
 #[test]
-#[cfg_attr(not(feature = "all-tests"), ignore)]
-fn dialogpt_multiple_multi_turn_conversation_with_truncation() -> anyhow::Result<()> {
-    //    Set-up conversation model
-    let conversation_config = ConversationConfig {
-        max_length: 36,
-        min_length_for_response: 24,
+fn gpt2_generation_warper_order() -> anyhow::Result<()> {
+    //    Set-up a reference greedy generator without repetition penalty
+    let generate_config = GenerateConfig {
+        max_length: 20,
         do_sample: false,
-        device: Device::Cpu,
+        num_beams: 1,
         ..Default::default()
     };
-    let conversation_model = ConversationModel::new(conversation_config)?;
+    let greedy_model = GPT2Generator::new(generate_config)?;
 
-    // Set-up conversation manager and add a conversation
-    let mut conversation_manager = ConversationManager::new();
-    let conversation_1_id =
-        conversation_manager.create("Going to the movies tonight - any suggestions?");
-    let conversation_2_id = conversation_manager.create("Hello how are you?");
-
-    // Turn 1
-    let output = conversation_model.generate_responses(&mut conversation_manager);
-    assert_eq!(output.len(), 2);
-    assert_eq!(output.get(&conversation_1_id).unwrap(), &"The Big Lebowski");
-    assert_eq!(
-        output.get(&conversation_2_id).unwrap(),
-        &"I'm good, how are you?"
-    );
+    //    Sampling with top-k = 1 is deterministic. When top-k filtering runs before the penalty, only the most
+    //    likely token remains and the penalty has no effect on the output
+    let generate_config = GenerateConfig {
+        max_length: 20,
+        do_sample: true,
+        num_beams: 1,
+        top_k: 1,
+        repetition_penalty: 100.0,
+        repetition_penalty_mode: RepetitionPenaltyMode::Additive,
+        warper_order: vec![
+            WarperKind::TopK,
+            WarperKind::RepetitionPenalty,
+            WarperKind::Temperature,
+            WarperKind::TopP,
+        ],
+        ..Default::default()
+    };
+    let penalty_last_model = GPT2Generator::new(generate_config)?;
 
-    // Turn 2
-    let _ = conversation_manager
-        .get(&conversation_1_id)
-        .unwrap()
-        .add_user_input("Is it an action movie?");
-    let _ = conversation_manager
-        .get(&conversation_2_id)
-        .unwrap()
-        .add_user_input("Fine.");
+    let input_context = "The dog";
+    let greedy_output =
+        greedy_model.generate_indices(Some(&[input_context]), None, None, None, None);
+    let penalty_last_output =
+        penalty_last_model.generate_indices(Some(&[input_context]), None, None, None, None);
 
-    let output = conversation_model.generate_responses(&mut conversation_manager);
-    assert_eq!(output.len(), 2);
-    assert_eq!(output.get(&conversation_1_id).unwrap(), &"It\'s a comedy.");
+    assert_eq!(penalty_last_output, greedy_output);
 
-    // Turn 3 (no new user input)
-    let output = conversation_model.generate_responses(&mut conversation_manager);
-    assert_eq!(output.len(), 0);
+    //    Warpers configured but omitted from the order are reported
+    let generate_config = GenerateConfig {
+        top_k: 1,
+        warper_order: vec![WarperKind::RepetitionPenalty, WarperKind::TopP],
+        ..Default::default()
+    };
+    let warnings = generate_config.warnings();
+    assert_eq!(warnings.len(), 1);
+    assert!(warnings[0].starts_with("TopK"));
+    assert!(GenerateConfig::default().warnings().is_empty());
 
     Ok(())
 }
 
 #[test]
-#[cfg_attr(not(feature = "all-tests"), ignore)]
-fn dialogpt_multiple_multi_turn_conversation_with_conversation_deletion() -> anyhow::Result<()> {
-    //    Set-up conversation model
-    let conversation_config = ConversationConfig {
+fn gpt2_tokenizer_strip_accents_override() -> anyhow::Result<()> {
+    //    Resources paths
+    let vocab_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2VocabResources::GPT2));
+    let merges_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2MergesResources::GPT2));
+    let vocab_path = vocab_resource.get_local_path()?;
+    let merges_path = merges_resource.get_local_path()?;
+    let tokenizer = |strip_accents: Option<bool>| {
+        TokenizerOption::from_file(
+            ModelType::GPT2,
+            vocab_path.to_str().unwrap(),
+            Some(merges_path.to_str().unwrap()),
+            false,
+            strip_accents,
+            None,
+        )
+    };
+
+    //    Byte-level BPE tokenizers do not strip accents: setting the override is rejected
+    assert!(tokenizer(None).is_ok());
+    assert!(tokenizer(Some(true)).is_err());
+    assert!(tokenizer(Some(false)).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_tokenizer_inverse_vocab_lookup() -> anyhow::Result<()> {
+    let model = TextGenerationModel::new(Default::default())?;
+    let tokenizer = model.get_tokenizer();
+
+    let tokens = tokenizer.tokenize("The dog barked");
+    let ids = tokenizer.convert_tokens_to_ids(&tokens);
+
+    assert_eq!(tokenizer.ids_to_tokens(&ids), tokens);
+    assert_eq!(tokenizer.id_to_token(ids[1]), Some("Ġdog".to_string()));
+    assert_eq!(
+        tokenizer.id_to_token(50256),
+        Some("<|endoftext|>".to_string())
+    );
+    assert_eq!(tokenizer.id_to_token(-1), None);
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_usage() -> anyhow::Result<()> {
+    //    Set-up generator, returning 2 sequences for each prompt
+    let generate_config = GenerateConfig {
+        max_length: 20,
+        do_sample: false,
+        num_beams: 3,
+        num_return_sequences: 2,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+
+    let input_context_1 = "The dog";
+    let input_context_2 = "The cat was";
+    let output = model.generate_indices_with_output(
+        Some(&[input_context_1, input_context_2]),
+        None,
+        None,
+        None,
+        None,
+    );
+
+    assert_eq!(output.usage.len(), 4);
+    assert_eq!(output.usage[0].prompt_tokens, 2);
+    assert_eq!(output.usage[1].prompt_tokens, 2);
+    assert_eq!(output.usage[2].prompt_tokens, 3);
+    assert_eq!(output.usage[3].prompt_tokens, 3);
+    for (usage, indices) in output.usage.iter().zip(output.indices.iter()) {
+        assert!(usage.completion_tokens > 0);
+        assert!(usage.completion_tokens <= indices.len() as i64 - 3);
+        assert_eq!(
+            usage.total_tokens,
+            usage.prompt_tokens + usage.completion_tokens
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_top_k_alternatives() -> anyhow::Result<()> {
+    //    Set-up greedy and beam search generators reporting the 3 most likely tokens at each step
+    let generate_config = GenerateConfig {
+        max_length: 20,
+        do_sample: false,
+        num_beams: 1,
+        output_top_k_alternatives: Some(3),
+        ..Default::default()
+    };
+    let greedy_model = GPT2Generator::new(generate_config)?;
+    let generate_config = GenerateConfig {
+        max_length: 20,
+        do_sample: false,
+        num_beams: 3,
+        output_top_k_alternatives: Some(3),
+        ..Default::default()
+    };
+    let beam_search_model = GPT2Generator::new(generate_config)?;
+
+    let input_context = "The dog";
+    let prompt_length = 2;
+
+    //    Greedy decoding picks the most likely alternative at each step
+    let output =
+        greedy_model.generate_indices_with_output(Some(&[input_context]), None, None, None, None);
+    let top_k_alternatives = output.top_k_alternatives.unwrap();
+    assert_eq!(top_k_alternatives.len(), 1);
+    assert_eq!(
+        top_k_alternatives[0].len() as i64,
+        output.usage[0].completion_tokens
+    );
+    let vocab_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2VocabResources::GPT2));
+    let merges_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2MergesResources::GPT2));
+    let tokenizer = Gpt2Tokenizer::from_file(
+        vocab_resource.get_local_path()?.to_str().unwrap(),
+        merges_resource.get_local_path()?.to_str().unwrap(),
+        false,
+    )?;
+    for (position, alternatives) in top_k_alternatives[0].iter().enumerate() {
+        assert_eq!(alternatives.len(), 3);
+        assert_eq!(
+            alternatives[0].0,
+            tokenizer.decode(
+                vec![output.indices[0][prompt_length + position]],
+                false,
+                false
+            )
+        );
+        assert!(alternatives[0].1 >= alternatives[1].1);
+        assert!(alternatives[1].1 >= alternatives[2].1);
+    }
+
+    //    Beam search reports the alternatives along the path of the returned beam
+    let output = beam_search_model.generate_indices_with_output(
+        Some(&[input_context]),
+        None,
+        None,
+        None,
+        None,
+    );
+    let top_k_alternatives = output.top_k_alternatives.unwrap();
+    assert_eq!(top_k_alternatives.len(), 1);
+    assert_eq!(
+        top_k_alternatives[0].len() as i64,
+        output.usage[0].completion_tokens
+    );
+    for alternatives in top_k_alternatives[0].iter() {
+        assert_eq!(alternatives.len(), 3);
+        assert!(alternatives
+            .iter()
+            .all(|(_, probability)| *probability <= 1.0));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_beam_search_multiple_prompts_microbatched() -> anyhow::Result<()> {
+    //    Resources definition
+    let config_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2ConfigResources::GPT2));
+    let vocab_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2VocabResources::GPT2));
+    let merges_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2MergesResources::GPT2));
+    let model_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2ModelResources::GPT2));
+
+    //    Set-up masked LM model, decoding the beams of a single prompt at a time
+    let generate_config = TextGenerationConfig {
+        model_type: ModelType::GPT2,
+        model_resource,
+        config_resource,
+        vocab_resource,
+        merges_resource,
+        max_length: 20,
+        do_sample: false,
+        num_beams: 5,
+        temperature: 1.2,
+        num_return_sequences: 3,
+        generation_microbatch_size: Some(5),
+        ..Default::default()
+    };
+    let model = TextGenerationModel::new(generate_config)?;
+
+    let input_context_1 = "The dog";
+    let input_context_2 = "The cat was";
+    let output = model.generate_flat(&[input_context_1, input_context_2], None);
+
+    assert_eq!(output.len(), 6);
+    assert_eq!(
+        output[0],
+        "The dog was found in the backyard of a home in the 6200 block of South Main Street"
+    );
+    assert_eq!(
+        output[1],
+        "The dog was found in the backyard of a home in the 6500 block of South Main Street"
+    );
+    assert_eq!(
+        output[2],
+        "The dog was found in the backyard of a home in the 6200 block of North Main Street"
+    );
+    assert_eq!(
+        output[3],
+        "The cat was taken to a local hospital, where it was treated and released.\n\nPolice said"
+    );
+    assert_eq!(
+        output[4],
+        "The cat was taken to a local hospital, where it was treated and released.\n\n\"It"
+    );
+    assert_eq!(
+        output[5],
+        "The cat was taken to a local hospital, where it was treated and released.\n\n\"We"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_diverse_beam_search_multiple_prompts_with_padding() -> anyhow::Result<()> {
+    //    Resources definition
+    let config_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2ConfigResources::GPT2));
+    let vocab_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2VocabResources::GPT2));
+    let merges_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2MergesResources::GPT2));
+    let model_resource =
+        Resource::Remote(RemoteResource::from_pretrained(Gpt2ModelResources::GPT2));
+
+    //    Set-up masked LM model
+    let generate_config = TextGenerationConfig {
+        model_type: ModelType::GPT2,
+        model_resource,
+        config_resource,
+        vocab_resource,
+        merges_resource,
+        min_length: 10,
+        max_length: 20,
+        do_sample: false,
+        num_beams: 6,
+        num_beam_groups: Some(3),
+        diversity_penalty: Some(5.5),
+        num_return_sequences: 3,
+        ..Default::default()
+    };
+    let model = TextGenerationModel::new(generate_config)?;
+
+    let input_context_1 = "It was a nice and";
+    let input_context_2 = "Language models can generate";
+    let output = model.generate_flat(&[input_context_1, input_context_2], None);
+
+    assert_eq!(output.len(), 6);
+    assert_eq!(
+        output[0],
+        "It was a nice and peaceful evening for me,\" he said.\n\n\"It was nice to"
+    );
+    assert_eq!(
+        output[1],
+        "It was a nice and warm day, and I'm glad I did.\n\n\"I'm"
+    );
+    assert_eq!(
+        output[2],
+        "It was a nice and warm day, and I'm glad I did.\n\n\"I was"
+    );
+    assert_eq!(
+        output[3],
+        "Language models can generate more complex models, but they are not the only way to do so."
+    );
+    assert_eq!(
+        output[4],
+        "Language models can generate more complex models, but they are not the only way to do this."
+    );
+    assert_eq!(
+        output[5],
+        "Language models can generate a lot of data, but they're not the only way to do it"
+    );
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn dialogpt_single_multi_turn_conversation() -> anyhow::Result<()> {
+    //    Set-up conversation model
+    let conversation_config = ConversationConfig {
+        do_sample: false,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let conversation_model = ConversationModel::new(conversation_config)?;
+
+    // Set-up conversation manager and add a conversation
+    let mut conversation_manager = ConversationManager::new();
+    let conversation_id =
+        conversation_manager.create("Going to the movies tonight - any suggestions?");
+
+    // Turn 1
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.len(), 1);
+    assert_eq!(output.get(&conversation_id).unwrap(), &"The Big Lebowski");
+
+    // Turn 2
+    let _ = conversation_manager
+        .get(&conversation_id)
+        .unwrap()
+        .add_user_input("Is it an action movie?");
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.len(), 1);
+    assert_eq!(output.get(&conversation_id).unwrap(), &"It\'s a comedy.");
+
+    // Turn 3 (no new user input)
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.len(), 0);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn dialogpt_conversation_repetition_blocklist() -> anyhow::Result<()> {
+    //    Set-up conversation model blocking the tokens of the previous response
+    let conversation_config = ConversationConfig {
+        do_sample: false,
+        repetition_blocklist_turns: 1,
+        repetition_blocklist_ngram_size: 1,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let conversation_model = ConversationModel::new(conversation_config)?;
+
+    let mut conversation_manager = ConversationManager::new();
+    let conversation_id =
+        conversation_manager.create("Going to the movies tonight - any suggestions?");
+
+    // Turn 1
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.get(&conversation_id).unwrap(), &"The Big Lebowski");
+
+    // Turn 2: the same question cannot be answered with the previous response
+    let _ = conversation_manager
+        .get(&conversation_id)
+        .unwrap()
+        .add_user_input("Going to the movies tonight - any suggestions?");
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.len(), 1);
+    assert!(!output.get(&conversation_id).unwrap().contains("Lebowski"));
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn dialogpt_multiple_multi_turn_conversation() -> anyhow::Result<()> {
+    //    Set-up conversation model
+    let conversation_config = ConversationConfig {
+        do_sample: false,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let conversation_model = ConversationModel::new(conversation_config)?;
+
+    // Set-up conversation manager and add a conversation
+    let mut conversation_manager = ConversationManager::new();
+    let conversation_1_id =
+        conversation_manager.create("Going to the movies tonight - any suggestions?");
+    let conversation_2_id = conversation_manager.create("What's the last book you have read?");
+
+    // Turn 1
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.len(), 2);
+    assert_eq!(output.get(&conversation_1_id).unwrap(), &"The Big Lebowski");
+    assert_eq!(
+        output.get(&conversation_2_id).unwrap(),
+        &"The Last Question"
+    );
+
+    // Turn 2
+    let _ = conversation_manager
+        .get(&conversation_1_id)
+        .unwrap()
+        .add_user_input("Is it an action movie?");
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.len(), 1);
+    assert_eq!(output.get(&conversation_1_id).unwrap(), &"It\'s a comedy.");
+
+    // Turn 3 (no new user input)
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.len(), 0);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn dialogpt_multiple_multi_turn_conversation_with_truncation() -> anyhow::Result<()> {
+    //    Set-up conversation model
+    let conversation_config = ConversationConfig {
+        max_length: 36,
+        min_length_for_response: 24,
+        do_sample: false,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let conversation_model = ConversationModel::new(conversation_config)?;
+
+    // Set-up conversation manager and add a conversation
+    let mut conversation_manager = ConversationManager::new();
+    let conversation_1_id =
+        conversation_manager.create("Going to the movies tonight - any suggestions?");
+    let conversation_2_id = conversation_manager.create("Hello how are you?");
+
+    // Turn 1
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.len(), 2);
+    assert_eq!(output.get(&conversation_1_id).unwrap(), &"The Big Lebowski");
+    assert_eq!(
+        output.get(&conversation_2_id).unwrap(),
+        &"I'm good, how are you?"
+    );
+
+    // Turn 2
+    let _ = conversation_manager
+        .get(&conversation_1_id)
+        .unwrap()
+        .add_user_input("Is it an action movie?");
+    let _ = conversation_manager
+        .get(&conversation_2_id)
+        .unwrap()
+        .add_user_input("Fine.");
+
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.len(), 2);
+    assert_eq!(output.get(&conversation_1_id).unwrap(), &"It\'s a comedy.");
+
+    // Turn 3 (no new user input)
+    let output = conversation_model.generate_responses(&mut conversation_manager);
+    assert_eq!(output.len(), 0);
+
+    Ok(())
+}
+
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn dialogpt_multiple_multi_turn_conversation_with_conversation_deletion() -> anyhow::Result<()> {
+    //    Set-up conversation model
+    let conversation_config = ConversationConfig {
         do_sample: false,
         device: Device::Cpu,
         ..Default::default()
@@ -555,3 +1286,526 @@ fn dialogpt_multiple_multi_turn_conversation_with_conversation_deletion() -> any
 
     Ok(())
 }
+
+#[test]
+fn gpt2_generation_empty_inputs() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = TextGenerationConfig {
+        max_length: 20,
+        do_sample: false,
+        num_beams: 1,
+        ..Default::default()
+    };
+    let model = TextGenerationModel::new(generate_config)?;
+
+    let output = model.generate_flat(&["", "   ", "The dog"], None);
+
+    assert_eq!(output.len(), 3);
+    assert_eq!(output[0], "");
+    assert_eq!(output[1], "");
+    assert!(output[2].starts_with("The dog"));
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_sequence_logprob() -> anyhow::Result<()> {
+    //    Set-up model
+    let model = GPT2Generator::new(Default::default())?;
+
+    let prompt = "The capital of France is";
+    let likely_logprob = model.sequence_logprob(prompt, " Paris")?;
+    let unlikely_logprob = model.sequence_logprob(prompt, " a potato")?;
+
+    assert!(likely_logprob < 0f64);
+    assert!(likely_logprob > unlikely_logprob);
+    assert_eq!(model.sequence_logprob(prompt, "")?, 0f64);
+
+    //    The continuation log-probability decomposes over its tokens
+    let full_logprob = model.sequence_logprob("The dog", " was found")?;
+    let first_logprob = model.sequence_logprob("The dog", " was")?;
+    let second_logprob = model.sequence_logprob("The dog was", " found")?;
+    assert!((full_logprob - (first_logprob + second_logprob)).abs() < 1e-4);
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_post_process() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = TextGenerationConfig {
+        max_length: 20,
+        do_sample: false,
+        num_beams: 1,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = TextGenerationModel::new(generate_config)?;
+    let post_process_config = TextGenerationConfig {
+        max_length: 20,
+        do_sample: false,
+        num_beams: 1,
+        post_process: Some(Box::new(|text: String| text.trim().to_uppercase())),
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let post_process_model = TextGenerationModel::new(post_process_config)?;
+
+    let input_context = "The dog";
+    let output = model.generate_flat(&[input_context], None);
+    let post_processed_output = post_process_model.generate_flat(&[input_context], None);
+
+    assert_eq!(output.len(), 1);
+    assert_eq!(post_processed_output.len(), 1);
+    assert_eq!(post_processed_output[0], output[0].trim().to_uppercase());
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_rerank() -> anyhow::Result<()> {
+    //    Set-up models, ranking the beam hypotheses by increasing length
+    let generate_config = TextGenerationConfig {
+        max_length: 20,
+        do_sample: false,
+        num_beams: 4,
+        num_return_sequences: 4,
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = TextGenerationModel::new(generate_config)?;
+    let rerank_generate_config = TextGenerationConfig {
+        max_length: 20,
+        do_sample: false,
+        num_beams: 4,
+        num_return_sequences: 2,
+        rerank: Some(Box::new(|text: &str| -(text.len() as f64))),
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let rerank_model = TextGenerationModel::new(rerank_generate_config)?;
+
+    let input_context = "The dog";
+    let hypotheses = model.generate_flat([input_context], None);
+    let output = rerank_model.generate([input_context], None);
+
+    let mut hypotheses_lengths = hypotheses
+        .iter()
+        .map(|hypothesis| hypothesis.len())
+        .collect::<Vec<usize>>();
+    hypotheses_lengths.sort_unstable();
+    assert_eq!(output.len(), 1);
+    assert_eq!(output[0].len(), 2);
+    for (text, hypothesis_length) in output[0].iter().zip(hypotheses_lengths) {
+        assert!(hypotheses.contains(&text.text));
+        assert_eq!(text.text.len(), hypothesis_length);
+        assert_eq!(text.rerank_score, Some(-(text.text.len() as f64)));
+    }
+    assert_eq!(
+        rerank_model.generate_flat([input_context], None),
+        output[0]
+            .iter()
+            .map(|text| text.text.clone())
+            .collect::<Vec<String>>()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_output_budget() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = GenerateConfig {
+        max_length: 40,
+        do_sample: false,
+        num_beams: 1,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+    let budget_config = GenerateConfig {
+        max_length: 40,
+        do_sample: false,
+        num_beams: 1,
+        max_output_chars: Some(20),
+        ..Default::default()
+    };
+    let budget_model = GPT2Generator::new(budget_config)?;
+
+    let input_context = "The dog";
+    let output = model.generate_flat(Some(&[input_context]), None, None, None, None);
+    let budget_output = budget_model.generate_flat(Some(&[input_context]), None, None, None, None);
+
+    assert_eq!(budget_output.len(), 1);
+    assert!(output[0].starts_with(&budget_output[0]));
+    assert!(budget_output[0].len() < output[0].len());
+    assert!(budget_output[0][input_context.len()..].chars().count() <= 20);
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_length_bias() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = GenerateConfig {
+        max_length: 20,
+        min_length: 6,
+        do_sample: false,
+        num_beams: 1,
+        length_bias: Some(LengthBias {
+            target: 0,
+            strength: 10.0,
+        }),
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+
+    //    EOS is banned until the minimum length and then strongly encouraged
+    let input_context = "The dog";
+    let output = model.generate_indices(Some(&[input_context]), None, None, None, None);
+    assert_eq!(output[0].len(), 7);
+    assert_eq!(*output[0].last().unwrap(), 50256);
+
+    //    A negative bias delays the end of the sequences with beam search
+    let generate_config = GenerateConfig {
+        max_length: 20,
+        do_sample: false,
+        num_beams: 3,
+        length_bias: Some(LengthBias {
+            target: 0,
+            strength: -10.0,
+        }),
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+    let output = model.generate_indices(Some(&[input_context]), None, None, None, None);
+    assert_eq!(output[0].len(), 20);
+    assert!(!output[0][2..].contains(&50256));
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_corpus_penalty() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = GenerateConfig {
+        max_length: 12,
+        do_sample: false,
+        num_beams: 1,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+
+    let input_context = "The dog";
+    let output = model.generate_indices(Some(&[input_context]), None, None, None, None);
+    let penalized_token_id = output[0][2];
+
+    //    A strongly penalized token is no longer selected
+    let mut corpus_penalty = HashMap::new();
+    corpus_penalty.insert(penalized_token_id, -100.0);
+    let generate_config = GenerateConfig {
+        max_length: 12,
+        do_sample: false,
+        num_beams: 1,
+        corpus_penalty: Some(corpus_penalty),
+        ..Default::default()
+    };
+    let penalized_model = GPT2Generator::new(generate_config)?;
+    let penalized_output =
+        penalized_model.generate_indices(Some(&[input_context]), None, None, None, None);
+    assert_eq!(penalized_output[0][..2], output[0][..2]);
+    assert!(!penalized_output[0][2..].contains(&penalized_token_id));
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_corpus_penalty_out_of_vocabulary() -> anyhow::Result<()> {
+    //    Set-up model
+    let mut corpus_penalty = HashMap::new();
+    corpus_penalty.insert(50257, -100.0);
+    let generate_config = GenerateConfig {
+        max_length: 12,
+        do_sample: false,
+        num_beams: 1,
+        corpus_penalty: Some(corpus_penalty),
+        ..Default::default()
+    };
+
+    //    Token ids outside of the vocabulary are rejected when building the generator
+    assert!(GPT2Generator::new(generate_config).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_cache_memory() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = GenerateConfig {
+        max_length: 12,
+        do_sample: false,
+        num_beams: 3,
+        output_cache_memory: true,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+
+    //    The cache memory grows at each decoding step
+    let input_context = "The dog";
+    let output = model.generate_indices_with_output(Some(&[input_context]), None, None, None, None);
+    assert_eq!(output.cache_memory.len(), 10);
+    for (memory_1, memory_2) in output
+        .cache_memory
+        .iter()
+        .zip(output.cache_memory.iter().skip(1))
+    {
+        assert!(memory_2 > memory_1);
+    }
+
+    //    The cache memory is not measured by default
+    let model = GPT2Generator::new(GenerateConfig {
+        max_length: 12,
+        do_sample: false,
+        num_beams: 3,
+        ..Default::default()
+    })?;
+    let output = model.generate_indices_with_output(Some(&[input_context]), None, None, None, None);
+    assert!(output.cache_memory.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_padding_attention_mask_with_pad_eos() -> anyhow::Result<()> {
+    //    GPT2 checkpoints commonly use the EOS token (50256) for padding
+    let eos_token_id = 50256;
+    let input_ids = Tensor::of_slice(&[
+        eos_token_id,
+        eos_token_id,
+        464,
+        3290,
+        eos_token_id,
+        383,
+        464,
+        eos_token_id,
+        3290,
+        eos_token_id,
+        383,
+        eos_token_id,
+    ])
+    .view([2, 6]);
+
+    //    Left padding: only the leading EOS tokens are masked
+    let attention_mask =
+        build_padding_attention_mask(&input_ids, Some(eos_token_id), PaddingSide::Left);
+    assert_eq!(
+        Vec::<i64>::from(attention_mask.get(0)),
+        vec![0, 0, 1, 1, 1, 1]
+    );
+    assert_eq!(
+        Vec::<i64>::from(attention_mask.get(1)),
+        vec![1, 1, 1, 1, 1, 1]
+    );
+
+    //    Right padding: only the trailing EOS tokens are masked
+    let attention_mask =
+        build_padding_attention_mask(&input_ids, Some(eos_token_id), PaddingSide::Right);
+    assert_eq!(
+        Vec::<i64>::from(attention_mask.get(0)),
+        vec![1, 1, 1, 1, 1, 1]
+    );
+    assert_eq!(
+        Vec::<i64>::from(attention_mask.get(1)),
+        vec![1, 1, 1, 1, 1, 0]
+    );
+
+    //    Without padding token, all positions are attended to
+    let attention_mask = build_padding_attention_mask(&input_ids, None, PaddingSide::Left);
+    assert_eq!(i64::from(attention_mask.sum(Kind::Int64)), 12);
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_with_trigger() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = GenerateConfig {
+        max_length: 30,
+        do_sample: false,
+        num_beams: 1,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+    let input_context = "The dog";
+    let second_input_context = "The cat was";
+    let output = model.generate_indices(
+        Some(&[input_context, second_input_context]),
+        None,
+        None,
+        None,
+        None,
+    );
+
+    //    Use the first generated token of the first prompt as a trigger and splice two tokens after it
+    let prompt_length = 3;
+    let trigger_token_id = output[0][prompt_length];
+    let spliced_ids = vec![464, 3290];
+    let triggered_outputs = Rc::new(RefCell::new(vec![]));
+    let callback_outputs = triggered_outputs.clone();
+    let callback_spliced_ids = spliced_ids.clone();
+    let triggered_output = model.generate_with_trigger(
+        Some(&[input_context, second_input_context]),
+        None,
+        None,
+        (
+            trigger_token_id,
+            Box::new(move |output: &str| {
+                callback_outputs.borrow_mut().push(output.to_string());
+                callback_spliced_ids.clone()
+            }),
+        ),
+    )?;
+
+    assert!(!triggered_outputs.borrow().is_empty());
+    assert_eq!(triggered_output.len(), 2);
+    assert_eq!(
+        triggered_output[0][..prompt_length + 1],
+        output[0][..prompt_length + 1]
+    );
+    assert_eq!(
+        triggered_output[0][prompt_length + 1..prompt_length + 3],
+        spliced_ids[..]
+    );
+    assert!(triggered_output.iter().all(|sequence| sequence.len() <= 30));
+
+    //    Triggers are not available for beam search
+    let beam_search_model = GPT2Generator::new(GenerateConfig {
+        max_length: 30,
+        do_sample: false,
+        num_beams: 3,
+        ..Default::default()
+    })?;
+    assert!(beam_search_model
+        .generate_with_trigger(
+            Some(&[input_context]),
+            None,
+            None,
+            (trigger_token_id, Box::new(|_: &str| vec![]))
+        )
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_snapshot() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = GenerateConfig {
+        max_length: 20,
+        do_sample: false,
+        num_beams: 1,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+
+    let input_context = "The dog";
+    let second_input_context = "The cat was";
+    let output = model.generate_indices(
+        Some(&[input_context, second_input_context]),
+        None,
+        None,
+        None,
+        None,
+    );
+
+    //    Generate by chunks of 5 steps, serializing the snapshot and saving its cache between chunks
+    let tensors_dir = tempfile::tempdir()?;
+    let tensors_path = tensors_dir.path().join("snapshot.ot");
+    let mut snapshot =
+        model.generate_snapshot(Some(&[input_context, second_input_context]), None, None, 5)?;
+    assert_eq!(snapshot.step(), 5);
+    while !snapshot.is_complete() {
+        let serialized_snapshot = serde_json::to_string(&snapshot)?;
+        snapshot.save_tensors(&tensors_path)?;
+        let mut restored_snapshot: GenerationSnapshot = serde_json::from_str(&serialized_snapshot)?;
+        restored_snapshot.load_tensors(&tensors_path, Device::Cpu)?;
+        snapshot = model.resume_generation(&restored_snapshot, 5)?;
+    }
+    assert_eq!(snapshot.token_ids, output);
+
+    //    Without its tensors, the cache of a snapshot is rebuilt from the generated tokens
+    let snapshot = model.generate_snapshot(Some(&[input_context]), None, None, 5)?;
+    let restored_snapshot: GenerationSnapshot =
+        serde_json::from_str(&serde_json::to_string(&snapshot)?)?;
+    assert_eq!(
+        model.resume_generation(&restored_snapshot, None)?.token_ids,
+        output[..1]
+    );
+
+    //    Beam search snapshots carry the beams and finished hypotheses
+    let beam_search_model = GPT2Generator::new(GenerateConfig {
+        max_length: 20,
+        do_sample: false,
+        num_beams: 3,
+        ..Default::default()
+    })?;
+    let beam_search_output = beam_search_model.generate_indices(
+        Some(&[input_context, second_input_context]),
+        None,
+        None,
+        None,
+        None,
+    );
+    let mut snapshot = beam_search_model.generate_snapshot(
+        Some(&[input_context, second_input_context]),
+        None,
+        None,
+        4,
+    )?;
+    assert_eq!(snapshot.token_ids.len(), 6);
+    assert_eq!(snapshot.beam_hypotheses.len(), 2);
+    while !snapshot.is_complete() {
+        snapshot.save_tensors(&tensors_path)?;
+        let mut restored_snapshot: GenerationSnapshot =
+            serde_json::from_str(&serde_json::to_string(&snapshot)?)?;
+        restored_snapshot.load_tensors(&tensors_path, Device::Cpu)?;
+        snapshot = beam_search_model.resume_generation(&restored_snapshot, 4)?;
+    }
+    assert_eq!(snapshot.token_ids, beam_search_output);
+
+    Ok(())
+}
+
+#[test]
+fn gpt2_generation_snapshot_sampling() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = GenerateConfig {
+        max_length: 20,
+        do_sample: true,
+        num_beams: 1,
+        num_return_sequences: 2,
+        ..Default::default()
+    };
+    let model = GPT2Generator::new(generate_config)?;
+    let input_context = "The dog";
+
+    //    A sampled generation resumed from snapshots samples the same tokens as an uninterrupted one
+    let snapshot = model.generate_snapshot(Some(&[input_context]), None, None, 0)?;
+    assert!(snapshot.rng_seed.is_some());
+    let output = model.resume_generation(&snapshot, None)?.token_ids;
+
+    let tensors_dir = tempfile::tempdir()?;
+    let tensors_path = tensors_dir.path().join("snapshot.ot");
+    let mut resumed_snapshot = model.resume_generation(&snapshot, 3)?;
+    while !resumed_snapshot.is_complete() {
+        resumed_snapshot.save_tensors(&tensors_path)?;
+        let mut restored_snapshot: GenerationSnapshot =
+            serde_json::from_str(&serde_json::to_string(&resumed_snapshot)?)?;
+        restored_snapshot.load_tensors(&tensors_path, Device::Cpu)?;
+        //    Sampling with another random state in between does not affect the resumed generation
+        let _ = model.generate_indices(Some(&[input_context]), None, None, None, None);
+        resumed_snapshot = model.resume_generation(&restored_snapshot, 3)?;
+    }
+    assert_eq!(resumed_snapshot.token_ids, output);
+
+    Ok(())
+}
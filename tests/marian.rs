@@ -22,3 +22,19 @@ fn test_translation() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_translation_empty_inputs() -> anyhow::Result<()> {
+    //    Set-up translation model
+    let translation_config = TranslationConfig::new(Language::EnglishToFrench, Device::Cpu);
+    let model = TranslationModel::new(translation_config)?;
+
+    let output = model.translate(&["", "   ", "The dog did not wake up"]);
+
+    assert_eq!(output.len(), 3);
+    assert_eq!(output[0], "");
+    assert_eq!(output[1], "");
+    assert_eq!(output[2], " Le chien ne s'est pas réveillé");
+
+    Ok(())
+}
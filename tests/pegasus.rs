@@ -1,9 +1,14 @@
 use rust_bert::pipelines::summarization::{SummarizationConfig, SummarizationModel};
 
-use rust_bert::pegasus::{PegasusConfigResources, PegasusModelResources, PegasusVocabResources};
+use rust_bert::initialization::{initialize_weights, WeightInitialization};
+use rust_bert::pegasus::{
+    PegasusConfig, PegasusConfigResources, PegasusForConditionalGeneration, PegasusModel,
+    PegasusModelResources, PegasusVocabResources,
+};
 use rust_bert::pipelines::common::ModelType;
 use rust_bert::resources::{RemoteResource, Resource};
-use tch::Device;
+use rust_bert::Config;
+use tch::{nn, no_grad, Device, Kind, Tensor};
 
 #[test]
 fn pegasus_summarization_greedy() -> anyhow::Result<()> {
@@ -66,3 +71,77 @@ about exoplanets like K2-18b."];
 
     Ok(())
 }
+
+#[test]
+fn pegasus_weight_initialization() -> anyhow::Result<()> {
+    //    Set-up untrained model
+    let config_resource = Resource::Remote(RemoteResource::from_pretrained(
+        PegasusConfigResources::CNN_DAILYMAIL,
+    ));
+    let config_path = config_resource.get_local_path()?;
+    let config = PegasusConfig::from_file(config_path);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let _model = PegasusForConditionalGeneration::new(&vs.root(), &config);
+
+    initialize_weights(
+        &vs,
+        WeightInitialization::Normal {
+            std: config.init_std,
+        },
+    );
+
+    let variables = vs.variables();
+    let embeddings = &variables["model.shared.weight"];
+    assert!((embeddings.std(true).double_value(&[]) - config.init_std).abs() < 1e-3);
+    assert!(embeddings.mean(tch::Kind::Float).double_value(&[]).abs() < 1e-3);
+    for (name, variable) in variables.iter() {
+        if name.ends_with("bias") {
+            assert_eq!(variable.abs().max().double_value(&[]), 0.0);
+        } else if variable.dim() == 1 {
+            assert_eq!(variable.min().double_value(&[]), 1.0);
+            assert_eq!(variable.max().double_value(&[]), 1.0);
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn pegasus_encoder_hidden_state_norms() -> anyhow::Result<()> {
+    //    Set-up untrained model collecting the hidden states
+    let config_resource = Resource::Remote(RemoteResource::from_pretrained(
+        PegasusConfigResources::CNN_DAILYMAIL,
+    ));
+    let config_path = config_resource.get_local_path()?;
+    let mut config = PegasusConfig::from_file(config_path);
+    config.output_hidden_states = Some(true);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = PegasusModel::new(&vs.root(), &config);
+
+    let input_ids = Tensor::of_slice(&[182i64, 117, 114, 1242, 1, 0, 0]).unsqueeze(0);
+    let attention_mask = input_ids.ne(0).to_kind(Kind::Int64);
+    let decoder_input_ids = Tensor::of_slice(&[0i64]).unsqueeze(0);
+    let output = no_grad(|| {
+        model.forward_t(
+            Some(&input_ids),
+            Some(&attention_mask),
+            &decoder_input_ids,
+            None,
+            None,
+            None,
+            false,
+        )
+    });
+
+    let norms = output
+        .encoder_hidden_state_norms(Some(&attention_mask))
+        .unwrap();
+    assert_eq!(norms.len() as i64, config.encoder_layers + 1);
+    assert!(norms.iter().all(|norm| norm.is_finite() && (*norm > 0.0)));
+
+    let max_activations = output.encoder_max_activations().unwrap();
+    assert_eq!(max_activations.len(), norms.len());
+    assert_eq!(max_activations[0].size(), vec![1, 7]);
+
+    Ok(())
+}
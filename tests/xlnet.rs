@@ -1,10 +1,11 @@
 use rust_bert::pipelines::common::ModelType;
+use rust_bert::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
 use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
 use rust_bert::resources::{RemoteResource, Resource};
 use rust_bert::xlnet::{
     XLNetConfig, XLNetConfigResources, XLNetForMultipleChoice, XLNetForQuestionAnswering,
-    XLNetForSequenceClassification, XLNetForTokenClassification, XLNetLMHeadModel, XLNetModel,
-    XLNetModelResources, XLNetVocabResources,
+    XLNetForSequenceClassification, XLNetForTokenClassification, XLNetGenerator, XLNetLMHeadModel,
+    XLNetModel, XLNetModelResources, XLNetVocabResources,
 };
 use rust_bert::Config;
 use rust_tokenizers::tokenizer::{MultiThreadedTokenizer, TruncationStrategy, XLNetTokenizer};
@@ -225,7 +226,7 @@ fn xlnet_generation_beam_search() -> anyhow::Result<()> {
     let model = TextGenerationModel::new(generate_config)?;
 
     let input_context = "Once upon a time,";
-    let output = model.generate(&[input_context], None);
+    let output = model.generate_flat(&[input_context], None);
 
     assert_eq!(output.len(), 1);
     assert_eq!(
@@ -236,6 +237,35 @@ fn xlnet_generation_beam_search() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn xlnet_sequence_logprob() -> anyhow::Result<()> {
+    //    Set-up model
+    let generate_config = GenerateConfig {
+        model_resource: Resource::Remote(RemoteResource::from_pretrained(
+            XLNetModelResources::XLNET_BASE_CASED,
+        )),
+        config_resource: Resource::Remote(RemoteResource::from_pretrained(
+            XLNetConfigResources::XLNET_BASE_CASED,
+        )),
+        vocab_resource: Resource::Remote(RemoteResource::from_pretrained(
+            XLNetVocabResources::XLNET_BASE_CASED,
+        )),
+        merges_resource: Resource::Remote(RemoteResource::from_pretrained(
+            XLNetVocabResources::XLNET_BASE_CASED,
+        )),
+        device: Device::Cpu,
+        ..Default::default()
+    };
+    let model = XLNetGenerator::new(generate_config)?;
+
+    //    XLNet does not provide causal token probabilities from a single forward pass
+    assert!(model
+        .sequence_logprob("Once upon a time,", " there")
+        .is_err());
+
+    Ok(())
+}
+
 #[test]
 fn xlnet_for_sequence_classification() -> anyhow::Result<()> {
     //    Resources paths